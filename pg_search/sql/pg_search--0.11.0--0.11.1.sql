@@ -11,3 +11,758 @@ DROP FUNCTION IF EXISTS search_config_restrict(planner_info internal, operator_o
 DROP FUNCTION IF EXISTS search_config_support(arg internal);
 DROP FUNCTION IF EXISTS search_with_search_config(element anyelement, config_json jsonb);
 
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:170
+-- pg_search::api::index::boosting
+CREATE  FUNCTION "boosting"(
+    "positive" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "negative" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "negative_boost" real /* f32 */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'boosting_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:134
+-- pg_search::api::index::index_schema
+CREATE  FUNCTION "index_schema"(
+    "indexrelid" oid /* pgrx_pg_sys::submodules::oids::Oid */
+) RETURNS TABLE (
+    "name" TEXT,  /* alloc::string::String */
+    "field_type" TEXT,  /* alloc::string::String */
+    "indexed" bool,  /* bool */
+    "stored" bool,  /* bool */
+    "fast" bool,  /* bool */
+    "tokenizer" TEXT  /* core::option::Option<alloc::string::String> */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'index_schema_wrapper';
+/* </end connected objects> */
+
+DROP FUNCTION IF EXISTS "boolean"(must SearchQueryInput[], should SearchQueryInput[], must_not SearchQueryInput[]);
+DROP FUNCTION IF EXISTS "boolean"(must SearchQueryInput, should SearchQueryInput, must_not SearchQueryInput);
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:174
+-- pg_search::api::index::boolean_arrays
+CREATE  FUNCTION "boolean"(
+    "must" SearchQueryInput[] DEFAULT ARRAY[]::searchqueryinput[], /* alloc::vec::Vec<pg_search::query::SearchQueryInput> */
+    "should" SearchQueryInput[] DEFAULT ARRAY[]::searchqueryinput[], /* alloc::vec::Vec<pg_search::query::SearchQueryInput> */
+    "must_not" SearchQueryInput[] DEFAULT ARRAY[]::searchqueryinput[], /* alloc::vec::Vec<pg_search::query::SearchQueryInput> */
+    "minimum_should_match" TEXT DEFAULT NULL, /* core::option::Option<alloc::string::String> */
+    "filter" SearchQueryInput[] DEFAULT ARRAY[]::searchqueryinput[] /* alloc::vec::Vec<pg_search::query::SearchQueryInput> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'boolean_arrays_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:195
+-- pg_search::api::index::boolean_singles
+CREATE  FUNCTION "boolean"(
+    "must" SearchQueryInput DEFAULT NULL, /* core::option::Option<pg_search::query::SearchQueryInput> */
+    "should" SearchQueryInput DEFAULT NULL, /* core::option::Option<pg_search::query::SearchQueryInput> */
+    "must_not" SearchQueryInput DEFAULT NULL, /* core::option::Option<pg_search::query::SearchQueryInput> */
+    "minimum_should_match" TEXT DEFAULT NULL, /* core::option::Option<alloc::string::String> */
+    "filter" SearchQueryInput DEFAULT NULL /* core::option::Option<pg_search::query::SearchQueryInput> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'boolean_singles_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:170
+-- pg_search::api::index::index_generation
+CREATE  FUNCTION "index_generation"(
+    "indexrelid" oid /* pgrx_pg_sys::submodules::oids::Oid */
+) RETURNS bigint /* i64 */
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'index_generation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:657
+-- pg_search::api::index::snapshot
+CREATE  FUNCTION "snapshot"(
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "as_of" bigint /* i64 */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'snapshot_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/snippet.rs:62
+-- pg_search::postgres::customscan::pdbscan::projections::snippet::snippets_from_relation
+CREATE  FUNCTION "snippets"(
+    "key" anyelement, /* pgrx::datum::anyelement::AnyElement */
+    "fields" TEXT[], /* alloc::vec::Vec<alloc::string::String> */
+    "start_tag" TEXT DEFAULT '<b>', /* alloc::string::String */
+    "end_tag" TEXT DEFAULT '</b>', /* alloc::string::String */
+    "max_num_chars" INT DEFAULT 150, /* i32 */
+    "field_options" jsonb DEFAULT '{}' /* pgrx::datum::json::JsonB */
+) RETURNS jsonb /* core::option::Option<pgrx::datum::json::JsonB> */
+    STRICT STABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'snippets_from_relation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:484
+-- pg_search::api::index::phrase_prefix_text
+CREATE  FUNCTION "phrase_prefix"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "value" TEXT, /* alloc::string::String */
+    "max_expansion" INT DEFAULT NULL /* core::option::Option<i32> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'phrase_prefix_text_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:189
+-- pg_search::api::index::warmup
+CREATE  FUNCTION "warmup"(
+    "indexrelid" oid /* pgrx_pg_sys::submodules::oids::Oid */
+) RETURNS TABLE (
+    "segno" TEXT,  /* alloc::string::String */
+    "num_docs" bigint,  /* i64 */
+    "byte_size" bigint  /* i64 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'warmup_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:728
+-- pg_search::api::index::similarity
+CREATE  FUNCTION "similarity"(
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "similarity" TEXT, /* alloc::string::String */
+    "k1" real DEFAULT NULL, /* core::option::Option<f32> */
+    "b" real DEFAULT NULL /* core::option::Option<f32> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'similarity_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:230
+-- pg_search::api::index::term_stats
+CREATE  FUNCTION "term_stats"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS TABLE (
+    "field" TEXT,  /* alloc::string::String */
+    "term" TEXT,  /* alloc::string::String */
+    "doc_freq" bigint,  /* i64 */
+    "total_term_freq" bigint  /* i64 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'term_stats_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1088
+-- pg_search::api::index::terms_query
+CREATE  FUNCTION "terms_query"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "terms" TEXT[], /* alloc::vec::Vec<alloc::string::String> */
+    "conjunction_mode" bool DEFAULT NULL /* core::option::Option<bool> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'terms_query_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/rank.rs:26
+-- pg_search::postgres::customscan::pdbscan::projections::rank::rank_from_relation
+CREATE  FUNCTION "rank"(
+    "_relation_reference" anyelement /* pgrx::datum::anyelement::AnyElement */
+) RETURNS bigint /* i64 */
+    STRICT STABLE PARALLEL SAFE  COST 1
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'rank_from_relation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:277
+-- pg_search::api::index::explain_query
+CREATE  FUNCTION "explain_query"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS jsonb /* pgrx::datum::json::JsonB */
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'explain_query_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/sort_expression.rs:31
+-- pg_search::postgres::customscan::pdbscan::projections::sort_expression::sort_expression_from_relation
+CREATE  FUNCTION "sort_expression"(
+    "_relation_reference" anyelement, /* pgrx::datum::anyelement::AnyElement */
+    "expression" TEXT /* &str */
+) RETURNS double precision /* f64 */
+    STRICT STABLE PARALLEL SAFE  COST 1
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'sort_expression_from_relation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/field_scores.rs:27
+-- pg_search::postgres::customscan::pdbscan::projections::field_scores::field_scores_from_relation
+CREATE  FUNCTION "field_scores"(
+    "_relation_reference" anyelement /* pgrx::datum::anyelement::AnyElement */
+) RETURNS jsonb /* pgrx::datum::json::JsonB */
+    STRICT STABLE PARALLEL SAFE  COST 1
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'field_scores_from_relation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:794
+-- pg_search::api::index::collapse
+CREATE  FUNCTION "collapse"(
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "collapse" TEXT /* alloc::string::String */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'collapse_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:548
+-- pg_search::api::index::raw_query
+CREATE  FUNCTION "raw_query"(
+    "query" TEXT /* &str */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'raw_query_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:301
+-- pg_search::api::index::search_json
+CREATE  FUNCTION "search_json"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "limit" bigint, /* i64 */
+    "fields" TEXT[] DEFAULT NULL /* core::option::Option<alloc::vec::Vec<alloc::string::String>> */
+) RETURNS jsonb /* pgrx::datum::json::JsonB */
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'search_json_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:40
+-- pg_search::api::array::array_min_i16
+CREATE  FUNCTION "array_min"(
+    "values" smallint[] /* alloc::vec::Vec<core::option::Option<i16>> */
+) RETURNS smallint /* core::option::Option<i16> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_min_i16_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:41
+-- pg_search::api::array::array_min_i32
+CREATE  FUNCTION "array_min"(
+    "values" integer[] /* alloc::vec::Vec<core::option::Option<i32>> */
+) RETURNS integer /* core::option::Option<i32> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_min_i32_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:42
+-- pg_search::api::array::array_min_i64
+CREATE  FUNCTION "array_min"(
+    "values" bigint[] /* alloc::vec::Vec<core::option::Option<i64>> */
+) RETURNS bigint /* core::option::Option<i64> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_min_i64_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:43
+-- pg_search::api::array::array_min_f32
+CREATE  FUNCTION "array_min"(
+    "values" real[] /* alloc::vec::Vec<core::option::Option<f32>> */
+) RETURNS real /* core::option::Option<f32> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_min_f32_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:44
+-- pg_search::api::array::array_min_f64
+CREATE  FUNCTION "array_min"(
+    "values" double precision[] /* alloc::vec::Vec<core::option::Option<f64>> */
+) RETURNS double precision /* core::option::Option<f64> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_min_f64_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:40
+-- pg_search::api::array::array_max_i16
+CREATE  FUNCTION "array_max"(
+    "values" smallint[] /* alloc::vec::Vec<core::option::Option<i16>> */
+) RETURNS smallint /* core::option::Option<i16> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_max_i16_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:41
+-- pg_search::api::array::array_max_i32
+CREATE  FUNCTION "array_max"(
+    "values" integer[] /* alloc::vec::Vec<core::option::Option<i32>> */
+) RETURNS integer /* core::option::Option<i32> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_max_i32_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:42
+-- pg_search::api::array::array_max_i64
+CREATE  FUNCTION "array_max"(
+    "values" bigint[] /* alloc::vec::Vec<core::option::Option<i64>> */
+) RETURNS bigint /* core::option::Option<i64> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_max_i64_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:43
+-- pg_search::api::array::array_max_f32
+CREATE  FUNCTION "array_max"(
+    "values" real[] /* alloc::vec::Vec<core::option::Option<f32>> */
+) RETURNS real /* core::option::Option<f32> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_max_f32_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/array.rs:44
+-- pg_search::api::array::array_max_f64
+CREATE  FUNCTION "array_max"(
+    "values" double precision[] /* alloc::vec::Vec<core::option::Option<f64>> */
+) RETURNS double precision /* core::option::Option<f64> */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'array_max_f64_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:717
+-- pg_search::api::index::proximity
+CREATE  FUNCTION "proximity"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "value" TEXT, /* alloc::string::String */
+    "slop" INT DEFAULT NULL, /* core::option::Option<i32> */
+    "boost" real DEFAULT NULL /* core::option::Option<f32> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'proximity_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:403
+-- pg_search::api::index::search_with_total
+CREATE  FUNCTION "search_with_total"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "limit" bigint, /* i64 */
+    "offset" bigint DEFAULT 0 /* i64 */
+) RETURNS TABLE (
+    "ctid" tid,  /* pgrx_pg_sys::include::pg13::ItemPointerData */
+    "score" real,  /* f32 */
+    "total" bigint  /* i64 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'search_with_total_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:635
+-- pg_search::api::index::has_child
+CREATE  FUNCTION "has_child"(
+    "parent_field" FieldName, /* pg_search::api::index::FieldName */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'has_child_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:645
+-- pg_search::api::index::has_parent
+CREATE  FUNCTION "has_parent"(
+    "parent_field" FieldName, /* pg_search::api::index::FieldName */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'has_parent_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:281
+-- pg_search::api::index::estimate_selectivity
+CREATE  FUNCTION "estimate_selectivity"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS double precision /* core::option::Option<f64> */
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'estimate_selectivity_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:850
+-- pg_search::api::index::synonyms
+CREATE  FUNCTION "synonyms"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "value" TEXT, /* alloc::string::String */
+    "synonym_table" oid /* pgrx_pg_sys::submodules::oids::Oid */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    STABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'synonyms_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1078
+-- pg_search::api::index::dedup
+CREATE  FUNCTION "dedup"(
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "dedup_by" TEXT /* alloc::string::String */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'dedup_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:485
+-- pg_search::api::index::score_matrix
+CREATE  FUNCTION "score_matrix"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "queries" SearchQueryInput[], /* alloc::vec::Vec<pg_search::query::SearchQueryInput> */
+    "ctids" tid[] /* alloc::vec::Vec<pgrx_pg_sys::include::pg13::ItemPointerData> */
+) RETURNS TABLE (
+    "query_idx" integer,  /* i32 */
+    "ctid" tid,  /* pgrx_pg_sys::include::pg13::ItemPointerData */
+    "score" real  /* f32 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'score_matrix_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:237
+-- pg_search::api::index::segment_info
+CREATE  FUNCTION "segment_info"(
+    "indexrelid" oid /* pgrx_pg_sys::submodules::oids::Oid */
+) RETURNS TABLE (
+    "segno" TEXT,  /* alloc::string::String */
+    "num_docs" bigint,  /* i64 */
+    "num_deleted" bigint,  /* i64 */
+    "byte_size" bigint  /* i64 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'segment_info_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:972
+-- pg_search::api::index::exact_case
+CREATE  FUNCTION "exact_case"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "value" TEXT, /* alloc::string::String */
+    "cased_field" FieldName, /* pg_search::api::index::FieldName */
+    "boost" real DEFAULT 2.0 /* f32 */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'exact_case_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:524
+-- pg_search::api::index::search_many
+CREATE  FUNCTION "search_many"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "queries" SearchQueryInput[], /* alloc::vec::Vec<pg_search::query::SearchQueryInput> */
+    "limit" bigint /* i64 */
+) RETURNS TABLE (
+    "query_idx" integer,  /* i32 */
+    "ctid" tid,  /* pgrx_pg_sys::include::pg13::ItemPointerData */
+    "score" real,  /* f32 */
+    "rank" bigint  /* i64 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'search_many_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1271
+-- pg_search::api::index::exclude
+CREATE  FUNCTION "exclude"(
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "exclude" tid[] /* alloc::vec::Vec<pgrx_pg_sys::include::pg13::ItemPointerData> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'exclude_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/matched_fields.rs:25
+-- pg_search::postgres::customscan::pdbscan::projections::matched_fields::matched_fields_from_relation
+CREATE  FUNCTION "matched_fields"(
+    "_relation_reference" anyelement /* pgrx::datum::anyelement::AnyElement */
+) RETURNS TEXT[] /* alloc::vec::Vec<alloc::string::String> */
+    STRICT STABLE PARALLEL SAFE  COST 1
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'matched_fields_from_relation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:980
+-- pg_search::api::index::cross_field_phrase
+CREATE  FUNCTION "cross_field_phrase"(
+    "fields" TEXT[], /* alloc::vec::Vec<alloc::string::String> */
+    "phrase" TEXT /* alloc::string::String */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'cross_field_phrase_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/relevance_pct.rs:26
+-- pg_search::postgres::customscan::pdbscan::projections::relevance_pct::relevance_pct_from_relation
+CREATE  FUNCTION "relevance_pct"(
+    "_relation_reference" anyelement /* pgrx::datum::anyelement::AnyElement */
+) RETURNS real /* core::option::Option<f32> */
+    STRICT STABLE PARALLEL SAFE  COST 1
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'relevance_pct_from_relation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:622
+-- pg_search::api::index::matches
+CREATE  FUNCTION "matches"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput /* pg_search::query::SearchQueryInput */
+) RETURNS bool /* bool */
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'matches_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:781
+-- pg_search::api::index::category_boost
+CREATE  FUNCTION "category_boost"(
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "field" TEXT, /* alloc::string::String */
+    "boosts" jsonb /* pgrx::datum::json::JsonB */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'category_boost_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:205
+-- pg_search::api::index::last_query_generation
+CREATE  FUNCTION "last_query_generation"() RETURNS bigint /* core::option::Option<i64> */
+    VOLATILE PARALLEL RESTRICTED
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'last_query_generation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1690
+-- pg_search::api::index::analyzed
+CREATE  FUNCTION "analyzed"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "value" TEXT, /* alloc::string::String */
+    "analyzer" jsonb, /* pgrx::datum::json::JsonB */
+    "conjunction_mode" bool DEFAULT NULL /* core::option::Option<bool> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'analyzed_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/postgres/customscan/pdbscan/projections/features.rs:32
+-- pg_search::postgres::customscan::pdbscan::projections::features::features_from_relation
+CREATE  FUNCTION "features"(
+    "_relation_reference" anyelement, /* pgrx::datum::anyelement::AnyElement */
+    "_names" TEXT[] /* alloc::vec::Vec<alloc::string::String> */
+) RETURNS jsonb /* pgrx::datum::json::JsonB */
+    STRICT STABLE PARALLEL SAFE  COST 1
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'features_from_relation_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1127
+-- pg_search::api::index::phrase_with_terms
+CREATE  FUNCTION "phrase_with_terms"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "phrases" TEXT[], /* alloc::vec::Vec<alloc::string::String> */
+    "slop" INT DEFAULT NULL, /* core::option::Option<i32> */
+    "term_boost" real DEFAULT NULL, /* core::option::Option<f32> */
+    "term_boosts" real[] DEFAULT NULL /* core::option::Option<alloc::vec::Vec<f32>> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'phrase_with_terms_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:568
+-- pg_search::api::index::score_histogram
+CREATE  FUNCTION "score_histogram"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "buckets" INT DEFAULT 10 /* i32 */
+) RETURNS TABLE (
+    "bucket" INT,  /* i32 */
+    "lower" real,  /* f32 */
+    "upper" real,  /* f32 */
+    "count" bigint  /* i64 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'score_histogram_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:568
+-- pg_search::api::index::search_tids
+CREATE  FUNCTION "search_tids"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "limit" bigint /* i64 */
+) RETURNS TABLE (
+    "ctid" tid,  /* pgrx_pg_sys::include::pg13::ItemPointerData */
+    "score" real  /* f32 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'search_tids_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1898
+-- pg_search::api::index::stemmed
+CREATE  FUNCTION "stemmed"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "value" TEXT, /* alloc::string::String */
+    "language" TEXT, /* alloc::string::String */
+    "conjunction_mode" bool DEFAULT NULL /* core::option::Option<bool> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'stemmed_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:393
+-- pg_search::api::index::analyze_query
+CREATE  FUNCTION "analyze_query"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "text" TEXT /* &str */
+) RETURNS TABLE (
+    "token" TEXT,  /* alloc::string::String */
+    "position" INT  /* i32 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'analyze_query_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1647
+-- pg_search::api::index::tiered
+CREATE  FUNCTION "tiered"(
+    "exact" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "inexact" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "recency_field" TEXT DEFAULT NULL, /* core::option::Option<alloc::string::String> */
+    "half_life" interval DEFAULT NULL /* core::option::Option<pgrx::datum::interval::Interval> */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'tiered_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:759
+-- pg_search::api::index::numeric_histogram
+CREATE  FUNCTION "numeric_histogram"(
+    "indexrelid" oid, /* pgrx_pg_sys::submodules::oids::Oid */
+    "query" SearchQueryInput, /* pg_search::query::SearchQueryInput */
+    "field" TEXT, /* alloc::string::String */
+    "interval" double precision, /* f64 */
+    "offset" double precision DEFAULT 0.0 /* f64 */
+) RETURNS TABLE (
+    "lower" double precision,  /* f64 */
+    "upper" double precision,  /* f64 */
+    "count" bigint  /* i64 */
+)
+    STRICT
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'numeric_histogram_wrapper';
+/* </end connected objects> */
+
+/* <begin connected objects> */
+-- pg_search/src/api/index.rs:1142
+-- pg_search::api::index::exists_boost
+CREATE  FUNCTION "exists_boost"(
+    "field" FieldName, /* pg_search::api::index::FieldName */
+    "factor" real /* f32 */
+) RETURNS SearchQueryInput /* pg_search::query::SearchQueryInput */
+    IMMUTABLE STRICT PARALLEL SAFE
+    LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'exists_boost_wrapper';
+/* </end connected objects> */
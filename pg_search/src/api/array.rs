@@ -0,0 +1,83 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::index::reader::SortReduction;
+use pgrx::{direct_function_call, pg_extern, pg_sys, IntoDatum};
+use std::ffi::CStr;
+
+macro_rules! array_reduction_fn {
+    ($min_name:ident, $max_name:ident, $value_type:ty, $cmp:expr) => {
+        /// The smallest of the array's non-NULL elements, or NULL if it has none
+        #[pg_extern(name = "array_min", immutable, strict, parallel_safe)]
+        pub fn $min_name(values: Vec<Option<$value_type>>) -> Option<$value_type> {
+            values.into_iter().flatten().min_by($cmp)
+        }
+
+        /// The largest of the array's non-NULL elements, or NULL if it has none
+        #[pg_extern(name = "array_max", immutable, strict, parallel_safe)]
+        pub fn $max_name(values: Vec<Option<$value_type>>) -> Option<$value_type> {
+            values.into_iter().flatten().max_by($cmp)
+        }
+    };
+}
+
+// An `ORDER BY` over one of these is pushed down to the BM25 index when the array is a
+// multivalued numeric fast field, which is why they are ours and not a generic aggregate
+array_reduction_fn!(array_min_i16, array_max_i16, i16, Ord::cmp);
+array_reduction_fn!(array_min_i32, array_max_i32, i32, Ord::cmp);
+array_reduction_fn!(array_min_i64, array_max_i64, i64, Ord::cmp);
+array_reduction_fn!(array_min_f32, array_max_f32, f32, f32::total_cmp);
+array_reduction_fn!(array_min_f64, array_max_f64, f64, f64::total_cmp);
+
+const ARRAY_REDUCTION_FUNCS: [(&CStr, SortReduction); 10] = [
+    (c"paradedb.array_min(smallint[])", SortReduction::Min),
+    (c"paradedb.array_min(integer[])", SortReduction::Min),
+    (c"paradedb.array_min(bigint[])", SortReduction::Min),
+    (c"paradedb.array_min(real[])", SortReduction::Min),
+    (
+        c"paradedb.array_min(double precision[])",
+        SortReduction::Min,
+    ),
+    (c"paradedb.array_max(smallint[])", SortReduction::Max),
+    (c"paradedb.array_max(integer[])", SortReduction::Max),
+    (c"paradedb.array_max(bigint[])", SortReduction::Max),
+    (c"paradedb.array_max(real[])", SortReduction::Max),
+    (
+        c"paradedb.array_max(double precision[])",
+        SortReduction::Max,
+    ),
+];
+
+/// Which reduction the function `funcid` is, if it's one of `paradedb.array_min()` or
+/// `paradedb.array_max()`
+pub fn array_reduction(funcid: pg_sys::Oid) -> Option<SortReduction> {
+    ARRAY_REDUCTION_FUNCS
+        .iter()
+        .find(|(signature, _)| {
+            let procoid = unsafe {
+                direct_function_call::<pg_sys::Oid>(
+                    pg_sys::regprocedurein,
+                    &[(*signature).into_datum()],
+                )
+                .expect(
+                    "the `paradedb.array_min()` and `paradedb.array_max()` functions should exist",
+                )
+            };
+            procoid == funcid
+        })
+        .map(|(_, reduction)| *reduction)
+}
@@ -18,15 +18,24 @@
 use pgrx::datum::RangeBound;
 use pgrx::{iter::TableIterator, *};
 
+use crate::bootstrap::create_bm25::index_segments;
+use crate::gucs;
+use crate::index::reader::{too_many_buckets, SortDirection};
+use crate::index::SearchIndex;
 use crate::postgres::index::open_search_index;
 use crate::postgres::types::TantivyValue;
-use crate::query::SearchQueryInput;
+use crate::postgres::utils::{item_pointer_to_u64, u64_to_item_pointer};
+use crate::postgres::visibility_checker::VisibilityChecker;
+use crate::query::{
+    query_tree, term_value_string, MinimumShouldMatch, SearchQueryInput, Similarity,
+};
 use crate::schema::IndexRecordOption;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::CStr;
 use std::fmt::{Display, Formatter};
 use std::ops::Bound;
+use std::sync::atomic::{AtomicI64, Ordering};
 use tantivy::json_utils::split_json_path;
 use tantivy::schema::{FieldType, OwnedValue, Value};
 
@@ -128,6 +137,876 @@ pub fn schema(
     TableIterator::new(field_rows)
 }
 
+/// A condensed version of [`schema`], keyed by index oid, describing each field in the index
+/// along with how it was configured.  Clients can use this to decide which predicates
+/// will be pushed down to the index.
+#[allow(clippy::type_complexity)]
+#[pg_extern]
+pub fn index_schema(
+    indexrelid: pg_sys::Oid,
+) -> TableIterator<
+    'static,
+    (
+        name!(name, String),
+        name!(field_type, String),
+        name!(indexed, bool),
+        name!(stored, bool),
+        name!(fast, bool),
+        name!(tokenizer, Option<String>),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    TableIterator::new(
+        schema(index)
+            .map(
+                |(name, field_type, stored, indexed, fast, _, _, tokenizer, _, _)| {
+                    (name, field_type, indexed, stored, fast, tokenizer)
+                },
+            )
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The generation of the index's most recent commit, for use with [`snapshot`]
+#[pg_extern]
+pub fn index_generation(indexrelid: pg_sys::Oid) -> i64 {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    search_index.generation().unwrap_or_else(|e| panic!("{e}")) as i64
+}
+
+/// The generation of the index that served this backend's most recent search, or -1 if it hasn't
+/// searched one yet
+static LAST_QUERY_GENERATION: AtomicI64 = AtomicI64::new(-1);
+
+/// Remember that this backend's most recent search was served by `generation` of its index
+pub fn set_last_query_generation(generation: u64) {
+    LAST_QUERY_GENERATION.store(generation as i64, Ordering::Relaxed);
+}
+
+/// The generation of the index that served the most recent search this backend ran, for telling
+/// whether results cached from it are stale, or NULL if it hasn't run one
+#[pg_extern(volatile, parallel_restricted)]
+pub fn last_query_generation() -> Option<i64> {
+    match LAST_QUERY_GENERATION.load(Ordering::Relaxed) {
+        n if n < 0 => None,
+        n => Some(n),
+    }
+}
+
+/// Pull the index into the OS page cache, returning how many bytes of each segment were warmed
+#[pg_extern]
+pub fn warmup(
+    indexrelid: pg_sys::Oid,
+) -> TableIterator<
+    'static,
+    (
+        name!(segno, String),
+        name!(num_docs, i64),
+        name!(byte_size, i64),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let warmed = search_index
+        .get_reader()
+        .and_then(|reader| reader.warmup())
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    TableIterator::new(
+        warmed
+            .into_iter()
+            .map(|(segment_id, num_docs, nbytes)| {
+                (
+                    segment_id.short_uuid_string(),
+                    num_docs as i64,
+                    nbytes as i64,
+                )
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The id of each of the index's segments, how many live and deleted documents it has, and how
+/// many bytes its files take up.  Like `paradedb.index_info`, but taking the index's oid
+#[pg_extern]
+pub fn segment_info(
+    indexrelid: pg_sys::Oid,
+) -> TableIterator<
+    'static,
+    (
+        name!(segno, String),
+        name!(num_docs, i64),
+        name!(num_deleted, i64),
+        name!(byte_size, i64),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let segments = index_segments(&index).unwrap_or_else(|e| panic!("{e}"));
+    TableIterator::new(
+        segments
+            .into_iter()
+            .map(|(segno, byte_size, num_docs, num_deleted)| {
+                (segno, num_docs, num_deleted, byte_size)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// How many documents contain each term of `query`, and how many times it occurs in all of them
+#[pg_extern]
+pub fn term_stats(
+    indexrelid: pg_sys::Oid,
+    query: SearchQueryInput,
+) -> TableIterator<
+    'static,
+    (
+        name!(field, String),
+        name!(term, String),
+        name!(doc_freq, i64),
+        name!(total_term_freq, i64),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let stats = search_index
+        .get_reader()
+        .and_then(|reader| reader.term_stats(search_index.query_parser(), query))
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    let schema = &search_index.schema.schema;
+    TableIterator::new(
+        stats
+            .into_iter()
+            .map(|(term, doc_freq, total_term_freq)| {
+                (
+                    schema.get_field_name(term.field()).to_string(),
+                    term_value_string(&term),
+                    doc_freq as i64,
+                    total_term_freq as i64,
+                )
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The fraction of the table's rows the planner estimates `query` matches, without running it
+/// over the whole index.  NULL if the table hasn't been analyzed, so its size isn't known
+#[pg_extern]
+pub fn estimate_selectivity(indexrelid: pg_sys::Oid, query: SearchQueryInput) -> Option<f64> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    crate::api::operator::estimate_selectivity(&index, &query)
+}
+
+/// The query tree tantivy searches with for `query`, as JSON.  See [`query_tree`] for its schema
+#[pg_extern]
+pub fn explain_query(indexrelid: pg_sys::Oid, query: SearchQueryInput) -> JsonB {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let query = search_index
+        .get_reader()
+        .and_then(|reader| reader.parse_query(search_index.query_parser(), query))
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    JsonB(query_tree(query.as_ref(), &search_index.schema.schema))
+}
+
+/// The tokens `text` is split into by the analyzer of `field`, and their positions, which are the
+/// same terms a query over that field searches for
+#[pg_extern]
+pub fn analyze_query(
+    indexrelid: pg_sys::Oid,
+    field: FieldName,
+    text: &str,
+) -> TableIterator<'static, (name!(token, String), name!(position, i32))> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    // a json field's values are all analyzed by the json field's analyzer, whatever their path
+    let (field, _) = split_field_and_path(&field);
+    let Ok(field) = search_index.schema.schema.get_field(&field) else {
+        panic!("field `{field}` is not part of the index");
+    };
+    let mut analyzer = search_index
+        .underlying_index
+        .tokenizer_for_field(field)
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    let mut stream = analyzer.token_stream(text);
+    let mut result = Vec::new();
+    while stream.advance() {
+        let token = stream.token();
+        result.push((token.text.to_string(), token.position as i32));
+    }
+
+    TableIterator::new(result)
+}
+
+/// The top `limit` visible rows matching `query`, most relevant first, as a jsonb array of
+/// objects holding each row's key, score, and the values of the fast `fields` asked for
+#[pg_extern]
+pub fn search_json(
+    indexrelid: pg_sys::Oid,
+    query: SearchQueryInput,
+    limit: i64,
+    fields: default!(Option<Vec<String>>, "NULL"),
+) -> JsonB {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+    if limit < 0 {
+        panic!("search_json limit must not be negative, but got {limit}");
+    }
+    let limit = limit as usize;
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let fields = fields.unwrap_or_default();
+    for field in &fields {
+        if !search_index.schema.is_field_fast(field) {
+            panic!("search_json field `{field}` is not a fast field of the index");
+        }
+    }
+    let key_field = search_index.key_field_name();
+
+    let search_reader = match query.as_of_generation() {
+        Some(generation) => search_index.get_reader_as_of(generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+    let tantivy_query = search_index.query(&query, &search_reader);
+
+    let heaprel = index
+        .heap_relation()
+        .expect("a bm25 index should have a heap relation");
+    let mut visibility_checker = unsafe {
+        VisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), pg_sys::GetActiveSnapshot())
+    };
+
+    // dead rows can take up some of the top `limit` places, so keep asking for more until we
+    // have enough visible ones or have run out of matches
+    let mut n = limit;
+    let hits = loop {
+        let results = search_reader
+            .search_top_n(
+                SearchIndex::executor(),
+                tantivy_query.as_ref(),
+                None,
+                &[],
+                None,
+                None,
+                SortDirection::Desc,
+                false,
+                gucs::score_tiebreak(),
+                n,
+            )
+            .collect::<Vec<_>>();
+        let nresults = results.len();
+        let visible = results
+            .into_iter()
+            .filter(|(scored, _)| {
+                visibility_checker
+                    .exec_if_visible(scored.ctid, |_, _, _| ())
+                    .is_some()
+            })
+            .take(limit)
+            .collect::<Vec<_>>();
+        if visible.len() == limit || nresults < n {
+            break visible;
+        }
+        n *= 2;
+    };
+
+    let to_json = |value: Option<TantivyValue>| {
+        value.map_or(serde_json::Value::Null, |value| {
+            serde_json::to_value(value.0).expect("fast field value should serialize to json")
+        })
+    };
+    let hits = hits
+        .into_iter()
+        .map(|(scored, doc_address)| {
+            let values = fields
+                .iter()
+                .map(|field| {
+                    let value = search_reader.fast_field_value(field, doc_address);
+                    (field.clone(), to_json(value))
+                })
+                .collect::<serde_json::Map<_, _>>();
+            serde_json::json!({
+                "key": to_json(search_reader.fast_field_value(&key_field, doc_address)),
+                "score": scored.bm25,
+                "fields": values,
+            })
+        })
+        .collect();
+    JsonB(serde_json::Value::Array(hits))
+}
+
+/// A page of the visible rows matching `query`, most relevant first: `limit` of them, after
+/// skipping the first `offset`.  Each row also holds the `total` number of visible rows that
+/// match, so that the page needn't be followed by a separate count
+#[pg_extern]
+pub fn search_with_total(
+    indexrelid: pg_sys::Oid,
+    query: SearchQueryInput,
+    limit: i64,
+    offset: default!(i64, 0),
+) -> TableIterator<
+    'static,
+    (
+        name!(ctid, pg_sys::ItemPointerData),
+        name!(score, f32),
+        name!(total, i64),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+    if limit < 0 {
+        panic!("search_with_total limit must not be negative, but got {limit}");
+    }
+    if offset < 0 {
+        panic!("search_with_total offset must not be negative, but got {offset}");
+    }
+    if let Some(max_result_window) = gucs::max_result_window() {
+        if (offset + limit) as usize > max_result_window {
+            panic!(
+                "offset plus limit of {} is more than paradedb.max_result_window allows, which is {max_result_window}.  Fetch deeper pages by filtering past the last row of the previous page, like search_after does, rather than by offset",
+                offset + limit
+            );
+        }
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let search_reader = match query.as_of_generation() {
+        Some(generation) => search_index.get_reader_as_of(generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+    let tantivy_query = search_index.query(&query, &search_reader);
+
+    let heaprel = index
+        .heap_relation()
+        .expect("a bm25 index should have a heap relation");
+    let mut visibility_checker = unsafe {
+        VisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), pg_sys::GetActiveSnapshot())
+    };
+    let (top, total) = search_reader.search_top_n_with_total(
+        SearchIndex::executor(),
+        tantivy_query.as_ref(),
+        (offset + limit) as usize,
+        |ctid| {
+            visibility_checker
+                .exec_if_visible(ctid, |_, _, _| ())
+                .is_some()
+        },
+    );
+
+    TableIterator::new(
+        top.into_iter()
+            .skip(offset as usize)
+            .map(|(scored, _)| {
+                let mut ctid = pg_sys::ItemPointerData::default();
+                u64_to_item_pointer(scored.ctid, &mut ctid);
+                (ctid, scored.bm25, total as i64)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The ctids and scores of the top `limit` rows matching `query`, most relevant first, straight
+/// from the index.  Nothing is read from the table, not even to check that a row is visible, so
+/// rows that have since been deleted but not vacuumed away can be among them, and it's up to
+/// whatever fetches their columns to check them
+#[pg_extern]
+pub fn search_tids(
+    indexrelid: pg_sys::Oid,
+    query: SearchQueryInput,
+    limit: i64,
+) -> TableIterator<'static, (name!(ctid, pg_sys::ItemPointerData), name!(score, f32))> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+    if limit < 0 {
+        panic!("search_tids limit must not be negative, but got {limit}");
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let search_reader = match query.as_of_generation() {
+        Some(generation) => search_index.get_reader_as_of(generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+    let tantivy_query = search_index.query(&query, &search_reader);
+
+    TableIterator::new(
+        search_reader
+            .search_top_n(
+                SearchIndex::executor(),
+                tantivy_query.as_ref(),
+                None,
+                &[],
+                None,
+                None,
+                SortDirection::Desc,
+                false,
+                gucs::score_tiebreak(),
+                limit as usize,
+            )
+            .map(|(scored, _)| {
+                let mut ctid = pg_sys::ItemPointerData::default();
+                u64_to_item_pointer(scored.ctid, &mut ctid);
+                (ctid, scored.bm25)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// How the scores of the visible rows matching `query` are distributed: the range from the lowest
+/// score to the highest is split into `buckets` of equal width, and each row holds how many
+/// matches score within one of them.  A score on the boundary of two buckets counts towards the
+/// higher one, and the highest score towards the last bucket
+#[pg_extern]
+pub fn score_histogram(
+    indexrelid: pg_sys::Oid,
+    query: SearchQueryInput,
+    buckets: default!(i32, 10),
+) -> TableIterator<
+    'static,
+    (
+        name!(bucket, i32),
+        name!(lower, f32),
+        name!(upper, f32),
+        name!(count, i64),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+    if buckets < 1 {
+        panic!("score_histogram buckets must be at least 1, but got {buckets}");
+    }
+    if let Some(max_buckets) = gucs::agg_max_buckets().filter(|&max| buckets as usize > max) {
+        panic!("{}", too_many_buckets(max_buckets));
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let search_reader = match query.as_of_generation() {
+        Some(generation) => search_index.get_reader_as_of(generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+    let tantivy_query = search_index.query(&query, &search_reader);
+
+    let heaprel = index
+        .heap_relation()
+        .expect("a bm25 index should have a heap relation");
+    let mut visibility_checker = unsafe {
+        VisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), pg_sys::GetActiveSnapshot())
+    };
+    let scores = search_reader
+        .search_via_channel(true, None, SearchIndex::executor(), tantivy_query.as_ref())
+        .filter(|(scored, _)| {
+            visibility_checker
+                .exec_if_visible(scored.ctid, |_, _, _| ())
+                .is_some()
+        })
+        .map(|(scored, _)| scored.bm25)
+        .collect::<Vec<_>>();
+    if scores.is_empty() {
+        return TableIterator::new(vec![]);
+    }
+
+    let lowest = scores.iter().copied().fold(f32::INFINITY, f32::min);
+    let highest = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let width = (highest - lowest) / buckets as f32;
+    let mut counts = vec![0i64; buckets as usize];
+    for score in scores {
+        // when every match scores the same, they all fall in the first bucket
+        let bucket = if width > 0.0 {
+            ((score - lowest) / width) as usize
+        } else {
+            0
+        };
+        counts[bucket.min(buckets as usize - 1)] += 1;
+    }
+
+    TableIterator::new(
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let lower = lowest + width * i as f32;
+                let upper = if i + 1 == buckets as usize {
+                    highest
+                } else {
+                    lowest + width * (i + 1) as f32
+                };
+                (i as i32 + 1, lower, upper, count)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The most buckets [`numeric_histogram`] returns, whatever `paradedb.agg_max_buckets` allows, as
+/// every one of them between the lowest and highest values is held in memory
+const MAX_NUMERIC_HISTOGRAM_BUCKETS: u64 = 10_000_000;
+
+/// How the values of the numeric fast field `field` are distributed among the visible rows
+/// matching `query`, in buckets of `interval` width aligned to `offset`, like price ranges for a
+/// facet.  Each row holds the bounds of a bucket, including its lower and excluding its upper, and
+/// how many matches have a value within it.  Every bucket from the lowest value's to the highest
+/// value's is returned, empty or not, and rows without a value aren't counted
+#[pg_extern]
+pub fn numeric_histogram(
+    indexrelid: pg_sys::Oid,
+    query: SearchQueryInput,
+    field: String,
+    interval: f64,
+    offset: default!(f64, 0.0),
+) -> TableIterator<'static, (name!(lower, f64), name!(upper, f64), name!(count, i64))> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+    if !interval.is_finite() || interval <= 0.0 {
+        panic!("numeric_histogram interval must be greater than zero, but got {interval}");
+    }
+    if !offset.is_finite() {
+        panic!("numeric_histogram offset must be finite, but got {offset}");
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let schema = &search_index.schema.schema;
+    let is_numeric_fast = schema.get_field(&field).is_ok_and(|numeric_field| {
+        let entry = schema.get_field_entry(numeric_field);
+        entry.is_fast()
+            && matches!(
+                entry.field_type(),
+                FieldType::I64(_) | FieldType::U64(_) | FieldType::F64(_)
+            )
+    });
+    if !is_numeric_fast {
+        panic!("numeric_histogram field `{field}` is not a numeric fast field of the index");
+    }
+
+    let search_reader = match query.as_of_generation() {
+        Some(generation) => search_index.get_reader_as_of(generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+    let tantivy_query = search_index.query(&query, &search_reader);
+
+    let heaprel = index
+        .heap_relation()
+        .expect("a bm25 index should have a heap relation");
+    let mut visibility_checker = unsafe {
+        VisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), pg_sys::GetActiveSnapshot())
+    };
+
+    // buckets are numbered by how many intervals their lower bound is from the offset
+    let max_buckets = gucs::agg_max_buckets();
+    let mut counts = BTreeMap::<i64, i64>::new();
+    for (scored, doc_address) in search_reader.search_via_channel(
+        false,
+        None,
+        SearchIndex::executor(),
+        tantivy_query.as_ref(),
+    ) {
+        if visibility_checker
+            .exec_if_visible(scored.ctid, |_, _, _| ())
+            .is_none()
+        {
+            continue;
+        }
+        let Some(value) = search_reader.numeric_fast_field_value(&field, doc_address) else {
+            continue;
+        };
+        let bucket = ((value - offset) / interval).floor() as i64;
+        *counts.entry(bucket).or_default() += 1;
+        if let Some(max_buckets) = max_buckets.filter(|&max| counts.len() > max) {
+            panic!("{}", too_many_buckets(max_buckets));
+        }
+    }
+
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return TableIterator::new(vec![]);
+    };
+    // every bucket in between is returned, however sparse the values are, so even without
+    // paradedb.agg_max_buckets there can only be so many of them
+    let span = last.checked_sub(first).map(|span| span as u64);
+    if let Some(max_buckets) =
+        max_buckets.filter(|&max| span.map_or(true, |span| span >= max as u64))
+    {
+        panic!("{}", too_many_buckets(max_buckets));
+    }
+    if span.map_or(true, |span| span >= MAX_NUMERIC_HISTOGRAM_BUCKETS) {
+        panic!(
+            "numeric_histogram would return more than {MAX_NUMERIC_HISTOGRAM_BUCKETS} buckets, so its interval must be wider"
+        );
+    }
+    TableIterator::new(
+        (first..=last)
+            .map(|bucket| {
+                let lower = offset + bucket as f64 * interval;
+                let count = counts.get(&bucket).copied().unwrap_or(0);
+                (lower, lower + interval, count)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// The `limit` most relevant visible rows matching each of the `queries`, all searched with the
+/// same index reader.  `query_idx` is the position of the query in `queries`, counting from 1,
+/// and `rank` the position of the row among that query's, counting from 1 as well
+#[pg_extern]
+pub fn search_many(
+    indexrelid: pg_sys::Oid,
+    queries: Vec<SearchQueryInput>,
+    limit: i64,
+) -> TableIterator<
+    'static,
+    (
+        name!(query_idx, i32),
+        name!(ctid, pg_sys::ItemPointerData),
+        name!(score, f32),
+        name!(rank, i64),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+    if limit < 0 {
+        panic!("search_many limit must not be negative, but got {limit}");
+    }
+
+    // every query is searched with the one reader, so they must agree on the generation it's at
+    let mut generations = queries
+        .iter()
+        .filter_map(SearchQueryInput::as_of_generation)
+        .collect::<Vec<_>>();
+    generations.sort();
+    generations.dedup();
+    if generations.len() > 1 {
+        panic!("search_many queries can't be pinned to different index generations");
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let search_reader = match generations.first() {
+        Some(generation) => search_index.get_reader_as_of(*generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+
+    let heaprel = index
+        .heap_relation()
+        .expect("a bm25 index should have a heap relation");
+    let mut visibility_checker = unsafe {
+        VisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), pg_sys::GetActiveSnapshot())
+    };
+
+    let mut rows = Vec::new();
+    for (query_idx, query) in queries.iter().enumerate() {
+        let tantivy_query = search_index.query(query, &search_reader);
+        let (top, _) = search_reader.search_top_n_with_total(
+            SearchIndex::executor(),
+            tantivy_query.as_ref(),
+            limit as usize,
+            |ctid| {
+                visibility_checker
+                    .exec_if_visible(ctid, |_, _, _| ())
+                    .is_some()
+            },
+        );
+        rows.extend(top.into_iter().enumerate().map(|(rank, (scored, _))| {
+            let mut ctid = pg_sys::ItemPointerData::default();
+            u64_to_item_pointer(scored.ctid, &mut ctid);
+            (query_idx as i32 + 1, ctid, scored.bm25, rank as i64 + 1)
+        }));
+    }
+    TableIterator::new(rows)
+}
+
+/// Whether any visible row matches `query`, which makes it cheap to annotate the rows of another
+/// relation with whether they have a match, as in a `LATERAL` join.  The search stops at the
+/// first visible match it finds, without scoring or sorting anything
+#[pg_extern]
+pub fn matches(indexrelid: pg_sys::Oid, query: SearchQueryInput) -> bool {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let search_reader = match query.as_of_generation() {
+        Some(generation) => search_index.get_reader_as_of(generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+
+    let heaprel = index
+        .heap_relation()
+        .expect("a bm25 index should have a heap relation");
+    let mut visibility_checker = unsafe {
+        VisibilityChecker::with_rel_and_snap(heaprel.as_ptr(), pg_sys::GetActiveSnapshot())
+    };
+
+    let tantivy_query = search_index.query(&query, &search_reader);
+    search_reader
+        .has_match(tantivy_query.as_ref(), |ctid| {
+            visibility_checker
+                .exec_if_visible(ctid, |_, _, _| ())
+                .is_some()
+        })
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The score each of the `queries` gives each of the rows with the `ctids`, all in one pass
+/// over the index.  `query_idx` is the position of the query in `queries`, counting from 1, and
+/// there's no row for a query and ctid it doesn't match
+#[pg_extern]
+pub fn score_matrix(
+    indexrelid: pg_sys::Oid,
+    queries: Vec<SearchQueryInput>,
+    ctids: Vec<pg_sys::ItemPointerData>,
+) -> TableIterator<
+    'static,
+    (
+        name!(query_idx, i32),
+        name!(ctid, pg_sys::ItemPointerData),
+        name!(score, f32),
+    ),
+> {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let index = unsafe { PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _) };
+    if !index.is_index() {
+        panic!("relation `{}` is not an index", index.name());
+    }
+
+    let search_index = open_search_index(&index).expect("should be able to open search index");
+    let search_reader = search_index.get_reader().unwrap_or_else(|e| panic!("{e}"));
+    let queries = queries
+        .iter()
+        .map(|query| search_index.query(query, &search_reader))
+        .collect::<Vec<_>>();
+    let ctids = ctids
+        .into_iter()
+        .map(item_pointer_to_u64)
+        .collect::<Vec<_>>();
+
+    let scores = search_reader
+        .score_matrix(&queries, &ctids)
+        .unwrap_or_else(|e| panic!("{e}"));
+    TableIterator::new(
+        scores
+            .into_iter()
+            .map(|(query_idx, ctid, score)| {
+                let mut ipd = pg_sys::ItemPointerData::default();
+                u64_to_item_pointer(ctid, &mut ipd);
+                (query_idx as i32 + 1, ipd, score)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
 #[pg_extern(immutable, parallel_safe)]
 pub fn all() -> SearchQueryInput {
     SearchQueryInput::All
@@ -138,11 +1017,21 @@ pub fn boolean_arrays(
     must: default!(Vec<SearchQueryInput>, "ARRAY[]::searchqueryinput[]"),
     should: default!(Vec<SearchQueryInput>, "ARRAY[]::searchqueryinput[]"),
     must_not: default!(Vec<SearchQueryInput>, "ARRAY[]::searchqueryinput[]"),
+    minimum_should_match: default!(Option<String>, "NULL"),
+    filter: default!(Vec<SearchQueryInput>, "ARRAY[]::searchqueryinput[]"),
 ) -> SearchQueryInput {
+    let minimum_should_match = minimum_should_match.map(|minimum| {
+        minimum
+            .parse::<MinimumShouldMatch>()
+            .unwrap_or_else(|e| panic!("{e}"))
+    });
+
     SearchQueryInput::Boolean {
         must,
         should,
         must_not,
+        filter,
+        minimum_should_match,
     }
 }
 
@@ -151,11 +1040,15 @@ pub fn boolean_singles(
     must: default!(Option<SearchQueryInput>, "NULL"),
     should: default!(Option<SearchQueryInput>, "NULL"),
     must_not: default!(Option<SearchQueryInput>, "NULL"),
+    minimum_should_match: default!(Option<String>, "NULL"),
+    filter: default!(Option<SearchQueryInput>, "NULL"),
 ) -> SearchQueryInput {
     boolean_arrays(
         must.map_or(vec![], |v| vec![v]),
         should.map_or(vec![], |v| vec![v]),
         must_not.map_or(vec![], |v| vec![v]),
+        minimum_should_match,
+        filter.map_or(vec![], |v| vec![v]),
     )
 }
 
@@ -167,6 +1060,44 @@ pub fn boost(boost: f32, query: SearchQueryInput) -> SearchQueryInput {
     }
 }
 
+#[pg_extern(immutable, parallel_safe)]
+pub fn boosting(
+    positive: SearchQueryInput,
+    negative: SearchQueryInput,
+    negative_boost: f32,
+) -> SearchQueryInput {
+    if negative_boost.is_nan() || negative_boost < 0.0 {
+        panic!("boosting negative_boost must be zero or greater, but got {negative_boost}");
+    }
+
+    SearchQueryInput::Boosting {
+        positive: Box::new(positive),
+        negative: Box::new(negative),
+        negative_boost,
+    }
+}
+
+/// Multiply the score of each document `query` matches by the boost of its category, its value
+/// of the text fast field `field`, given by `boosts` as a JSON object of categories to boosts,
+/// like `{"premium": 2.0}`
+#[pg_extern(immutable, parallel_safe)]
+pub fn category_boost(query: SearchQueryInput, field: String, boosts: JsonB) -> SearchQueryInput {
+    let boosts: BTreeMap<String, f32> = serde_json::from_value(boosts.0).unwrap_or_else(|_| {
+        panic!("category_boost boosts must be a JSON object of categories to numeric boosts")
+    });
+    for (category, boost) in &boosts {
+        if boost.is_nan() || *boost < 0.0 {
+            panic!("category_boost boosts must be zero or greater, but '{category}' has {boost}");
+        }
+    }
+
+    SearchQueryInput::CategoryBoost {
+        query: Box::new(query),
+        field,
+        boosts,
+    }
+}
+
 #[pg_extern(immutable, parallel_safe)]
 pub fn const_score(score: f32, query: SearchQueryInput) -> SearchQueryInput {
     SearchQueryInput::ConstScore {
@@ -198,6 +1129,23 @@ pub fn exists(field: FieldName) -> SearchQueryInput {
     }
 }
 
+/// Add `factor` to the score of each document with a value in the fast field `field`.  As a
+/// `should` clause of a boolean query, this ranks the documents that have the field higher than
+/// those that don't, without leaving any of them out
+#[pg_extern(immutable, parallel_safe)]
+pub fn exists_boost(field: FieldName, factor: f32) -> SearchQueryInput {
+    if !factor.is_finite() || factor < 0.0 {
+        panic!("exists_boost factor must be zero or greater, but got {factor}");
+    }
+
+    SearchQueryInput::ConstScore {
+        query: Box::new(SearchQueryInput::Exists {
+            field: field.into_inner(),
+        }),
+        score: factor,
+    }
+}
+
 // Not clear on whether this query makes sense to support, as only our "key_field" is a fast
 // field... and the user can just use SQL to select a range. We'll keep the implementation here
 // for now, but we should remove when we decide definitively that we don't need this.
@@ -268,6 +1216,26 @@ pub fn fuzzy_phrase(
     }
 }
 
+/// Match the parents of the documents that match `query`.  Each child holds its parent's key in
+/// the fast field `parent_field`
+#[pg_extern(immutable, parallel_safe)]
+pub fn has_child(parent_field: FieldName, query: SearchQueryInput) -> SearchQueryInput {
+    SearchQueryInput::HasChild {
+        parent_field: parent_field.into_inner(),
+        query: Box::new(query),
+    }
+}
+
+/// Match the children of the documents that match `query`.  Each child holds its parent's key in
+/// the fast field `parent_field`
+#[pg_extern(immutable, parallel_safe)]
+pub fn has_parent(parent_field: FieldName, query: SearchQueryInput) -> SearchQueryInput {
+    SearchQueryInput::HasParent {
+        parent_field: parent_field.into_inner(),
+        query: Box::new(query),
+    }
+}
+
 #[pg_extern(name = "more_like_this", immutable, parallel_safe)]
 pub fn more_like_this_empty() -> SearchQueryInput {
     panic!("more_like_this must be called with either with_document_id or with_document_fields");
@@ -352,6 +1320,13 @@ pub fn parse(
     }
 }
 
+/// Deserialize a query from its JSON representation, the one `EXPLAIN` shows as the "Tantivy
+/// Query" of a custom scan
+#[pg_extern(immutable, parallel_safe)]
+pub fn raw_query(query: &str) -> SearchQueryInput {
+    serde_json::from_str(query).unwrap_or_else(|e| panic!("invalid raw query `{query}`: {e}"))
+}
+
 #[pg_extern(immutable, parallel_safe)]
 pub fn parse_with_field(
     field: FieldName,
@@ -382,6 +1357,11 @@ pub fn phrase(
     }
 }
 
+#[pg_extern(immutable, parallel_safe)]
+pub fn cross_field_phrase(fields: Vec<String>, phrase: String) -> SearchQueryInput {
+    SearchQueryInput::CrossFieldPhrase { fields, phrase }
+}
+
 #[pg_extern(immutable, parallel_safe)]
 pub fn phrase_prefix(
     field: FieldName,
@@ -397,6 +1377,140 @@ pub fn phrase_prefix(
     }
 }
 
+#[pg_extern(name = "phrase_prefix", immutable, parallel_safe)]
+pub fn phrase_prefix_text(
+    field: FieldName,
+    value: String,
+    max_expansion: default!(Option<i32>, "NULL"),
+) -> SearchQueryInput {
+    let (field, path) = split_field_and_path(&field);
+    SearchQueryInput::PhrasePrefixText {
+        field,
+        value,
+        max_expansions: max_expansion.map(|n| n as u32),
+        path,
+    }
+}
+
+#[pg_extern(immutable, parallel_safe)]
+pub fn proximity(
+    field: FieldName,
+    value: String,
+    slop: default!(Option<i32>, "NULL"),
+    boost: default!(Option<f32>, "NULL"),
+) -> SearchQueryInput {
+    if let Some(slop) = slop {
+        if slop < 0 {
+            panic!("proximity slop must be zero or greater, but got {slop}");
+        }
+    }
+    if let Some(boost) = boost {
+        if boost.is_nan() || boost < 0.0 {
+            panic!("proximity boost must be zero or greater, but got {boost}");
+        }
+    }
+
+    let (field, path) = split_field_and_path(&field);
+    SearchQueryInput::Proximity {
+        field,
+        value,
+        slop: slop.map(|n| n as u32),
+        boost,
+        path,
+    }
+}
+
+/// The phrase `phrases` make up, or any of its terms on their own.  A document that has the
+/// phrase scores highest, and each term a document has adds to its score by its boost in
+/// `term_boosts`, or by `term_boost` if there's no list of boosts
+#[pg_extern(immutable, parallel_safe)]
+pub fn phrase_with_terms(
+    field: FieldName,
+    phrases: Vec<String>,
+    slop: default!(Option<i32>, "NULL"),
+    term_boost: default!(Option<f32>, "NULL"),
+    term_boosts: default!(Option<Vec<f32>>, "NULL"),
+) -> SearchQueryInput {
+    if let Some(slop) = slop {
+        if slop < 0 {
+            panic!("phrase_with_terms slop must be zero or greater, but got {slop}");
+        }
+    }
+    for boost in term_boost.iter().chain(term_boosts.iter().flatten()) {
+        if boost.is_nan() || *boost < 0.0 {
+            panic!("phrase_with_terms boosts must be zero or greater, but got {boost}");
+        }
+    }
+    if let Some(term_boosts) = &term_boosts {
+        if term_boosts.len() != phrases.len() {
+            panic!(
+                "phrase_with_terms needs a boost for each of its {} terms, but got {}",
+                phrases.len(),
+                term_boosts.len()
+            );
+        }
+    }
+
+    let (field, path) = split_field_and_path(&field);
+    SearchQueryInput::PhraseWithTerms {
+        field,
+        phrases,
+        slop: slop.map(|n| n as u32),
+        term_boost,
+        term_boosts,
+        path,
+    }
+}
+
+/// `value`, or any of the synonyms `synonym_table` lists for it.  Each of its rows maps the text
+/// in its `term` column to a synonym in its `synonym` column
+#[pg_extern(stable, parallel_safe)]
+pub fn synonyms(field: FieldName, value: String, synonym_table: pg_sys::Oid) -> SearchQueryInput {
+    // # Safety
+    //
+    // We lock the relation ourselves, and `PgRelation::with_lock` raises an ERROR if the
+    // relation doesn't exist.
+    let table = unsafe { PgRelation::with_lock(synonym_table, pg_sys::AccessShareLock as _) };
+    let query = format!(
+        "SELECT DISTINCT synonym::text FROM {}.{} WHERE term = $1 AND synonym IS NOT NULL",
+        spi::quote_identifier(table.namespace()),
+        spi::quote_identifier(table.name())
+    );
+    let args = vec![(PgBuiltInOids::TEXTOID.oid(), value.clone().into_datum())];
+    let synonyms = Spi::connect(|client| {
+        client
+            .select(&query, None, Some(args))?
+            .filter_map(|row| row.get::<String>(1).transpose())
+            .collect::<spi::Result<Vec<_>>>()
+    })
+    .unwrap_or_else(|e| panic!("{e}"));
+
+    let (field, path) = split_field_and_path(&field);
+    SearchQueryInput::Synonyms {
+        field,
+        value,
+        synonyms,
+        path,
+    }
+}
+
+/// `value`, analyzed the way `field` is, scoring documents `boost` times higher if they also match
+/// it analyzed the way `cased_field` is, which should be a field that keeps its case
+#[pg_extern(immutable, parallel_safe)]
+pub fn exact_case(
+    field: FieldName,
+    value: String,
+    cased_field: FieldName,
+    boost: default!(f32, 2.0),
+) -> SearchQueryInput {
+    SearchQueryInput::ExactCase {
+        field: field.into_inner(),
+        cased_field: cased_field.into_inner(),
+        value,
+        boost,
+    }
+}
+
 #[pg_extern(name = "range", immutable, parallel_safe)]
 pub fn range_i32(field: FieldName, range: Range<i32>) -> SearchQueryInput {
     let (field, path) = split_field_and_path(&field);
@@ -569,6 +1683,116 @@ pub fn regex(field: FieldName, pattern: String) -> SearchQueryInput {
     }
 }
 
+/// Pin `query` to the index as it was at generation `as_of`, so that writes made since then
+/// don't change its results
+#[pg_extern(immutable, parallel_safe)]
+pub fn snapshot(query: SearchQueryInput, as_of: i64) -> SearchQueryInput {
+    if as_of < 0 {
+        panic!("snapshot as_of must be a valid index generation, but got {as_of}");
+    }
+
+    SearchQueryInput::AsOf {
+        generation: as_of as u64,
+        query: Box::new(query),
+    }
+}
+
+/// Return only the highest-scoring of the documents `query` matches for each value of the fast
+/// field `collapse`
+#[pg_extern(immutable, parallel_safe)]
+pub fn collapse(query: SearchQueryInput, collapse: String) -> SearchQueryInput {
+    SearchQueryInput::Collapse {
+        field: collapse,
+        query: Box::new(query),
+    }
+}
+
+/// Return only the first of the documents `query` matches for each value of the fast field
+/// `dedup_by`, which is the highest-scoring one when they're returned by score
+#[pg_extern(immutable, parallel_safe)]
+pub fn dedup(query: SearchQueryInput, dedup_by: String) -> SearchQueryInput {
+    SearchQueryInput::Dedup {
+        field: dedup_by,
+        query: Box::new(query),
+    }
+}
+
+/// The documents `query` matches, other than those of the rows whose ctids are in `exclude`, such
+/// as the ones already shown
+#[pg_extern(immutable, parallel_safe)]
+pub fn exclude(query: SearchQueryInput, exclude: Vec<pg_sys::ItemPointerData>) -> SearchQueryInput {
+    SearchQueryInput::Exclude {
+        query: Box::new(query),
+        ctids: exclude.into_iter().map(item_pointer_to_u64).collect(),
+    }
+}
+
+/// Score the documents `query` matches with the `similarity` model: `bm25`, whose `k1` and `b`
+/// parameters can be tuned, `tfidf`, or `boolean`
+#[pg_extern(immutable, parallel_safe)]
+pub fn similarity(
+    query: SearchQueryInput,
+    similarity: String,
+    k1: default!(Option<f32>, "NULL"),
+    b: default!(Option<f32>, "NULL"),
+) -> SearchQueryInput {
+    let similarity = match similarity.parse::<Similarity>() {
+        Ok(Similarity::Bm25 { .. }) => Similarity::Bm25 {
+            k1: k1.unwrap_or(Similarity::DEFAULT_K1),
+            b: b.unwrap_or(Similarity::DEFAULT_B),
+        },
+        Ok(_) if k1.is_some() || b.is_some() => {
+            panic!("k1 and b can only be set for the 'bm25' similarity")
+        }
+        Ok(similarity) => similarity,
+        Err(e) => panic!("{e}"),
+    };
+
+    if let Similarity::Bm25 { k1, b } = similarity {
+        if k1 < 0.0 || !(0.0..=1.0).contains(&b) {
+            panic!("bm25 requires k1 >= 0 and b between 0 and 1, but got k1={k1} and b={b}");
+        }
+    }
+
+    SearchQueryInput::Similarity {
+        query: Box::new(query),
+        similarity,
+    }
+}
+
+/// Score every document `exact` matches above every document only `inexact` matches, such as a
+/// term query above a fuzzy one.  Within each tier, if a `recency_field` is given, scores halve
+/// every `half_life` that a document's value of that datetime fast field is older than now
+#[pg_extern(immutable, parallel_safe)]
+pub fn tiered(
+    exact: SearchQueryInput,
+    inexact: SearchQueryInput,
+    recency_field: default!(Option<String>, "NULL"),
+    half_life: default!(Option<Interval>, "NULL"),
+) -> SearchQueryInput {
+    // months are counted as 30 days, like Postgres does when it compares intervals
+    let half_life = half_life.map(|half_life| {
+        (half_life.months() as f64 * 30.0 + half_life.days() as f64) * 86_400.0
+            + half_life.micros() as f64 / 1_000_000.0
+    });
+    match (&recency_field, half_life) {
+        (Some(_), Some(half_life)) if half_life <= 0.0 => {
+            panic!("tiered half_life must be greater than zero, but got {half_life} seconds")
+        }
+        (Some(_), None) | (None, Some(_)) => {
+            panic!("tiered recency_field and half_life must be given together")
+        }
+        _ => {}
+    }
+
+    SearchQueryInput::Tiered {
+        exact: Box::new(exact),
+        inexact: Box::new(inexact),
+        recency_field,
+        half_life,
+    }
+}
+
 macro_rules! term_fn {
     ($func_name:ident, $value_type:ty) => {
         #[pg_extern(name = "term", immutable, parallel_safe)]
@@ -833,6 +2057,98 @@ pub fn term_set(
     SearchQueryInput::TermSet { terms }
 }
 
+/// Match `value`, analyzed by an analyzer chain put together for this query rather than the way
+/// `field` was indexed, such as `'{"tokenizer": {"type": "whitespace"}, "filters": ["lowercase",
+/// "ascii_folding"]}'`.  A document must contain any of the terms it analyzes into, or with
+/// `conjunction_mode`, all of them.
+#[pg_extern(immutable, parallel_safe)]
+pub fn analyzed(
+    field: FieldName,
+    value: String,
+    analyzer: JsonB,
+    conjunction_mode: default!(Option<bool>, "NULL"),
+) -> SearchQueryInput {
+    if let Err(e) = tokenizers::chain::AnalyzerChain::from_json_value(&analyzer.0) {
+        panic!("invalid analyzer chain: {e}");
+    }
+
+    let (field, path) = split_field_and_path(&field);
+    SearchQueryInput::Analyzed {
+        field,
+        value,
+        analyzer: analyzer.0,
+        conjunction_mode,
+        path,
+    }
+}
+
+/// Match `value` stemmed the way words of `language` are, such as `'fr'` or `'French'`, no matter
+/// how the field is analyzed.  A document must contain any of the stemmed terms, or with
+/// `conjunction_mode`, all of them.
+#[pg_extern(immutable, parallel_safe)]
+pub fn stemmed(
+    field: FieldName,
+    value: String,
+    language: String,
+    conjunction_mode: default!(Option<bool>, "NULL"),
+) -> SearchQueryInput {
+    if tokenizers::manager::language_from_str(&language).is_none() {
+        panic!("invalid language `{language}`: expected a code like 'fr' or a name like 'French'");
+    }
+
+    let (field, path) = split_field_and_path(&field);
+    SearchQueryInput::Stemmed {
+        field,
+        value,
+        language,
+        conjunction_mode,
+        path,
+    }
+}
+
+/// Match exact terms, as they're stored in the index, without running `terms` through the
+/// field's tokenizer first.  A document must contain any of the terms, or with
+/// `conjunction_mode`, all of them.
+#[pg_extern(immutable, parallel_safe)]
+pub fn terms_query(
+    field: FieldName,
+    terms: Vec<String>,
+    conjunction_mode: default!(Option<bool>, "NULL"),
+) -> SearchQueryInput {
+    if terms.is_empty() {
+        return SearchQueryInput::Empty;
+    }
+
+    let (field, path) = split_field_and_path(&field);
+    let terms = terms
+        .into_iter()
+        .map(|term| SearchQueryInput::Term {
+            field: Some(field.clone()),
+            value: OwnedValue::Str(term),
+            path: path.clone(),
+            is_datetime: false,
+        })
+        .collect::<Vec<_>>();
+
+    if conjunction_mode.unwrap_or(false) {
+        SearchQueryInput::Boolean {
+            must: terms,
+            should: Default::default(),
+            must_not: Default::default(),
+            filter: Default::default(),
+            minimum_should_match: None,
+        }
+    } else {
+        SearchQueryInput::Boolean {
+            must: Default::default(),
+            should: terms,
+            must_not: Default::default(),
+            filter: Default::default(),
+            minimum_should_match: None,
+        }
+    }
+}
+
 /// A type used whenever our builder functions require a fieldname.
 #[derive(
     Debug, Clone, Ord, Eq, PartialOrd, PartialEq, Hash, Serialize, Deserialize, PostgresType,
@@ -14,6 +14,7 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
+use crate::api::index::set_last_query_generation;
 use crate::api::operator::{estimate_selectivity, find_var_relation, ReturnedNodePointer};
 use crate::gucs::per_tuple_cost;
 use crate::index::SearchIndex;
@@ -55,7 +56,12 @@ pub fn search_with_query_input(
         })
         .expect("should be able to open search index");
 
-        let scan_state = search_index.get_reader().unwrap();
+        let scan_state = match query.as_of_generation() {
+            Some(generation) => search_index.get_reader_as_of(generation),
+            None => search_index.get_reader(),
+        }
+        .unwrap_or_else(|e| panic!("{e}"));
+        set_last_query_generation(scan_state.generation);
         let top_docs = scan_state.search_via_channel(
             query.contains_more_like_this(),
             Some(search_index.key_field_name()),
@@ -26,7 +26,7 @@ use pgrx::{pg_extern, pg_sys, AnyElement, FromDatum, Internal, PgList};
 
 /// This is the function behind the `@@@(anyelement, text)` operator. Since we transform those to
 /// use `@@@(anyelement, searchqueryinput`), this function won't be called in normal circumstances, but it
-/// could be called if the rhs of the @@@ is some kind of volatile value.
+/// could be called if the rhs of the @@@ isn't a literal and our custom scan didn't search for it.
 ///
 /// And in that case we just have to give up.
 #[pg_extern(immutable, parallel_safe, cost = 1000000000)]
@@ -53,21 +53,20 @@ fn text_support_request_simplify(arg: Internal) -> Option<ReturnedNodePointer> {
         let mut input_args = PgList::<pg_sys::Node>::from_pg((*(*srs).fcall).args);
 
         let var = nodecast!(Var, T_Var, input_args.get_ptr(0)?)?;
-        let query = nodecast!(Const, T_Const, input_args.get_ptr(1)?).map(|const_| {
-            // the field name comes from the lhs of the @@@ operator
-            let (_, query) = make_query_from_var_and_const((*srs).root, var, const_);
-            query
-        });
-
-        if query.is_none() {
-            panic!("when the left side of the `@@@` operator is a column name the right side must be a text literal");
-        }
+
+        // when the right side isn't a literal, like a column of the outer query in a correlated
+        // subquery, its value isn't known until the query runs.  The operator stays as it is, and
+        // our custom scan searches for each value it gets
+        let const_ = nodecast!(Const, T_Const, input_args.get_ptr(1)?)?;
+
+        // the field name comes from the lhs of the @@@ operator
+        let (_, query) = make_query_from_var_and_const((*srs).root, var, const_);
 
         Some(make_search_query_input_opexpr_node(
             srs,
             &mut input_args,
             var,
-            query,
+            Some(query),
             anyelement_text_opoid(),
             anyelement_text_procoid(),
         ))
@@ -345,8 +345,14 @@ fn index_info(
     // long we do not pass pg_sys::NoLock without any other locking mechanism of our own.
     let index = unsafe { PgRelation::with_lock(index.oid(), pg_sys::AccessShareLock as _) };
 
+    Ok(TableIterator::new(index_segments(&index)?))
+}
+
+/// The short id of each of the index's segments, how many bytes its files take up, and how many
+/// live and deleted documents it has.  The caller must hold a lock on the index
+pub fn index_segments(index: &PgRelation) -> Result<Vec<(String, i64, i64, i64)>> {
     // open the specified index
-    let index = open_search_index(&index).expect("should be able to open search index");
+    let index = open_search_index(index).expect("should be able to open search index");
     let directory = index.directory.clone();
     let data = index
         .underlying_index
@@ -378,7 +384,7 @@ fn index_info(
         })
         .collect::<Vec<_>>();
 
-    Ok(TableIterator::new(data))
+    Ok(data)
 }
 
 extension_sql!(
@@ -15,9 +15,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use crate::index::reader::ScoreTieBreak;
 use crate::index::Parallelism;
-use pgrx::{pg_sys, GucContext, GucFlags, GucRegistry, GucSetting};
+use pgrx::{pg_sys, GucContext, GucFlags, GucRegistry, GucSetting, PostgresGucEnum};
+use std::ffi::CStr;
+use std::fmt::{Display, Formatter};
 use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::time::Duration;
 
 /// Is our telemetry tracking enabled?  Default is `true`.
 static TELEMETRY: GucSetting<bool> = GucSetting::<bool>::new(true);
@@ -57,6 +62,135 @@ static STATEMENT_PARALLELISM: GucSetting<i32> = GucSetting::<i32>::new(8);
 /// thread.  So if there's 10 threads and this value is 100MB, then a total of 1GB will be allocated.
 static STATEMENT_MEMORY_BUDGET: GucSetting<i32> = GucSetting::<i32>::new(0);
 
+/// Caps the number of index segments a "ParadeDB Scan" will search.  Zero, the default, means
+/// all segments are searched.
+///
+/// This is intended for quick previews over very large indexes and is explicitly approximate:
+/// matching documents that live in segments beyond the cap are never returned.
+static MAX_SEGMENTS: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// Caps the number of parallel workers a parallel index scan of a BM25 index asks for, whatever
+/// `max_parallel_workers_per_gather` allows.  Zero, the default, means there's no cap.
+static MAX_SCAN_PARALLELISM: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// How should documents with equal scores be ordered when the custom scan handles an
+/// `ORDER BY paradedb.score(...) LIMIT n` query?  Either `ctid`, the default, which orders ties by
+/// ascending ctid, as it does those with equal values of an `ORDER BY` field, `none`, which
+/// leaves them in the order they're found, or `random(<seed>)` which orders ties by a seeded hash
+/// so that tied documents rotate fairly between seeds.
+static SCORE_TIEBREAK: GucSetting<Option<&'static CStr>> =
+    GucSetting::<Option<&'static CStr>>::new(Some(c"ctid"));
+
+/// What should happen to documents tied on score with the last one inside an
+/// `ORDER BY paradedb.score(...) LIMIT n`?  Either `cut`, the default, which returns exactly `n`
+/// documents, or `include`, which also returns every document tied with the last one.
+static LIMIT_TIES: GucSetting<LimitTies> = GucSetting::<LimitTies>::new(LimitTies::Cut);
+
+/// What `paradedb.score()` is for rows a "ParadeDB Scan" emits without having scored them, such
+/// as those of an `ORDER BY <field> LIMIT n` pushed down to the index.  Either `compute`, the
+/// default, which doesn't push such an `ORDER BY` down so that every row is scored, or `null`,
+/// `one`, or `error`.
+static UNSCORED_SCORE_VALUE: GucSetting<UnscoredScoreValue> =
+    GucSetting::<UnscoredScoreValue>::new(UnscoredScoreValue::Compute);
+
+/// What should happen to an `ORDER BY ... LIMIT n` a "ParadeDB Scan" can't sort by itself, like
+/// one over a column that isn't a fast field?  Either `sort`, the default, which still searches
+/// with the scan and leaves sorting its matches to Postgres, or `error`.
+static UNPUSHED_ORDER_BY: GucSetting<UnpushedOrderBy> =
+    GucSetting::<UnpushedOrderBy>::new(UnpushedOrderBy::Sort);
+
+/// Round scores to this many decimal places, so that they sort and compare identically across
+/// platforms whose floating-point arithmetic differs in the last few bits.  Negative, the
+/// default, leaves scores as they are.
+static SCORE_PRECISION: GucSetting<i32> = GucSetting::<i32>::new(-1);
+
+/// Only return documents whose scores are at or above this percentile of the scores of every
+/// document a "ParadeDB Scan" matches.  Zero, the default, returns them all.
+///
+/// This is approximate: the cutoff is computed over every document the index matches, including
+/// those of dead rows that haven't been vacuumed away yet, which are never returned.
+static SCORE_PERCENTILE: GucSetting<f64> = GucSetting::<f64>::new(0.0);
+
+/// Recheck this fraction of the rows a "ParadeDB Scan" returns against the pushed-down clauses
+/// they're meant to satisfy, to find where what's indexed has drifted from what's in the table.
+/// Zero, the default, rechecks none of them.
+static RECHECK_SAMPLE_RATE: GucSetting<f64> = GucSetting::<f64>::new(0.0);
+
+/// When debugging, emit every hit a "ParadeDB Scan" finds, along with its segment ordinal, rather
+/// than only the first hit for each ctid.  Default is `false`.
+static DEBUG_EMIT_DUPLICATES: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// Log every "ParadeDB Scan" that runs for at least this many milliseconds, along with its query
+/// and how many documents it matched.  Negative, the default, logs none of them.
+static LOG_MIN_DURATION_SCAN: GucSetting<i32> = GucSetting::<i32>::new(-1);
+
+/// Return the documents of a "ParadeDB Scan" top-N in reverse order, from the last document
+/// inside the limit back to the first, without changing which documents those are.  Default is
+/// `false`.
+static REVERSE_TOP_N: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// Raise an ERROR while planning a "ParadeDB Scan" that returns a column whose values the index
+/// doesn't hold, because it's neither the key field nor a fast field.  This only checks the
+/// returned columns: each matching row is still fetched from the heap to check its visibility.
+/// Default is `false`.
+static REQUIRE_COVERING: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// Caps the number of terms a query string can search for.  Zero, the default, means there's no
+/// cap.  What happens to a query string over the cap is up to `paradedb.max_query_terms_action`.
+static MAX_QUERY_TERMS: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// What should happen to a query string with more terms than `paradedb.max_query_terms`?  Either
+/// `error`, the default, which raises an ERROR, or `truncate`, which only searches as many of its
+/// clauses as fit under the cap, and raises a WARNING.
+static MAX_QUERY_TERMS_ACTION: GucSetting<MaxQueryTermsAction> =
+    GucSetting::<MaxQueryTermsAction>::new(MaxQueryTermsAction::Error);
+
+/// Caps the number of terms a fuzzy, regex, or phrase prefix query can expand into.  Zero, the
+/// default, means there's no cap.  What happens to a query over the cap is up to
+/// `paradedb.max_term_expansions_action`.
+static MAX_TERM_EXPANSIONS: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// What should happen to a query that expands into more terms than
+/// `paradedb.max_term_expansions`?  Either `error`, the default, which raises an ERROR, or
+/// `truncate`, which only searches the first of its terms, in term order, and raises a WARNING.
+static MAX_TERM_EXPANSIONS_ACTION: GucSetting<MaxTermExpansionsAction> =
+    GucSetting::<MaxTermExpansionsAction>::new(MaxTermExpansionsAction::Error);
+
+/// Score with document frequencies and field lengths that only count live documents, rather than
+/// also counting deleted documents that haven't been merged away yet, so that scores don't change
+/// as segments merge.  Default is `false`.
+static GLOBAL_IDF: GucSetting<bool> = GucSetting::<bool>::new(false);
+
+/// What a query whose text analyzes into no terms at all, like one of nothing but stopwords,
+/// matches.  Either `match_none`, the default, `match_all`, or `error`, which raises an ERROR.
+static EMPTY_QUERY_MODE: GucSetting<EmptyQueryMode> =
+    GucSetting::<EmptyQueryMode>::new(EmptyQueryMode::MatchNone);
+
+/// How a query string's clauses that are all required and search the same field, like those of
+/// `description:shoes AND description:running`, are searched.  Either `all`, the default, which
+/// requires every one of them to match the field, as the query string says, or `any`, which only
+/// requires one of them to.
+static SAME_FIELD_CLAUSES: GucSetting<SameFieldClauses> =
+    GucSetting::<SameFieldClauses>::new(SameFieldClauses::All);
+
+/// How many of the rows a ParadeDB Scan returns under `EXPLAIN ANALYZE` have their scores broken
+/// down in the plan.  Zero, the default, breaks none of them down.
+static EXPLAIN_SCORES: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// The largest `OFFSET` plus `LIMIT` a query over a BM25 index may ask for, since every row
+/// before the offset has to be found and sorted too.  Zero, the default, means there's no cap.
+static MAX_RESULT_WINDOW: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+/// The most buckets a facet, histogram or grouping over a BM25 index may track, beyond which it
+/// raises an ERROR rather than grow its memory without bound.  Zero means there's no cap.
+static AGG_MAX_BUCKETS: GucSetting<i32> = GucSetting::<i32>::new(65_536);
+
+/// What happens to query text with control characters in it, or with characters that aren't
+/// valid unicode.  Either `sanitize`, the default, which replaces each of them with a space before
+/// the text is analyzed, or `error`, which raises an ERROR that says where the first one is.
+static ON_INVALID_QUERY_TEXT: GucSetting<OnInvalidQueryText> =
+    GucSetting::<OnInvalidQueryText>::new(OnInvalidQueryText::Sanitize);
+
 pub fn init() {
     // Note that Postgres is very specific about the naming convention of variables.
     // They must be namespaced... we use 'paradedb.<variable>' below.
@@ -144,6 +278,244 @@ pub fn init() {
         GucFlags::UNIT_MB,
     );
 
+    GucRegistry::define_int_guc(
+        "paradedb.max_segments",
+        "The maximum number of index segments a ParadeDB Scan will search",
+        "Default is 0, meaning all segments.  Results are approximate when set, as matches in the remaining segments are not returned",
+        &MAX_SEGMENTS,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_string_guc(
+        "paradedb.score_tiebreak",
+        "How documents with equal scores or sort field values are ordered in a top-N ParadeDB Scan",
+        "Either 'ctid', the default, to order ties by ascending ctid, 'none' to leave them in the order they're found, or 'random(<seed>)' to order them by a hash seeded with <seed>",
+        &SCORE_TIEBREAK,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.limit_ties",
+        "How documents tied on score at the LIMIT of a top-N ParadeDB Scan are handled",
+        "Either 'cut', the default, to return exactly LIMIT documents, or 'include' to also return every document tied with the last one",
+        &LIMIT_TIES,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.unscored_score_value",
+        "The score of rows a ParadeDB Scan emits without scoring them",
+        "Either 'compute', the default, to score every row that projects its score, or 'null', 'one', or 'error' to let an ORDER BY over a field be pushed down and give its rows that score instead",
+        &UNSCORED_SCORE_VALUE,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.unpushed_order_by",
+        "What happens to an ORDER BY ... LIMIT a ParadeDB Scan can't sort by itself",
+        "Either 'sort', the default, to search with the ParadeDB Scan and let Postgres sort its matches, or 'error' to raise an ERROR",
+        &UNPUSHED_ORDER_BY,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.score_precision",
+        "The number of decimal places to round scores to",
+        "Default is -1, meaning scores are not rounded.  Rounded scores sort and compare identically across platforms",
+        &SCORE_PRECISION,
+        -1,
+        9,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_float_guc(
+        "paradedb.score_percentile",
+        "Only return documents scoring at or above this percentile of all matching documents",
+        "Default is 0, meaning all matching documents are returned.  For example, 90 returns the top 10% by score.  The cutoff counts the documents of dead rows until they're vacuumed away",
+        &SCORE_PERCENTILE,
+        0.0,
+        100.0,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_bool_guc(
+        "paradedb.debug_emit_duplicates",
+        "Emit every hit a ParadeDB Scan finds, even if its ctid was already emitted",
+        "Each hit is reported, with the ordinal of the segment it came from, as a NOTICE.  This is only meant for diagnosing duplicate documents",
+        &DEBUG_EMIT_DUPLICATES,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.log_min_duration_scan",
+        "Log each ParadeDB Scan that runs for at least this long, with its query",
+        "Default is -1, which logs no scans.  Zero logs every scan",
+        &LOG_MIN_DURATION_SCAN,
+        -1,
+        i32::MAX,
+        GucContext::Suset,
+        GucFlags::UNIT_MS,
+    );
+
+    GucRegistry::define_bool_guc(
+        "paradedb.reverse_top_n",
+        "Return the documents of a ParadeDB Scan's top-N in reverse order",
+        "The same documents are returned, but from the last one inside the LIMIT back to the first.  Queries with an OFFSET or WITH TIES aren't reversed",
+        &REVERSE_TOP_N,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_bool_guc(
+        "paradedb.require_covering",
+        "Raise an ERROR when a ParadeDB Scan returns a column the index doesn't hold",
+        "Only checks that every column a ParadeDB Scan returns is the index's key field or one of its fast fields; matching rows are still fetched from the heap to check their visibility",
+        &REQUIRE_COVERING,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.max_query_terms",
+        "The maximum number of terms a query string can search for",
+        "Default is 0, meaning there's no maximum.  paradedb.max_query_terms_action decides what happens to a query string with more terms",
+        &MAX_QUERY_TERMS,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.max_query_terms_action",
+        "What happens to a query string with more terms than paradedb.max_query_terms",
+        "Either 'error', the default, to raise an ERROR, or 'truncate' to only search as many of its clauses as fit, with a WARNING",
+        &MAX_QUERY_TERMS_ACTION,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.max_term_expansions",
+        "The maximum number of terms a fuzzy, regex, or phrase prefix query can expand into",
+        "Default is 0, meaning there's no maximum.  paradedb.max_term_expansions_action decides what happens to a query that expands into more terms",
+        &MAX_TERM_EXPANSIONS,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.max_term_expansions_action",
+        "What happens to a query that expands into more terms than paradedb.max_term_expansions",
+        "Either 'error', the default, to raise an ERROR, or 'truncate' to only search the first of its terms, with a WARNING",
+        &MAX_TERM_EXPANSIONS_ACTION,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_bool_guc(
+        "paradedb.global_idf",
+        "Score with statistics of the index's live documents, so that scores don't change as segments merge",
+        "Document frequencies and field lengths then leave out deleted documents that haven't been merged away yet, which costs reading the postings of each searched term in segments with deletes",
+        &GLOBAL_IDF,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_float_guc(
+        "paradedb.recheck_sample_rate",
+        "The fraction of the rows a ParadeDB Scan returns to recheck against their pushed-down clauses",
+        "Default is 0, meaning no rows are rechecked.  A WARNING is raised at the end of a scan if any sampled row doesn't satisfy a clause the index said it matched",
+        &RECHECK_SAMPLE_RATE,
+        0.0,
+        1.0,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.empty_query_mode",
+        "What a query whose text analyzes into no terms, like one of only stopwords, matches",
+        "Either 'match_none', the default, to match no documents, 'match_all' to match every document, or 'error' to raise an ERROR",
+        &EMPTY_QUERY_MODE,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.max_scan_parallelism",
+        "The maximum number of parallel workers a parallel scan of a BM25 index asks for",
+        "Default is 0, meaning there's no maximum other than max_parallel_workers_per_gather",
+        &MAX_SCAN_PARALLELISM,
+        0,
+        1024,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.same_field_clauses",
+        "How a query string's required clauses that search the same field are combined",
+        "Either 'all', the default, to require every one of them to match, like field:a AND field:b says, or 'any' to only require one of them to",
+        &SAME_FIELD_CLAUSES,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.explain_scores",
+        "The number of rows of a ParadeDB Scan whose scores EXPLAIN ANALYZE breaks down",
+        "Default is 0, meaning no scores are broken down.  The first rows the scan returns are shown with how the query scored them",
+        &EXPLAIN_SCORES,
+        0,
+        1000,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.max_result_window",
+        "The largest OFFSET plus LIMIT a query over a BM25 index may ask for",
+        "Default is 0, meaning there's no maximum.  Deeper pages should be fetched by filtering past the last row of the previous page",
+        &MAX_RESULT_WINDOW,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_int_guc(
+        "paradedb.agg_max_buckets",
+        "The most buckets a facet, histogram or grouping over a BM25 index may track",
+        "Default is 65536.  A query that needs more raises an ERROR, and 0 means there's no maximum",
+        &AGG_MAX_BUCKETS,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
+    GucRegistry::define_enum_guc(
+        "paradedb.on_invalid_query_text",
+        "What happens to query text with control characters or invalid unicode in it",
+        "Either 'sanitize', the default, to replace each such character with a space, or 'error' to raise an ERROR",
+        &ON_INVALID_QUERY_TEXT,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+
     pgrx::warning!("GUCS initialized");
 }
 
@@ -181,6 +553,275 @@ pub fn statement_memory_budget() -> usize {
     adjust_budget(STATEMENT_MEMORY_BUDGET.get(), statement_parallelism())
 }
 
+pub fn max_segments() -> Option<usize> {
+    match MAX_SEGMENTS.get() {
+        n if n <= 0 => None,
+        n => Some(n as usize),
+    }
+}
+
+pub fn max_scan_parallelism() -> Option<usize> {
+    match MAX_SCAN_PARALLELISM.get() {
+        n if n <= 0 => None,
+        n => Some(n as usize),
+    }
+}
+
+pub fn explain_scores() -> Option<usize> {
+    match EXPLAIN_SCORES.get() {
+        n if n <= 0 => None,
+        n => Some(n as usize),
+    }
+}
+
+pub fn max_result_window() -> Option<usize> {
+    match MAX_RESULT_WINDOW.get() {
+        n if n <= 0 => None,
+        n => Some(n as usize),
+    }
+}
+
+pub fn agg_max_buckets() -> Option<usize> {
+    match AGG_MAX_BUCKETS.get() {
+        n if n <= 0 => None,
+        n => Some(n as usize),
+    }
+}
+
+pub fn score_tiebreak() -> ScoreTieBreak {
+    SCORE_TIEBREAK
+        .get()
+        .map(|tiebreak| {
+            tiebreak
+                .to_str()
+                .expect("paradedb.score_tiebreak should be valid utf8")
+                .parse()
+                .unwrap_or_else(|e| panic!("{e}"))
+        })
+        .unwrap_or_default()
+}
+
+/// How documents tied on score at the boundary of a `LIMIT` are handled
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum LimitTies {
+    /// Return exactly as many documents as the `LIMIT` asks for
+    #[default]
+    #[name = c"cut"]
+    Cut,
+    /// Also return every document whose score ties with the last one inside the `LIMIT`
+    #[name = c"include"]
+    Include,
+}
+
+pub fn limit_ties() -> LimitTies {
+    LIMIT_TIES.get()
+}
+
+/// The score of rows that are emitted without being scored
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum UnscoredScoreValue {
+    /// Don't emit rows without scoring them if their score is projected
+    #[default]
+    #[name = c"compute"]
+    Compute,
+    /// Their score is `NULL`
+    #[name = c"null"]
+    Null,
+    /// Their score is `1.0`, like the score of a document matching a query without scoring
+    #[name = c"one"]
+    One,
+    /// Projecting their score is an error
+    #[name = c"error"]
+    Error,
+}
+
+impl Display for UnscoredScoreValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnscoredScoreValue::Compute => write!(f, "compute"),
+            UnscoredScoreValue::Null => write!(f, "null"),
+            UnscoredScoreValue::One => write!(f, "one"),
+            UnscoredScoreValue::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl FromStr for UnscoredScoreValue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "compute" => Ok(UnscoredScoreValue::Compute),
+            "null" => Ok(UnscoredScoreValue::Null),
+            "one" => Ok(UnscoredScoreValue::One),
+            "error" => Ok(UnscoredScoreValue::Error),
+            other => Err(format!(
+                "invalid paradedb.unscored_score_value `{other}`: expected `compute`, `null`, `one`, or `error`"
+            )),
+        }
+    }
+}
+
+pub fn unscored_score_value() -> UnscoredScoreValue {
+    UNSCORED_SCORE_VALUE.get()
+}
+
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum UnpushedOrderBy {
+    /// Search with the "ParadeDB Scan", and let Postgres sort its matches
+    #[default]
+    #[name = c"sort"]
+    Sort,
+    /// Raise an ERROR
+    #[name = c"error"]
+    Error,
+}
+
+pub fn unpushed_order_by() -> UnpushedOrderBy {
+    UNPUSHED_ORDER_BY.get()
+}
+
+pub fn score_precision() -> Option<u32> {
+    match SCORE_PRECISION.get() {
+        n if n < 0 => None,
+        n => Some(n as u32),
+    }
+}
+
+pub fn score_percentile() -> Option<f64> {
+    match SCORE_PERCENTILE.get() {
+        n if n <= 0.0 => None,
+        n => Some(n),
+    }
+}
+
+pub fn debug_emit_duplicates() -> bool {
+    DEBUG_EMIT_DUPLICATES.get()
+}
+
+pub fn log_min_duration_scan() -> Option<Duration> {
+    match LOG_MIN_DURATION_SCAN.get() {
+        n if n < 0 => None,
+        n => Some(Duration::from_millis(n as u64)),
+    }
+}
+
+pub fn reverse_top_n() -> bool {
+    REVERSE_TOP_N.get()
+}
+
+pub fn require_covering() -> bool {
+    REQUIRE_COVERING.get()
+}
+
+pub fn max_query_terms() -> Option<usize> {
+    match MAX_QUERY_TERMS.get() {
+        n if n <= 0 => None,
+        n => Some(n as usize),
+    }
+}
+
+/// What happens to a query string with more terms than `paradedb.max_query_terms`
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum MaxQueryTermsAction {
+    /// Raise an ERROR
+    #[default]
+    #[name = c"error"]
+    Error,
+    /// Only search as many of its clauses as fit, and raise a WARNING
+    #[name = c"truncate"]
+    Truncate,
+}
+
+pub fn max_query_terms_action() -> MaxQueryTermsAction {
+    MAX_QUERY_TERMS_ACTION.get()
+}
+
+pub fn max_term_expansions() -> Option<usize> {
+    match MAX_TERM_EXPANSIONS.get() {
+        n if n <= 0 => None,
+        n => Some(n as usize),
+    }
+}
+
+/// What happens to a query that expands into more terms than `paradedb.max_term_expansions`
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum MaxTermExpansionsAction {
+    /// Raise an ERROR
+    #[default]
+    #[name = c"error"]
+    Error,
+    /// Only search the first of its terms, and raise a WARNING
+    #[name = c"truncate"]
+    Truncate,
+}
+
+pub fn max_term_expansions_action() -> MaxTermExpansionsAction {
+    MAX_TERM_EXPANSIONS_ACTION.get()
+}
+
+pub fn global_idf() -> bool {
+    GLOBAL_IDF.get()
+}
+
+pub fn recheck_sample_rate() -> Option<f64> {
+    match RECHECK_SAMPLE_RATE.get() {
+        n if n <= 0.0 => None,
+        n => Some(n),
+    }
+}
+
+/// What a query whose text analyzes into no terms matches
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum EmptyQueryMode {
+    /// No documents
+    #[default]
+    #[name = c"match_none"]
+    MatchNone,
+    /// Every document
+    #[name = c"match_all"]
+    MatchAll,
+    /// Raise an ERROR
+    #[name = c"error"]
+    Error,
+}
+
+pub fn empty_query_mode() -> EmptyQueryMode {
+    EMPTY_QUERY_MODE.get()
+}
+
+/// How a query string's required clauses that search the same field are combined
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum SameFieldClauses {
+    /// Every one of them must match
+    #[default]
+    #[name = c"all"]
+    All,
+    /// Any one of them must match
+    #[name = c"any"]
+    Any,
+}
+
+pub fn same_field_clauses() -> SameFieldClauses {
+    SAME_FIELD_CLAUSES.get()
+}
+
+/// What happens to query text with control characters or invalid unicode in it
+#[derive(PostgresGucEnum, Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum OnInvalidQueryText {
+    /// Replace each such character with a space
+    #[default]
+    #[name = c"sanitize"]
+    Sanitize,
+    /// Raise an ERROR
+    #[name = c"error"]
+    Error,
+}
+
+pub fn on_invalid_query_text() -> OnInvalidQueryText {
+    ON_INVALID_QUERY_TEXT.get()
+}
+
 fn adjust_nthreads(nthreads: i32) -> NonZeroUsize {
     let nthreads = if nthreads <= 0 {
         std::thread::available_parallelism()
@@ -18,6 +18,8 @@
 pub mod directory;
 pub mod reader;
 pub mod search;
+pub mod sort_expression;
+pub mod statistics;
 pub mod writer;
 
 pub use directory::*;
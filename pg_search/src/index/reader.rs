@@ -16,28 +16,39 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use super::SearchIndex;
+use crate::gucs;
+use crate::index::sort_expression::SortExpression;
+use crate::index::statistics::ScoringStatistics;
 use crate::postgres::types::TantivyValue;
-use crate::query::SearchQueryInput;
+use crate::query::{QuantizedScoreQuery, SearchQueryInput};
 use crate::schema::{SearchFieldName, SearchIndexSchema};
 use anyhow::Result;
-use pgrx::pg_sys;
-use std::cmp::Ordering;
-use std::fmt::{Debug, Formatter};
+use pgrx::{check_for_interrupts, pg_sys};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 use std::sync::Arc;
 use tantivy::collector::{Collector, TopDocs};
-use tantivy::columnar::{ColumnValues, StrColumn};
+use tantivy::columnar::{Column, ColumnValues, StrColumn};
+use tantivy::directory::error::OpenReadError;
 use tantivy::fastfield::FastFieldReaders;
-use tantivy::query::QueryParser;
-use tantivy::schema::{FieldType, Value};
+use tantivy::postings::Postings;
+use tantivy::query::{BooleanQuery, EnableScoring, ExistsQuery, Occur, QueryParser};
+use tantivy::schema::{FieldType, IndexRecordOption, Value};
 use tantivy::{
-    query::Query, DocAddress, DocId, Order, Score, Searcher, SegmentOrdinal, TantivyDocument,
-    TantivyError,
+    query::Query, Directory, DocAddress, DocId, DocSet, Index, Order, Score, Searcher,
+    SegmentComponent, SegmentId, SegmentOrdinal, TantivyDocument, TantivyError, Term, TERMINATED,
 };
 use tantivy::{snippet::SnippetGenerator, Executor};
 use tracing::debug;
 
 const CACHE_NUM_BLOCKS: usize = 10;
 
+/// Reading a single byte from each page of a file is enough to fault it into the OS page cache
+const WARMUP_PAGE_SIZE: usize = 4096;
+
 /// Represents a matching document from a tantivy search.  Typically it is returned as an Iterator
 /// Item alongside the originating tantivy [`DocAddress`]
 #[derive(Clone)]
@@ -62,12 +73,149 @@ impl Debug for SearchIndexScore {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SortDirection {
     Asc,
     Desc,
 }
 
+/// One of the fields the documents are sorted by when sorting by several numeric or boolean fast
+/// fields, one after the other, each in its own direction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub field: String,
+    pub direction: SortDirection,
+    /// Whether the documents without a value come before those with one
+    pub nulls_first: bool,
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let direction = match self.direction {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        };
+        let nulls = if self.nulls_first { "first" } else { "last" };
+        write!(f, "{} {direction} nulls {nulls}", self.field)
+    }
+}
+
+/// How documents with equal scores, or equal values of the field they're sorted by, are ordered
+/// relative to each other when searching for the "top N" documents
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum ScoreTieBreak {
+    /// Ties are ordered by ascending ctid, whichever way the documents are sorted, so that the
+    /// same query always returns the same documents in the same order
+    #[default]
+    Ctid,
+
+    /// Ties are returned in whatever order tantivy finds them, which saves reading their ctids
+    None,
+
+    /// Ties are ordered by a hash of each document's ctid, seeded with the specified value.  The
+    /// same seed always produces the same order, and different seeds rotate through the tied
+    /// documents
+    Random(u64),
+}
+
+impl ScoreTieBreak {
+    /// The key that orders the document with `ctid` amongst those it ties with, when the top-N is
+    /// sorted in `sortdir`: the document with the greatest key comes first in descending order,
+    /// and the one with the smallest in ascending order
+    #[inline(always)]
+    pub(crate) fn key(&self, ctid: u64, sortdir: SortDirection) -> u64 {
+        match self {
+            ScoreTieBreak::Ctid => match sortdir {
+                SortDirection::Asc => ctid,
+                SortDirection::Desc => u64::MAX - ctid,
+            },
+            ScoreTieBreak::None => 0,
+            ScoreTieBreak::Random(seed) => {
+                // splitmix64, which is plenty good at scattering consecutive ctids
+                let mut z = seed.wrapping_add(ctid).wrapping_add(0x9E3779B97F4A7C15);
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+                z ^ (z >> 31)
+            }
+        }
+    }
+}
+
+impl Display for ScoreTieBreak {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScoreTieBreak::Ctid => write!(f, "ctid"),
+            ScoreTieBreak::None => write!(f, "none"),
+            ScoreTieBreak::Random(seed) => write!(f, "random({seed})"),
+        }
+    }
+}
+
+impl FromStr for ScoreTieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if s.is_empty() || s == "ctid" {
+            return Ok(ScoreTieBreak::Ctid);
+        }
+        if s == "none" {
+            return Ok(ScoreTieBreak::None);
+        }
+
+        s.strip_prefix("random(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .and_then(|seed| seed.trim().parse::<u64>().ok())
+            .map(ScoreTieBreak::Random)
+            .ok_or_else(|| {
+                format!(
+                    "invalid score tie-break `{s}`: expected `ctid`, `none`, or `random(<seed>)`"
+                )
+            })
+    }
+}
+
+/// Which of its values a document of a multivalued field is sorted by, such as when sorting by
+/// the highest price of each product
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SortReduction {
+    Min,
+    Max,
+}
+
+impl SortReduction {
+    fn reduce<T: PartialOrd>(&self, values: impl Iterator<Item = T>) -> Option<T> {
+        values.reduce(|reduced, value| match self {
+            SortReduction::Min if value < reduced => value,
+            SortReduction::Max if value > reduced => value,
+            _ => reduced,
+        })
+    }
+}
+
+impl Display for SortReduction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortReduction::Min => write!(f, "min"),
+            SortReduction::Max => write!(f, "max"),
+        }
+    }
+}
+
+impl FromStr for SortReduction {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "min" => Ok(SortReduction::Min),
+            "max" => Ok(SortReduction::Max),
+            other => Err(format!(
+                "invalid sort reduction `{other}`: expected `min` or `max`"
+            )),
+        }
+    }
+}
+
 /// An iterator of the different styles of search results we can return
 #[derive(Default)]
 pub enum SearchResults {
@@ -84,8 +232,46 @@ pub enum SearchResults {
     ),
 
     SingleSegment(usize, std::vec::IntoIter<(SearchIndexScore, DocAddress)>),
+
+    /// Every matching document, only put in score order as each one is returned
+    Lazy(usize, BinaryHeap<InScoreOrder>),
+}
+
+/// A matching document, ordered so that a [`BinaryHeap`] of them pops them in score order: most
+/// relevant first, or least relevant first if `ascending`.  Documents with equal scores are popped
+/// in ctid order
+pub struct InScoreOrder {
+    scored: SearchIndexScore,
+    doc_address: DocAddress,
+    ascending: bool,
+}
+
+impl Ord for InScoreOrder {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let by_score = self.scored.bm25.total_cmp(&other.scored.bm25);
+        let by_score = if self.ascending {
+            by_score.reverse()
+        } else {
+            by_score
+        };
+        by_score.then_with(|| other.scored.ctid.cmp(&self.scored.ctid))
+    }
+}
+
+impl PartialOrd for InScoreOrder {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
+impl PartialEq for InScoreOrder {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for InScoreOrder {}
+
 impl Debug for SearchResults {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -99,6 +285,9 @@ impl Debug for SearchResults {
             SearchResults::SingleSegment(count, iter) => {
                 write!(f, "SearchResults::SingleSegment({count}, {:?})", iter.len())
             }
+            SearchResults::Lazy(count, heap) => {
+                write!(f, "SearchResults::Lazy({count}, {:?})", heap.len())
+            }
         }
     }
 }
@@ -115,6 +304,9 @@ impl Iterator for SearchResults {
                 .next()
                 .map(|result| result.unwrap_or_else(|e| panic!("{e}"))),
             SearchResults::SingleSegment(_, iter) => iter.next(),
+            SearchResults::Lazy(_, heap) => heap
+                .pop()
+                .map(|in_order| (in_order.scored, in_order.doc_address)),
         }
     }
 
@@ -125,6 +317,7 @@ impl Iterator for SearchResults {
             SearchResults::TopN(_, iter) => iter.size_hint(),
             SearchResults::Channel(iter) => iter.size_hint(),
             SearchResults::SingleSegment(_, iter) => iter.size_hint(),
+            SearchResults::Lazy(_, heap) => (heap.len(), Some(heap.len())),
         }
     }
 
@@ -138,6 +331,7 @@ impl Iterator for SearchResults {
             SearchResults::TopN(count, _) => count,
             SearchResults::Channel(iter) => iter.count(),
             SearchResults::SingleSegment(count, _) => count,
+            SearchResults::Lazy(count, _) => count,
         }
     }
 }
@@ -149,7 +343,106 @@ impl SearchResults {
             SearchResults::TopN(count, _) => Some(*count),
             SearchResults::Channel(_) => None,
             SearchResults::SingleSegment(count, _) => Some(*count),
+            SearchResults::Lazy(count, _) => Some(*count),
+        }
+    }
+
+    /// The best score of a top-N that's yet to be returned, if it was scored
+    pub fn top_score(&self) -> Option<Score> {
+        match self {
+            SearchResults::TopN(_, iter) => iter
+                .as_slice()
+                .iter()
+                .map(|(scored, _)| scored.bm25)
+                .filter(|bm25| !bm25.is_nan())
+                .reduce(Score::max),
+            _ => None,
+        }
+    }
+}
+
+/// The counts of the documents matching a query by their value of a text fast field, which are
+/// returned one group at a time in the order of their values.  Rather than the values themselves,
+/// each segment holds a count for every term ordinal of its column, and only the next value of
+/// each segment is looked up at a time.  Documents without a value are returned last, under
+/// [`None`].
+#[derive(Default)]
+pub struct OrderedGroups {
+    /// Each segment's column, and the count of the matching documents for each of its term ordinals
+    segments: Vec<(StrColumn, Vec<u32>)>,
+    /// The value of the next term ordinal with a count of each segment, smallest first
+    heads: BinaryHeap<Reverse<(Vec<u8>, usize, u64)>>,
+    null_count: i64,
+    /// The most groups that may be returned, and how many have been
+    max_buckets: Option<usize>,
+    returned: usize,
+}
+
+impl OrderedGroups {
+    /// Queue up the first term ordinal of `segment`, from `ord` onwards, that has a count
+    fn push_head(&mut self, segment: usize, ord: u64) {
+        let (column, counts) = &self.segments[segment];
+        let Some(ord) = (ord as usize..counts.len()).find(|&ord| counts[ord] > 0) else {
+            return;
+        };
+        let mut bytes = Vec::new();
+        column
+            .ord_to_bytes(ord as u64, &mut bytes)
+            .expect("term ordinal should be in the column's dictionary");
+        self.heads.push(Reverse((bytes, segment, ord as u64)));
+    }
+}
+
+impl Iterator for OrderedGroups {
+    type Item = (Option<TantivyValue>, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.heads.is_empty() && self.null_count == 0 {
+            return None;
+        }
+        self.returned += 1;
+        if let Some(max_buckets) = self.max_buckets.filter(|&max| self.returned > max) {
+            panic!("{}", too_many_buckets(max_buckets));
+        }
+
+        let Some(Reverse((bytes, segment, ord))) = self.heads.pop() else {
+            return Some((None, std::mem::take(&mut self.null_count)));
+        };
+
+        // every segment whose next value is this one adds its count to the group
+        let mut count = self.segments[segment].1[ord as usize] as i64;
+        self.push_head(segment, ord + 1);
+        while matches!(self.heads.peek(), Some(Reverse((next, ..))) if *next == bytes) {
+            let Reverse((_, segment, ord)) = self.heads.pop().unwrap();
+            count += self.segments[segment].1[ord as usize] as i64;
+            self.push_head(segment, ord + 1);
+        }
+
+        let value = String::from_utf8(bytes).expect("text fast field terms should be utf8");
+        Some((Some(TantivyValue(value.into())), count))
+    }
+}
+
+/// The error a facet, histogram or grouping fails with when it needs more than `max_buckets`
+/// buckets, which is what `paradedb.agg_max_buckets` allows
+pub fn too_many_buckets(max_buckets: usize) -> anyhow::Error {
+    anyhow::anyhow!(
+        "more than {max_buckets} buckets are needed, which is the most paradedb.agg_max_buckets allows"
+    )
+}
+
+/// How many times [`SearchIndexReader::new`] opens a reader while commits and merges keep coming
+/// in, before it settles for the last one it opened
+const MAX_READER_ATTEMPTS: usize = 10;
+
+/// Did opening a reader fail because one of its segments' files has since been deleted?
+fn is_missing_file(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<TantivyError>() {
+        Some(TantivyError::OpenReadError(OpenReadError::FileDoesNotExist(_))) => true,
+        Some(TantivyError::OpenReadError(OpenReadError::IoError { io_error, .. })) => {
+            io_error.kind() == std::io::ErrorKind::NotFound
         }
+        _ => false,
     }
 }
 
@@ -158,22 +451,415 @@ pub struct SearchIndexReader {
     pub searcher: Searcher,
     pub schema: SearchIndexSchema,
     pub underlying_reader: tantivy::IndexReader,
+    /// The generation of the commit this reader searches, which is its opstamp
+    pub generation: u64,
+    /// If set, only this many segments, in segment ordinal order, are searched
+    pub max_segments: Option<usize>,
+    /// Whether documents are scored with statistics that only count live documents
+    pub global_idf: bool,
 }
 
 impl SearchIndexReader {
     pub fn new(search_index: &SearchIndex) -> Result<Self> {
-        let schema = search_index.schema.clone();
-        let reader = search_index
-            .underlying_index
+        // a reader searches exactly the segments of the one commit whose `meta.json` it loads,
+        // which tantivy replaces atomically, so it never sees both a merge's input segments and
+        // the segment they were merged into.  But a merge in another backend can commit, and
+        // garbage collect its inputs, right after we've loaded the commit before it and before
+        // we've opened them, in which case we start over with the newer commit.  We also start
+        // over when another commit came in while the reader opened, so that it can tell which
+        // one it loaded, but only so many times, as the last reader is as good to search
+        let mut attempts = 1;
+        loop {
+            check_for_interrupts!();
+
+            match Self::open(&search_index.underlying_index, search_index.schema.clone()) {
+                Ok((reader, is_loaded_commit))
+                    if is_loaded_commit || attempts == MAX_READER_ATTEMPTS =>
+                {
+                    return Ok(reader)
+                }
+                Ok(_) => {}
+                Err(e) if attempts < MAX_READER_ATTEMPTS && is_missing_file(&e) => {}
+                Err(e) => return Err(e),
+            }
+            attempts += 1;
+        }
+    }
+
+    /// Open a reader over the specified tantivy [`Index`], which need not be the one
+    /// belonging to a [`SearchIndex`], such as when reading an older generation of it.
+    ///
+    /// The reader loads whichever commit is the latest as it opens, and its `generation` is the
+    /// commit that's the latest once it has opened.  The returned `bool` says whether that commit
+    /// has the same segments, with the same deletes, as the reader loaded, so that it's the one
+    /// the reader loaded, or one that's no different to search.  Otherwise another commit came in
+    /// between, and the reader is a little behind its `generation`
+    pub fn open(index: &Index, schema: SearchIndexSchema) -> Result<(Self, bool)> {
+        let reader: tantivy::IndexReader = index
             .reader_builder()
             .reload_policy(tantivy::ReloadPolicy::Manual)
             .try_into()?;
         let searcher = reader.searcher();
-        Ok(SearchIndexReader {
+        let metas = index.load_metas()?;
+
+        let loaded = searcher.generation().segments();
+        let is_loaded_commit = metas.segments.len() == loaded.len()
+            && metas.segments.iter().all(|segment_meta| {
+                loaded.get(&segment_meta.id()) == Some(&segment_meta.delete_opstamp())
+            });
+        let reader = SearchIndexReader {
             searcher,
-            schema: schema.clone(),
+            schema,
             underlying_reader: reader,
-        })
+            generation: metas.opstamp,
+            max_segments: None,
+            global_idf: gucs::global_idf(),
+        };
+        Ok((reader, is_loaded_commit))
+    }
+
+    /// The statistics to score documents with, which only count live documents when
+    /// `global_idf` is set
+    fn scoring_statistics(&self) -> ScoringStatistics<'_> {
+        ScoringStatistics::new(&self.searcher, self.global_idf)
+    }
+
+    /// Restrict searching to the first `max_segments` segments of this reader.  Results from a
+    /// reader restricted in this way are approximate, as any matches in the remaining segments
+    /// are never returned.
+    pub fn with_max_segments(mut self, max_segments: Option<usize>) -> Self {
+        self.max_segments = max_segments;
+        self
+    }
+
+    /// Score documents with statistics that only count live documents, rather than with whatever
+    /// `paradedb.global_idf` was when this reader was opened
+    pub fn with_global_idf(mut self, global_idf: bool) -> Self {
+        self.global_idf = global_idf;
+        self
+    }
+
+    /// Pull the term dictionaries, fast fields, and fieldnorms of every segment this reader
+    /// searches into the OS page cache, so that the first searches after a restart don't have
+    /// to wait on the disk.
+    ///
+    /// Returns the id of each segment, how many documents it has, and how many bytes were warmed.
+    pub fn warmup(&self) -> Result<Vec<(SegmentId, u32, usize)>> {
+        let index = self.searcher.index();
+        let metas = index.searchable_segment_metas()?;
+
+        self.searcher
+            .segment_readers()
+            .iter()
+            .map(|segment_reader| {
+                let segment_id = segment_reader.segment_id();
+                let meta = metas
+                    .iter()
+                    .find(|meta| meta.id() == segment_id)
+                    .ok_or_else(|| anyhow::anyhow!("segment `{segment_id}` has no metadata"))?;
+
+                let mut nbytes = 0;
+                for component in [
+                    SegmentComponent::Terms,
+                    SegmentComponent::FastFields,
+                    SegmentComponent::FieldNorms,
+                ] {
+                    let bytes = index
+                        .directory()
+                        .open_read(&meta.relative_path(component))?
+                        .read_bytes()?;
+                    let checksum = bytes
+                        .as_slice()
+                        .iter()
+                        .step_by(WARMUP_PAGE_SIZE)
+                        .fold(0u8, |acc, byte| acc ^ byte);
+                    std::hint::black_box(checksum);
+                    nbytes += bytes.len();
+                }
+
+                Ok((segment_id, segment_reader.num_docs(), nbytes))
+            })
+            .collect()
+    }
+
+    /// Build the tantivy [`Query`] for `search_query_input`, without the score rounding
+    /// [`SearchIndex::query`] might add
+    pub fn parse_query(
+        &self,
+        mut query_parser: QueryParser,
+        search_query_input: SearchQueryInput,
+    ) -> Result<Box<dyn Query>> {
+        Ok(search_query_input.into_tantivy_query(
+            &self.schema,
+            &mut query_parser,
+            &self.searcher,
+        )?)
+    }
+
+    /// Every term of `search_query_input`, paired with the number of documents that contain it
+    /// and the number of times it occurs across all of them.
+    ///
+    /// Like the statistics tantivy scores with, these count deleted documents that haven't been
+    /// merged away yet.  Queries that don't match on specific terms, such as ranges, contribute
+    /// nothing.
+    pub fn term_stats(
+        &self,
+        query_parser: QueryParser,
+        search_query_input: SearchQueryInput,
+    ) -> Result<Vec<(Term, u64, u64)>> {
+        let query = self.parse_query(query_parser, search_query_input)?;
+        let mut terms = Vec::<Term>::new();
+        query.query_terms(&mut |term, _| {
+            if !terms.contains(term) {
+                terms.push(term.clone());
+            }
+        });
+
+        terms
+            .into_iter()
+            .map(|term| {
+                let mut doc_freq = 0;
+                let mut total_term_freq = 0;
+                for segment_reader in self.searcher.segment_readers() {
+                    let inverted_index = segment_reader.inverted_index(term.field())?;
+                    let Some(mut postings) =
+                        inverted_index.read_postings(&term, IndexRecordOption::WithFreqs)?
+                    else {
+                        continue;
+                    };
+
+                    doc_freq += postings.doc_freq() as u64;
+                    while postings.doc() != TERMINATED {
+                        total_term_freq += postings.term_freq() as u64;
+                        postings.advance();
+                    }
+                }
+                Ok((term, doc_freq, total_term_freq))
+            })
+            .collect()
+    }
+
+    /// How much each field contributed to the score `query` gives the document at `doc_address`.
+    ///
+    /// A [`BooleanQuery`] scores a document with the sum of the scores of its clauses, so each of
+    /// the clauses that matches the document contributes to the fields it searches.  Any other
+    /// query contributes its whole score to the field it searches, or to `"*"` if it searches
+    /// more than one field (or none at all).
+    pub fn field_scores(
+        &self,
+        query: &dyn Query,
+        doc_address: DocAddress,
+    ) -> BTreeMap<String, Score> {
+        let mut field_scores = BTreeMap::new();
+        self.collect_field_scores(query, doc_address, &mut field_scores);
+        field_scores
+    }
+
+    fn collect_field_scores(
+        &self,
+        query: &dyn Query,
+        doc_address: DocAddress,
+        field_scores: &mut BTreeMap<String, Score>,
+    ) {
+        if let Some(quantized) = query.downcast_ref::<QuantizedScoreQuery>() {
+            self.collect_field_scores(quantized.query(), doc_address, field_scores);
+            return;
+        } else if let Some(boolean) = query.downcast_ref::<BooleanQuery>() {
+            for (occur, clause) in boolean.clauses() {
+                if *occur != Occur::MustNot {
+                    self.collect_field_scores(clause.as_ref(), doc_address, field_scores);
+                }
+            }
+            return;
+        }
+
+        // a query that doesn't match the document doesn't add to its score
+        let Ok(explanation) = query.explain(&self.searcher, doc_address) else {
+            return;
+        };
+
+        let mut fields = Vec::new();
+        query.query_terms(&mut |term, _| {
+            if !fields.contains(&term.field()) {
+                fields.push(term.field());
+            }
+        });
+        let field_name = match fields.as_slice() {
+            [field] => self.schema.schema.get_field_name(*field).to_string(),
+            _ => "*".to_string(),
+        };
+        *field_scores.entry(field_name).or_default() += explanation.value();
+    }
+
+    /// The names of the fields `query` matched the document at `doc_address` on, in order.  Parts
+    /// of the query that search more than one field report the fields whose terms the document
+    /// contains, and those that don't search for terms at all, like ranges, are reported as `"*"`
+    pub fn matched_fields(&self, query: &dyn Query, doc_address: DocAddress) -> Vec<String> {
+        let mut matched_fields = BTreeSet::new();
+        self.collect_matched_fields(query, doc_address, &mut matched_fields);
+        matched_fields.into_iter().collect()
+    }
+
+    fn collect_matched_fields(
+        &self,
+        query: &dyn Query,
+        doc_address: DocAddress,
+        matched_fields: &mut BTreeSet<String>,
+    ) {
+        if let Some(quantized) = query.downcast_ref::<QuantizedScoreQuery>() {
+            self.collect_matched_fields(quantized.query(), doc_address, matched_fields);
+            return;
+        } else if let Some(boolean) = query.downcast_ref::<BooleanQuery>() {
+            for (occur, clause) in boolean.clauses() {
+                if *occur != Occur::MustNot {
+                    self.collect_matched_fields(clause.as_ref(), doc_address, matched_fields);
+                }
+            }
+            return;
+        }
+
+        if query.explain(&self.searcher, doc_address).is_err() {
+            return;
+        }
+
+        let mut terms = Vec::<Term>::new();
+        query.query_terms(&mut |term, _| {
+            if !terms.contains(term) {
+                terms.push(term.clone());
+            }
+        });
+        let mut fields = terms.iter().map(|term| term.field()).collect::<Vec<_>>();
+        fields.sort();
+        fields.dedup();
+
+        if let [field] = fields.as_slice() {
+            matched_fields.insert(self.schema.schema.get_field_name(*field).to_string());
+            return;
+        }
+
+        let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
+        let mut found = false;
+        for term in &terms {
+            let contains_term = segment_reader
+                .inverted_index(term.field())
+                .ok()
+                .and_then(|index| {
+                    index
+                        .read_postings(term, IndexRecordOption::Basic)
+                        .ok()
+                        .flatten()
+                })
+                // a posting list can only seek forward from its first document
+                .map(|mut postings| {
+                    postings.doc() <= doc_address.doc_id
+                        && postings.seek(doc_address.doc_id) == doc_address.doc_id
+                })
+                .unwrap_or(false);
+            if contains_term {
+                matched_fields.insert(self.schema.schema.get_field_name(term.field()).to_string());
+                found = true;
+            }
+        }
+        if !found {
+            matched_fields.insert("*".to_string());
+        }
+    }
+
+    /// The value of the fast field `field_name` for the document at `doc_address`, or [`None`] if
+    /// the document doesn't have one
+    pub fn fast_field_value(
+        &self,
+        field_name: &str,
+        doc_address: DocAddress,
+    ) -> Option<TantivyValue> {
+        let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
+        FFType::try_new(segment_reader.fast_fields(), field_name)
+            .and_then(|ff| ff.try_value(doc_address.doc_id))
+    }
+
+    /// The first value of the numeric fast field `field_name` of the document at `doc_address`, as
+    /// an f64.  Unlike [`Self::fast_field_value`], which reads a missing number as `0`, this is
+    /// [`None`] for a document without a value
+    pub fn numeric_fast_field_value(
+        &self,
+        field_name: &str,
+        doc_address: DocAddress,
+    ) -> Option<f64> {
+        let fast_fields = self
+            .searcher
+            .segment_reader(doc_address.segment_ord)
+            .fast_fields();
+        let doc = doc_address.doc_id;
+        if let Ok(ff) = fast_fields.u64(field_name) {
+            ff.first(doc).map(|value| value as f64)
+        } else if let Ok(ff) = fast_fields.i64(field_name) {
+            ff.first(doc).map(|value| value as f64)
+        } else {
+            fast_fields.f64(field_name).ok()?.first(doc)
+        }
+    }
+
+    /// The score each of the `queries` gives each of the documents with the `ctids`, as the
+    /// position of the query in `queries`, the document's ctid, and its score.
+    ///
+    /// The documents are found through the index of the ctid field, and each query only scores
+    /// them, by seeking its scorer to each in turn, rather than every document it matches.  A
+    /// document a query doesn't match, or a ctid that isn't in the index, has no score for it.
+    pub fn score_matrix(
+        &self,
+        queries: &[Box<dyn Query>],
+        ctids: &[u64],
+    ) -> Result<Vec<(usize, u64, Score)>> {
+        let ctid_field = self.schema.ctid_field().id.0;
+        let mut candidates = BTreeMap::<SegmentOrdinal, Vec<(DocId, u64)>>::new();
+        for (segment_ord, segment_reader) in self.searcher.segment_readers().iter().enumerate() {
+            let inverted_index = segment_reader.inverted_index(ctid_field)?;
+            for &ctid in ctids {
+                let term = Term::from_field_u64(ctid_field, ctid);
+                let Some(mut postings) =
+                    inverted_index.read_postings(&term, IndexRecordOption::Basic)?
+                else {
+                    continue;
+                };
+
+                while postings.doc() != TERMINATED {
+                    if !segment_reader.is_deleted(postings.doc()) {
+                        candidates
+                            .entry(segment_ord as SegmentOrdinal)
+                            .or_default()
+                            .push((postings.doc(), ctid));
+                    }
+                    postings.advance();
+                }
+            }
+        }
+
+        // a scorer can only seek forward
+        for docs in candidates.values_mut() {
+            docs.sort_unstable();
+            docs.dedup();
+        }
+
+        let mut scores = Vec::new();
+        for (query_idx, query) in queries.iter().enumerate() {
+            let weight = query.weight(EnableScoring::Enabled {
+                searcher: &self.searcher,
+                statistics_provider: &self.scoring_statistics(),
+            })?;
+            for (segment_ord, docs) in &candidates {
+                let segment_reader = self.searcher.segment_reader(*segment_ord);
+                let mut scorer = weight.scorer(segment_reader, 1.0)?;
+                for &(doc, ctid) in docs {
+                    if scorer.doc() == TERMINATED {
+                        break;
+                    }
+                    if scorer.seek(doc) == doc {
+                        scores.push((query_idx, ctid, scorer.score()));
+                    }
+                }
+            }
+        }
+        Ok(scores)
     }
 
     pub fn get_doc(&self, doc_address: DocAddress) -> tantivy::Result<TantivyDocument> {
@@ -253,39 +939,363 @@ impl SearchIndexReader {
     /// handle that, if it's necessary.
     pub fn search_via_channel(
         &self,
-        need_scores: bool,
-        key_field: Option<String>,
+        need_scores: bool,
+        key_field: Option<String>,
+        executor: &'static Executor,
+        query: &dyn Query,
+    ) -> SearchResults {
+        let (sender, receiver) = crossbeam::channel::unbounded();
+        let collector = collector::ChannelCollector::new(need_scores, sender, key_field);
+        let searcher = self.searcher.clone();
+        let schema = self.schema.schema.clone();
+        let max_segments = self.max_segments;
+        let global_idf = self.global_idf;
+
+        let owned_query = query.box_clone();
+        std::thread::spawn(move || {
+            let statistics = ScoringStatistics::new(&searcher, global_idf);
+            search_with_executor(
+                &searcher,
+                max_segments,
+                &owned_query,
+                &collector,
+                executor,
+                if need_scores {
+                    tantivy::query::EnableScoring::Enabled {
+                        searcher: &searcher,
+                        statistics_provider: &statistics,
+                    }
+                } else {
+                    tantivy::query::EnableScoring::Disabled {
+                        schema: &schema,
+                        searcher_opt: Some(&searcher),
+                    }
+                },
+            )
+            .expect("failed to search")
+        });
+
+        SearchResults::Channel(receiver.into_iter().flatten())
+    }
+
+    /// Search the Tantivy index for the documents whose scores are at or above the `percentile`
+    /// of the scores of every matching document.
+    ///
+    /// The cutoff depends on the matching documents themselves, so this is done in two phases:
+    /// first every matching document is scored, and then only those at or above the cutoff are
+    /// returned.  They're returned in score order.  Most relevant first if `sortdir` is
+    /// [`SortDirection::Desc`], or least relevant first if it's [`SortDirection::Asc`].
+    ///
+    /// It has no understanding of Postgres MVCC visibility, so the cutoff is computed over the
+    /// documents of dead rows too, until they're vacuumed away.  It is the caller's responsibility
+    /// to skip them as they're returned.
+    pub fn search_above_percentile(
+        &self,
+        executor: &'static Executor,
+        query: &dyn Query,
+        percentile: f64,
+        sortdir: SortDirection,
+    ) -> SearchResults {
+        let mut results = self.search_by_score(executor, query);
+        keep_above_percentile(&mut results, percentile);
+
+        if sortdir == SortDirection::Asc {
+            results.reverse();
+        }
+        SearchResults::TopN(results.len(), results.into_iter())
+    }
+
+    /// Search the Tantivy index for matching documents, and return every one of them in score
+    /// order.  Most relevant first if `sortdir` is [`SortDirection::Desc`], or least relevant first
+    /// if it's [`SortDirection::Asc`].
+    ///
+    /// It has no understanding of Postgres MVCC visibility.  It is the caller's responsibility to
+    /// handle that, if it's necessary.
+    pub fn search_in_score_order(
+        &self,
+        executor: &'static Executor,
+        query: &dyn Query,
+        sortdir: SortDirection,
+    ) -> SearchResults {
+        let mut results = self.search_by_score(executor, query);
+        if sortdir == SortDirection::Asc {
+            results.reverse();
+        }
+        SearchResults::TopN(results.len(), results.into_iter())
+    }
+
+    /// Like [`SearchIndexReader::search_in_score_order`], but rather than sorting every matching
+    /// document up front, they're held in a heap that each one is only taken from, in order, as
+    /// it's returned.  Documents with equal scores are returned in ctid order.
+    ///
+    /// Every document still has to be scored before the first can be returned, as it could be the
+    /// most relevant, but returning the first `n` then only costs putting those `n` in order.
+    ///
+    /// It has no understanding of Postgres MVCC visibility.  It is the caller's responsibility to
+    /// handle that, if it's necessary.
+    pub fn search_lazily_in_score_order(
+        &self,
+        executor: &'static Executor,
+        query: &dyn Query,
+        sortdir: SortDirection,
+    ) -> SearchResults {
+        let ascending = sortdir == SortDirection::Asc;
+        let heap = self
+            .search_via_channel(true, None, executor, query)
+            .map(|(scored, doc_address)| InScoreOrder {
+                scored,
+                doc_address,
+                ascending,
+            })
+            .collect::<BinaryHeap<_>>();
+        SearchResults::Lazy(heap.len(), heap)
+    }
+
+    /// Count the documents matching `query` by their value of the fast field `group_field`, like a
+    /// terms aggregation.  Documents without a value are counted together under [`None`].
+    ///
+    /// Only documents whose ctid `is_visible` accepts are counted, which is how the caller
+    /// applies Postgres MVCC visibility.  It's called on this thread, one document at a time.
+    ///
+    /// Fails once there are more groups than `paradedb.agg_max_buckets` allows.
+    pub fn count_by_field(
+        &self,
+        executor: &'static Executor,
+        query: &dyn Query,
+        group_field: &str,
+        mut is_visible: impl FnMut(u64) -> bool,
+    ) -> Result<HashMap<Option<TantivyValue>, i64>> {
+        let max_buckets = gucs::agg_max_buckets();
+        let mut fast_fields = HashMap::new();
+        let mut counts = HashMap::new();
+        for (scored, doc_address) in self.search_via_channel(false, None, executor, query) {
+            if !is_visible(scored.ctid) {
+                continue;
+            }
+
+            let ff = fast_fields
+                .entry(doc_address.segment_ord)
+                .or_insert_with(|| {
+                    let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
+                    FFType::try_new(segment_reader.fast_fields(), group_field)
+                });
+            let value = ff.as_ref().and_then(|ff| ff.try_value(doc_address.doc_id));
+            *counts.entry(value).or_default() += 1;
+            if let Some(max_buckets) = max_buckets.filter(|&max| counts.len() > max) {
+                return Err(too_many_buckets(max_buckets));
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Like [`SearchIndexReader::count_by_field`], but for a `group_field` that's a text fast field,
+    /// in which case the groups are returned one at a time in the order of their values, without
+    /// holding every value in memory.  Returns [`None`] for any other kind of field.
+    ///
+    /// Returning more groups than `paradedb.agg_max_buckets` allows raises an ERROR.
+    ///
+    /// Only documents whose ctid `is_visible` accepts are counted, which is how the caller
+    /// applies Postgres MVCC visibility.  It's called on this thread, one document at a time.
+    pub fn ordered_count_by_field(
+        &self,
+        query: &dyn Query,
+        group_field: &str,
+        mut is_visible: impl FnMut(u64) -> bool,
+    ) -> Result<Option<OrderedGroups>> {
+        let Ok(field) = self.schema.schema.get_field(group_field) else {
+            return Ok(None);
+        };
+        if !matches!(
+            self.schema.schema.get_field_entry(field).field_type(),
+            FieldType::Str(options) if options.is_fast()
+        ) {
+            return Ok(None);
+        }
+
+        let mut groups = OrderedGroups {
+            max_buckets: gucs::agg_max_buckets(),
+            ..Default::default()
+        };
+        let weight = query.weight(EnableScoring::disabled_from_searcher(&self.searcher))?;
+        let segment_readers = self
+            .searcher
+            .segment_readers()
+            .iter()
+            .take(self.max_segments.unwrap_or(usize::MAX));
+        for segment_reader in segment_readers {
+            let fast_fields = segment_reader.fast_fields();
+            let ctid_ff = FFType::new(fast_fields, "ctid");
+            let column = fast_fields.str(group_field)?;
+            let mut counts = vec![
+                0u32;
+                column
+                    .as_ref()
+                    .map(|column| column.dictionary().num_terms())
+                    .unwrap_or(0)
+            ];
+            let mut scorer = weight.scorer(segment_reader, 1.0)?;
+            let mut doc = scorer.doc();
+            while doc != TERMINATED {
+                if !segment_reader.is_deleted(doc)
+                    && is_visible(ctid_ff.as_u64(doc).expect("DocId should have a ctid"))
+                {
+                    match column
+                        .as_ref()
+                        .and_then(|column| column.term_ords(doc).next())
+                    {
+                        Some(ord) => counts[ord as usize] += 1,
+                        None => groups.null_count += 1,
+                    }
+                }
+                doc = scorer.advance();
+            }
+
+            if let Some(column) = column {
+                groups.segments.push((column, counts));
+                groups.push_head(groups.segments.len() - 1, 0);
+            }
+        }
+        Ok(Some(groups))
+    }
+
+    /// The distinct values of the fast field `group_field` among the documents matching `query`,
+    /// in the order they're first found, stopping as soon as there are `limit` of them.  Documents
+    /// without a value all share [`None`].  Also returns how many matching documents were looked
+    /// at along the way.
+    ///
+    /// Only documents whose ctid `is_visible` accepts can add a value, which is how the caller
+    /// applies Postgres MVCC visibility.  It's only asked about documents with a value that
+    /// hasn't been found yet.
+    pub fn distinct_values(
+        &self,
+        query: &dyn Query,
+        group_field: &str,
+        limit: usize,
+        mut is_visible: impl FnMut(u64) -> bool,
+    ) -> Result<(Vec<Option<TantivyValue>>, usize)> {
+        let mut values = Vec::new();
+        let mut seen = HashSet::new();
+        let mut visited = 0;
+        if limit == 0 {
+            return Ok((values, visited));
+        }
+
+        let weight = query.weight(EnableScoring::disabled_from_searcher(&self.searcher))?;
+        let segment_readers = self
+            .searcher
+            .segment_readers()
+            .iter()
+            .take(self.max_segments.unwrap_or(usize::MAX));
+        for segment_reader in segment_readers {
+            let fast_fields = segment_reader.fast_fields();
+            let ctid_ff = FFType::new(fast_fields, "ctid");
+            let ff = FFType::try_new(fast_fields, group_field);
+            let mut scorer = weight.scorer(segment_reader, 1.0)?;
+            let mut doc = scorer.doc();
+            while doc != TERMINATED {
+                if !segment_reader.is_deleted(doc) {
+                    visited += 1;
+                    let value = ff.as_ref().and_then(|ff| ff.try_value(doc));
+                    if !seen.contains(&value) {
+                        let ctid = ctid_ff.as_u64(doc).expect("DocId should have a ctid");
+                        if is_visible(ctid) {
+                            seen.insert(value.clone());
+                            values.push(value);
+                            if values.len() == limit {
+                                return Ok((values, visited));
+                            }
+                        }
+                    }
+                }
+                doc = scorer.advance();
+            }
+        }
+        Ok((values, visited))
+    }
+
+    /// Whether any of the documents matching `query` has a ctid `is_visible` accepts, which is how
+    /// the caller applies Postgres MVCC visibility.  The search stops as soon as it finds one, so
+    /// it's only asked about the documents up to the first visible one.
+    pub fn has_match(
+        &self,
+        query: &dyn Query,
+        mut is_visible: impl FnMut(u64) -> bool,
+    ) -> Result<bool> {
+        let weight = query.weight(EnableScoring::disabled_from_searcher(&self.searcher))?;
+        let segment_readers = self
+            .searcher
+            .segment_readers()
+            .iter()
+            .take(self.max_segments.unwrap_or(usize::MAX));
+        for segment_reader in segment_readers {
+            let ctid_ff = FFType::new(segment_reader.fast_fields(), "ctid");
+            let mut scorer = weight.scorer(segment_reader, 1.0)?;
+            let mut doc = scorer.doc();
+            while doc != TERMINATED {
+                if !segment_reader.is_deleted(doc) {
+                    let ctid = ctid_ff.as_u64(doc).expect("DocId should have a ctid");
+                    if is_visible(ctid) {
+                        return Ok(true);
+                    }
+                }
+                doc = scorer.advance();
+            }
+        }
+        Ok(false)
+    }
+
+    /// Search for the `n` most relevant documents matching `query`, while counting every matching
+    /// document along the way, so that a page of results also knows how many there are in all.
+    /// The documents are returned most relevant first, with ties ordered by ctid.
+    ///
+    /// Only documents whose ctid `is_visible` accepts are returned and counted, which is how the
+    /// caller applies Postgres MVCC visibility.  It's called on this thread, one at a time.
+    pub fn search_top_n_with_total(
+        &self,
+        executor: &'static Executor,
+        query: &dyn Query,
+        n: usize,
+        mut is_visible: impl FnMut(u64) -> bool,
+    ) -> (Vec<(SearchIndexScore, DocAddress)>, usize) {
+        let by_relevance = |(a, _): &(SearchIndexScore, DocAddress),
+                            (b, _): &(SearchIndexScore, DocAddress)| {
+            b.bm25.total_cmp(&a.bm25).then(a.ctid.cmp(&b.ctid))
+        };
+
+        let mut total = 0;
+        let mut top = Vec::new();
+        for (scored, doc_address) in self.search_via_channel(true, None, executor, query) {
+            if !is_visible(scored.ctid) {
+                continue;
+            }
+            total += 1;
+            if n == 0 {
+                continue;
+            }
+
+            // only ever hold onto twice as many documents as we need
+            top.push((scored, doc_address));
+            if top.len() == 2 * n {
+                top.sort_by(by_relevance);
+                top.truncate(n);
+            }
+        }
+        top.sort_by(by_relevance);
+        top.truncate(n);
+        (top, total)
+    }
+
+    /// Every document matching `query`, most relevant first
+    fn search_by_score(
+        &self,
         executor: &'static Executor,
         query: &dyn Query,
-    ) -> SearchResults {
-        let (sender, receiver) = crossbeam::channel::unbounded();
-        let collector = collector::ChannelCollector::new(need_scores, sender, key_field);
-        let searcher = self.searcher.clone();
-        let schema = self.schema.schema.clone();
-
-        let owned_query = query.box_clone();
-        std::thread::spawn(move || {
-            searcher
-                .search_with_executor(
-                    &owned_query,
-                    &collector,
-                    executor,
-                    if need_scores {
-                        tantivy::query::EnableScoring::Enabled {
-                            searcher: &searcher,
-                            statistics_provider: &searcher,
-                        }
-                    } else {
-                        tantivy::query::EnableScoring::Disabled {
-                            schema: &schema,
-                            searcher_opt: Some(&searcher),
-                        }
-                    },
-                )
-                .expect("failed to search")
-        });
-
-        SearchResults::Channel(receiver.into_iter().flatten())
+    ) -> Vec<(SearchIndexScore, DocAddress)> {
+        let mut results = self
+            .search_via_channel(true, None, executor, query)
+            .collect::<Vec<_>>();
+        results.sort_by(|(a, _), (b, _)| b.bm25.total_cmp(&a.bm25));
+        results
     }
 
     /// Search a specific index segment for matching documents.
@@ -302,11 +1312,12 @@ impl SearchIndexReader {
         query: &dyn Query,
     ) -> SearchResults {
         let collector = vec_collector::VecCollector::new(need_scores, key_field);
+        let statistics = self.scoring_statistics();
         let weight = query
             .weight(if need_scores {
                 tantivy::query::EnableScoring::Enabled {
                     searcher: &self.searcher,
-                    statistics_provider: &self.searcher,
+                    statistics_provider: &statistics,
                 }
             } else {
                 tantivy::query::EnableScoring::Disabled {
@@ -325,31 +1336,69 @@ impl SearchIndexReader {
     /// Search the Tantivy index for the "top N" matching documents.
     ///
     /// The documents are returned in score order.  Most relevant first if `sortdir` is [`SortDirection::Desc`],
-    /// or least relevant first if it's [`SortDirection::Asc`].
+    /// or least relevant first if it's [`SortDirection::Asc`].  Documents with equal scores are
+    /// ordered according to `tiebreak`.  Instead of their scores, they're ordered by `sort_field`
+    /// or by the value of `sort_expression`, if either is given, in which case only a `tiebreak`
+    /// of [`ScoreTieBreak::Ctid`] orders the documents with equal values.  The documents without a value
+    /// for a `sort_field` come first if `nulls_first`, and last otherwise.  If there are any
+    /// `sort_keys`, the documents are ordered by each of their fields in turn instead.
     ///
     /// It has no understanding of Postgres MVCC visibility.  It is the caller's responsibility to
     /// handle that, if it's necessary.
+    #[allow(clippy::too_many_arguments)]
     pub fn search_top_n(
         &self,
         executor: &'static Executor,
         query: &dyn Query,
         sort_field: Option<String>,
+        sort_keys: &[SortKey],
+        sort_expression: Option<&SortExpression>,
+        sort_reduction: Option<SortReduction>,
         sortdir: SortDirection,
+        nulls_first: bool,
+        tiebreak: ScoreTieBreak,
         n: usize,
     ) -> SearchResults {
-        if let Some(sort_field) = sort_field {
-            self.top_by_field(executor, query, sort_field, sortdir, n)
+        if !sort_keys.is_empty() {
+            self.top_by_fields(executor, query, sort_keys, tiebreak, n)
+        } else if let (Some(sort_field), Some(reduction)) = (&sort_field, sort_reduction) {
+            self.top_by_reduced_field(executor, query, sort_field, reduction, sortdir, tiebreak, n)
+        } else if let Some(sort_field) = sort_field {
+            self.top_by_field(
+                executor,
+                query,
+                sort_field,
+                sortdir,
+                nulls_first,
+                tiebreak,
+                n,
+            )
+        } else if let Some(sort_expression) = sort_expression {
+            self.top_by_expression(
+                executor,
+                query,
+                sort_expression.clone(),
+                sortdir,
+                tiebreak,
+                n,
+            )
         } else {
-            self.top_by_score(executor, query, sortdir, n)
+            self.top_by_score(executor, query, sortdir, tiebreak, n)
         }
     }
 
+    /// The top `n` documents by the value of `sort_field`.  The documents without a value come
+    /// before all of those with one if `nulls_first`, and after them otherwise, amongst themselves
+    /// in ctid order, so that they're never just dropped from the top-N for sorting like a zero
+    #[allow(clippy::too_many_arguments)]
     fn top_by_field(
         &self,
         executor: &Executor,
         query: &dyn Query,
         sort_field: String,
         sortdir: SortDirection,
+        nulls_first: bool,
+        tiebreak: ScoreTieBreak,
         n: usize,
     ) -> SearchResults {
         impl From<SortDirection> for tantivy::Order {
@@ -366,24 +1415,270 @@ impl SearchIndexReader {
             .get_search_field(&SearchFieldName(sort_field.clone()))
             .expect("sort field should exist in index schema");
 
-        let collector =
-            TopDocs::with_limit(n).order_by_u64_field(&sort_field.name.0, sortdir.into());
-        let results = self
-            .searcher
-            .search_with_executor(
-                query,
+        let top_n = |occur: Occur, order_by: &str, order: Order, n: usize| {
+            let query = BooleanQuery::new(vec![
+                (Occur::Must, query.box_clone()),
+                (
+                    occur,
+                    Box::new(ExistsQuery::new_exists_query(sort_field.name.0.clone())),
+                ),
+            ]);
+            let statistics = self.scoring_statistics();
+            let enable_scoring = tantivy::query::EnableScoring::Enabled {
+                searcher: &self.searcher,
+                statistics_provider: &statistics,
+            };
+            if tiebreak != ScoreTieBreak::Ctid {
+                let collector = TopDocs::with_limit(n).order_by_u64_field(order_by, order);
+                return search_with_executor(
+                    &self.searcher,
+                    self.max_segments,
+                    &query,
+                    &collector,
+                    executor,
+                    enable_scoring,
+                )
+                .expect("failed to search")
+                .into_iter()
+                .map(|(_ff_u64_value, doc_address)| doc_address)
+                .collect::<Vec<_>>();
+            }
+
+            // like `order_by_u64_field()`, but documents with equal values go in ctid order
+            let order_by = order_by.to_string();
+            let collector = TopDocs::with_limit(n).custom_score(
+                move |segment_reader: &tantivy::SegmentReader| {
+                    let fast_fields = segment_reader.fast_fields();
+                    let column = fast_fields
+                        .u64_lenient_for_type(None, &order_by)
+                        .expect("sort field should be a fast field")
+                        .map(|(column, _)| column);
+                    let ctid_ff = FFType::new(fast_fields, "ctid");
+                    move |doc: DocId| {
+                        let value = column
+                            .as_ref()
+                            .and_then(|column| column.first(doc))
+                            .unwrap_or(0);
+                        let value = match order {
+                            Order::Desc => value,
+                            Order::Asc => u64::MAX - value,
+                        };
+                        let ctid = ctid_ff.as_u64(doc).unwrap_or(0);
+                        (value, u64::MAX - ctid)
+                    }
+                },
+            );
+            search_with_executor(
+                &self.searcher,
+                self.max_segments,
+                &query,
                 &collector,
                 executor,
-                tantivy::query::EnableScoring::Enabled {
-                    searcher: &self.searcher,
-                    statistics_provider: &self.searcher,
-                },
+                enable_scoring,
             )
             .expect("failed to search")
-            .into_iter();
+            .into_iter()
+            .map(|(_key, doc_address)| doc_address)
+            .collect::<Vec<_>>()
+        };
+        let with_values = |n: usize| top_n(Occur::Must, &sort_field.name.0, sortdir.into(), n);
+        let without_values = |n: usize| top_n(Occur::MustNot, "ctid", Order::Asc, n);
+
+        // only look for the documents that come second if the first don't already fill the top-N
+        let mut results = if nulls_first {
+            without_values(n)
+        } else {
+            with_values(n)
+        };
+        if results.len() < n {
+            let rest = n - results.len();
+            results.extend(if nulls_first {
+                with_values(rest)
+            } else {
+                without_values(rest)
+            });
+        }
+
+        self.unscored_top_n(results)
+    }
+
+    /// The top `n` documents by the values of the fields of `sort_keys`, compared one after the
+    /// other, each in its own direction.  Documents that tie on every field are ordered by
+    /// `tiebreak`
+    fn top_by_fields(
+        &self,
+        executor: &Executor,
+        query: &dyn Query,
+        sort_keys: &[SortKey],
+        tiebreak: ScoreTieBreak,
+        n: usize,
+    ) -> SearchResults {
+        let sort_keys = sort_keys.to_vec();
+        // the documents with the greatest keys come first
+        let collector =
+            TopDocs::with_limit(n).custom_score(move |segment_reader: &tantivy::SegmentReader| {
+                let fast_fields = segment_reader.fast_fields();
+                let columns = sort_keys
+                    .iter()
+                    .map(|key| {
+                        let column = fast_fields
+                            .u64_lenient_for_type(None, &key.field)
+                            .expect("sort field should be a fast field")
+                            .map(|(column, _)| column);
+                        (column, key.direction, key.nulls_first)
+                    })
+                    .collect::<Vec<_>>();
+                let ctid_ff = FFType::new(fast_fields, "ctid");
+                move |doc: DocId| {
+                    let mut key = Vec::with_capacity(columns.len() * 2 + 1);
+                    for (column, direction, nulls_first) in &columns {
+                        // whether the document has a value decides which comes first before
+                        // the value itself does
+                        match column.as_ref().and_then(|column| column.first(doc)) {
+                            Some(value) => {
+                                key.push(!nulls_first as u64);
+                                key.push(match direction {
+                                    SortDirection::Desc => value,
+                                    SortDirection::Asc => u64::MAX - value,
+                                });
+                            }
+                            None => {
+                                key.push(*nulls_first as u64);
+                                key.push(0);
+                            }
+                        }
+                    }
+                    let ctid = ctid_ff.as_u64(doc).unwrap_or(0);
+                    key.push(tiebreak.key(ctid, SortDirection::Desc));
+                    key
+                }
+            });
+        let statistics = self.scoring_statistics();
+        let enable_scoring = tantivy::query::EnableScoring::Enabled {
+            searcher: &self.searcher,
+            statistics_provider: &statistics,
+        };
+        let results = search_with_executor(
+            &self.searcher,
+            self.max_segments,
+            query,
+            &collector,
+            executor,
+            enable_scoring,
+        )
+        .expect("failed to search")
+        .into_iter()
+        .map(|(_key, doc_address)| doc_address)
+        .collect::<Vec<_>>();
+
+        self.unscored_top_n(results)
+    }
+
+    /// The documents at `results`, in that order, with their ctids but without scores
+    fn unscored_top_n(&self, results: Vec<DocAddress>) -> SearchResults {
+        let mut top_docs = Vec::with_capacity(results.len());
+        for doc_address in results {
+            let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
+            let fast_fields = segment_reader.fast_fields();
+            let ctid_ff = FFType::new(fast_fields, "ctid");
+
+            let ctid = ctid_ff
+                .as_u64(doc_address.doc_id)
+                .expect("DocId should have a ctid");
+
+            let scored = SearchIndexScore {
+                bm25: f32::NAN,
+                key: None,
+                ctid,
+            };
+
+            top_docs.push((scored, doc_address));
+        }
+
+        SearchResults::TopN(top_docs.len(), top_docs.into_iter())
+    }
+
+    /// Like [`SearchIndexReader::top_by_field`], for a numeric field with many values per
+    /// document, which are sorted by the smallest or largest of them.  Documents without a value
+    /// sort last in ascending order and first in descending order, like Postgres' NULLs do
+    #[allow(clippy::too_many_arguments)]
+    fn top_by_reduced_field(
+        &self,
+        executor: &Executor,
+        query: &dyn Query,
+        sort_field: &str,
+        reduction: SortReduction,
+        sortdir: SortDirection,
+        tiebreak: ScoreTieBreak,
+        n: usize,
+    ) -> SearchResults {
+        #[derive(PartialEq, Clone)]
+        struct OrderedValue {
+            dir: SortDirection,
+            value: Option<f64>,
+            tiebreak: u64,
+        }
+
+        impl PartialOrd for OrderedValue {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                let cmp = match (self.value, other.value) {
+                    (None, None) => Some(Ordering::Equal),
+                    (None, Some(_)) => Some(Ordering::Greater),
+                    (Some(_), None) => Some(Ordering::Less),
+                    (Some(value), Some(other)) => value.partial_cmp(&other),
+                }
+                .map(|o| o.then(self.tiebreak.cmp(&other.tiebreak)));
+                match self.dir {
+                    SortDirection::Desc => cmp,
+                    SortDirection::Asc => cmp.map(|o| o.reverse()),
+                }
+            }
+        }
+
+        let sort_field = sort_field.to_string();
+        let collector =
+            TopDocs::with_limit(n).tweak_score(move |segment_reader: &tantivy::SegmentReader| {
+                let fast_fields = segment_reader.fast_fields();
+                let column = if let Ok(column) = fast_fields.i64(&sort_field) {
+                    Some(MultiValueColumn::I64(column))
+                } else if let Ok(column) = fast_fields.u64(&sort_field) {
+                    Some(MultiValueColumn::U64(column))
+                } else if let Ok(column) = fast_fields.f64(&sort_field) {
+                    Some(MultiValueColumn::F64(column))
+                } else {
+                    None
+                };
+                let ctid_ff =
+                    (tiebreak == ScoreTieBreak::Ctid).then(|| FFType::new(fast_fields, "ctid"));
+                move |doc: DocId, _original_score: Score| OrderedValue {
+                    dir: sortdir,
+                    value: column
+                        .as_ref()
+                        .and_then(|column| column.reduce(doc, reduction)),
+                    tiebreak: ctid_ff
+                        .as_ref()
+                        .and_then(|ctid_ff| ctid_ff.as_u64(doc))
+                        .map(|ctid| tiebreak.key(ctid, sortdir))
+                        .unwrap_or(0),
+                }
+            });
+
+        let results = search_with_executor(
+            &self.searcher,
+            self.max_segments,
+            query,
+            &collector,
+            executor,
+            tantivy::query::EnableScoring::Disabled {
+                schema: &self.schema.schema,
+                searcher_opt: Some(&self.searcher),
+            },
+        )
+        .expect("failed to search")
+        .into_iter();
 
         let mut top_docs = Vec::with_capacity(results.len());
-        for (_ff_u64_value, doc_address) in results {
+        for (_, doc_address) in results {
             let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
             let fast_fields = segment_reader.fast_fields();
             let ctid_ff = FFType::new(fast_fields, "ctid");
@@ -404,51 +1699,194 @@ impl SearchIndexReader {
         SearchResults::TopN(top_docs.len(), top_docs.into_iter())
     }
 
+    fn top_by_expression(
+        &self,
+        executor: &Executor,
+        query: &dyn Query,
+        sort_expression: SortExpression,
+        sortdir: SortDirection,
+        tiebreak: ScoreTieBreak,
+        n: usize,
+    ) -> SearchResults {
+        #[derive(PartialEq, Clone)]
+        struct OrderedValue {
+            dir: SortDirection,
+            value: f64,
+            score: Score,
+            tiebreak: u64,
+        }
+
+        impl PartialOrd for OrderedValue {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                let cmp = self
+                    .value
+                    .partial_cmp(&other.value)
+                    .map(|o| o.then(self.tiebreak.cmp(&other.tiebreak)));
+                match self.dir {
+                    SortDirection::Desc => cmp,
+                    SortDirection::Asc => cmp.map(|o| o.reverse()),
+                }
+            }
+        }
+
+        let sort_expression = Arc::new(sort_expression);
+        let collector =
+            TopDocs::with_limit(n).tweak_score(move |segment_reader: &tantivy::SegmentReader| {
+                let fast_fields = segment_reader.fast_fields();
+                let columns = sort_expression
+                    .fields()
+                    .into_iter()
+                    .map(|name| (name.to_string(), FFType::try_new(fast_fields, name)))
+                    .collect::<Vec<_>>();
+                let ctid_ff =
+                    (tiebreak == ScoreTieBreak::Ctid).then(|| FFType::new(fast_fields, "ctid"));
+                let sort_expression = sort_expression.clone();
+                move |doc: DocId, original_score: Score| OrderedValue {
+                    dir: sortdir,
+                    value: sort_expression.evaluate(original_score as f64, &|name| {
+                        columns
+                            .iter()
+                            .find(|(column, _)| column == name)
+                            .and_then(|(_, ff)| ff.as_ref()?.as_f64(doc))
+                    }),
+                    score: original_score,
+                    tiebreak: ctid_ff
+                        .as_ref()
+                        .and_then(|ctid_ff| ctid_ff.as_u64(doc))
+                        .map(|ctid| tiebreak.key(ctid, sortdir))
+                        .unwrap_or(0),
+                }
+            });
+
+        let results = search_with_executor(
+            &self.searcher,
+            self.max_segments,
+            query,
+            &collector,
+            executor,
+            tantivy::query::EnableScoring::Enabled {
+                searcher: &self.searcher,
+                statistics_provider: &self.scoring_statistics(),
+            },
+        )
+        .expect("failed to search")
+        .into_iter();
+
+        let mut top_docs = Vec::with_capacity(results.len());
+        for (OrderedValue { score, .. }, doc_address) in results {
+            let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
+            let fast_fields = segment_reader.fast_fields();
+            let ctid_ff = FFType::new(fast_fields, "ctid");
+
+            let ctid = ctid_ff
+                .as_u64(doc_address.doc_id)
+                .expect("DocId should have a ctid");
+
+            let scored = SearchIndexScore {
+                bm25: score,
+                key: None,
+                ctid,
+            };
+
+            top_docs.push((scored, doc_address));
+        }
+
+        SearchResults::TopN(top_docs.len(), top_docs.into_iter())
+    }
+
+    /// The value of `sort_expression` for the document at `doc_address`, whose score is `score`
+    pub fn sort_expression_value(
+        &self,
+        sort_expression: &SortExpression,
+        score: Score,
+        doc_address: DocAddress,
+    ) -> f64 {
+        let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
+        let fast_fields = segment_reader.fast_fields();
+        sort_expression.evaluate(score as f64, &|name| {
+            FFType::try_new(fast_fields, name)?.as_f64(doc_address.doc_id)
+        })
+    }
+
     fn top_by_score(
         &self,
         executor: &Executor,
         query: &dyn Query,
         sortdir: SortDirection,
+        tiebreak: ScoreTieBreak,
         n: usize,
     ) -> SearchResults {
         #[derive(PartialEq, Clone)]
         struct OrderedScore {
             dir: SortDirection,
             score: Score,
+            tiebreak: u64,
         }
 
         impl PartialOrd for OrderedScore {
             fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-                let cmp = self.score.partial_cmp(&other.score);
+                let cmp = self
+                    .score
+                    .partial_cmp(&other.score)
+                    .map(|o| o.then(self.tiebreak.cmp(&other.tiebreak)));
                 match self.dir {
                     SortDirection::Desc => cmp,
                     SortDirection::Asc => cmp.map(|o| o.reverse()),
                 }
             }
         }
-        let collector = TopDocs::with_limit(n).tweak_score(move |_: &tantivy::SegmentReader| {
-            move |_: DocId, original_score: Score| OrderedScore {
-                dir: sortdir,
-                score: original_score,
-            }
-        });
+        let collector =
+            TopDocs::with_limit(n).tweak_score(move |segment_reader: &tantivy::SegmentReader| {
+                let ctid_ff = (tiebreak != ScoreTieBreak::None)
+                    .then(|| FFType::new(segment_reader.fast_fields(), "ctid"));
+                move |doc: DocId, original_score: Score| OrderedScore {
+                    dir: sortdir,
+                    score: original_score,
+                    tiebreak: ctid_ff
+                        .as_ref()
+                        .and_then(|ctid_ff| ctid_ff.as_u64(doc))
+                        .map(|ctid| tiebreak.key(ctid, sortdir))
+                        .unwrap_or(0),
+                }
+            });
 
-        let results = self
-            .searcher
-            .search_with_executor(
+        let statistics = self.scoring_statistics();
+        let enable_scoring = tantivy::query::EnableScoring::Enabled {
+            searcher: &self.searcher,
+            statistics_provider: &statistics,
+        };
+        let results = if n == 1 {
+            // finding the single best match only needs to remember one document, not to
+            // maintain a heap of them
+            let collector = collector::BestScoreCollector::new(sortdir, tiebreak);
+            search_with_executor(
+                &self.searcher,
+                self.max_segments,
                 query,
                 &collector,
                 executor,
-                tantivy::query::EnableScoring::Enabled {
-                    searcher: &self.searcher,
-                    statistics_provider: &self.searcher,
-                },
+                enable_scoring,
+            )
+            .expect("failed to search")
+            .into_iter()
+            .collect::<Vec<_>>()
+        } else {
+            search_with_executor(
+                &self.searcher,
+                self.max_segments,
+                query,
+                &collector,
+                executor,
+                enable_scoring,
             )
             .expect("failed to search")
-            .into_iter();
+            .into_iter()
+            .map(|(OrderedScore { score, .. }, doc_address)| (score, doc_address))
+            .collect::<Vec<_>>()
+        };
 
         let mut top_docs = Vec::with_capacity(results.len());
-        for (OrderedScore { score, .. }, doc_address) in results {
+        for (score, doc_address) in results {
             let segment_reader = self.searcher.segment_reader(doc_address.segment_ord);
             let fast_fields = segment_reader.fast_fields();
             let ctid_ff = FFType::new(fast_fields, "ctid");
@@ -522,6 +1960,53 @@ impl SearchIndexReader {
     }
 }
 
+/// Run `collector` over the segments of `searcher`, using `executor`.  If `max_segments` is set, only
+/// that many segments, in segment ordinal order, are collected and they're collected serially.
+fn search_with_executor<C: Collector>(
+    searcher: &Searcher,
+    max_segments: Option<usize>,
+    query: &dyn Query,
+    collector: &C,
+    executor: &Executor,
+    enable_scoring: tantivy::query::EnableScoring,
+) -> tantivy::Result<C::Fruit> {
+    let Some(max_segments) = max_segments else {
+        return searcher.search_with_executor(query, collector, executor, enable_scoring);
+    };
+
+    let weight = query.weight(enable_scoring)?;
+    let fruits = searcher
+        .segment_readers()
+        .iter()
+        .take(max_segments)
+        .enumerate()
+        .map(|(segment_ord, segment_reader)| {
+            collector.collect_segment(
+                weight.as_ref(),
+                segment_ord as SegmentOrdinal,
+                segment_reader,
+            )
+        })
+        .collect::<tantivy::Result<Vec<_>>>()?;
+    collector.merge_fruits(fruits)
+}
+
+/// Cut `results`, which are in descending score order, down to those at or above the score
+/// `percentile` of them all
+fn keep_above_percentile(results: &mut Vec<(SearchIndexScore, DocAddress)>, percentile: f64) {
+    if results.is_empty() {
+        return;
+    }
+
+    // the cutoff is the score of the last document in the top `100 - percentile` percent,
+    // and any others that tie with it make the cut too
+    let nkeep = ((results.len() as f64 * (100.0 - percentile) / 100.0).ceil() as usize)
+        .clamp(1, results.len());
+    let cutoff = results[nkeep - 1].0.bm25;
+    let nkeep = results.partition_point(|(scored, _)| scored.bm25 >= cutoff);
+    results.truncate(nkeep);
+}
+
 /// Helper for working with different "fast field" types as if they're all one type
 enum FFType {
     Text(StrColumn),
@@ -536,20 +2021,27 @@ impl FFType {
     /// Construct the proper [`FFType`] for the specified `field_name`, which
     /// should be a known field name in the Tantivy index
     fn new(ffr: &FastFieldReaders, field_name: &str) -> Self {
+        Self::try_new(ffr, field_name).unwrap_or_else(|| {
+            panic!("`{field_name}` is missing or is not configured as a fast field")
+        })
+    }
+
+    /// Like [`FFType::new`], but returns [`None`] if the segment has no such fast field
+    fn try_new(ffr: &FastFieldReaders, field_name: &str) -> Option<Self> {
         if let Ok(Some(ff)) = ffr.str(field_name) {
-            Self::Text(ff)
+            Some(Self::Text(ff))
         } else if let Ok(ff) = ffr.u64(field_name) {
-            Self::U64(ff.first_or_default_col(0))
+            Some(Self::U64(ff.first_or_default_col(0)))
         } else if let Ok(ff) = ffr.i64(field_name) {
-            Self::I64(ff.first_or_default_col(0))
+            Some(Self::I64(ff.first_or_default_col(0)))
         } else if let Ok(ff) = ffr.f64(field_name) {
-            Self::F64(ff.first_or_default_col(0.0))
+            Some(Self::F64(ff.first_or_default_col(0.0)))
         } else if let Ok(ff) = ffr.bool(field_name) {
-            Self::Bool(ff.first_or_default_col(false))
+            Some(Self::Bool(ff.first_or_default_col(false)))
         } else if let Ok(ff) = ffr.date(field_name) {
-            Self::Date(ff.first_or_default_col(tantivy::DateTime::MIN))
+            Some(Self::Date(ff.first_or_default_col(tantivy::DateTime::MIN)))
         } else {
-            panic!("`{field_name}` is missing or is not configured as a fast field")
+            None
         }
     }
 
@@ -577,6 +2069,21 @@ impl FFType {
         value
     }
 
+    /// Like [`FFType::value`], but returns [`None`] for a String field without a value
+    #[inline(always)]
+    fn try_value(&self, doc: DocId) -> Option<TantivyValue> {
+        match self {
+            FFType::Text(ff) => {
+                let ord = ff.term_ords(doc).next()?;
+                let mut s = String::new();
+                ff.ord_to_str(ord, &mut s)
+                    .expect("string should be retrievable for term ord");
+                Some(TantivyValue(s.into()))
+            }
+            other => Some(other.value(doc)),
+        }
+    }
+
     /// Given a [`DocId`], what is its "fast field" value?  In the case of a String field, we
     /// don't reconstruct the full string, and instead return the term ord as a u64
     #[inline(always)]
@@ -597,6 +2104,21 @@ impl FFType {
         value
     }
 
+    /// Given a [`DocId`], what is its numeric "fast field" value, as an f64?  Booleans are `0`
+    /// or `1`.
+    ///
+    /// If this [`FFType`] isn't numeric, this function returns [`None`].
+    #[inline(always)]
+    fn as_f64(&self, doc: DocId) -> Option<f64> {
+        match self {
+            FFType::I64(ff) => Some(ff.get_val(doc) as f64),
+            FFType::F64(ff) => Some(ff.get_val(doc)),
+            FFType::U64(ff) => Some(ff.get_val(doc) as f64),
+            FFType::Bool(ff) => Some(if ff.get_val(doc) { 1.0 } else { 0.0 }),
+            FFType::Text(_) | FFType::Date(_) => None,
+        }
+    }
+
     /// Given a [`DocId`], what is its u64 "fast field" value?
     ///
     /// If this [`FFType`] isn't [`FFType::U64`], this function returns [`None`].
@@ -610,12 +2132,136 @@ impl FFType {
     }
 }
 
+/// Every value of a numeric fast field that a document can have many of
+enum MultiValueColumn {
+    I64(Column<i64>),
+    U64(Column<u64>),
+    F64(Column<f64>),
+}
+
+impl MultiValueColumn {
+    /// The smallest or largest of the document's values, as an f64, or [`None`] if it has none
+    #[inline(always)]
+    fn reduce(&self, doc: DocId, reduction: SortReduction) -> Option<f64> {
+        match self {
+            MultiValueColumn::I64(column) => reduction
+                .reduce(column.values_for_doc(doc))
+                .map(|v| v as f64),
+            MultiValueColumn::U64(column) => reduction
+                .reduce(column.values_for_doc(doc))
+                .map(|v| v as f64),
+            MultiValueColumn::F64(column) => reduction.reduce(column.values_for_doc(doc)),
+        }
+    }
+}
+
 mod collector {
     use crate::index::reader::FFType;
     use crate::index::reader::SearchIndexScore;
+    use crate::index::reader::{ScoreTieBreak, SortDirection};
+    use std::cmp::Ordering;
     use tantivy::collector::{Collector, SegmentCollector};
     use tantivy::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
 
+    /// A [`Collector`] that finds the single best scoring document, in the same order, and with
+    /// the same ties going to the same document, as a `TopDocs` collector with a limit of 1 would
+    pub struct BestScoreCollector {
+        sortdir: SortDirection,
+        tiebreak: ScoreTieBreak,
+    }
+
+    impl BestScoreCollector {
+        pub fn new(sortdir: SortDirection, tiebreak: ScoreTieBreak) -> Self {
+            Self { sortdir, tiebreak }
+        }
+
+        /// Does `(score, tiebreak)` sort strictly before `best`?  Earlier documents win ties
+        fn is_better(&self, score: Score, tiebreak: u64, best: (Score, u64)) -> bool {
+            let cmp = score
+                .partial_cmp(&best.0)
+                .map(|o| o.then(tiebreak.cmp(&best.1)));
+            match self.sortdir {
+                SortDirection::Desc => cmp == Some(Ordering::Greater),
+                SortDirection::Asc => cmp == Some(Ordering::Less),
+            }
+        }
+    }
+
+    impl Collector for BestScoreCollector {
+        type Fruit = Option<(Score, DocAddress)>;
+        type Child = BestScoreSegmentCollector;
+
+        fn for_segment(
+            &self,
+            segment_local_id: SegmentOrdinal,
+            segment_reader: &SegmentReader,
+        ) -> tantivy::Result<Self::Child> {
+            let ctid_ff = (self.tiebreak != ScoreTieBreak::None)
+                .then(|| FFType::new(segment_reader.fast_fields(), "ctid"));
+
+            Ok(BestScoreSegmentCollector {
+                collector: BestScoreCollector::new(self.sortdir, self.tiebreak),
+                segment_ord: segment_local_id,
+                ctid_ff,
+                best: None,
+            })
+        }
+
+        fn requires_scoring(&self) -> bool {
+            true
+        }
+
+        fn merge_fruits(
+            &self,
+            segment_fruits: Vec<Option<(Score, u64, DocAddress)>>,
+        ) -> tantivy::Result<Self::Fruit> {
+            // the fruits are in segment order, so keeping the first of equals keeps the lowest
+            // DocAddress, like `TopDocs` does
+            let mut best: Option<(Score, u64, DocAddress)> = None;
+            for (score, tiebreak, doc_address) in segment_fruits.into_iter().flatten() {
+                if best.map_or(true, |(best_score, best_tiebreak, _)| {
+                    self.is_better(score, tiebreak, (best_score, best_tiebreak))
+                }) {
+                    best = Some((score, tiebreak, doc_address));
+                }
+            }
+            Ok(best.map(|(score, _, doc_address)| (score, doc_address)))
+        }
+    }
+
+    pub struct BestScoreSegmentCollector {
+        collector: BestScoreCollector,
+        segment_ord: SegmentOrdinal,
+        ctid_ff: Option<FFType>,
+        best: Option<(Score, u64, DocId)>,
+    }
+
+    impl SegmentCollector for BestScoreSegmentCollector {
+        type Fruit = Option<(Score, u64, DocAddress)>;
+
+        fn collect(&mut self, doc: DocId, score: Score) {
+            let tiebreak = self
+                .ctid_ff
+                .as_ref()
+                .and_then(|ctid_ff| ctid_ff.as_u64(doc))
+                .map(|ctid| self.collector.tiebreak.key(ctid, self.collector.sortdir))
+                .unwrap_or(0);
+
+            if self.best.map_or(true, |(best_score, best_tiebreak, _)| {
+                self.collector
+                    .is_better(score, tiebreak, (best_score, best_tiebreak))
+            }) {
+                self.best = Some((score, tiebreak, doc));
+            }
+        }
+
+        fn harvest(self) -> Self::Fruit {
+            self.best.map(|(score, tiebreak, doc)| {
+                (score, tiebreak, DocAddress::new(self.segment_ord, doc))
+            })
+        }
+    }
+
     /// A [`Collector`] that uses a crossbeam channel to stream the results directly out of
     /// each segment, in parallel, as tantivy find each doc.
     pub struct ChannelCollector {
@@ -20,9 +20,10 @@ use super::IndexError;
 use crate::gucs;
 use crate::index::SearchIndexWriter;
 use crate::index::{
-    BlockingDirectory, SearchDirectoryError, SearchFs, TantivyDirPath, WriterDirectory,
+    BlockingDirectory, GenerationDirectory, SearchDirectoryError, SearchFs, TantivyDirPath,
+    WriterDirectory,
 };
-use crate::query::SearchQueryInput;
+use crate::query::{QuantizedScoreQuery, SearchQueryInput};
 use crate::schema::{
     SearchDocument, SearchField, SearchFieldConfig, SearchFieldName, SearchFieldType,
     SearchIndexSchema, SearchIndexSchemaError,
@@ -32,7 +33,7 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::num::NonZeroUsize;
 use tantivy::query::Query;
-use tantivy::{query::QueryParser, Executor, Index};
+use tantivy::{query::QueryParser, Directory, Executor, Index};
 use thiserror::Error;
 use tokenizers::{create_normalizer_manager, create_tokenizer_manager};
 use tracing::trace;
@@ -101,6 +102,34 @@ impl SearchIndex {
         SearchIndexReader::new(self)
     }
 
+    /// Retrieve a reader over the index as it was at the specified `generation`, which is the
+    /// opstamp of one of its commits, regardless of what's been written to it since
+    pub fn get_reader_as_of(&self, generation: u64) -> Result<SearchIndexReader> {
+        let TantivyDirPath(tantivy_dir_path) = self.directory.tantivy_dir_path(false)?;
+        let directory = GenerationDirectory::open(tantivy_dir_path, generation)?;
+        let mut underlying_index = Index::open(directory)?;
+
+        // the generation's metadata can outlive its segments if they weren't garbage collected
+        // together, so make sure they're all still there
+        for segment_meta in underlying_index.searchable_segment_metas()? {
+            for path in segment_meta.list_files() {
+                if !underlying_index.directory().exists(&path)? {
+                    return Err(IndexError::GenerationNotFound(generation).into());
+                }
+            }
+        }
+
+        Self::setup_tokenizers(&mut underlying_index, &self.schema);
+        // its `meta.json` is fixed, so the reader always loads that very commit
+        let (reader, _) = SearchIndexReader::open(&underlying_index, self.schema.clone())?;
+        Ok(reader)
+    }
+
+    /// The generation of the index's most recent commit
+    pub fn generation(&self) -> Result<u64> {
+        Ok(self.underlying_index.load_metas()?.opstamp)
+    }
+
     /// Retrieve an owned writer for a given index. This will block until this process
     /// can get an exclusive lock on the Tantivy writer. The return type needs to
     /// be entirely owned by the new process, with no references.
@@ -174,13 +203,32 @@ impl SearchIndex {
         &self,
         search_query_input: &SearchQueryInput,
         reader: &SearchIndexReader,
+    ) -> Box<dyn Query> {
+        self.query_with_score_precision(search_query_input, reader, gucs::score_precision())
+    }
+
+    /// Like [`SearchIndex::query`], but with scores rounded to `score_precision` decimal places,
+    /// if any, rather than to `paradedb.score_precision`.  A planned scan rounds them as the
+    /// setting was when it was planned
+    pub fn query_with_score_precision(
+        &self,
+        search_query_input: &SearchQueryInput,
+        reader: &SearchIndexReader,
+        score_precision: Option<u32>,
     ) -> Box<dyn Query> {
         let mut parser = self.query_parser();
         let searcher = reader.underlying_reader.searcher();
-        search_query_input
+        let query = search_query_input
             .clone()
             .into_tantivy_query(&self.schema, &mut parser, &searcher)
-            .expect("must be able to parse query")
+            .expect("must be able to parse query");
+
+        // rounding the scores here means everything that sorts by or projects them sees the same
+        // rounded value
+        match score_precision {
+            Some(decimal_places) => Box::new(QuantizedScoreQuery::new(query, decimal_places)),
+            None => query,
+        }
     }
 
     pub fn insert(
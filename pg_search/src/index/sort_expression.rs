@@ -0,0 +1,307 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+use thiserror::Error;
+
+/// An arithmetic expression over a document's score and its numeric fast fields, that documents
+/// can be sorted by, like `score * 0.7 + views / 1000.0 * 0.3`.
+///
+/// It supports `+`, `-`, `*`, `/`, parentheses, numeric constants, the name `score` for the
+/// document's BM25 score, and the names of numeric fast fields.  It's evaluated with `f64`s,
+/// where:
+///
+/// - a field without a value for the document is `0`
+/// - dividing by `0` is `0`, so every document has a value to sort by
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortExpression {
+    Constant(f64),
+    Score,
+    Field(String),
+    Negate(Box<SortExpression>),
+    Add(Box<SortExpression>, Box<SortExpression>),
+    Subtract(Box<SortExpression>, Box<SortExpression>),
+    Multiply(Box<SortExpression>, Box<SortExpression>),
+    Divide(Box<SortExpression>, Box<SortExpression>),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum SortExpressionError {
+    #[error("sort expression is empty")]
+    Empty,
+    #[error("unexpected '{0}' at position {1} of sort expression")]
+    UnexpectedCharacter(char, usize),
+    #[error("invalid number '{0}' in sort expression")]
+    InvalidNumber(String),
+    #[error("sort expression ends unexpectedly")]
+    UnexpectedEnd,
+    #[error("unclosed '(' at position {0} of sort expression")]
+    Unclosed(usize),
+}
+
+impl SortExpression {
+    /// The names of the fields the expression reads, in the order they first appear
+    pub fn fields(&self) -> Vec<&str> {
+        fn collect<'a>(expression: &'a SortExpression, fields: &mut Vec<&'a str>) {
+            match expression {
+                SortExpression::Constant(_) | SortExpression::Score => {}
+                SortExpression::Field(name) => {
+                    if !fields.contains(&name.as_str()) {
+                        fields.push(name)
+                    }
+                }
+                SortExpression::Negate(operand) => collect(operand, fields),
+                SortExpression::Add(left, right)
+                | SortExpression::Subtract(left, right)
+                | SortExpression::Multiply(left, right)
+                | SortExpression::Divide(left, right) => {
+                    collect(left, fields);
+                    collect(right, fields);
+                }
+            }
+        }
+
+        let mut fields = Vec::new();
+        collect(self, &mut fields);
+        fields
+    }
+
+    /// Does the expression use the document's score?
+    pub fn uses_score(&self) -> bool {
+        match self {
+            SortExpression::Constant(_) | SortExpression::Field(_) => false,
+            SortExpression::Score => true,
+            SortExpression::Negate(operand) => operand.uses_score(),
+            SortExpression::Add(left, right)
+            | SortExpression::Subtract(left, right)
+            | SortExpression::Multiply(left, right)
+            | SortExpression::Divide(left, right) => left.uses_score() || right.uses_score(),
+        }
+    }
+
+    /// Compute the expression for a document with the given `score`, and whose values for the
+    /// fields of the expression are given by `field`
+    pub fn evaluate(&self, score: f64, field: &dyn Fn(&str) -> Option<f64>) -> f64 {
+        match self {
+            SortExpression::Constant(value) => *value,
+            SortExpression::Score => score,
+            SortExpression::Field(name) => field(name).unwrap_or(0.0),
+            SortExpression::Negate(operand) => -operand.evaluate(score, field),
+            SortExpression::Add(left, right) => {
+                left.evaluate(score, field) + right.evaluate(score, field)
+            }
+            SortExpression::Subtract(left, right) => {
+                left.evaluate(score, field) - right.evaluate(score, field)
+            }
+            SortExpression::Multiply(left, right) => {
+                left.evaluate(score, field) * right.evaluate(score, field)
+            }
+            SortExpression::Divide(left, right) => {
+                let divisor = right.evaluate(score, field);
+                if divisor == 0.0 {
+                    0.0
+                } else {
+                    left.evaluate(score, field) / divisor
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for SortExpression {
+    type Err = SortExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.char_indices().peekable(),
+        };
+        if parser.peek().is_none() {
+            return Err(SortExpressionError::Empty);
+        }
+
+        let expression = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some((position, c)) => Err(SortExpressionError::UnexpectedCharacter(c, position)),
+        }
+    }
+}
+
+/// A recursive descent parser for [`SortExpression`]s, where `*` and `/` bind tighter than `+`
+/// and `-`, and operators of the same precedence associate to the left
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    /// The next character that isn't whitespace, and its position
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some((_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().copied()
+    }
+
+    fn sum(&mut self) -> Result<SortExpression, SortExpressionError> {
+        let mut expression = self.product()?;
+        while let Some((_, c @ ('+' | '-'))) = self.peek() {
+            self.chars.next();
+            let right = Box::new(self.product()?);
+            expression = match c {
+                '+' => SortExpression::Add(Box::new(expression), right),
+                _ => SortExpression::Subtract(Box::new(expression), right),
+            };
+        }
+        Ok(expression)
+    }
+
+    fn product(&mut self) -> Result<SortExpression, SortExpressionError> {
+        let mut expression = self.operand()?;
+        while let Some((_, c @ ('*' | '/'))) = self.peek() {
+            self.chars.next();
+            let right = Box::new(self.operand()?);
+            expression = match c {
+                '*' => SortExpression::Multiply(Box::new(expression), right),
+                _ => SortExpression::Divide(Box::new(expression), right),
+            };
+        }
+        Ok(expression)
+    }
+
+    fn operand(&mut self) -> Result<SortExpression, SortExpressionError> {
+        match self.peek() {
+            None => Err(SortExpressionError::UnexpectedEnd),
+            Some((_, '-')) => {
+                self.chars.next();
+                Ok(SortExpression::Negate(Box::new(self.operand()?)))
+            }
+            Some((position, '(')) => {
+                self.chars.next();
+                let expression = self.sum()?;
+                match self.peek() {
+                    Some((_, ')')) => {
+                        self.chars.next();
+                        Ok(expression)
+                    }
+                    Some((position, c)) => {
+                        Err(SortExpressionError::UnexpectedCharacter(c, position))
+                    }
+                    None => Err(SortExpressionError::Unclosed(position)),
+                }
+            }
+            Some((_, c)) if c.is_ascii_digit() || c == '.' => {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                number
+                    .parse()
+                    .map(SortExpression::Constant)
+                    .map_err(|_| SortExpressionError::InvalidNumber(number))
+            }
+            Some((_, c)) if c.is_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                if name == "score" {
+                    Ok(SortExpression::Score)
+                } else {
+                    Ok(SortExpression::Field(name))
+                }
+            }
+            Some((position, c)) => Err(SortExpressionError::UnexpectedCharacter(c, position)),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some((_, c)) = self.chars.peek() {
+            if !predicate(*c) {
+                break;
+            }
+            taken.push(*c);
+            self.chars.next();
+        }
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SortExpression, SortExpressionError};
+
+    fn evaluate(expression: &str, score: f64, views: f64) -> f64 {
+        let expression = expression
+            .parse::<SortExpression>()
+            .expect("expression should parse");
+        expression.evaluate(score, &|_| Some(views))
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3", 0.0, 0.0), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3", 0.0, 0.0), 9.0);
+        assert_eq!(evaluate("8 - 4 - 2", 0.0, 0.0), 2.0);
+        assert_eq!(evaluate("8 / 4 / 2", 0.0, 0.0), 1.0);
+        assert_eq!(evaluate("-2 * -score", 1.5, 0.0), 3.0);
+    }
+
+    #[test]
+    fn test_weighted_fields() {
+        let expression = "score * 0.7 + views / 1000.0 * 0.3"
+            .parse::<SortExpression>()
+            .unwrap();
+        assert_eq!(expression.fields(), vec!["views"]);
+        assert!(expression.uses_score());
+        assert_eq!(
+            expression.evaluate(2.0, &|_| Some(500.0)),
+            2.0 * 0.7 + 500.0 / 1000.0 * 0.3
+        );
+
+        // a field without a value counts as 0
+        assert_eq!(expression.evaluate(2.0, &|_| None), 2.0 * 0.7);
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        assert_eq!(evaluate("score / views", 3.0, 0.0), 0.0);
+        assert_eq!(evaluate("1 + score / (views - views)", 3.0, 2.0), 1.0);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert_eq!(
+            "".parse::<SortExpression>(),
+            Err(SortExpressionError::Empty)
+        );
+        assert_eq!(
+            "score +".parse::<SortExpression>(),
+            Err(SortExpressionError::UnexpectedEnd)
+        );
+        assert_eq!(
+            "score % 2".parse::<SortExpression>(),
+            Err(SortExpressionError::UnexpectedCharacter('%', 6))
+        );
+        assert_eq!(
+            "(score".parse::<SortExpression>(),
+            Err(SortExpressionError::Unclosed(0))
+        );
+        assert_eq!(
+            "1.2.3".parse::<SortExpression>(),
+            Err(SortExpressionError::InvalidNumber("1.2.3".into()))
+        );
+    }
+}
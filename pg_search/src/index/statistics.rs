@@ -0,0 +1,96 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::fieldnorm::id_to_fieldnorm;
+use tantivy::query::Bm25StatisticsProvider;
+use tantivy::schema::{Field, IndexRecordOption};
+use tantivy::{Searcher, Term};
+
+/// The statistics a [`Searcher`] scores documents with.
+///
+/// Tantivy's own count the documents deleted from a segment until a merge drops them, so a
+/// document's score can change when the segments around it merge, even though none of the
+/// documents that are still live have.  With `live_only`, these only count live documents, which
+/// leaves every such statistic just as it will be once the segments have merged.
+#[derive(Clone, Copy)]
+pub struct ScoringStatistics<'a> {
+    searcher: &'a Searcher,
+    live_only: bool,
+}
+
+impl<'a> ScoringStatistics<'a> {
+    pub fn new(searcher: &'a Searcher, live_only: bool) -> Self {
+        Self {
+            searcher,
+            live_only,
+        }
+    }
+}
+
+impl Bm25StatisticsProvider for ScoringStatistics<'_> {
+    fn total_num_tokens(&self, field: Field) -> tantivy::Result<u64> {
+        if !self.live_only {
+            return self.searcher.total_num_tokens(field);
+        }
+
+        // this is how a merge counts them, from the field norms of the live documents, which
+        // are only approximate but are all a merged segment keeps
+        let mut total_num_tokens = 0u64;
+        let mut counts = [0u64; 256];
+        for segment_reader in self.searcher.segment_readers() {
+            match segment_reader.get_fieldnorms_reader(field) {
+                Ok(fieldnorms) if segment_reader.has_deletes() => {
+                    for doc in segment_reader.doc_ids_alive() {
+                        counts[fieldnorms.fieldnorm_id(doc) as usize] += 1;
+                    }
+                }
+                _ => total_num_tokens += segment_reader.inverted_index(field)?.total_num_tokens(),
+            }
+        }
+
+        Ok(total_num_tokens
+            + counts
+                .iter()
+                .enumerate()
+                .map(|(fieldnorm_id, count)| count * id_to_fieldnorm(fieldnorm_id as u8) as u64)
+                .sum::<u64>())
+    }
+
+    fn total_num_docs(&self) -> tantivy::Result<u64> {
+        // already only counts live documents
+        self.searcher.total_num_docs()
+    }
+
+    fn doc_freq(&self, term: &Term) -> tantivy::Result<u64> {
+        if !self.live_only {
+            return self.searcher.doc_freq(term);
+        }
+
+        let mut doc_freq = 0u64;
+        for segment_reader in self.searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(term.field())?;
+            doc_freq += match segment_reader.alive_bitset() {
+                None => inverted_index.doc_freq(term)? as u64,
+                Some(alive_bitset) => inverted_index
+                    .read_postings(term, IndexRecordOption::Basic)?
+                    .map(|postings| postings.doc_freq_given_deletes(alive_bitset) as u64)
+                    .unwrap_or(0),
+            };
+        }
+        Ok(doc_freq)
+    }
+}
@@ -25,7 +25,10 @@ use crate::{
 use anyhow::{Context, Result};
 use once_cell::sync::Lazy;
 use std::sync::Arc;
-use std::{collections::HashSet, path::Path};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 use std::{fs, io, result};
 use tantivy::directory::{
     DirectoryClone, DirectoryLock, FileHandle, FileSlice, Lock, WatchCallback, WatchHandle,
@@ -110,6 +113,91 @@ impl Directory for BlockingDirectory {
     }
 }
 
+/// The file where tantivy records the segments that make up its most recent commit
+static META_FILE_NAME: &str = "meta.json";
+
+/// Each commit to the index is a new "generation", identified by the commit's opstamp.  We keep
+/// a copy of the `meta.json` tantivy wrote for that commit at this path.
+fn generation_meta_path(generation: u64) -> PathBuf {
+    PathBuf::from(format!("meta.{generation}.json"))
+}
+
+/// A tantivy::directory::Directory that presents the `meta.json` of an older generation of the
+/// index, so that opening a tantivy::Index over it sees the index exactly as it was then.
+///
+/// Older generations are only available for as long as their segment files are, which is until
+/// tantivy next garbage collects files no longer used by the latest generation, such as after a
+/// merge or a VACUUM.
+#[derive(Debug, Clone)]
+pub struct GenerationDirectory {
+    directory: MmapDirectory,
+    meta: Arc<Vec<u8>>,
+}
+
+impl GenerationDirectory {
+    pub fn open(directory_path: impl AsRef<Path>, generation: u64) -> Result<Self, IndexError> {
+        let directory =
+            MmapDirectory::open(directory_path).map_err(|e| IndexError::TantivyError(e.into()))?;
+        let meta = match directory.atomic_read(&generation_meta_path(generation)) {
+            Ok(meta) => meta,
+            Err(OpenReadError::FileDoesNotExist(_)) => {
+                return Err(IndexError::GenerationNotFound(generation))
+            }
+            Err(e) => return Err(IndexError::TantivyError(e.into())),
+        };
+
+        Ok(Self {
+            directory,
+            meta: Arc::new(meta),
+        })
+    }
+}
+
+impl Directory for GenerationDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        self.directory.get_file_handle(path)
+    }
+
+    fn open_read(&self, path: &Path) -> result::Result<FileSlice, OpenReadError> {
+        self.directory.open_read(path)
+    }
+
+    fn open_write(&self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
+        self.directory.open_write(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.directory.atomic_write(path, data)
+    }
+
+    fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
+        if path == Path::new(META_FILE_NAME) {
+            return Ok(self.meta.as_ref().clone());
+        }
+        self.directory.atomic_read(path)
+    }
+
+    fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
+        self.directory.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.directory.exists(path)
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> result::Result<DirectoryLock, LockError> {
+        self.directory.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tantivy::Result<WatchHandle> {
+        self.directory.watch(watch_callback)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.directory.sync_directory()
+    }
+}
+
 /// A global store of which indexes have been created during a transaction,
 /// so that they can be committed or rolled back in case of an abort.
 static mut PENDING_INDEX_CREATES: Lazy<HashSet<WriterDirectory>> = Lazy::new(HashSet::new);
@@ -163,12 +251,22 @@ impl SearchIndexWriter {
     }
 
     pub fn commit(&mut self) -> Result<()> {
-        self.underlying_writer
-            .as_mut()
-            .unwrap()
+        let writer = self.underlying_writer.as_mut().unwrap();
+        let generation = writer
             .commit()
             .context("error committing to tantivy index")?;
 
+        // keep this generation's metadata around so that it can be searched with `as_of`.
+        // tantivy manages the copy like any other file, and garbage collects it along with
+        // the segments it refers to once they're no longer used
+        let directory = writer.index().directory();
+        let meta = directory
+            .atomic_read(Path::new(META_FILE_NAME))
+            .context("error reading tantivy index metadata")?;
+        directory
+            .atomic_write(&generation_meta_path(generation), &meta)
+            .context("error saving tantivy index generation metadata")?;
+
         Ok(())
     }
 
@@ -260,4 +358,7 @@ pub enum IndexError {
 
     #[error("key_field column '{0}' cannot be NULL")]
     KeyIdNull(String),
+
+    #[error("generation {0} of the index is no longer available, as it has since been merged or vacuumed away")]
+    GenerationNotFound(u64),
 }
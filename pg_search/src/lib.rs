@@ -84,6 +84,8 @@ pub unsafe extern "C" fn _PG_init() {
     pgrx::hooks::register_hook(&mut TRACE_HOOK);
 
     customscan::register_rel_pathlist(customscan::pdbscan::PdbScan);
+    customscan::register_final_paths(customscan::pdbscan::PdbScan);
+    customscan::register_upper_path(customscan::aggregatescan::AggregateScan);
 }
 
 /// This module is required by `cargo pgrx test` invocations.
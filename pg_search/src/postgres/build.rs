@@ -83,10 +83,14 @@ pub extern "C" fn ambuild(
     };
 
     // Create a map from column name to column type. We'll use this to verify that index
-    // configurations passed by the user reference the correct types for each column.
-    let name_type_map: HashMap<SearchFieldName, SearchFieldType> = heap_relation
-        .tuple_desc()
-        .into_iter()
+    // configurations passed by the user reference the correct types for each column.  The
+    // index's own columns include the ones it computes from an expression, like `lower(title)`,
+    // which are named after it
+    let heap_tupdesc = heap_relation.tuple_desc();
+    let index_tupdesc = index_relation.tuple_desc();
+    let name_type_map: HashMap<SearchFieldName, SearchFieldType> = heap_tupdesc
+        .iter()
+        .chain(index_tupdesc.iter())
         .filter_map(|attribute| {
             let attname = attribute.name();
             let attribute_type_oid = attribute.type_oid();
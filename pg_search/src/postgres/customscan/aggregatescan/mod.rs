@@ -0,0 +1,481 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! A custom scan that answers `SELECT <field>, count(*) ... GROUP BY <field>` queries over a single
+//! table straight from the index, by counting the matching documents by their value of the field's
+//! fast field, without ever returning the rows themselves to be aggregated.
+//!
+//! It answers `SELECT DISTINCT <field>` queries the same way, and when they have a `LIMIT` but no
+//! `ORDER BY`, stops looking as soon as it has found enough distinct values.
+
+mod privdat;
+mod scan_state;
+
+use crate::api::operator::anyelement_query_input_opoid;
+use crate::index::SearchIndex;
+use crate::nodecast;
+use crate::postgres::customscan::aggregatescan::privdat::{describe_private, PrivateData};
+use crate::postgres::customscan::aggregatescan::scan_state::{AggregateColumn, AggregateScanState};
+use crate::postgres::customscan::builders::custom_path::{
+    flags_description, CustomPathBuilder, Flags,
+};
+use crate::postgres::customscan::builders::custom_scan::CustomScanBuilder;
+use crate::postgres::customscan::builders::custom_state::{
+    CustomScanStateBuilder, CustomScanStateWrapper,
+};
+use crate::postgres::customscan::explainer::Explainer;
+use crate::postgres::customscan::pdbscan::qual_inspect::extract_quals;
+use crate::postgres::customscan::CustomScan;
+use crate::postgres::index::open_search_index;
+use crate::postgres::rel_get_bm25_index;
+use crate::postgres::types::TantivyValue;
+use crate::postgres::visibility_checker::VisibilityChecker;
+use crate::query::SearchQueryInput;
+use crate::DEFAULT_STARTUP_COST;
+use pgrx::{pg_sys, FromDatum, IntoDatum, PgBuiltInOids, PgList, PgOid, PgRelation};
+use std::ffi::CStr;
+
+#[derive(Default)]
+pub struct AggregateScan;
+
+impl CustomScan for AggregateScan {
+    const NAME: &'static CStr = c"ParadeDB Aggregate Scan";
+    type State = AggregateScanState;
+    type PrivateData = PrivateData;
+
+    fn callback(mut builder: CustomPathBuilder<Self::PrivateData>) -> Option<pg_sys::CustomPath> {
+        unsafe {
+            let root = builder.args().root;
+            let parse = (*root).parse;
+            let rti = builder.args().rti;
+
+            // we only know how to group by a single column, and can't filter the groups
+            if !(*parse).groupingSets.is_null()
+                || !(*parse).havingQual.is_null()
+                || (*parse).hasWindowFuncs
+                || (*parse).hasTargetSRFs
+            {
+                return None;
+            }
+
+            // a `SELECT DISTINCT <column>` is grouped by that column just the same, and only
+            // returns the groups' values
+            let distinct =
+                (*parse).groupClause.is_null() && !(*parse).hasAggs && !(*parse).hasDistinctOn;
+            let group_clause = PgList::<pg_sys::SortGroupClause>::from_pg(if distinct {
+                (*parse).distinctClause
+            } else {
+                (*parse).groupClause
+            });
+            if group_clause.len() != 1 {
+                return None;
+            }
+            let group_var = nodecast!(
+                Var,
+                T_Var,
+                pg_sys::get_sortgroupclause_expr(group_clause.get_ptr(0)?, (*parse).targetList)
+            )?;
+            if (*group_var).varno as pg_sys::Index != rti
+                || (*group_var).varlevelsup != 0
+                || (*group_var).varattno <= 0
+            {
+                return None;
+            }
+
+            // the planner doesn't set the target of the rel that makes rows distinct, whose rows
+            // are those of the table, made distinct
+            let input_rel = *(*root).simple_rel_array.add(rti as usize);
+            if distinct {
+                builder = builder.set_path_target((*input_rel).reltarget);
+            }
+
+            // and all we can produce are the group's value and its `count(*)`
+            let exprs = PgList::<pg_sys::Node>::from_pg((*builder.path_target()).exprs);
+            for expr in exprs.iter_ptr() {
+                let is_group_var = nodecast!(Var, T_Var, expr).is_some_and(|var| {
+                    (*var).varno == (*group_var).varno
+                        && (*var).varattno == (*group_var).varattno
+                        && (*var).varlevelsup == 0
+                });
+                if !is_group_var && !is_count_star(expr) {
+                    return None;
+                }
+            }
+
+            let (table, bm25_index) = {
+                let rte = builder.args().rte();
+
+                // of a plain relation, without any inheritance children
+                if rte.rtekind != pg_sys::RTEKind::RTE_RELATION || rte.inh {
+                    return None;
+                }
+                let relkind = pg_sys::get_rel_relkind(rte.relid) as u8;
+                if relkind != pg_sys::RELKIND_RELATION && relkind != pg_sys::RELKIND_MATVIEW {
+                    return None;
+                }
+
+                // that has a `USING bm25` index, which has every row
+                let (table, bm25_index) = rel_get_bm25_index(rte.relid)?;
+                if !pg_sys::RelationGetIndexPredicate(bm25_index.as_ptr()).is_null() {
+                    return None;
+                }
+
+                (table, bm25_index)
+            };
+
+            // the group column must be a fast field we can read the exact value of.  Numeric and
+            // boolean fast fields read as a default value for rows that have none, so their
+            // columns can't hold NULLs either
+            let search_index =
+                open_search_index(&bm25_index).expect("should be able to open search index");
+            let tupdesc = table.tuple_desc();
+            let att = tupdesc.get((*group_var).varattno as usize - 1)?;
+            let group_field = att.name().to_string();
+            let supported_type = match PgOid::from(att.atttypid) {
+                PgOid::BuiltIn(PgBuiltInOids::TEXTOID | PgBuiltInOids::VARCHAROID) => true,
+                PgOid::BuiltIn(
+                    PgBuiltInOids::BOOLOID
+                    | PgBuiltInOids::INT2OID
+                    | PgBuiltInOids::INT4OID
+                    | PgBuiltInOids::INT8OID,
+                ) => att.attnotnull,
+                _ => false,
+            };
+            if !supported_type || !search_index.schema.is_field_raw_sortable(&group_field) {
+                return None;
+            }
+
+            // and every one of the table's quals must become part of the search, which has to
+            // be known while planning, so they can't compare against Params
+            let restrict_info =
+                PgList::<pg_sys::RestrictInfo>::from_pg((*input_rel).baserestrictinfo);
+            if restrict_info.is_empty() {
+                return None;
+            }
+            let quals = extract_quals(
+                rti,
+                restrict_info.as_ptr().cast(),
+                anyelement_query_input_opoid(),
+                &table,
+                &search_index.schema,
+            )
+            .filter(|quals| !quals.lacks_search_operator())
+            .filter(|quals| quals.params().is_empty() && quals.external_exprs().is_empty())?;
+
+            builder.custom_private().set_heaprelid(table.oid());
+            builder.custom_private().set_indexrelid(bm25_index.oid());
+            builder.custom_private().set_group_field(group_field);
+            builder
+                .custom_private()
+                .set_query(SearchQueryInput::from(quals));
+
+            // without an ORDER BY, any of the distinct values will do for a LIMIT, so we can stop
+            // once we've found as many as it needs
+            let limit = if distinct && (*parse).sortClause.is_null() {
+                const_limit(parse)
+            } else {
+                None
+            };
+            builder.custom_private().set_limit(limit);
+
+            // every matching document is counted before the first group is returned, but that's
+            // still far less work than returning every matching row to be aggregated.  With a
+            // limit, we expect to find the distinct values we need sooner
+            let groups = PgList::<pg_sys::Path>::from_pg(builder.args().rel().pathlist)
+                .get_ptr(0)
+                .map(|path| (*path).rows)
+                .unwrap_or(1.0);
+            let matching_rows = match limit {
+                Some(limit) => (*input_rel).rows * (limit as f64 / groups.max(1.0)).min(1.0),
+                None => (*input_rel).rows,
+            };
+            let startup_cost = DEFAULT_STARTUP_COST + matching_rows * pg_sys::cpu_index_tuple_cost;
+            let total_cost = startup_cost + groups * pg_sys::cpu_tuple_cost;
+
+            builder = builder.set_rows(groups);
+            builder = builder.set_startup_cost(startup_cost);
+            builder = builder.set_total_cost(total_cost);
+            builder = builder.set_flag(Flags::Projection);
+
+            Some(builder.build())
+        }
+    }
+
+    fn plan_custom_path(builder: CustomScanBuilder<Self::PrivateData>) -> pg_sys::CustomScan {
+        unsafe {
+            // the tuples we produce are the grouped rows themselves, which the target list
+            // will then refer to
+            let scan_tlist = pg_sys::copyObjectImpl(builder.args().tlist.as_ptr().cast());
+            builder.set_scan_tlist(scan_tlist.cast()).build()
+        }
+    }
+
+    fn create_custom_scan_state(
+        mut builder: CustomScanStateBuilder<Self, Self::PrivateData>,
+    ) -> *mut CustomScanStateWrapper<Self> {
+        unsafe {
+            let heaprelid = builder.custom_private().heaprelid();
+            let indexrelid = builder.custom_private().indexrelid();
+            let group_field = builder.custom_private().group_field().to_string();
+            let search_query_input = builder.custom_private().query().clone();
+            let limit = builder.custom_private().limit();
+            let index_name = PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _)
+                .name()
+                .to_string();
+
+            let scan_tlist =
+                PgList::<pg_sys::TargetEntry>::from_pg((*builder.args().cscan).custom_scan_tlist);
+            let columns = scan_tlist
+                .iter_ptr()
+                .map(|te| match nodecast!(Var, T_Var, (*te).expr) {
+                    Some(var) => AggregateColumn::GroupValue((*var).vartype),
+                    None => AggregateColumn::Count,
+                })
+                .collect::<Vec<_>>();
+            let streaming = limit.is_none()
+                && columns.iter().any(|column| {
+                    matches!(column, AggregateColumn::GroupValue(typoid)
+                        if [pg_sys::TEXTOID, pg_sys::VARCHAROID].contains(typoid))
+                });
+
+            builder.custom_state().heaprelid = heaprelid;
+            builder.custom_state().indexrelid = indexrelid;
+            builder.custom_state().index_name = index_name;
+            builder.custom_state().group_field = group_field;
+            builder.custom_state().search_query_input = search_query_input;
+            builder.custom_state().columns = columns;
+            builder.custom_state().limit = limit;
+            builder.custom_state().streaming = streaming;
+            builder.build()
+        }
+    }
+
+    fn explain_custom_scan(
+        state: &CustomScanStateWrapper<Self>,
+        ancestors: *mut pg_sys::List,
+        explainer: &mut Explainer,
+    ) {
+        explainer.add_text("Table", state.custom_state().heaprelname());
+        explainer.add_text("Index", &state.custom_state().index_name);
+        explainer.add_text("Custom Flags", flags_description(state.csstate.flags));
+        explainer.add_text("Group Field", &state.custom_state().group_field);
+        if let Some(limit) = state.custom_state().limit {
+            explainer.add_unsigned_integer("Distinct Limit", limit as u64, None);
+        }
+        if state.custom_state().streaming {
+            explainer.add_bool("Streaming Groups", true);
+        }
+        if explainer.is_analyze() {
+            explainer.add_unsigned_integer("Groups", state.custom_state().group_count as u64, None);
+            if state.custom_state().limit.is_some() {
+                explainer.add_unsigned_integer(
+                    "   Documents Visited",
+                    state.custom_state().documents_visited as u64,
+                    None,
+                );
+            }
+        }
+
+        let query = &state.custom_state().search_query_input;
+        let pretty_json = if explainer.is_verbose() {
+            serde_json::to_string_pretty(&query)
+        } else {
+            serde_json::to_string(&query)
+        }
+        .expect("query should serialize to json");
+        explainer.add_text("Tantivy Query", &pretty_json);
+    }
+
+    fn describe_private(private: &Self::PrivateData) -> String {
+        describe_private(private)
+    }
+
+    fn begin_custom_scan(
+        state: &mut CustomScanStateWrapper<Self>,
+        estate: *mut pg_sys::EState,
+        eflags: i32,
+    ) {
+        // ExecInitCustomScan already made our scan tuple slot from the scan's target list, so
+        // there's only the heap relation to open, which we need for mvcc checking
+        unsafe {
+            let heaprel =
+                pg_sys::relation_open(state.custom_state().heaprelid, pg_sys::AccessShareLock as _);
+            state.custom_state_mut().heaprel = Some(heaprel);
+        }
+
+        if eflags & (pg_sys::EXEC_FLAG_EXPLAIN_ONLY as i32) != 0 {
+            // don't do anything else if we're only explaining the query
+            return;
+        }
+
+        AggregateScan::rescan_custom_scan(state)
+    }
+
+    fn exec_custom_scan(state: &mut CustomScanStateWrapper<Self>) -> *mut pg_sys::TupleTableSlot {
+        let Some((value, count)) = state
+            .custom_state_mut()
+            .groups
+            .as_mut()
+            .and_then(|groups| groups.next())
+        else {
+            return std::ptr::null_mut();
+        };
+        state.custom_state_mut().group_count += 1;
+
+        unsafe {
+            let slot = state.scanslot();
+            if let Some(clear) = (*(*slot).tts_ops).clear {
+                clear(slot);
+            }
+
+            let natts = state.custom_state().columns.len();
+            let values = std::slice::from_raw_parts_mut((*slot).tts_values, natts);
+            let isnull = std::slice::from_raw_parts_mut((*slot).tts_isnull, natts);
+            for (i, column) in state.custom_state().columns.iter().enumerate() {
+                let datum = match column {
+                    AggregateColumn::GroupValue(typoid) => value.clone().and_then(|value| {
+                        value
+                            .try_into_datum(PgOid::from(*typoid))
+                            .unwrap_or_else(|e| panic!("{e}"))
+                    }),
+                    AggregateColumn::Count => count.into_datum(),
+                };
+                values[i] = datum.unwrap_or(pg_sys::Datum::null());
+                isnull[i] = datum.is_none();
+            }
+            pg_sys::ExecStoreVirtualTuple(slot);
+
+            let projection_info = state.projection_info();
+            if projection_info.is_null() {
+                // the target list is the scan's tuples as they are
+                return slot;
+            }
+            (*(*projection_info).pi_exprContext).ecxt_scantuple = slot;
+            pg_sys::ExecProject(projection_info)
+        }
+    }
+
+    fn shutdown_custom_scan(state: &mut CustomScanStateWrapper<Self>) {}
+
+    fn end_custom_scan(state: &mut CustomScanStateWrapper<Self>) {
+        drop(state.custom_state_mut().groups.take());
+
+        if let Some(heaprel) = state.custom_state_mut().heaprel.take() {
+            unsafe {
+                pg_sys::relation_close(heaprel, pg_sys::AccessShareLock as _);
+            }
+        }
+    }
+
+    fn rescan_custom_scan(state: &mut CustomScanStateWrapper<Self>) {
+        let indexrel = unsafe {
+            PgRelation::with_lock(
+                state.custom_state().indexrelid,
+                pg_sys::AccessShareLock as _,
+            )
+        };
+        let search_index =
+            open_search_index(&indexrel).expect("should be able to open search index");
+        let search_reader = search_index.get_reader().unwrap_or_else(|e| panic!("{e}"));
+        let query = search_index.query(&state.custom_state().search_query_input, &search_reader);
+
+        // dead rows might still be in the index, so only those we can see are counted
+        let mut visibility_checker = unsafe {
+            VisibilityChecker::with_rel_and_snap(
+                state.custom_state().heaprel(),
+                pg_sys::GetActiveSnapshot(),
+            )
+        };
+        let is_visible = |ctid| {
+            visibility_checker
+                .exec_if_visible(ctid, |_, _, _| ())
+                .is_some()
+        };
+        let group_field = &state.custom_state().group_field;
+        let streaming = state.custom_state().streaming;
+        let groups: Box<dyn Iterator<Item = (Option<TantivyValue>, i64)>> =
+            match state.custom_state().limit {
+                // a `SELECT DISTINCT` doesn't return the counts, so there's no need to finish them
+                Some(limit) => {
+                    let (values, visited) = search_reader
+                        .distinct_values(query.as_ref(), group_field, limit, is_visible)
+                        .unwrap_or_else(|e| panic!("{e}"));
+                    state.custom_state_mut().documents_visited = visited;
+                    Box::new(values.into_iter().map(|value| (value, 0)))
+                }
+                // a text group field is counted by term ordinal, whose values are only looked up
+                // as each group is returned
+                None if streaming => Box::new(
+                    search_reader
+                        .ordered_count_by_field(query.as_ref(), group_field, is_visible)
+                        .unwrap_or_else(|e| panic!("{e}"))
+                        .unwrap_or_else(|| panic!("{group_field} should be a text fast field")),
+                ),
+                None => Box::new(
+                    search_reader
+                        .count_by_field(
+                            SearchIndex::executor(),
+                            query.as_ref(),
+                            group_field,
+                            is_visible,
+                        )
+                        .unwrap_or_else(|e| panic!("{e}"))
+                        .into_iter(),
+                ),
+            };
+
+        state.custom_state_mut().group_count = 0;
+        state.custom_state_mut().groups = Some(groups);
+    }
+}
+
+unsafe fn const_limit(parse: *mut pg_sys::Query) -> Option<usize> {
+    if (*parse).limitOption == pg_sys::LimitOption::LIMIT_OPTION_WITH_TIES {
+        return None;
+    }
+    let const_value = |node: *mut pg_sys::Node| match nodecast!(Const, T_Const, node) {
+        Some(constant) if !(*constant).constisnull => {
+            i64::from_datum((*constant).constvalue, false).map(|value| value.max(0) as usize)
+        }
+        _ => None,
+    };
+
+    // `LIMIT ALL` and `LIMIT NULL` don't limit anything
+    let count = const_value((*parse).limitCount)?;
+    let offset = if (*parse).limitOffset.is_null() {
+        0
+    } else {
+        const_value((*parse).limitOffset)?
+    };
+    Some(count + offset)
+}
+
+/// The oid of the built-in `count(*)` aggregate, as assigned in `pg_proc.dat`, which is the same
+/// in every Postgres version
+const COUNT_STAR_OID: u32 = 2803;
+
+/// Is the node a call of the built-in `count(*)`, and nothing more?  A `count` function of some
+/// other schema is not
+unsafe fn is_count_star(node: *mut pg_sys::Node) -> bool {
+    let Some(aggref) = nodecast!(Aggref, T_Aggref, node) else {
+        return false;
+    };
+    (*aggref).aggstar
+        && (*aggref).aggfilter.is_null()
+        && (*aggref).aggdistinct.is_null()
+        && (*aggref).aggfnoid.as_u32() == COUNT_STAR_OID
+}
@@ -0,0 +1,128 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::api::AsCStr;
+use crate::query::SearchQueryInput;
+use pgrx::pg_sys::AsPgCStr;
+use pgrx::{pg_sys, PgList};
+use serde::{Deserialize, Serialize};
+
+/// None of what we need to remember between planning and execution is an expression Postgres
+/// itself has to look at, so it's all kept in a single String node, as json
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct PrivateData {
+    heaprelid: u32,
+    indexrelid: u32,
+    group_field: String,
+    query: SearchQueryInput,
+    limit: Option<usize>,
+}
+
+impl From<*mut pg_sys::List> for PrivateData {
+    fn from(list: *mut pg_sys::List) -> Self {
+        unsafe {
+            let list = PgList::<pg_sys::Node>::from_pg(list);
+            let json = list.get_ptr(0).and_then(|node| {
+                node.as_c_str().map(|json| {
+                    json.to_str()
+                        .expect("private data should be valid utf8")
+                        .to_string()
+                })
+            });
+            match json {
+                Some(json) => {
+                    serde_json::from_str(&json).expect("private data should deserialize from json")
+                }
+                None => PrivateData::default(),
+            }
+        }
+    }
+}
+
+impl From<PrivateData> for *mut pg_sys::List {
+    fn from(value: PrivateData) -> Self {
+        unsafe {
+            let json =
+                serde_json::to_string(&value).expect("private data should serialize to json");
+            let mut list = PgList::<pg_sys::Node>::new();
+            list.push(pg_sys::makeString(json.as_pg_cstr()).cast());
+            list.into_pg()
+        }
+    }
+}
+
+//
+// setter functions
+//
+
+impl PrivateData {
+    pub fn set_heaprelid(&mut self, oid: pg_sys::Oid) {
+        self.heaprelid = oid.as_u32();
+    }
+
+    pub fn set_indexrelid(&mut self, oid: pg_sys::Oid) {
+        self.indexrelid = oid.as_u32();
+    }
+
+    pub fn set_group_field(&mut self, field: String) {
+        self.group_field = field;
+    }
+
+    pub fn set_query(&mut self, query: SearchQueryInput) {
+        self.query = query;
+    }
+
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+}
+
+//
+// getter functions
+//
+
+impl PrivateData {
+    pub fn heaprelid(&self) -> pg_sys::Oid {
+        self.heaprelid.into()
+    }
+
+    pub fn indexrelid(&self) -> pg_sys::Oid {
+        self.indexrelid.into()
+    }
+
+    pub fn group_field(&self) -> &str {
+        &self.group_field
+    }
+
+    pub fn query(&self) -> &SearchQueryInput {
+        &self.query
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+}
+
+/// A one-line summary of what the scan was planned to do, decoded from its private data
+pub fn describe_private(privdat: &PrivateData) -> String {
+    let query = serde_json::to_string(&privdat.query).expect("query should serialize to json");
+    let mut description = format!("query={query}, group_field={}", privdat.group_field);
+    if let Some(limit) = privdat.limit {
+        description.push_str(&format!(", limit={limit}"));
+    }
+    description
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::postgres::customscan::CustomScanState;
+use crate::postgres::types::TantivyValue;
+use crate::query::SearchQueryInput;
+use pgrx::{name_data_to_str, pg_sys};
+
+/// What each column of the tuples we produce holds
+#[derive(Debug, Copy, Clone)]
+pub enum AggregateColumn {
+    /// The value we're grouping by, as the type of the column it comes from
+    GroupValue(pg_sys::Oid),
+    /// The `count(*)` of the group
+    Count,
+}
+
+#[derive(Default)]
+pub struct AggregateScanState {
+    pub heaprelid: pg_sys::Oid,
+    pub indexrelid: pg_sys::Oid,
+
+    pub index_name: String,
+    pub group_field: String,
+    pub search_query_input: SearchQueryInput,
+    pub columns: Vec<AggregateColumn>,
+    /// For a `SELECT DISTINCT`, the most values its `LIMIT` can need
+    pub limit: Option<usize>,
+    /// Whether the groups are counted by term ordinal and returned in the order of their values,
+    /// rather than gathered by value up front, which is how a text group field is counted
+    pub streaming: bool,
+
+    pub heaprel: Option<pg_sys::Relation>,
+
+    /// The count of every group, which we return one at a time
+    pub groups: Option<Box<dyn Iterator<Item = (Option<TantivyValue>, i64)>>>,
+    /// How many groups we've returned
+    pub group_count: usize,
+    pub documents_visited: usize,
+}
+
+impl CustomScanState for AggregateScanState {}
+
+impl AggregateScanState {
+    #[inline(always)]
+    pub fn heaprel(&self) -> pg_sys::Relation {
+        self.heaprel.unwrap()
+    }
+
+    pub fn heaprelname(&self) -> &str {
+        unsafe { name_data_to_str(&(*(*self.heaprel()).rd_rel).relname) }
+    }
+}
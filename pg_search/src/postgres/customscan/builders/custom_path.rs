@@ -16,6 +16,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use crate::api::Cardinality;
+use crate::index::reader::SortReduction;
 use crate::postgres::customscan::CustomScan;
 use pgrx::{pg_sys, PgList};
 use std::collections::HashSet;
@@ -81,6 +82,12 @@ impl From<SortDirection> for u32 {
 pub enum OrderByStyle {
     Score(*mut pg_sys::PathKey),
     Field(*mut pg_sys::PathKey, String),
+    /// Sorted by the value of a `paradedb.sort_expression()` call with the given expression text
+    Expression(*mut pg_sys::PathKey, String),
+    /// Sorted by the smallest or largest of the values of a multivalued field
+    ReducedField(*mut pg_sys::PathKey, String, SortReduction),
+    /// Sorted by the values of several fields, one after the other, each with its own pathkey
+    Fields(Vec<(*mut pg_sys::PathKey, String)>),
 }
 
 impl OrderByStyle {
@@ -88,6 +95,9 @@ impl OrderByStyle {
         match self {
             OrderByStyle::Score(pathkey) => *pathkey,
             OrderByStyle::Field(pathkey, _) => *pathkey,
+            OrderByStyle::Expression(pathkey, _) => *pathkey,
+            OrderByStyle::ReducedField(pathkey, ..) => *pathkey,
+            OrderByStyle::Fields(keys) => keys[0].0,
         }
     }
 
@@ -99,6 +109,16 @@ impl OrderByStyle {
             (*self.pathkey()).pk_strategy.into()
         }
     }
+
+    /// Whether the rows without a value are sorted before those with one
+    pub fn nulls_first(&self) -> bool {
+        unsafe {
+            let pathkey = self.pathkey();
+            assert!(!pathkey.is_null());
+
+            (*pathkey).pk_nulls_first
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -136,6 +156,26 @@ pub enum Flags {
     Projection = 0x0004,
 }
 
+impl Flags {
+    const ALL: [Flags; 3] = [Flags::BackwardScan, Flags::MarkRestore, Flags::Projection];
+}
+
+/// Describe the [`Flags`] that are set in the `flags` bitmask [`CustomPathBuilder::build()`]
+/// produced, such as "BackwardScan, Projection", or "None" if none are set
+pub fn flags_description(flags: u32) -> String {
+    let names = Flags::ALL
+        .iter()
+        .filter(|flag| flags & **flag as u32 != 0)
+        .map(|flag| format!("{flag:?}"))
+        .collect::<Vec<_>>();
+
+    if names.is_empty() {
+        "None".into()
+    } else {
+        names.join(", ")
+    }
+}
+
 pub struct CustomPathBuilder<P: Into<*mut pg_sys::List> + Default> {
     args: Args,
     flags: HashSet<Flags>,
@@ -206,11 +246,83 @@ impl<P: Into<*mut pg_sys::List> + Default> CustomPathBuilder<P> {
     }
 
     pub fn path_target(&self) -> *mut pg_sys::PathTarget {
-        self.args.rel().reltarget
+        self.custom_path_node.path.pathtarget
     }
 
     pub fn limit(&self) -> i32 {
-        unsafe { (*self.args().root).limit_tuples.round() as i32 }
+        self.limit_tuples().round() as i32
+    }
+
+    /// The most rows the query needs from us, or `-1` if there's no such limit.
+    ///
+    /// This is the planner's own `limit_tuples`, except for a subquery that's only projected, and
+    /// then limited, by the query around it, like `SELECT id, rating * 2 FROM (SELECT ... ORDER BY
+    /// rating) s LIMIT 5`.  The subquery isn't pulled up into the outer query when it has an ORDER
+    /// BY, so it's planned on its own without a limit, but the first rows it returns are the only
+    /// ones the outer query keeps.
+    pub fn limit_tuples(&self) -> f64 {
+        unsafe {
+            let root = self.args.root;
+            if (*root).limit_tuples > -1.0 {
+                return (*root).limit_tuples;
+            }
+
+            let parent_root = (*root).parent_root;
+            if parent_root.is_null() || (*parent_root).limit_tuples <= -1.0 {
+                return -1.0;
+            }
+
+            // our own query must not be grouped or aggregated, as then there's no telling how
+            // many of our rows make up the rows the outer query limits
+            let parse = (*root).parse;
+            if !(*parse).groupClause.is_null()
+                || !(*parse).groupingSets.is_null()
+                || !(*parse).distinctClause.is_null()
+                || !(*parse).havingQual.is_null()
+                || (*parse).hasAggs
+                || (*parse).hasWindowFuncs
+                || (*parse).hasTargetSRFs
+                || !(*parse).setOperations.is_null()
+            {
+                return -1.0;
+            }
+
+            // and the outer query must do nothing but project our rows, in our order, before it
+            // limits them: it reads from our subquery alone, with no conditions and no ORDER BY
+            // of its own
+            let outer = (*parent_root).parse;
+            if !(*outer).sortClause.is_null()
+                || !(*outer).setOperations.is_null()
+                || (*outer).jointree.is_null()
+                || !(*(*outer).jointree).quals.is_null()
+            {
+                return -1.0;
+            }
+            let fromlist = PgList::<pg_sys::Node>::from_pg((*(*outer).jointree).fromlist);
+            let only_subquery = match fromlist.get_ptr(0) {
+                Some(node)
+                    if fromlist.len() == 1
+                        && (*node).type_ == pg_sys::NodeTag::T_RangeTblRef
+                        && !(*parent_root).simple_rel_array.is_null() =>
+                {
+                    // the outer query's relations are set up just before its subqueries in FROM
+                    // are planned, and a subquery's relation only gets its plan afterwards, which
+                    // is how we know we're that subquery, and not one of an expression
+                    let rtindex = (*node.cast::<pg_sys::RangeTblRef>()).rtindex;
+                    let rel = *(*parent_root).simple_rel_array.add(rtindex as usize);
+                    !rel.is_null()
+                        && (*rel).rtekind == pg_sys::RTEKind::RTE_SUBQUERY
+                        && (*rel).subroot.is_null()
+                }
+                _ => false,
+            };
+
+            if only_subquery {
+                (*parent_root).limit_tuples
+            } else {
+                -1.0
+            }
+        }
     }
 
     //
@@ -251,12 +363,33 @@ impl<P: Into<*mut pg_sys::List> + Default> CustomPathBuilder<P> {
         self
     }
 
+    /// Produce the columns of `target` rather than those of the relation the path is for, which
+    /// is what an upper relation whose own target isn't set needs
+    pub fn set_path_target(mut self, target: *mut pg_sys::PathTarget) -> Self {
+        self.custom_path_node.path.pathtarget = target;
+        self
+    }
+
+    /// Make this a path that's only usable inside a nested loop, which supplies the values of the
+    /// columns of the outer relations the path depends on
+    pub fn set_param_info(mut self, param_info: *mut pg_sys::ParamPathInfo) -> Self {
+        self.custom_path_node.path.param_info = param_info;
+        self
+    }
+
     pub fn add_path_key(mut self, pathkey: &Option<OrderByStyle>) -> Self {
         unsafe {
             if let Some(style) = pathkey {
                 let mut pklist =
                     PgList::<pg_sys::PathKey>::from_pg(self.custom_path_node.path.pathkeys);
-                pklist.push(style.pathkey());
+                match style {
+                    OrderByStyle::Fields(keys) => {
+                        for (pathkey, _) in keys {
+                            pklist.push(*pathkey);
+                        }
+                    }
+                    _ => pklist.push(style.pathkey()),
+                }
 
                 self.custom_path_node.path.pathkeys = pklist.into_pg();
             }
@@ -275,3 +408,30 @@ impl<P: Into<*mut pg_sys::List> + Default> CustomPathBuilder<P> {
         self.custom_path_node
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_description() {
+        assert_eq!(flags_description(0), "None");
+        assert_eq!(flags_description(Flags::Projection as u32), "Projection");
+        assert_eq!(
+            flags_description(Flags::BackwardScan as u32 | Flags::Projection as u32),
+            "BackwardScan, Projection"
+        );
+        assert_eq!(
+            flags_description(
+                Flags::BackwardScan as u32 | Flags::MarkRestore as u32 | Flags::Projection as u32
+            ),
+            "BackwardScan, MarkRestore, Projection"
+        );
+
+        // bits that aren't one of our flags are ignored
+        assert_eq!(
+            flags_description(u32::MAX),
+            "BackwardScan, MarkRestore, Projection"
+        );
+    }
+}
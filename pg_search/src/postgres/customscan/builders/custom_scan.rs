@@ -113,6 +113,21 @@ impl<P: Into<*mut pg_sys::List> + From<*mut pg_sys::List> + Default> CustomScanB
         &mut self.custom_private
     }
 
+    /// Describe the tuples the scan produces, for a scan that doesn't return rows of the relation
+    /// it's planned for, such as one above a grouping.  The target list then refers to them.
+    pub fn set_scan_tlist(mut self, tlist: *mut pg_sys::List) -> Self {
+        self.custom_scan_node.custom_scan_tlist = tlist;
+        self
+    }
+
+    /// Expressions the executor evaluates for the scan.  Unlike `custom_private`, the planner
+    /// prepares them for execution, such as by replacing the columns of the outer relations of a
+    /// nested loop with the Params that carry their values
+    pub fn set_custom_exprs(mut self, exprs: *mut pg_sys::List) -> Self {
+        self.custom_scan_node.custom_exprs = exprs;
+        self
+    }
+
     pub fn build(self) -> pg_sys::CustomScan {
         let mut node = self.custom_scan_node;
         node.custom_private = self.custom_private.into();
@@ -102,10 +102,13 @@ pub extern "C" fn explain_custom_scan<CS: CustomScan>(
 ) {
     let custom_state = custom_state::<CS>(node);
     unsafe {
-        CS::explain_custom_scan(
-            custom_state.as_ref(),
-            ancestors,
-            &mut Explainer::new(es).expect("`ExplainState` should not be null"),
-        )
+        let mut explainer = Explainer::new(es).expect("`ExplainState` should not be null");
+        CS::explain_custom_scan(custom_state.as_ref(), ancestors, &mut explainer);
+
+        if explainer.is_verbose() {
+            let cscan = (*node).ss.ps.plan.cast::<pg_sys::CustomScan>();
+            let private = CS::PrivateData::from((*cscan).custom_private);
+            explainer.add_text("Private Data", CS::describe_private(&private));
+        }
     }
 }
@@ -18,6 +18,7 @@
 use crate::gucs;
 use crate::postgres::customscan::builders::custom_path::CustomPathBuilder;
 use crate::postgres::customscan::CustomScan;
+use crate::postgres::parallel;
 use once_cell::sync::Lazy;
 use pgrx::{pg_guard, pg_sys, PgMemoryContexts};
 use rustc_hash::FxHashMap;
@@ -66,14 +67,122 @@ pub extern "C" fn paradedb_rel_pathlist_callback<CS: CustomScan>(
     rte: *mut pg_sys::RangeTblEntry,
 ) {
     unsafe {
+        parallel::limit_parallel_workers(rel);
+
         if !gucs::enable_custom_scan() {
             return;
         }
 
-        if let Some(mut path) = CS::callback(CustomPathBuilder::new::<CS>(root, rel, rti, rte)) {
+        let paths = [
+            CS::callback(CustomPathBuilder::new::<CS>(root, rel, rti, rte)),
+            CS::parameterized_callback(CustomPathBuilder::new::<CS>(root, rel, rti, rte)),
+        ];
+        for mut path in paths.into_iter().flatten() {
             let path = PgMemoryContexts::CurrentMemoryContext
                 .copy_ptr_into(&mut path, std::mem::size_of_val(&path));
             pg_sys::add_path(rel, path.cast());
         }
     }
 }
+
+pub fn register_upper_path<CS: CustomScan + 'static>(_: CS) {
+    unsafe {
+        static mut PREV_HOOKS: Lazy<
+            FxHashMap<std::any::TypeId, pg_sys::create_upper_paths_hook_type>,
+        > = Lazy::new(Default::default);
+
+        #[pg_guard]
+        extern "C" fn __priv_callback<CS: CustomScan + 'static>(
+            root: *mut pg_sys::PlannerInfo,
+            stage: pg_sys::UpperRelationKind::Type,
+            input_rel: *mut pg_sys::RelOptInfo,
+            output_rel: *mut pg_sys::RelOptInfo,
+            extra: *mut std::ffi::c_void,
+        ) {
+            unsafe {
+                if let Some(Some(prev_hook)) = PREV_HOOKS.get(&std::any::TypeId::of::<CS>()) {
+                    (*prev_hook)(root, stage, input_rel, output_rel, extra);
+                }
+
+                paradedb_upper_path_callback::<CS>(root, stage, input_rel, output_rel);
+            }
+        }
+
+        match PREV_HOOKS.entry(std::any::TypeId::of::<CS>()) {
+            Entry::Occupied(_) => panic!("{} is already registered", std::any::type_name::<CS>()),
+            Entry::Vacant(entry) => entry.insert(pg_sys::create_upper_paths_hook),
+        };
+
+        pg_sys::create_upper_paths_hook = Some(__priv_callback::<CS>);
+    }
+}
+
+/// Called when the planner builds the paths that group and aggregate, or make distinct, the rows
+/// of `input_rel` into `output_rel`.  We only offer paths that do so for a single base relation,
+/// and the [`CustomPathBuilder`] is made for `output_rel` with the range table index of that
+/// relation.
+#[pg_guard]
+pub extern "C" fn paradedb_upper_path_callback<CS: CustomScan>(
+    root: *mut pg_sys::PlannerInfo,
+    stage: pg_sys::UpperRelationKind::Type,
+    input_rel: *mut pg_sys::RelOptInfo,
+    output_rel: *mut pg_sys::RelOptInfo,
+) {
+    unsafe {
+        if !gucs::enable_custom_scan() {
+            return;
+        }
+
+        if (stage != pg_sys::UpperRelationKind::UPPERREL_GROUP_AGG
+            && stage != pg_sys::UpperRelationKind::UPPERREL_DISTINCT)
+            || (*input_rel).reloptkind != pg_sys::RelOptKind::RELOPT_BASEREL
+        {
+            return;
+        }
+
+        let rti = (*input_rel).relid;
+        let rte = *(*root).simple_rte_array.add(rti as usize);
+        if let Some(mut path) =
+            CS::callback(CustomPathBuilder::new::<CS>(root, output_rel, rti, rte))
+        {
+            let path = PgMemoryContexts::CurrentMemoryContext
+                .copy_ptr_into(&mut path, std::mem::size_of_val(&path));
+            pg_sys::add_path(output_rel, path.cast());
+        }
+    }
+}
+
+pub fn register_final_paths<CS: CustomScan + 'static>(_: CS) {
+    unsafe {
+        static mut PREV_HOOKS: Lazy<
+            FxHashMap<std::any::TypeId, pg_sys::create_upper_paths_hook_type>,
+        > = Lazy::new(Default::default);
+
+        #[pg_guard]
+        extern "C" fn __priv_callback<CS: CustomScan + 'static>(
+            root: *mut pg_sys::PlannerInfo,
+            stage: pg_sys::UpperRelationKind::Type,
+            input_rel: *mut pg_sys::RelOptInfo,
+            output_rel: *mut pg_sys::RelOptInfo,
+            extra: *mut std::ffi::c_void,
+        ) {
+            unsafe {
+                if let Some(Some(prev_hook)) = PREV_HOOKS.get(&std::any::TypeId::of::<CS>()) {
+                    (*prev_hook)(root, stage, input_rel, output_rel, extra);
+                }
+
+                if stage == pg_sys::UpperRelationKind::UPPERREL_FINAL && gucs::enable_custom_scan()
+                {
+                    CS::final_paths_callback(root, output_rel);
+                }
+            }
+        }
+
+        match PREV_HOOKS.entry(std::any::TypeId::of::<CS>()) {
+            Entry::Occupied(_) => panic!("{} is already registered", std::any::type_name::<CS>()),
+            Entry::Vacant(entry) => entry.insert(pg_sys::create_upper_paths_hook),
+        };
+
+        pg_sys::create_upper_paths_hook = Some(__priv_callback::<CS>);
+    }
+}
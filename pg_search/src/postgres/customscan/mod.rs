@@ -21,7 +21,10 @@
 #![allow(dead_code)]
 #![allow(clippy::tabs_in_doc_comments)]
 
+use once_cell::sync::Lazy;
 use pgrx::{pg_sys, PgMemoryContexts};
+use rustc_hash::FxHashMap;
+use std::any::TypeId;
 use std::ffi::CStr;
 
 mod builders;
@@ -31,6 +34,7 @@ mod hook;
 mod path;
 mod scan;
 
+pub mod aggregatescan;
 mod explainer;
 pub mod pdbscan;
 
@@ -51,11 +55,11 @@ use crate::postgres::customscan::builders::custom_state::{
 use crate::postgres::customscan::explainer::Explainer;
 use crate::postgres::customscan::path::{plan_custom_path, reparameterize_custom_path_by_child};
 use crate::postgres::customscan::scan::create_custom_scan_state;
-pub use hook::register_rel_pathlist;
+pub use hook::{register_final_paths, register_rel_pathlist, register_upper_path};
 
 pub trait CustomScanState: Default {}
 
-pub trait CustomScan: Default + Sized {
+pub trait CustomScan: Default + Sized + 'static {
     const NAME: &'static CStr;
     type State: CustomScanState;
     type PrivateData: From<*mut pg_sys::List> + Into<*mut pg_sys::List> + Default;
@@ -69,13 +73,17 @@ pub trait CustomScan: Default + Sized {
     // ensures the returned pointer holding the function pointers lives for the life of the
     // process, which Postgres requires of these.
     //
+    // A `static` inside a generic function is shared by all of its monomorphizations, however, so
+    // the allocations are kept per CustomScan impl, keyed by its [`TypeId`].
+    //
 
     fn custom_path_methods() -> *const pg_sys::CustomPathMethods {
         unsafe {
-            static mut METHODS: *mut pg_sys::CustomPathMethods = std::ptr::null_mut();
+            static mut METHODS: Lazy<FxHashMap<TypeId, *mut pg_sys::CustomPathMethods>> =
+                Lazy::new(Default::default);
 
-            if METHODS.is_null() {
-                METHODS = PgMemoryContexts::TopMemoryContext.leak_and_drop_on_delete(
+            *METHODS.entry(TypeId::of::<Self>()).or_insert_with(|| {
+                PgMemoryContexts::TopMemoryContext.leak_and_drop_on_delete(
                     pg_sys::CustomPathMethods {
                         CustomName: Self::NAME.as_ptr(),
                         PlanCustomPath: Some(plan_custom_path::<Self>),
@@ -83,9 +91,8 @@ pub trait CustomScan: Default + Sized {
                             reparameterize_custom_path_by_child::<Self>,
                         ),
                     },
-                );
-            }
-            METHODS
+                )
+            })
         }
     }
 
@@ -105,10 +112,11 @@ pub trait CustomScan: Default + Sized {
 
     fn exec_methods() -> *const pg_sys::CustomExecMethods {
         unsafe {
-            static mut METHODS: *mut pg_sys::CustomExecMethods = std::ptr::null_mut();
+            static mut METHODS: Lazy<FxHashMap<TypeId, *mut pg_sys::CustomExecMethods>> =
+                Lazy::new(Default::default);
 
-            if METHODS.is_null() {
-                METHODS = PgMemoryContexts::TopMemoryContext.leak_and_drop_on_delete(
+            *METHODS.entry(TypeId::of::<Self>()).or_insert_with(|| {
+                PgMemoryContexts::TopMemoryContext.leak_and_drop_on_delete(
                     pg_sys::CustomExecMethods {
                         CustomName: Self::NAME.as_ptr(),
                         BeginCustomScan: Some(begin_custom_scan::<Self>),
@@ -124,14 +132,26 @@ pub trait CustomScan: Default + Sized {
                         ShutdownCustomScan: Some(shutdown_custom_scan::<Self>),
                         ExplainCustomScan: Some(explain_custom_scan::<Self>),
                     },
-                );
-            }
-            METHODS
+                )
+            })
         }
     }
 
     fn callback(builder: CustomPathBuilder<Self::PrivateData>) -> Option<pg_sys::CustomPath>;
 
+    /// Another path for the relation, alongside the one [`CustomScan::callback`] builds, that's
+    /// only usable inside a nested loop, which supplies the values of the outer relations' columns
+    /// that it searches for
+    fn parameterized_callback(
+        _builder: CustomPathBuilder<Self::PrivateData>,
+    ) -> Option<pg_sys::CustomPath> {
+        None
+    }
+
+    /// Called once the planner has built the final paths of the query, such as those that apply
+    /// its `LIMIT`, so that the paths above one of ours can be adjusted to what it returns
+    fn final_paths_callback(_root: *mut pg_sys::PlannerInfo, _final_rel: *mut pg_sys::RelOptInfo) {}
+
     fn plan_custom_path(builder: CustomScanBuilder<Self::PrivateData>) -> pg_sys::CustomScan;
 
     fn create_custom_scan_state(
@@ -144,6 +164,10 @@ pub trait CustomScan: Default + Sized {
         explainer: &mut Explainer,
     );
 
+    /// A human-readable summary of the private data the scan was planned with, which `EXPLAIN
+    /// (VERBOSE)` shows, since Postgres can only show its `custom_private` as an opaque list
+    fn describe_private(private: &Self::PrivateData) -> String;
+
     fn begin_custom_scan(
         state: &mut CustomScanStateWrapper<Self>,
         estate: *mut pg_sys::EState,
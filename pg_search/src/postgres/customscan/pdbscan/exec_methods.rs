@@ -46,21 +46,24 @@ pub fn normal_scan_exec(
     state: &mut CustomScanStateWrapper<PdbScan>,
     _: *mut std::ffi::c_void,
 ) -> ExecState {
-    match state.custom_state_mut().search_results.next() {
-        None => ExecState::Eof,
-        Some((scored, doc_address)) => {
-            let scanslot = state.scanslot();
-            let bslot = state.scanslot() as *mut pg_sys::BufferHeapTupleTableSlot;
-
-            match make_tuple_table_slot(state, &scored, bslot) {
-                None => ExecState::Invisible { scored },
-                Some(slot) => ExecState::Found {
-                    scored,
-                    doc_address,
-                    slot,
-                },
+    loop {
+        return match state.custom_state_mut().next_search_result() {
+            None => ExecState::Eof,
+            Some((scored, doc_address)) => {
+                let scanslot = state.scanslot();
+                let bslot = state.scanslot() as *mut pg_sys::BufferHeapTupleTableSlot;
+
+                match make_tuple_table_slot(state, &scored, bslot) {
+                    None => ExecState::Invisible { scored },
+                    Some(_) if state.custom_state_mut().is_duplicate_key(doc_address) => continue,
+                    Some(slot) => ExecState::Found {
+                        scored,
+                        doc_address,
+                        slot,
+                    },
+                }
             }
-        }
+        };
     }
 }
 
@@ -71,6 +74,14 @@ pub struct TopNScanExecState {
     pub have_less: bool,
     found: usize,
     pub chunk_size: usize,
+
+    /// Keep returning documents past the limit, for as long as they're tied with the last
+    /// document inside it
+    pub with_ties: bool,
+    boundary_score: Option<f32>,
+
+    /// Whether the top-N has been collected to be returned in reverse
+    collected: bool,
 }
 
 #[inline(always)]
@@ -81,15 +92,26 @@ pub fn top_n_scan_exec(
     unsafe {
         let topn_state = isc.cast::<TopNScanExecState>().as_mut().unwrap();
 
-        let mut next = state.custom_state_mut().search_results.next();
+        let mut next = state.custom_state_mut().next_search_result();
         loop {
             match next {
                 None => {
-                    if topn_state.found == topn_state.limit || topn_state.have_less {
+                    if topn_state.have_less
+                        || (topn_state.found >= topn_state.limit && !topn_state.with_ties)
+                    {
                         // we found all the matching rows
                         return ExecState::Eof;
                     }
                 }
+                Some((scored, _))
+                    if topn_state.with_ties
+                        && topn_state.found >= topn_state.limit
+                        && topn_state.boundary_score != Some(scored.bm25) =>
+                {
+                    // we're past the limit and this document isn't tied with the last one
+                    // inside it, so neither are any that follow
+                    return ExecState::Eof;
+                }
                 Some((scored, doc_address)) => {
                     let scanslot = state.scanslot();
                     let bslot = state.scanslot() as *mut pg_sys::BufferHeapTupleTableSlot;
@@ -98,8 +120,16 @@ pub fn top_n_scan_exec(
 
                     return match make_tuple_table_slot(state, &scored, bslot) {
                         None => ExecState::Invisible { scored },
+                        Some(_) if state.custom_state_mut().is_duplicate_key(doc_address) => {
+                            // like an invisible document, it doesn't count towards the limit
+                            next = state.custom_state_mut().next_search_result();
+                            continue;
+                        }
                         Some(slot) => {
                             topn_state.found += 1;
+                            if topn_state.found == topn_state.limit {
+                                topn_state.boundary_score = Some(scored.bm25);
+                            }
                             ExecState::Found {
                                 scored,
                                 doc_address,
@@ -136,10 +166,15 @@ pub fn top_n_scan_exec(
                 SUBSEQUENT_RETRY_SCALE_FACTOR
             };
 
-            // set the chunk size to the scaling factor times the limit
+            // set the chunk size to the scaling factor times the limit.  When looking for ties
+            // there's no telling how many there are, so we keep growing until we've seen them all
             topn_state.chunk_size = (topn_state.chunk_size * factor)
                 .max(topn_state.limit * factor)
-                .min(MAX_CHUNK_SIZE);
+                .min(if topn_state.with_ties {
+                    usize::MAX
+                } else {
+                    MAX_CHUNK_SIZE
+                });
 
             let mut results = state
                 .custom_state()
@@ -150,6 +185,9 @@ pub fn top_n_scan_exec(
                     SearchIndex::executor(),
                     state.custom_state().query.as_ref().unwrap(),
                     state.custom_state().sort_field.clone(),
+                    &state.custom_state().sort_keys,
+                    state.custom_state().sort_expression(),
+                    state.custom_state().sort_reduction,
                     state
                         .custom_state()
                         .sort_direction
@@ -157,6 +195,8 @@ pub fn top_n_scan_exec(
                         .cloned()
                         .unwrap()
                         .into(),
+                    state.custom_state().sort_nulls_first,
+                    state.custom_state().score_tiebreak,
                     topn_state.chunk_size,
                 );
 
@@ -180,10 +220,61 @@ pub fn top_n_scan_exec(
             };
 
             // we now have a new iterator of results to use going forward
-            state.custom_state_mut().search_results = results;
+            state.custom_state_mut().set_search_results(results);
 
             // but we'll loop back around and evaluate whatever `next` is now pointing to
             continue;
         }
     }
 }
+
+/// Return the same documents as [`top_n_scan_exec`], but from the last one inside the limit back
+/// to the first.  We can't know which document that is until we've found all the visible ones
+/// before it, so the whole top-N is collected first, and then returned from the back
+#[inline(always)]
+pub fn reversed_top_n_scan_exec(
+    state: &mut CustomScanStateWrapper<PdbScan>,
+    isc: *mut std::ffi::c_void,
+) -> ExecState {
+    unsafe {
+        let topn_state = isc.cast::<TopNScanExecState>().as_mut().unwrap();
+
+        if !topn_state.collected {
+            topn_state.collected = true;
+
+            let mut top_n = Vec::with_capacity(topn_state.limit);
+            while top_n.len() < topn_state.limit {
+                match top_n_scan_exec(state, isc) {
+                    ExecState::Eof => break,
+                    ExecState::Invisible { .. } => {
+                        state.custom_state_mut().invisible_tuple_count += 1;
+                    }
+                    ExecState::Found {
+                        scored,
+                        doc_address,
+                        ..
+                    } => top_n.push((scored, doc_address)),
+                }
+            }
+            state.custom_state_mut().reversed_results = top_n;
+        }
+
+        match state.custom_state_mut().reversed_results.pop() {
+            None => ExecState::Eof,
+            Some((scored, doc_address)) => {
+                // the heap tuple was visible when we collected it, and our snapshot hasn't changed,
+                // but it has to be fetched again into the slot
+                let bslot = state.scanslot() as *mut pg_sys::BufferHeapTupleTableSlot;
+
+                match make_tuple_table_slot(state, &scored, bslot) {
+                    None => ExecState::Invisible { scored },
+                    Some(slot) => ExecState::Found {
+                        scored,
+                        doc_address,
+                        slot,
+                    },
+                }
+            }
+        }
+    }
+}
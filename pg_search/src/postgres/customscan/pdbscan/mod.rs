@@ -19,60 +19,99 @@
 mod exec_methods;
 mod privdat;
 mod projections;
-mod qual_inspect;
+pub mod qual_inspect;
 mod scan_state;
 
+use crate::api::array::array_reduction;
+use crate::api::index::set_last_query_generation;
 use crate::api::operator::{
     anyelement_query_input_opoid, attname_from_var, estimate_selectivity, find_var_relation,
 };
 use crate::api::{AsCStr, AsInt, Cardinality};
-use crate::index::reader::SearchIndexScore;
+use crate::gucs::{LimitTies, UnpushedOrderBy, UnscoredScoreValue};
+use crate::index::reader::{ScoreTieBreak, SearchIndexScore, SearchResults, SortReduction};
+use crate::index::sort_expression::SortExpression;
 use crate::index::SearchIndex;
-use crate::postgres::customscan::builders::custom_path::{CustomPathBuilder, Flags, OrderByStyle};
+use crate::postgres::customscan::builders::custom_path::{
+    flags_description, CustomPathBuilder, Flags, OrderByStyle, SortDirection,
+};
 use crate::postgres::customscan::builders::custom_scan::CustomScanBuilder;
 use crate::postgres::customscan::builders::custom_state::{
     CustomScanStateBuilder, CustomScanStateWrapper,
 };
 use crate::postgres::customscan::explainer::Explainer;
 use crate::postgres::customscan::pdbscan::exec_methods::{
-    normal_scan_exec, top_n_scan_exec, ExecState, TopNScanExecState,
+    normal_scan_exec, reversed_top_n_scan_exec, top_n_scan_exec, ExecState, TopNScanExecState,
+};
+use crate::postgres::customscan::pdbscan::privdat::{describe_private, PrivateData};
+use crate::postgres::customscan::pdbscan::projections::features::{
+    features_funcoid, inject_features, uses_features,
+};
+use crate::postgres::customscan::pdbscan::projections::field_scores::{
+    field_scores_funcoid, inject_field_scores, uses_field_scores,
+};
+use crate::postgres::customscan::pdbscan::projections::matched_fields::{
+    inject_matched_fields, matched_fields_funcoid, uses_matched_fields,
+};
+use crate::postgres::customscan::pdbscan::projections::rank::{
+    inject_rank, rank_funcoid, uses_rank,
+};
+use crate::postgres::customscan::pdbscan::projections::relevance_pct::{
+    inject_relevance_pct, relevance_pct_funcoid, uses_relevance_pct,
 };
-use crate::postgres::customscan::pdbscan::privdat::PrivateData;
 use crate::postgres::customscan::pdbscan::projections::score::{
     inject_scores, is_score_func, score_funcoid, uses_scores,
 };
 use crate::postgres::customscan::pdbscan::projections::snippet::{
-    inject_snippet, snippet_funcoid, uses_snippets, SnippetInfo,
+    inject_snippet, inject_snippets, snippet_funcoid, snippets_funcoid, uses_snippets, SnippetInfo,
+};
+use crate::postgres::customscan::pdbscan::projections::sort_expression::{
+    inject_sort_expressions, is_sort_expression_func, sort_expression_funcoid,
+    uses_sort_expressions,
 };
 use crate::postgres::customscan::pdbscan::projections::{
-    maybe_needs_const_projections, pullout_funcexprs,
+    maybe_needs_const_projections, maybe_needs_const_projections_besides_scores, pullout_funcexprs,
+};
+use crate::postgres::customscan::pdbscan::qual_inspect::{
+    array_elements, extract_quals, pull_vars, ParamValue,
 };
-use crate::postgres::customscan::pdbscan::qual_inspect::extract_quals;
 use crate::postgres::customscan::pdbscan::scan_state::PdbScanState;
 use crate::postgres::customscan::CustomScan;
 use crate::postgres::index::open_search_index;
 use crate::postgres::options::SearchIndexCreateOptions;
 use crate::postgres::rel_get_bm25_index;
+use crate::postgres::types::TantivyValue;
+use crate::postgres::utils::u64_to_item_pointer;
 use crate::postgres::visibility_checker::VisibilityChecker;
 use crate::query::SearchQueryInput;
-use crate::{nodecast, DEFAULT_STARTUP_COST, UNKNOWN_SELECTIVITY};
+use crate::{gucs, nodecast, DEFAULT_STARTUP_COST, UNKNOWN_SELECTIVITY};
+use pgrx::itemptr::item_pointer_get_both;
 use pgrx::pg_sys::AsPgCStr;
 use pgrx::{direct_function_call, pg_sys, IntoDatum, PgList, PgMemoryContexts, PgRelation};
 use std::collections::HashMap;
 use std::ffi::CStr;
 use std::ptr::{addr_of, addr_of_mut};
+use std::time::{Duration, Instant};
 use tantivy::snippet::SnippetGenerator;
 use tantivy::DocAddress;
 
+/// The most rows a subquery may return for us to search for a field to equal any of them, as in
+/// `id = ANY(ARRAY(SELECT ...))`.  Beyond that estimate, the query isn't pushed down.  And if the
+/// subquery returns more than that anyway, we search without them, and filter the rows by the
+/// clause instead
+const MAX_PARAM_TERMS: f64 = 10_000.0;
+
 #[derive(Default)]
 pub struct PdbScan;
 
-impl CustomScan for PdbScan {
-    const NAME: &'static CStr = c"ParadeDB Scan";
-    type State = PdbScanState;
-    type PrivateData = PrivateData;
-
-    fn callback(mut builder: CustomPathBuilder<Self::PrivateData>) -> Option<pg_sys::CustomPath> {
+impl PdbScan {
+    /// The path [`CustomScan::callback`] builds, and when `join_comparisons`, the one
+    /// [`CustomScan::parameterized_callback`] builds, which also searches for the clauses that
+    /// compare a field against a column of another relation, such as `field = other.column`
+    fn create_path(
+        mut builder: CustomPathBuilder<PrivateData>,
+        join_comparisons: bool,
+    ) -> Option<pg_sys::CustomPath> {
         unsafe {
             if builder.restrict_info().is_empty() {
                 return None;
@@ -81,7 +120,8 @@ impl CustomScan for PdbScan {
             let (table, bm25_index, is_join) = {
                 let rte = builder.args().rte();
 
-                // first, we only work on plain relations
+                // first, we only work on plain relations, which includes materialized views, as
+                // they're stored just like a table
                 if rte.rtekind != pg_sys::RTEKind::RTE_RELATION
                     && rte.rtekind != pg_sys::RTEKind::RTE_JOIN
                 {
@@ -101,10 +141,37 @@ impl CustomScan for PdbScan {
             let root = builder.args().root;
             let search_index =
                 open_search_index(&bm25_index).expect("should be able to open search index");
-            let pathkey = pullup_orderby_pathkey(&mut builder, rti, &search_index, root);
-            let limit = if pathkey.is_some() && (*builder.args().root).limit_tuples > -1.0 {
+            let pathkey =
+                pullup_orderby_pathkey(&mut builder, rti, &bm25_index, &search_index, root);
+            // an ORDER BY over several numeric or boolean fast fields can be sorted by all of
+            // them at once, whichever direction each of them is in
+            let pathkey = match pathkey {
+                Some(OrderByStyle::Field(..)) => {
+                    pullup_orderby_fields(root, rti, &search_index).or(pathkey)
+                }
+                pathkey => pathkey,
+            };
+
+            // if the ORDER BY has more keys than the first one, which is all we can satisfy,
+            // Postgres will finish sorting our results itself.  For that to be correct we can't
+            // stop at the limit, but must also return every document tied with the last one
+            // inside it, and we can only tell which those are when sorting by score
+            //
+            // the same goes for `FETCH FIRST n ROWS WITH TIES`, which Postgres finishes itself
+            let parse = (*builder.args().root).parse;
+            let fetch_with_ties =
+                (*parse).limitOption == pg_sys::LimitOption::LIMIT_OPTION_WITH_TIES;
+            let sorts_by_more_keys =
+                PgList::<pg_sys::PathKey>::from_pg((*builder.args().root).query_pathkeys).len() > 1
+                    && !matches!(&pathkey, Some(OrderByStyle::Fields(..)));
+            let limit_with_ties = fetch_with_ties || sorts_by_more_keys;
+            let limit_tuples = builder.limit_tuples();
+            let limit = if pathkey.is_some()
+                && limit_tuples > -1.0
+                && !(limit_with_ties && !matches!(&pathkey, Some(OrderByStyle::Score(..))))
+            {
                 // we can only use the limit if we have an orderby score pathkey
-                Some((*builder.args().root).limit_tuples)
+                Some(limit_tuples)
             } else {
                 None
             };
@@ -117,13 +184,151 @@ impl CustomScan for PdbScan {
             //
             // look for quals we can support
             //
-            let restrict_info = builder.restrict_info();
+            let mut restrict_info = builder.restrict_info();
+            if let Some(index_info) = find_index_info(builder.args().rel, bm25_index.oid()) {
+                if !(*index_info).indpred.is_null() {
+                    // a partial index only has the rows that satisfy its predicate, so it can
+                    // only answer queries that are limited to those rows anyway.  The clauses
+                    // the predicate implies then hold for everything we return without our
+                    // having to search for them
+                    if !(*index_info).predOK {
+                        return None;
+                    }
+                    if !builder.args().rel().baserestrictinfo.is_null() {
+                        restrict_info = PgList::from_pg((*index_info).indrestrictinfo);
+                    }
+                    if restrict_info.is_empty() {
+                        return None;
+                    }
+                }
+            }
+
+            // a search for the value of another relation's column, like the outer query's in a
+            // correlated `EXISTS`, is a join clause.  It has to be part of our search too, but
+            // only a nested loop, which gives us that value one row at a time, can ask for it
+            //
+            // a comparison against another relation's column, like a join's `field = other.column`,
+            // can also be left for Postgres to check, so it's only searched for by a second path,
+            // and the join can still choose to scan us without it
+            let joininfo = PgList::<pg_sys::RestrictInfo>::from_pg(builder.args().rel().joininfo);
+            let mut has_join_comparisons = false;
+            let external_clauses = joininfo
+                .iter_ptr()
+                .filter(|ri| {
+                    let Some(quals) = extract_quals(
+                        rti,
+                        (*ri).cast(),
+                        anyelement_query_input_opoid(),
+                        &table,
+                        &search_index.schema,
+                    ) else {
+                        return false;
+                    };
+                    if quals.external_exprs().is_empty() || quals.has_ignore() {
+                        return false;
+                    }
+                    if quals.has_external_comparison() {
+                        has_join_comparisons |= join_comparisons;
+                        return join_comparisons;
+                    }
+                    true
+                })
+                .collect::<Vec<_>>();
+            if join_comparisons && !has_join_comparisons {
+                // the path would be the same as the other one
+                return None;
+            }
+            if !external_clauses.is_empty() {
+                let mut with_external = PgList::<pg_sys::RestrictInfo>::new();
+                if !builder.args().rel().baserestrictinfo.is_null() {
+                    for ri in restrict_info.iter_ptr() {
+                        with_external.push(ri);
+                    }
+                }
+                for ri in &external_clauses {
+                    with_external.push(*ri);
+                }
+                restrict_info = with_external;
+            }
+
             if let Some(quals) = extract_quals(
                 rti,
                 restrict_info.as_ptr().cast(),
                 anyelement_query_input_opoid(),
-            ) {
-                let selectivity = if let Some(limit) = limit {
+                &table,
+                &search_index.schema,
+            )
+            .filter(|quals| !quals.lacks_search_operator())
+            .filter(|quals| {
+                // the value of a Param we compare against must not change while we're scanning
+                quals
+                    .params()
+                    .into_iter()
+                    .all(|param| is_init_plan_param(root, param))
+            })
+            .filter(|quals| {
+                // a set of terms is only worth searching for while it's small, and must be one we
+                // can filter by instead should it turn out not to be
+                !quals.has_negated_term_set()
+                    && quals.term_set_params().into_iter().all(|param| {
+                        init_plan_rows(root, param).is_some_and(|rows| rows <= MAX_PARAM_TERMS)
+                    })
+            }) {
+                // the relations whose columns we search for must be scanned first, and then our
+                // path is only for the inside of a nested loop over them.  That loop must not
+                // expect clauses from us that we don't search for
+                let external_exprs = quals.external_exprs();
+                let mut required_outer: *mut pg_sys::Bitmapset = std::ptr::null_mut();
+                for expr in &external_exprs {
+                    for var in PgList::<pg_sys::Var>::from_pg(pull_vars(*expr)).iter_ptr() {
+                        required_outer = pg_sys::bms_add_member(required_outer, (*var).varno as _);
+                    }
+                }
+                let param_info = if required_outer.is_null() {
+                    None
+                } else {
+                    let param_info = pg_sys::get_baserel_parampath_info(
+                        root,
+                        builder.args().rel,
+                        required_outer,
+                    );
+                    let ppi_clauses =
+                        PgList::<pg_sys::RestrictInfo>::from_pg((*param_info).ppi_clauses);
+                    if !ppi_clauses
+                        .iter_ptr()
+                        .all(|ri| external_clauses.contains(&ri))
+                    {
+                        return None;
+                    }
+                    Some(param_info)
+                };
+
+                // inside a semijoin, like an `EXISTS` that's been pulled up into the outer query,
+                // each outer row only needs to know whether there's a match.  When we search for
+                // every clause that joins us to it, the first match we can see is all we return
+                let exists_only = param_info.is_some()
+                    && joininfo.len() == external_clauses.len()
+                    && is_semijoin_inner(root, rti);
+
+                // if the quals contradict themselves we know there's nothing to find, so there's
+                // no work to do at all.  We can't tell when they compare against Params, or search
+                // for expressions, whose values aren't known yet
+                let has_params = !quals.params().is_empty() || !external_exprs.is_empty();
+                let search_query_input = SearchQueryInput::from(quals);
+                let matches_nothing = !has_params
+                    && search_query_input.matches_nothing(&|field| {
+                        is_single_valued_field(&table, &search_index, field)
+                    });
+
+                let selectivity = if matches_nothing {
+                    0.0
+                } else if exists_only {
+                    1.0 / table.reltuples().map(|n| n as Cardinality).unwrap_or(1.0)
+                } else if let Some(param_info) = param_info {
+                    // what the planner expects for each row of the nested loop
+                    (*param_info).ppi_rows
+                        / table.reltuples().map(|n| n as Cardinality).unwrap_or(1.0)
+                } else if let Some(limit) = limit {
                     // use the limit
                     limit / table.reltuples().map(|n| n as Cardinality).unwrap_or(limit)
                 } else if restrict_info.len() == 1 {
@@ -131,28 +336,170 @@ impl CustomScan for PdbScan {
                     (*restrict_info.get_ptr(0).unwrap()).norm_selec
                 } else {
                     // ask the index
-                    let search_config = SearchQueryInput::from(quals);
-                    estimate_selectivity(&bm25_index, &search_config).unwrap_or(UNKNOWN_SELECTIVITY)
+                    estimate_selectivity(&bm25_index, &search_query_input)
+                        .unwrap_or(UNKNOWN_SELECTIVITY)
                 };
 
                 builder.custom_private().set_heaprelid(table.oid());
                 builder.custom_private().set_indexrelid(bm25_index.oid());
                 builder.custom_private().set_range_table_index(rti);
                 builder.custom_private().set_quals(restrict_info);
+                builder
+                    .custom_private()
+                    .set_max_segments(gucs::max_segments());
+                builder
+                    .custom_private()
+                    .set_matches_nothing(matches_nothing);
+                builder
+                    .custom_private()
+                    .set_as_of_generation(search_query_input.as_of_generation());
+                let score_percentile = gucs::score_percentile();
+                builder
+                    .custom_private()
+                    .set_score_percentile(score_percentile);
+                // scores are computed as these were when the scan was planned, not when it runs,
+                // so a cached plan keeps scoring the way it did
+                builder
+                    .custom_private()
+                    .set_score_precision(gucs::score_precision());
+                builder.custom_private().set_global_idf(gucs::global_idf());
+                let collapse_field = search_query_input.collapse_field();
+                if let Some(field) = &collapse_field {
+                    if !search_index.schema.is_field_fast(field) {
+                        panic!("collapse field `{field}` is not a fast field of the index");
+                    }
+                }
+                builder
+                    .custom_private()
+                    .set_collapse_field(collapse_field.clone());
+                let dedup_field = search_query_input.dedup_field();
+                if let Some(field) = &dedup_field {
+                    if !search_index.schema.is_field_fast(field) {
+                        panic!("dedup field `{field}` is not a fast field of the index");
+                    }
+                }
+                builder.custom_private().set_dedup_field(dedup_field);
+                if !external_exprs.is_empty() {
+                    let mut exprs = PgList::<pg_sys::Node>::new();
+                    for expr in &external_exprs {
+                        exprs.push(*expr);
+                    }
+                    builder
+                        .custom_private()
+                        .set_external_exprs(Some(exprs.into_pg()));
+                }
 
-                if limit.is_some() && pathkey.is_some() {
-                    // sorting by a field only works if we're not doing const projections
+                let mut sorted = false;
+                if exists_only {
+                    // the top match by score is as good as any
+                    sorted = true;
+                    builder.custom_private().set_exists_only(true);
+                    builder.custom_private().set_limit(Some(1.0));
+                    builder
+                        .custom_private()
+                        .set_sort_direction(Some(SortDirection::Desc));
+                } else if limit.is_some() && pathkey.is_some() && param_info.is_none() {
+                    // the query's ORDER BY and LIMIT are for the rows of the whole join, not for
+                    // each time a nested loop scans us
+                    //
+                    // sorting by a field only works if we're not doing const projections, other
+                    // than of scores when it's been said what the score of the unscored rows is,
+                    // and sorting by a field or an expression doesn't work when cutting off
+                    // results by score percentile or collapsing them, which return them in
+                    // score order
                     //
                     // and sorting by score always works
-                    if !(maybe_needs_const_projections
-                        && matches!(&pathkey, Some(OrderByStyle::Field(..))))
+                    let is_score = matches!(&pathkey, Some(OrderByStyle::Score(..)));
+                    let is_field = matches!(
+                        &pathkey,
+                        Some(
+                            OrderByStyle::Field(..)
+                                | OrderByStyle::ReducedField(..)
+                                | OrderByStyle::Fields(..)
+                        )
+                    );
+                    let unscored_score_value = gucs::unscored_score_value();
+                    let field_needs_const_projections = maybe_needs_const_projections
+                        && (unscored_score_value == UnscoredScoreValue::Compute
+                            || maybe_needs_const_projections_besides_scores(
+                                (*(*builder.args().root).parse).targetList.cast(),
+                            ));
+                    let in_score_order = score_percentile.is_some() || collapse_field.is_some();
+                    if !((is_field && field_needs_const_projections)
+                        || (in_score_order && !is_score))
                     {
+                        if is_field && maybe_needs_const_projections {
+                            builder
+                                .custom_private()
+                                .set_unscored_score_value(unscored_score_value);
+                        }
+                        sorted = true;
                         builder = builder.add_path_key(&pathkey);
                         builder.custom_private().set_sort_field(&pathkey);
                         builder.custom_private().set_limit(limit);
                         builder
                             .custom_private()
                             .set_sort_direction(pathkey.map(|style| style.direction()));
+                        builder
+                            .custom_private()
+                            .set_score_tiebreak(gucs::score_tiebreak());
+
+                        // asking to include the documents tied with the last one inside the
+                        // limit is the same as the query having said `WITH TIES` itself.  We
+                        // return the ties, and should this path be chosen, the `Limit` above it
+                        // is made to pass them along in `final_paths_callback`
+                        let include_ties = is_score
+                            && !(*parse).sortClause.is_null()
+                            && gucs::limit_ties() == LimitTies::Include;
+                        builder
+                            .custom_private()
+                            .set_limit_with_ties(limit_with_ties || include_ties);
+
+                        // the top-N can only be returned in reverse if Postgres passes along all
+                        // of it as is, rather than skipping over an OFFSET or looking for ties
+                        if gucs::reverse_top_n()
+                            && !(limit_with_ties || include_ties)
+                            && (*parse).limitOffset.is_null()
+                        {
+                            builder.custom_private().set_reverse_top_n(true);
+                        }
+                    }
+                } else if limit.is_none()
+                    && matches!(&pathkey, Some(OrderByStyle::Score(..)))
+                    && param_info.is_none()
+                    && (*builder.args().root).tuple_fraction > 0.0
+                    && (*builder.args().root).tuple_fraction < 1.0
+                {
+                    // a cursor only expects to fetch a fraction of the rows, so rather than have
+                    // Postgres sort every match before returning the first, we return them in
+                    // score order ourselves, only putting each in order as it's fetched
+                    sorted = true;
+                    builder = builder.add_path_key(&pathkey);
+                    builder
+                        .custom_private()
+                        .set_sort_direction(pathkey.map(|style| style.direction()));
+                }
+
+                // an ORDER BY we can't sort by ourselves doesn't stop us from searching for the
+                // quals.  Postgres sorts our matches above us, only keeping the top of them
+                if !sorted
+                    && param_info.is_none()
+                    && limit_tuples > -1.0
+                    && orders_by_relation(root, rti)
+                    && gucs::unpushed_order_by() == UnpushedOrderBy::Error
+                {
+                    panic!(
+                        "the ORDER BY of this query can't be pushed down to the BM25 index, and paradedb.unpushed_order_by is 'error'"
+                    );
+                }
+
+                // Postgres's limit_tuples is the OFFSET plus the LIMIT, which is how many rows
+                // we'd have to find for the page it asks for
+                if let Some(max_result_window) = gucs::max_result_window() {
+                    if limit_tuples > max_result_window as f64 {
+                        panic!(
+                            "OFFSET plus LIMIT of {limit_tuples} is more than paradedb.max_result_window allows, which is {max_result_window}.  Fetch deeper pages by filtering past the last row of the previous page, like search_after does, rather than by OFFSET"
+                        );
                     }
                 }
 
@@ -174,14 +521,18 @@ impl CustomScan for PdbScan {
                 };
 
                 let (startup_cost, total_cost, cpu_run_cost) =
-                    if is_join || maybe_needs_const_projections {
+                    if is_join || maybe_needs_const_projections || matches_nothing {
                         // NB:  just force smallest costs possible so we'll be used in join and
-                        // other situations where we need const projections
+                        // other situations where we need const projections, and when we
+                        // already know there's nothing to return
                         (0.0, 0.0, 0.0)
                     } else {
                         (startup_cost, total_cost, cpu_run_cost)
                     };
 
+                if let Some(param_info) = param_info {
+                    builder = builder.set_param_info(param_info);
+                }
                 builder = builder.set_rows(rows);
                 builder = builder.set_startup_cost(startup_cost);
                 builder = builder.set_total_cost(total_cost + cpu_run_cost);
@@ -193,6 +544,60 @@ impl CustomScan for PdbScan {
 
         None
     }
+}
+
+impl CustomScan for PdbScan {
+    const NAME: &'static CStr = c"ParadeDB Scan";
+    type State = PdbScanState;
+    type PrivateData = PrivateData;
+
+    fn callback(builder: CustomPathBuilder<Self::PrivateData>) -> Option<pg_sys::CustomPath> {
+        PdbScan::create_path(builder, false)
+    }
+
+    fn parameterized_callback(
+        builder: CustomPathBuilder<Self::PrivateData>,
+    ) -> Option<pg_sys::CustomPath> {
+        PdbScan::create_path(builder, true)
+    }
+
+    fn final_paths_callback(_root: *mut pg_sys::PlannerInfo, final_rel: *mut pg_sys::RelOptInfo) {
+        unsafe {
+            if gucs::limit_ties() != LimitTies::Include {
+                return;
+            }
+
+            // a `Limit` directly over a top-N of ours that returns the documents tied with the
+            // last one inside the limit must pass them all along, as if the query had said
+            // `WITH TIES`.  Only the paths are changed, never the query, so a plan that doesn't
+            // use our scan still stops at the limit
+            let paths = PgList::<pg_sys::Path>::from_pg((*final_rel).pathlist);
+            for path in paths.iter_ptr() {
+                let Some(limit_path) = nodecast!(LimitPath, T_LimitPath, path) else {
+                    continue;
+                };
+                if (*limit_path).limitOption != pg_sys::LimitOption::LIMIT_OPTION_COUNT {
+                    continue;
+                }
+
+                let mut subpath = (*limit_path).subpath;
+                if let Some(projection) = nodecast!(ProjectionPath, T_ProjectionPath, subpath) {
+                    subpath = (*projection).subpath;
+                }
+                let Some(custom_path) = nodecast!(CustomPath, T_CustomPath, subpath) else {
+                    continue;
+                };
+                if (*custom_path).methods != Self::custom_path_methods() {
+                    continue;
+                }
+
+                let private_data = PrivateData::from((*custom_path).custom_private);
+                if private_data.limit().is_some() && private_data.limit_with_ties() {
+                    (*limit_path).limitOption = pg_sys::LimitOption::LIMIT_OPTION_WITH_TIES;
+                }
+            }
+        }
+    }
 
     fn plan_custom_path(mut builder: CustomScanBuilder<Self::PrivateData>) -> pg_sys::CustomScan {
         unsafe {
@@ -205,15 +610,55 @@ impl CustomScan for PdbScan {
                 .expect("range table index should have been set")
                 .try_into()
                 .expect("range table index should not be negative");
+
+            if gucs::require_covering() {
+                let uncovered = uncovered_columns(
+                    builder.args().best_path.cast(),
+                    rti as _,
+                    private_data
+                        .heaprelid()
+                        .expect("heaprelid should have a value"),
+                    private_data
+                        .indexrelid()
+                        .expect("indexrelid should have a value"),
+                );
+                if !uncovered.is_empty() {
+                    panic!(
+                        "paradedb.require_covering is set, but the index doesn't hold the values of {}, as they aren't fast fields",
+                        uncovered.join(", ")
+                    );
+                }
+            }
+
             let processed_tlist =
                 PgList::<pg_sys::TargetEntry>::from_pg((*builder.args().root).processed_tlist);
 
             let mut attname_lookup = PgList::<pg_sys::Node>::new();
             let score_funcoid = score_funcoid();
+            let rank_funcoid = rank_funcoid();
             let snippet_funcoid = snippet_funcoid();
+            let snippets_funcoid = snippets_funcoid();
+            let sort_expression_funcoid = sort_expression_funcoid();
+            let field_scores_funcoid = field_scores_funcoid();
+            let matched_fields_funcoid = matched_fields_funcoid();
+            let relevance_pct_funcoid = relevance_pct_funcoid();
+            let features_funcoid = features_funcoid();
             for te in processed_tlist.iter_ptr() {
-                let func_vars_at_level =
-                    pullout_funcexprs(te.cast(), &[score_funcoid, snippet_funcoid], rti);
+                let func_vars_at_level = pullout_funcexprs(
+                    te.cast(),
+                    &[
+                        score_funcoid,
+                        rank_funcoid,
+                        snippet_funcoid,
+                        snippets_funcoid,
+                        sort_expression_funcoid,
+                        field_scores_funcoid,
+                        matched_fields_funcoid,
+                        relevance_pct_funcoid,
+                        features_funcoid,
+                    ],
+                    rti,
+                );
 
                 for (funcexpr, var) in func_vars_at_level {
                     // if we have a tlist, then we need to add the specific function that uses
@@ -242,6 +687,13 @@ impl CustomScan for PdbScan {
             builder
                 .custom_private_mut()
                 .set_var_attname_lookup(attname_lookup.into_pg());
+
+            // the executor evaluates what we search for, once the planner has replaced the columns
+            // of the relations outside of a nested loop with the Params that carry their values
+            if let Some(exprs) = builder.custom_private().external_exprs() {
+                builder.custom_private_mut().set_external_exprs(None);
+                builder = builder.set_custom_exprs(exprs);
+            }
             builder.build()
         }
     }
@@ -281,15 +733,118 @@ impl CustomScan for PdbScan {
             // information about if we're sorted by score and our limit
             builder.custom_state().limit = builder.custom_private().limit();
             builder.custom_state().sort_field = builder.custom_private().sort_field();
+            builder.custom_state().sort_keys = builder.custom_private().sort_keys();
+            builder.custom_state().sort_expression =
+                builder.custom_private().sort_expression().map(|text| {
+                    let expression = text.parse().unwrap_or_else(|e| panic!("{e}"));
+                    (text, expression)
+                });
+            builder.custom_state().sort_reduction = builder.custom_private().sort_reduction();
             builder.custom_state().sort_direction = builder.custom_private().sort_direction();
+            builder.custom_state().sort_nulls_first =
+                builder.custom_private().sort_nulls_first().unwrap_or(false);
+            builder.custom_state().max_segments = builder.custom_private().max_segments();
+            builder.custom_state().score_tiebreak = builder
+                .custom_private()
+                .score_tiebreak()
+                .unwrap_or_default();
+            builder.custom_state().matches_nothing = builder.custom_private().matches_nothing();
+            builder.custom_state().as_of_generation = builder.custom_private().as_of_generation();
+            builder.custom_state().score_percentile = builder.custom_private().score_percentile();
+            builder.custom_state().score_precision = builder.custom_private().score_precision();
+            builder.custom_state().global_idf = builder.custom_private().global_idf();
+            builder.custom_state().limit_with_ties = builder.custom_private().limit_with_ties();
+            builder.custom_state().reverse_top_n = builder.custom_private().reverse_top_n();
+            builder.custom_state().unscored_score_value =
+                builder.custom_private().unscored_score_value();
+            builder.custom_state().collapse_field = builder.custom_private().collapse_field();
+            builder.custom_state().dedup_field = builder.custom_private().dedup_field();
+            builder.custom_state().exists_only = builder.custom_private().exists_only();
 
             // store our query quals into our custom state too
-            let quals = builder
+            let heaprel = PgRelation::with_lock(
+                builder.custom_state().heaprelid,
+                pg_sys::AccessShareLock as _,
+            );
+            let indexrel = PgRelation::with_lock(
+                builder.custom_state().indexrelid,
+                pg_sys::AccessShareLock as _,
+            );
+            let search_index =
+                open_search_index(&indexrel).expect("should be able to open search index");
+            let mut quals = builder
                 .custom_private()
-                .quals()
+                .quals(&heaprel, &search_index.schema)
                 .expect("should have a Qual structure");
+            let custom_exprs =
+                PgList::<pg_sys::Node>::from_pg((*builder.args().cscan).custom_exprs);
+            if !custom_exprs.is_empty() {
+                quals.replace_external_exprs(&mut custom_exprs.iter_ptr());
+            }
+            if !quals.params().is_empty() || !quals.external_exprs().is_empty() {
+                builder.custom_state().param_quals = Some(quals.clone());
+            }
             builder.custom_state().search_query_input = SearchQueryInput::from(quals);
 
+            // the pushed-down clauses with a set of terms are the ones we filter our rows by, if
+            // the set turns out to be too large to search for
+            let rti = builder.custom_state().rti;
+            let mut term_set_clauses = PgList::<pg_sys::Expr>::new();
+            for ri in builder
+                .custom_private()
+                .restrict_info()
+                .unwrap_or_default()
+                .iter_ptr()
+            {
+                let Some(qual) = extract_quals(
+                    rti,
+                    ri.cast(),
+                    anyelement_query_input_opoid(),
+                    &heaprel,
+                    &search_index.schema,
+                ) else {
+                    continue;
+                };
+                if !qual.term_set_params().is_empty() && qual.external_exprs().is_empty() {
+                    term_set_clauses.push((*ri).clause);
+                }
+            }
+            if !term_set_clauses.is_empty() {
+                builder.custom_state().term_set_clauses = Some(term_set_clauses.into_pg());
+            }
+
+            // the pushed-down clauses that don't search with `@@@`, which is all the index knows
+            // about, are the ones a sample of our rows can be rechecked against
+            if let Some(rate) = gucs::recheck_sample_rate() {
+                let mut clauses = PgList::<pg_sys::Expr>::new();
+                for ri in builder
+                    .custom_private()
+                    .restrict_info()
+                    .unwrap_or_default()
+                    .iter_ptr()
+                {
+                    let Some(qual) = extract_quals(
+                        rti,
+                        ri.cast(),
+                        anyelement_query_input_opoid(),
+                        &heaprel,
+                        &search_index.schema,
+                    ) else {
+                        continue;
+                    };
+                    if qual.lacks_search_operator()
+                        && qual.params().is_empty()
+                        && qual.external_exprs().is_empty()
+                    {
+                        clauses.push((*ri).clause);
+                    }
+                }
+                if !clauses.is_empty() {
+                    builder.custom_state().recheck_sample_rate = Some(rate);
+                    builder.custom_state().recheck_clauses = Some(clauses.into_pg());
+                }
+            }
+
             // now build up the var attribute name lookup map
             unsafe fn populate_var_attname_lookup(
                 lookup: &mut HashMap<(i32, pg_sys::AttrNumber), String>,
@@ -335,16 +890,69 @@ impl CustomScan for PdbScan {
 
             builder.custom_state().score_funcoid = score_funcoid();
             builder.custom_state().snippet_funcoid = snippet_funcoid();
+            builder.custom_state().snippets_funcoid = snippets_funcoid();
             builder.custom_state().need_scores = uses_scores(
                 builder.target_list().as_ptr().cast(),
                 builder.custom_state().score_funcoid,
             );
+            builder.custom_state().rank_funcoid = rank_funcoid();
+            builder.custom_state().need_rank = uses_rank(
+                builder.target_list().as_ptr().cast(),
+                builder.custom_state().rank_funcoid,
+            );
+            builder.custom_state().field_scores_funcoid = field_scores_funcoid();
+            builder.custom_state().need_field_scores = uses_field_scores(
+                builder.target_list().as_ptr().cast(),
+                builder.custom_state().field_scores_funcoid,
+            );
+            builder.custom_state().matched_fields_funcoid = matched_fields_funcoid();
+            builder.custom_state().need_matched_fields = uses_matched_fields(
+                builder.target_list().as_ptr().cast(),
+                builder.custom_state().matched_fields_funcoid,
+            );
+            builder.custom_state().relevance_pct_funcoid = relevance_pct_funcoid();
+            builder.custom_state().need_relevance_pct = uses_relevance_pct(
+                builder.target_list().as_ptr().cast(),
+                builder.custom_state().relevance_pct_funcoid,
+            );
+            if builder.custom_state().need_relevance_pct {
+                builder.custom_state().need_scores = true;
+            }
+            builder.custom_state().sort_expression_funcoid = sort_expression_funcoid();
+            let sort_expressions = uses_sort_expressions(
+                builder.custom_state().rti,
+                builder.target_list().as_ptr().cast(),
+                builder.custom_state().sort_expression_funcoid,
+            );
+            for expression in sort_expressions.values() {
+                validate_sort_expression(&search_index, expression);
+            }
+            if sort_expressions.values().any(SortExpression::uses_score) {
+                builder.custom_state().need_scores = true;
+            }
+            builder.custom_state().sort_expressions = sort_expressions;
+            builder.custom_state().features_funcoid = features_funcoid();
+            let features = uses_features(
+                builder.custom_state().rti,
+                builder.target_list().as_ptr().cast(),
+                builder.custom_state().features_funcoid,
+            );
+            for name in features.iter().flatten() {
+                if !search_index.schema.is_field_fast(name) {
+                    panic!("feature field `{name}` is not a fast field of the index");
+                }
+            }
+            if !features.is_empty() {
+                builder.custom_state().need_scores = true;
+            }
+            builder.custom_state().features = features;
             let node = builder.target_list().as_ptr().cast();
             let snippet_funcoid = builder.custom_state().snippet_funcoid;
+            let snippets_funcoid = builder.custom_state().snippets_funcoid;
             let rti = builder.custom_state().rti;
             let attname_lookup = &builder.custom_state().var_attname_lookup;
             builder.custom_state().snippet_generators =
-                uses_snippets(rti, attname_lookup, node, snippet_funcoid)
+                uses_snippets(rti, attname_lookup, node, snippet_funcoid, snippets_funcoid)
                     .into_iter()
                     .map(|field| (field, None))
                     .collect();
@@ -360,6 +968,7 @@ impl CustomScan for PdbScan {
     ) {
         explainer.add_text("Table", state.custom_state().heaprelname());
         explainer.add_text("Index", &state.custom_state().index_name);
+        explainer.add_text("Custom Flags", flags_description(state.csstate.flags));
         if explainer.is_analyze() && state.custom_state().invisible_tuple_count > 0 {
             explainer.add_unsigned_integer(
                 "Invisible Tuples",
@@ -367,19 +976,67 @@ impl CustomScan for PdbScan {
                 None,
             );
         }
+        if let Some(rate) = state.custom_state().recheck_sample_rate {
+            explainer.add_float("Recheck Sample Rate", rate, None, 2);
+            if explainer.is_analyze() {
+                explainer.add_unsigned_integer(
+                    "   Rechecked Tuples",
+                    state.custom_state().recheck_count as u64,
+                    None,
+                );
+                explainer.add_unsigned_integer(
+                    "   Recheck Failures",
+                    state.custom_state().recheck_failure_count as u64,
+                    None,
+                );
+            }
+        }
 
         explainer.add_bool("Scores", state.custom_state().need_scores());
         if let (Some(sort_direction), Some(limit)) = (
             state.custom_state().sort_direction,
             state.custom_state().limit,
         ) {
-            if let Some(sort_field) = &state.custom_state().sort_field {
+            if !state.custom_state().sort_keys.is_empty() {
+                let sort_keys = state
+                    .custom_state()
+                    .sort_keys
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect::<Vec<_>>();
+                explainer.add_text("   Sort Keys", sort_keys.join(", "));
+            } else if let Some(sort_field) = &state.custom_state().sort_field {
                 explainer.add_text("   Sort Field", sort_field);
+                if let Some(reduction) = state.custom_state().sort_reduction {
+                    explainer.add_text("   Sort Reduction", reduction.to_string());
+                } else {
+                    explainer.add_bool("   Nulls First", state.custom_state().sort_nulls_first);
+                }
+                if let Some(value) = state.custom_state().unscored_score_value {
+                    explainer.add_text("   Unscored Score Value", value.to_string());
+                }
+            } else if let Some((text, _)) = &state.custom_state().sort_expression {
+                explainer.add_text("   Sort Expression", text);
             } else {
                 explainer.add_text("   Sort Field", "paradedb.score()");
             }
             explainer.add_text("   Sort Direction", sort_direction);
+            if state.custom_state().sort_field.is_none()
+                && state.custom_state().sort_keys.is_empty()
+                && state.custom_state().score_tiebreak != ScoreTieBreak::default()
+            {
+                explainer.add_text(
+                    "   Tie Break",
+                    state.custom_state().score_tiebreak.to_string(),
+                );
+            }
             explainer.add_unsigned_integer("   Top N Limit", limit as u64, None);
+            if state.custom_state().limit_with_ties {
+                explainer.add_bool("   Limit With Ties", true);
+            }
+            if state.custom_state().reverse_top_n {
+                explainer.add_bool("   Reversed", true);
+            }
             if explainer.is_analyze() && state.custom_state().retry_count > 0 {
                 explainer.add_unsigned_integer(
                     "   Invisible Tuple Retries",
@@ -387,6 +1044,46 @@ impl CustomScan for PdbScan {
                     None,
                 );
             }
+        } else if let Some(sort_direction) = state.custom_state().sort_direction {
+            explainer.add_text("   Sort Field", "paradedb.score()");
+            explainer.add_text("   Sort Direction", sort_direction);
+            explainer.add_bool("   Lazy Sort", true);
+        }
+
+        if state.custom_state().exists_only {
+            explainer.add_bool("Exists Only", true);
+        }
+        if state.custom_state().matches_nothing {
+            explainer.add_bool("Matches Nothing", true);
+        }
+        if let Some(max_segments) = state.custom_state().max_segments {
+            explainer.add_unsigned_integer("Max Segments", max_segments as u64, None);
+        }
+        if let Some(generation) = state.custom_state().as_of_generation {
+            explainer.add_unsigned_integer("As Of Generation", generation, None);
+        }
+        if let Some(percentile) = state.custom_state().score_percentile {
+            explainer.add_float("Score Percentile", percentile, None, 2);
+        }
+        if let Some(field) = &state.custom_state().collapse_field {
+            explainer.add_text("Collapse Field", field);
+        }
+        if let Some(field) = &state.custom_state().dedup_field {
+            explainer.add_text("Dedup Field", field);
+            if explainer.is_analyze() {
+                explainer.add_unsigned_integer(
+                    "   Duplicates Removed",
+                    state.custom_state().duplicate_count as u64,
+                    None,
+                );
+            }
+        }
+
+        if explainer.is_analyze() && state.custom_state().explain_scores.is_some() {
+            for (ctid, explanation) in &state.custom_state().score_explanations {
+                let (blockno, offno) = (ctid >> 16, ctid & 0xffff);
+                explainer.add_text(&format!("Score of ({blockno},{offno})"), explanation);
+            }
         }
 
         let query = &state.custom_state().search_query_input;
@@ -397,6 +1094,13 @@ impl CustomScan for PdbScan {
         }
         .expect("query should serialize to json");
         explainer.add_text("Tantivy Query", &pretty_json);
+        if explainer.is_verbose() {
+            explainer.add_text("Query Tree", query.explain_tree());
+        }
+    }
+
+    fn describe_private(private: &Self::PrivateData) -> String {
+        describe_private(private)
     }
 
     fn begin_custom_scan(
@@ -421,6 +1125,26 @@ impl CustomScan for PdbScan {
                 VisibilityChecker::with_rel_and_snap(heaprel, pg_sys::GetActiveSnapshot()),
             );
 
+            // read ahead of the search results and prefetch their heap pages, as far ahead as the
+            // Postgres `effective_io_concurrency` setting says is worthwhile
+            state.custom_state_mut().prefetch_distance =
+                pg_sys::effective_io_concurrency.max(0) as usize;
+
+            // documents are only emitted once, unless we've been asked to show every hit
+            state.custom_state_mut().emit_duplicates = gucs::debug_emit_duplicates();
+
+            if let Some(clauses) = state.custom_state().term_set_clauses {
+                state.custom_state_mut().term_set_qual =
+                    Some(pg_sys::ExecInitQual(clauses, state.planstate()));
+            }
+
+            // each scan samples a different set of rows to recheck
+            if let Some(clauses) = state.custom_state().recheck_clauses {
+                state.custom_state_mut().recheck_qual =
+                    Some(pg_sys::ExecInitQual(clauses, state.planstate()));
+                state.custom_state_mut().recheck_seed = pg_sys::GetCurrentTimestamp() as u64;
+            }
+
             // and finally, get the custom scan itself properly initialized
             let tupdesc = state.custom_state().heaptupdesc();
             pg_sys::ExecInitScanTupleSlot(
@@ -441,6 +1165,13 @@ impl CustomScan for PdbScan {
             return;
         }
 
+        // the scores of the first rows are only broken down for an EXPLAIN ANALYZE, which is
+        // when the executor is instrumented
+        if unsafe { (*estate).es_instrument } != 0 {
+            state.custom_state_mut().explain_scores = gucs::explain_scores();
+        }
+
+        state.custom_state_mut().started_at = Some(Instant::now());
         PdbScan::rescan_custom_scan(state)
     }
 
@@ -475,7 +1206,22 @@ impl CustomScan for PdbScan {
                     doc_address,
                     slot,
                 } => {
+                    state.custom_state_mut().rank += 1;
+                    state.custom_state_mut().returned_tuple_count += 1;
                     unsafe {
+                        if state.custom_state().should_recheck(scored.ctid) {
+                            recheck(state, scored.ctid, slot);
+                        }
+                        if state
+                            .custom_state()
+                            .explain_scores
+                            .is_some_and(|explain_scores| {
+                                state.custom_state().score_explanations.len() < explain_scores
+                            })
+                        {
+                            explain_score(state, scored.ctid, doc_address);
+                        }
+
                         // project it if we need to
                         let projection_info =
                             maybe_rebuild_projinfo_for_const_projection(state, scored, doc_address);
@@ -492,6 +1238,19 @@ impl CustomScan for PdbScan {
     fn shutdown_custom_scan(state: &mut CustomScanStateWrapper<Self>) {}
 
     fn end_custom_scan(state: &mut CustomScanStateWrapper<Self>) {
+        if let Some(started_at) = state.custom_state_mut().started_at.take() {
+            maybe_log_slow_scan(state.custom_state(), started_at.elapsed());
+        }
+        if state.custom_state().recheck_failure_count > 0 {
+            pgrx::warning!(
+                "{} of {} sampled rows from `{}` didn't satisfy the clauses `{}` said they matched, and the index may need to be rebuilt",
+                state.custom_state().recheck_failure_count,
+                state.custom_state().recheck_count,
+                state.custom_state().heaprelname(),
+                state.custom_state().index_name
+            );
+        }
+
         // get some things dropped now
         drop(state.custom_state_mut().visibility_checker.take());
         drop(state.custom_state_mut().search_reader.take());
@@ -499,6 +1258,15 @@ impl CustomScan for PdbScan {
             &mut state.custom_state_mut().snippet_generators,
         ));
         drop(std::mem::take(&mut state.custom_state_mut().search_results));
+        drop(std::mem::take(
+            &mut state.custom_state_mut().prefetched_results,
+        ));
+        drop(std::mem::take(&mut state.custom_state_mut().emitted_ctids));
+        drop(std::mem::take(&mut state.custom_state_mut().dedup_keys));
+        drop(std::mem::take(&mut state.custom_state_mut().collapse_keys));
+        drop(std::mem::take(
+            &mut state.custom_state_mut().reversed_results,
+        ));
 
         if let Some(heaprel) = state.custom_state_mut().heaprel.take() {
             unsafe {
@@ -513,6 +1281,7 @@ impl CustomScan for PdbScan {
     }
 
     fn rescan_custom_scan(state: &mut CustomScanStateWrapper<Self>) {
+        state.custom_state_mut().rank = 0;
         let need_scores = state.custom_state().need_scores();
         let need_snippets = state.custom_state().need_snippets();
 
@@ -525,13 +1294,95 @@ impl CustomScan for PdbScan {
             .expect("custom_state.indexrel should already be open");
         let search_index =
             open_search_index(&indexrel).expect("should be able to open search index");
-        let search_reader = search_index
-            .get_reader()
-            .expect("search index reader should have been constructed correctly");
+        let search_reader = match state.custom_state().as_of_generation {
+            Some(generation) => search_index.get_reader_as_of(generation),
+            None => search_index.get_reader(),
+        }
+        .unwrap_or_else(|e| panic!("{e}"))
+        .with_max_segments(state.custom_state().max_segments)
+        .with_global_idf(state.custom_state().global_idf);
+        set_last_query_generation(search_reader.generation);
+
+        if let Some(quals) = state.custom_state().param_quals.clone() {
+            // the InitPlans our quals compare against have run (or will now), and a nested loop
+            // has given us the values of its current row, so they can finally go into the query.
+            // Except for a set of terms that's too large to search for, which matches every
+            // document instead, so that we filter the rows by the clause it came from
+            let planstate = state.planstate();
+            let term_set_params = quals.term_set_params();
+            let mut filter_term_sets = false;
+            state.custom_state_mut().search_query_input =
+                quals.into_search_query_input(&mut |expr| unsafe {
+                    match eval_param(planstate, expr) {
+                        ParamValue::Value(datum)
+                            if term_set_params.contains(&expr.cast())
+                                && array_elements(
+                                    datum,
+                                    pg_sys::get_element_type(pg_sys::exprType(expr)),
+                                )
+                                .len() as f64
+                                    > MAX_PARAM_TERMS =>
+                        {
+                            filter_term_sets = true;
+                            ParamValue::Unknown
+                        }
+                        value => value,
+                    }
+                });
+            state.custom_state_mut().filter_term_sets = filter_term_sets;
+        }
 
-        state.custom_state_mut().query =
-            Some(search_index.query(&state.custom_state().search_query_input, &search_reader));
-        let search_results = if let (Some(limit), Some(sort_direction)) = (
+        state.custom_state_mut().query = Some(search_index.query_with_score_precision(
+            &state.custom_state().search_query_input,
+            &search_reader,
+            state.custom_state().score_precision,
+        ));
+        let search_results = if state.custom_state().matches_nothing {
+            // the planner proved the quals contradict each other, so don't bother searching
+            state.custom_state_mut().scan_func = Some(normal_scan_exec);
+            state.custom_state_mut().inner_scan_state = Some(std::ptr::null_mut());
+            SearchResults::None
+        } else if state.custom_state().collapse_field.is_some() {
+            // every match is returned in score order, so the first visible one of each value of
+            // the collapse field is the highest-scoring, and the rest are skipped as they're
+            // returned.  Collapsing any earlier could keep a dead row that hides its whole group
+            let sort_direction = state
+                .custom_state()
+                .sort_direction
+                .unwrap_or(SortDirection::Desc);
+            let results = match state.custom_state().score_percentile {
+                Some(percentile) => search_reader.search_above_percentile(
+                    SearchIndex::executor(),
+                    state.custom_state().query.as_ref().unwrap(),
+                    percentile,
+                    sort_direction.into(),
+                ),
+                None => search_reader.search_in_score_order(
+                    SearchIndex::executor(),
+                    state.custom_state().query.as_ref().unwrap(),
+                    sort_direction.into(),
+                ),
+            };
+            state.custom_state_mut().scan_func = Some(normal_scan_exec);
+            state.custom_state_mut().inner_scan_state = Some(std::ptr::null_mut());
+            results
+        } else if let Some(percentile) = state.custom_state().score_percentile {
+            // every match needs to be scored before we know which of them make the cut, and
+            // those that do are returned in score order
+            let sort_direction = state
+                .custom_state()
+                .sort_direction
+                .unwrap_or(SortDirection::Desc);
+            let results = search_reader.search_above_percentile(
+                SearchIndex::executor(),
+                state.custom_state().query.as_ref().unwrap(),
+                percentile,
+                sort_direction.into(),
+            );
+            state.custom_state_mut().scan_func = Some(normal_scan_exec);
+            state.custom_state_mut().inner_scan_state = Some(std::ptr::null_mut());
+            results
+        } else if let (Some(limit), Some(sort_direction)) = (
             state.custom_state().limit,
             state.custom_state().sort_direction,
         ) {
@@ -539,14 +1390,25 @@ impl CustomScan for PdbScan {
                 SearchIndex::executor(),
                 state.custom_state().query.as_ref().unwrap(),
                 state.custom_state().sort_field.clone(),
+                &state.custom_state().sort_keys,
+                state.custom_state().sort_expression(),
+                state.custom_state().sort_reduction,
                 sort_direction.into(),
+                state.custom_state().sort_nulls_first,
+                state.custom_state().score_tiebreak,
                 limit,
             );
-            state.custom_state_mut().scan_func = Some(top_n_scan_exec);
+            state.custom_state_mut().reversed_results.clear();
+            state.custom_state_mut().scan_func = if state.custom_state().reverse_top_n {
+                Some(reversed_top_n_scan_exec)
+            } else {
+                Some(top_n_scan_exec)
+            };
             state.custom_state_mut().inner_scan_state = unsafe {
                 let mut topn_state = TopNScanExecState::default();
                 topn_state.limit = results.len().unwrap();
                 topn_state.have_less = topn_state.limit < state.custom_state().limit.unwrap();
+                topn_state.with_ties = state.custom_state().limit_with_ties;
                 Some(
                     PgMemoryContexts::CurrentMemoryContext
                         .copy_ptr_into(&mut topn_state, std::mem::size_of::<TopNScanExecState>())
@@ -554,6 +1416,39 @@ impl CustomScan for PdbScan {
                 )
             };
             results
+        } else if let (Some(sort_direction), None, None) = (
+            state.custom_state().sort_direction,
+            &state.custom_state().sort_field,
+            &state.custom_state().sort_expression,
+        ) {
+            // a cursor asked for the matches in score order, without a limit, so every one of
+            // them has to be scored, but they're only put in order as they're fetched
+            let results = search_reader.search_lazily_in_score_order(
+                SearchIndex::executor(),
+                state.custom_state().query.as_ref().unwrap(),
+                sort_direction.into(),
+            );
+            state.custom_state_mut().scan_func = Some(normal_scan_exec);
+            state.custom_state_mut().inner_scan_state = Some(std::ptr::null_mut());
+            results
+        } else if state.custom_state().dedup_field.is_some()
+            && state.custom_state().sort_field.is_none()
+            && state.custom_state().sort_expression.is_none()
+        {
+            // the first document returned for each value is the one that's kept, and that ought
+            // to be the highest-scoring one
+            let sort_direction = state
+                .custom_state()
+                .sort_direction
+                .unwrap_or(SortDirection::Desc);
+            let results = search_reader.search_in_score_order(
+                SearchIndex::executor(),
+                state.custom_state().query.as_ref().unwrap(),
+                sort_direction.into(),
+            );
+            state.custom_state_mut().scan_func = Some(normal_scan_exec);
+            state.custom_state_mut().inner_scan_state = Some(std::ptr::null_mut());
+            results
         } else {
             let need_scores = state.custom_state().need_scores
                 || state
@@ -571,7 +1466,16 @@ impl CustomScan for PdbScan {
             results
         };
 
-        state.custom_state_mut().search_results = search_results;
+        // a top-N by score is all scored before any of it is returned, so we know its best score
+        state.custom_state_mut().top_score = (state.custom_state().need_relevance_pct()
+            && state.custom_state().sort_field.is_none()
+            && state.custom_state().sort_expression.is_none())
+        .then(|| search_results.top_score())
+        .flatten();
+        state.custom_state_mut().set_search_results(search_results);
+        state.custom_state_mut().emitted_ctids.clear();
+        state.custom_state_mut().dedup_keys.clear();
+        state.custom_state_mut().collapse_keys.clear();
 
         assert!(
             state.custom_state().scan_func.is_some(),
@@ -611,7 +1515,7 @@ fn make_tuple_table_slot(
     scored: &SearchIndexScore,
     bslot: *mut pg_sys::BufferHeapTupleTableSlot,
 ) -> Option<*mut pg_sys::TupleTableSlot> {
-    state
+    let slot = state
         .custom_state_mut()
         .visibility_checker()
         .exec_if_visible(scored.ctid, move |heaprelid, htup, buffer| unsafe {
@@ -625,7 +1529,91 @@ fn make_tuple_table_slot(
                 bslot.cast(),
                 buffer,
             )
-        })
+        })?;
+
+    // a row that doesn't satisfy the set of terms we didn't search for is treated just like one
+    // we can't see
+    if state.custom_state().filter_term_sets {
+        let qual = state
+            .custom_state()
+            .term_set_qual
+            .expect("term set qual should be initialized");
+        if !unsafe { satisfies_qual(state, qual, slot) } {
+            return None;
+        }
+    }
+    Some(slot)
+}
+
+/// The columns of the `rti` relation that `path` returns, or passes to one of our functions, that
+/// the index doesn't hold the values of, because they're neither the key field nor fast fields
+unsafe fn uncovered_columns(
+    path: *mut pg_sys::Path,
+    rti: pg_sys::Index,
+    heaprelid: pg_sys::Oid,
+    indexrelid: pg_sys::Oid,
+) -> Vec<String> {
+    let heaprel = PgRelation::with_lock(heaprelid, pg_sys::AccessShareLock as _);
+    let indexrel = PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _);
+    let search_index = open_search_index(&indexrel).expect("should be able to open search index");
+    let key_field = search_index.schema.key_field().name.0;
+    let tupdesc = heaprel.tuple_desc();
+
+    let vars = PgList::<pg_sys::Node>::from_pg(pull_vars((*(*path).pathtarget).exprs.cast()));
+    let mut uncovered = Vec::new();
+    for var in vars
+        .iter_ptr()
+        .filter_map(|node| nodecast!(Var, T_Var, node))
+    {
+        if (*var).varno as pg_sys::Index != rti {
+            continue;
+        }
+
+        let name = match (*var).varattno as i32 {
+            // every document holds its ctid
+            pg_sys::SelfItemPointerAttributeNumber => continue,
+            attno if attno > 0 => match tupdesc.get(attno as usize - 1) {
+                Some(att) => att.name().to_string(),
+                None => continue,
+            },
+            0 => format!("{}.*", heaprel.name()),
+            attno => CStr::from_ptr(pg_sys::get_attname(heaprelid, attno as _, false))
+                .to_string_lossy()
+                .into_owned(),
+        };
+        if name != key_field
+            && !search_index.schema.is_field_fast(&name)
+            && !uncovered.contains(&name)
+        {
+            uncovered.push(name);
+        }
+    }
+    uncovered
+}
+
+/// Record how the query scores the row with `ctid`, in the JSON tantivy explains it with, for
+/// `EXPLAIN ANALYZE` to show
+fn explain_score(state: &mut CustomScanStateWrapper<PdbScan>, ctid: u64, doc_address: DocAddress) {
+    let search_reader = state
+        .custom_state()
+        .search_reader
+        .as_ref()
+        .expect("CustomState should have a SearchIndexReader to explain its scores");
+    let query = state
+        .custom_state()
+        .query
+        .as_ref()
+        .expect("CustomState should have a query to explain its scores");
+    let explanation = match query.explain(&search_reader.searcher, doc_address) {
+        Ok(explanation) => {
+            serde_json::to_string(&explanation).expect("explanation should serialize to json")
+        }
+        Err(e) => format!("not scored: {e}"),
+    };
+    state
+        .custom_state_mut()
+        .score_explanations
+        .push((ctid, explanation));
 }
 
 unsafe fn maybe_rebuild_projinfo_for_const_projection(
@@ -633,8 +1621,16 @@ unsafe fn maybe_rebuild_projinfo_for_const_projection(
     scored: SearchIndexScore,
     doc_address: DocAddress,
 ) -> *mut pg_sys::ProjectionInfo {
-    if !state.custom_state().need_scores() && !state.custom_state().need_snippets() {
-        // scores/snippets aren't necessary so we use whatever we originally setup as our ProjectionInfo
+    if !state.custom_state().need_scores()
+        && !state.custom_state().need_rank()
+        && !state.custom_state().need_snippets()
+        && !state.custom_state().need_sort_expressions()
+        && !state.custom_state().need_field_scores()
+        && !state.custom_state().need_matched_fields()
+        && !state.custom_state().need_relevance_pct()
+        && !state.custom_state().need_features()
+    {
+        // scores/ranks/snippets/sort expressions/field scores/matched fields/relevance percentages/features aren't necessary so we use whatever we originally setup as our ProjectionInfo
         return state.projection_info();
     }
 
@@ -652,10 +1648,149 @@ unsafe fn maybe_rebuild_projinfo_for_const_projection(
     let mut const_projected_targetlist = projection_targetlist;
 
     if state.custom_state().need_scores() {
+        // a row of a top-N ordered by a field isn't scored, and what its score is instead was
+        // decided when planning
+        let score = match state.custom_state().unscored_score_value {
+            Some(UnscoredScoreValue::One) if scored.bm25.is_nan() => 1.0,
+            Some(UnscoredScoreValue::Error) if scored.bm25.is_nan() => panic!(
+                "paradedb.score() is not computed for rows ordered by a field, which \
+                paradedb.unscored_score_value = 'error' does not allow"
+            ),
+            _ => scored.bm25,
+        };
         const_projected_targetlist = inject_scores(
             const_projected_targetlist.cast(),
             state.custom_state().score_funcoid,
-            scored.bm25,
+            score,
+        )
+        .cast();
+    }
+    if state.custom_state().need_rank() {
+        // we only know a row's rank if we return rows in their final order, which we don't when
+        // Postgres still has to sort the ones that tie with each other
+        let rank = (state.custom_state().sort_direction.is_some()
+            && !state.custom_state().limit_with_ties)
+            .then_some(state.custom_state().rank);
+        const_projected_targetlist = inject_rank(
+            const_projected_targetlist.cast(),
+            state.custom_state().rank_funcoid,
+            rank,
+        )
+        .cast();
+    }
+    if state.custom_state().need_relevance_pct() {
+        // when every score is the same, even if it's zero, they're all the best one
+        let relevance_pct = state.custom_state().top_score.map(|top_score| {
+            if top_score > 0.0 {
+                scored.bm25 / top_score * 100.0
+            } else {
+                100.0
+            }
+        });
+        const_projected_targetlist = inject_relevance_pct(
+            const_projected_targetlist.cast(),
+            state.custom_state().relevance_pct_funcoid,
+            relevance_pct,
+        )
+        .cast();
+    }
+    if state.custom_state().need_sort_expressions() {
+        let search_reader = state
+            .custom_state()
+            .search_reader
+            .as_ref()
+            .expect("CustomState should have a SearchIndexReader for its sort expressions");
+        let values = state
+            .custom_state()
+            .sort_expressions
+            .iter()
+            .map(|(text, expression)| {
+                let value =
+                    search_reader.sort_expression_value(expression, scored.bm25, doc_address);
+                (text.clone(), value)
+            })
+            .collect();
+        const_projected_targetlist = inject_sort_expressions(
+            state.custom_state().rti,
+            const_projected_targetlist.cast(),
+            state.custom_state().sort_expression_funcoid,
+            &values,
+        )
+        .cast();
+    }
+    if state.custom_state().need_field_scores() {
+        let search_reader = state
+            .custom_state()
+            .search_reader
+            .as_ref()
+            .expect("CustomState should have a SearchIndexReader for its field scores");
+        let query = state
+            .custom_state()
+            .query
+            .as_ref()
+            .expect("CustomState should have a query for its field scores");
+        let field_scores = search_reader.field_scores(query.as_ref(), doc_address);
+        const_projected_targetlist = inject_field_scores(
+            const_projected_targetlist.cast(),
+            state.custom_state().field_scores_funcoid,
+            field_scores,
+        )
+        .cast();
+    }
+    if state.custom_state().need_features() {
+        let search_reader = state
+            .custom_state()
+            .search_reader
+            .as_ref()
+            .expect("CustomState should have a SearchIndexReader for its features");
+        let to_json = |value: Option<TantivyValue>| {
+            value.map_or(serde_json::Value::Null, |value| {
+                serde_json::to_value(value.0).expect("fast field value should serialize to json")
+            })
+        };
+        let features = state
+            .custom_state()
+            .features
+            .iter()
+            .map(|names| {
+                let values = names
+                    .iter()
+                    .map(|name| {
+                        let value = search_reader.fast_field_value(name, doc_address);
+                        (name.clone(), to_json(value))
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                let features = serde_json::json!({
+                    "score": scored.bm25,
+                    "features": values,
+                });
+                (names.clone(), features)
+            })
+            .collect();
+        const_projected_targetlist = inject_features(
+            state.custom_state().rti,
+            const_projected_targetlist.cast(),
+            state.custom_state().features_funcoid,
+            features,
+        )
+        .cast();
+    }
+    if state.custom_state().need_matched_fields() {
+        let search_reader = state
+            .custom_state()
+            .search_reader
+            .as_ref()
+            .expect("CustomState should have a SearchIndexReader for its matched fields");
+        let query = state
+            .custom_state()
+            .query
+            .as_ref()
+            .expect("CustomState should have a query for its matched fields");
+        let matched_fields = search_reader.matched_fields(query.as_ref(), doc_address);
+        const_projected_targetlist = inject_matched_fields(
+            const_projected_targetlist.cast(),
+            state.custom_state().matched_fields_funcoid,
+            matched_fields,
         )
         .cast();
     }
@@ -683,6 +1818,16 @@ unsafe fn maybe_rebuild_projinfo_for_const_projection(
             )
             .cast();
         }
+
+        const_projected_targetlist = inject_snippets(
+            state.custom_state().rti,
+            const_projected_targetlist.cast(),
+            state.custom_state().snippets_funcoid,
+            search_state,
+            &state.custom_state().snippet_generators,
+            doc_address,
+        )
+        .cast();
     }
 
     // build the new ProjectionInfo based on our modified TargetList
@@ -698,6 +1843,7 @@ unsafe fn maybe_rebuild_projinfo_for_const_projection(
 unsafe fn pullup_orderby_pathkey<P: Into<*mut pg_sys::List> + Default>(
     builder: &mut CustomPathBuilder<P>,
     rti: pg_sys::Index,
+    bm25_index: &PgRelation,
     search_index: &SearchIndex,
     root: *mut pg_sys::PlannerInfo,
 ) -> Option<OrderByStyle> {
@@ -712,6 +1858,37 @@ unsafe fn pullup_orderby_pathkey<P: Into<*mut pg_sys::List> + Default>(
 
             if is_score_func(expr.cast(), rti as _) {
                 return Some(OrderByStyle::Score(first_pathkey));
+            } else if let Some(text) = is_sort_expression_func(expr.cast(), rti as _) {
+                let expression = text
+                    .parse::<SortExpression>()
+                    .unwrap_or_else(|e| panic!("{e}"));
+                validate_sort_expression(search_index, &expression);
+                return Some(OrderByStyle::Expression(first_pathkey, text));
+            } else if let Some(field) =
+                index_expression_field(builder.args().rel, bm25_index, expr.cast())
+                    .filter(|field| search_index.schema.is_field_raw_sortable(field))
+            {
+                // the index stores the value of the expression we're sorted by as it is
+                return Some(OrderByStyle::Field(first_pathkey, field));
+            } else if let Some((var, reduction)) = is_array_reduction_func(expr.cast(), rti as _) {
+                // a document without values sorts like a NULL does by default, which is last in
+                // ascending order and first in descending order
+                let direction: SortDirection = (*first_pathkey).pk_strategy.into();
+                if (*first_pathkey).pk_nulls_first != matches!(direction, SortDirection::Desc) {
+                    continue;
+                }
+                let (heaprelid, attno, _) = find_var_relation(var, root);
+                let heaprel = PgRelation::with_lock(heaprelid, pg_sys::AccessShareLock as _);
+                let tupdesc = heaprel.tuple_desc();
+                if let Some(att) = tupdesc.get(attno as usize - 1) {
+                    if search_index.schema.is_field_numeric_fast(att.name()) {
+                        return Some(OrderByStyle::ReducedField(
+                            first_pathkey,
+                            att.name().to_string(),
+                            reduction,
+                        ));
+                    }
+                }
             } else if let Some(var) = is_lower_func(expr.cast(), rti as _) {
                 let (heaprelid, attno, _) = find_var_relation(var, root);
                 let heaprel = PgRelation::with_lock(heaprelid, pg_sys::AccessShareLock as _);
@@ -750,6 +1927,242 @@ unsafe fn pullup_orderby_pathkey<P: Into<*mut pg_sys::List> + Default>(
     None
 }
 
+/// The numeric or boolean fast fields of relation `rti` the query's ORDER BY sorts by, if it sorts
+/// by more than one and every one of its keys is such a field
+unsafe fn pullup_orderby_fields(
+    root: *mut pg_sys::PlannerInfo,
+    rti: pg_sys::Index,
+    search_index: &SearchIndex,
+) -> Option<OrderByStyle> {
+    let pathkeys = PgList::<pg_sys::PathKey>::from_pg((*root).query_pathkeys);
+    if pathkeys.len() < 2 {
+        return None;
+    }
+
+    let mut keys = Vec::with_capacity(pathkeys.len());
+    for pathkey in pathkeys.iter_ptr() {
+        let members =
+            PgList::<pg_sys::EquivalenceMember>::from_pg((*(*pathkey).pk_eclass).ec_members);
+        let field = members.iter_ptr().find_map(|member| {
+            let expr = (*member).em_expr.cast::<pg_sys::Node>();
+            let var = match nodecast!(RelabelType, T_RelabelType, expr) {
+                Some(relabel) => nodecast!(Var, T_Var, (*relabel).arg)?,
+                None => nodecast!(Var, T_Var, expr)?,
+            };
+            if (*var).varno as pg_sys::Index != rti {
+                return None;
+            }
+            let (heaprelid, attno, _) = find_var_relation(var, root);
+            let heaprel = PgRelation::with_lock(heaprelid, pg_sys::AccessShareLock as _);
+            let tupdesc = heaprel.tuple_desc();
+            let att = tupdesc.get(attno as usize - 1)?;
+            search_index
+                .schema
+                .is_field_numeric_fast(att.name())
+                .then(|| att.name().to_string())
+        })?;
+        keys.push((pathkey, field));
+    }
+    Some(OrderByStyle::Fields(keys))
+}
+
+/// Whether the first key of the query's ORDER BY is computed from the columns of relation `rti`
+unsafe fn orders_by_relation(root: *mut pg_sys::PlannerInfo, rti: pg_sys::Index) -> bool {
+    let pathkeys = PgList::<pg_sys::PathKey>::from_pg((*root).query_pathkeys);
+    let Some(first_pathkey) = pathkeys.get_ptr(0) else {
+        return false;
+    };
+    let members =
+        PgList::<pg_sys::EquivalenceMember>::from_pg((*(*first_pathkey).pk_eclass).ec_members);
+    members
+        .iter_ptr()
+        .any(|member| pg_sys::bms_is_member(rti as _, (*member).em_relids))
+}
+
+/// The name of the field of `bm25_index` that stores the value of `expr`, if one of the index's
+/// columns is computed from an expression, like `lower(title)`, that's the same as it
+unsafe fn index_expression_field(
+    rel: *mut pg_sys::RelOptInfo,
+    bm25_index: &PgRelation,
+    expr: *mut pg_sys::Node,
+) -> Option<String> {
+    let index_info = find_index_info(rel, bm25_index.oid())?;
+    let indexprs = PgList::<pg_sys::Node>::from_pg((*index_info).indexprs);
+    let mut indexprs = indexprs.iter_ptr();
+    let tupdesc = bm25_index.tuple_desc();
+    for column in 0..(*index_info).ncolumns as usize {
+        // a column that's a plain attribute of the table isn't one of the expressions
+        if *(*index_info).indexkeys.add(column) != 0 {
+            continue;
+        }
+        let indexpr = indexprs.next()?;
+        if pg_sys::equal(indexpr.cast(), expr.cast()) {
+            return tupdesc.get(column).map(|att| att.name().to_string());
+        }
+    }
+    None
+}
+
+/// Raise an ERROR if `expression` reads a field that isn't a numeric fast field of `search_index`
+fn validate_sort_expression(search_index: &SearchIndex, expression: &SortExpression) {
+    for field in expression.fields() {
+        if !search_index.schema.is_field_numeric_fast(field) {
+            panic!("sort expression field `{field}` is not a numeric fast field of the index");
+        }
+    }
+}
+
+/// Find what the planner knows about the index `indexrelid` of `rel`
+unsafe fn find_index_info(
+    rel: *mut pg_sys::RelOptInfo,
+    indexrelid: pg_sys::Oid,
+) -> Option<*mut pg_sys::IndexOptInfo> {
+    PgList::<pg_sys::IndexOptInfo>::from_pg((*rel).indexlist)
+        .iter_ptr()
+        .find(|index_info| (**index_info).indexoid == indexrelid)
+}
+
+/// Is `param` the output of one of the query's InitPlans?  Those are computed once, the first time
+/// they're needed, and keep their value for the rest of the query.
+unsafe fn is_init_plan_param(
+    mut root: *mut pg_sys::PlannerInfo,
+    param: *mut pg_sys::Param,
+) -> bool {
+    while !root.is_null() {
+        let init_plans = PgList::<pg_sys::SubPlan>::from_pg((*root).init_plans);
+        if init_plans
+            .iter_ptr()
+            .any(|subplan| pg_sys::list_member_int((*subplan).setParam, (*param).paramid))
+        {
+            return true;
+        }
+        root = (*root).parent_root;
+    }
+    false
+}
+
+/// Log the scan if it ran for longer than `paradedb.log_min_duration_scan`.  The documents it
+/// matched are those it returned plus those it found to be invisible, although a scan that's cut
+/// short, such as by a `LIMIT`, won't have seen all of its matches
+fn maybe_log_slow_scan(state: &PdbScanState, elapsed: Duration) {
+    if !gucs::log_min_duration_scan().is_some_and(|min_duration| elapsed >= min_duration) {
+        return;
+    }
+
+    let query =
+        serde_json::to_string(&state.search_query_input).expect("query should serialize to json");
+    pgrx::log!(
+        "ParadeDB Scan on index {} took {:.3} ms, matched {} documents and returned {} rows: {query}",
+        state.index_name,
+        elapsed.as_secs_f64() * 1000.0,
+        state.returned_tuple_count + state.invisible_tuple_count,
+        state.returned_tuple_count,
+    );
+}
+
+/// The number of rows the InitPlan that computes `param` is estimated to return
+unsafe fn init_plan_rows(
+    mut root: *mut pg_sys::PlannerInfo,
+    param: *mut pg_sys::Param,
+) -> Option<f64> {
+    while !root.is_null() {
+        let init_plans = PgList::<pg_sys::SubPlan>::from_pg((*root).init_plans);
+        if let Some(subplan) = init_plans
+            .iter_ptr()
+            .find(|subplan| pg_sys::list_member_int((*subplan).setParam, (*param).paramid))
+        {
+            let plans = PgList::<pg_sys::Plan>::from_pg((*(*root).glob).subplans);
+            let plan = plans.get_ptr((*subplan).plan_id as usize - 1)?;
+            return Some((*plan).plan_rows);
+        }
+        root = (*root).parent_root;
+    }
+    None
+}
+
+/// Recheck the row in `slot`, the one with `ctid`, against the pushed-down clauses the index said
+/// it matches, and count it as a failure if it doesn't satisfy them
+unsafe fn recheck(
+    state: &mut CustomScanStateWrapper<PdbScan>,
+    ctid: u64,
+    slot: *mut pg_sys::TupleTableSlot,
+) {
+    let qual = state
+        .custom_state()
+        .recheck_qual
+        .expect("recheck qual should be initialized");
+
+    state.custom_state_mut().recheck_count += 1;
+    if !satisfies_qual(state, qual, slot) {
+        state.custom_state_mut().recheck_failure_count += 1;
+        let mut ipd = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(ctid, &mut ipd);
+        let (blockno, offno) = item_pointer_get_both(ipd);
+        pgrx::debug1!("row ({blockno},{offno}) failed its recheck");
+    }
+}
+
+/// Does the row in `slot` satisfy `qual`, which was initialized for our scan?
+unsafe fn satisfies_qual(
+    state: &mut CustomScanStateWrapper<PdbScan>,
+    qual: *mut pg_sys::ExprState,
+    slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    let econtext = (*state.planstate()).ps_ExprContext;
+    pg_sys::MemoryContextReset((*econtext).ecxt_per_tuple_memory);
+    (*econtext).ecxt_scantuple = slot;
+
+    let evalfunc = (*qual)
+        .evalfunc
+        .expect("qual's ExprState should have an evalfunc");
+    let mut isnull = false;
+    let satisfied = PgMemoryContexts::For((*econtext).ecxt_per_tuple_memory)
+        .switch_to(|_| evalfunc(qual, econtext, &mut isnull));
+    !isnull && satisfied.value() != 0
+}
+
+/// Evaluate `expr`, such as a Param, in the context of `planstate`.  That runs a Param's InitPlan
+/// if it hasn't yet
+unsafe fn eval_param(planstate: *mut pg_sys::PlanState, expr: *mut pg_sys::Node) -> ParamValue {
+    let exprstate = pg_sys::ExecInitExpr(expr.cast(), planstate);
+    let evalfunc = (*exprstate)
+        .evalfunc
+        .expect("expression's ExprState should have an evalfunc");
+    let mut isnull = false;
+    let datum = evalfunc(exprstate, (*planstate).ps_ExprContext, &mut isnull);
+    if isnull {
+        return ParamValue::Null;
+    }
+    ParamValue::Value(datum)
+}
+
+/// Is `rti` alone on the inside of a semijoin, such as the relation of an `EXISTS` subquery that's
+/// been pulled up into the outer query?
+unsafe fn is_semijoin_inner(root: *mut pg_sys::PlannerInfo, rti: pg_sys::Index) -> bool {
+    PgList::<pg_sys::SpecialJoinInfo>::from_pg((*root).join_info_list)
+        .iter_ptr()
+        .any(|sjinfo| {
+            (*sjinfo).jointype == pg_sys::JoinType::JOIN_SEMI
+                && pg_sys::bms_is_member(rti as _, (*sjinfo).syn_righthand)
+                && pg_sys::bms_num_members((*sjinfo).syn_righthand) == 1
+        })
+}
+
+/// Can the field named `field` hold at most one value per row?  It must index a single term per
+/// value and must not be backed by an array column.
+fn is_single_valued_field(table: &PgRelation, search_index: &SearchIndex, field: &str) -> bool {
+    if !search_index.schema.is_field_single_term(field) {
+        return false;
+    }
+
+    match table.tuple_desc().iter().find(|att| att.name() == field) {
+        Some(att) => unsafe { pg_sys::get_element_type(att.atttypid) == pg_sys::InvalidOid },
+
+        // the "ctid" field, which isn't a user column, is always single-valued
+        None => field == "ctid",
+    }
+}
+
 unsafe fn is_lower_func(node: *mut pg_sys::Node, rti: i32) -> Option<*mut pg_sys::Var> {
     let funcexpr = nodecast!(FuncExpr, T_FuncExpr, node)?;
     if (*funcexpr).funcid == text_lower_funcoid() {
@@ -776,6 +2189,19 @@ unsafe fn is_lower_func(node: *mut pg_sys::Node, rti: i32) -> Option<*mut pg_sys
     None
 }
 
+/// The column and reduction of `node`, if it's a `paradedb.array_min()` or `paradedb.array_max()`
+/// call over a column of the relation at `rti`
+unsafe fn is_array_reduction_func(
+    node: *mut pg_sys::Node,
+    rti: i32,
+) -> Option<(*mut pg_sys::Var, SortReduction)> {
+    let funcexpr = nodecast!(FuncExpr, T_FuncExpr, node)?;
+    let reduction = array_reduction((*funcexpr).funcid)?;
+    let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+    let var = nodecast!(Var, T_Var, args.get_ptr(0)?)?;
+    ((*var).varno as i32 == rti).then_some((var, reduction))
+}
+
 pub fn text_lower_funcoid() -> pg_sys::Oid {
     unsafe {
         direct_function_call::<pg_sys::Oid>(
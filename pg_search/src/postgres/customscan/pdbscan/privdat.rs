@@ -17,10 +17,15 @@
 
 use crate::api::operator::anyelement_query_input_opoid;
 use crate::api::Cardinality;
+use crate::gucs::UnscoredScoreValue;
+use crate::index::reader::{ScoreTieBreak, SortKey, SortReduction};
 use crate::postgres::customscan::builders::custom_path::OrderByStyle;
 use crate::postgres::customscan::builders::custom_path::SortDirection;
 use crate::postgres::customscan::pdbscan::qual_inspect::{extract_quals, Qual};
-use pgrx::{pg_sys, PgList};
+use crate::postgres::index::open_search_index;
+use crate::query::SearchQueryInput;
+use crate::schema::SearchIndexSchema;
+use pgrx::{pg_sys, PgList, PgRelation};
 
 #[derive(Default, Debug)]
 pub struct PrivateData {
@@ -32,6 +37,24 @@ pub struct PrivateData {
     sort_field: Option<String>,
     sort_direction: Option<SortDirection>,
     var_attname_lookup: Option<*mut pg_sys::List>,
+    max_segments: Option<usize>,
+    score_tiebreak: Option<ScoreTieBreak>,
+    matches_nothing: Option<bool>,
+    as_of_generation: Option<u64>,
+    score_percentile: Option<f64>,
+    limit_with_ties: Option<bool>,
+    sort_expression: Option<String>,
+    collapse_field: Option<String>,
+    exists_only: Option<bool>,
+    external_exprs: Option<*mut pg_sys::List>,
+    sort_reduction: Option<SortReduction>,
+    unscored_score_value: Option<UnscoredScoreValue>,
+    reverse_top_n: Option<bool>,
+    dedup_field: Option<String>,
+    sort_nulls_first: Option<bool>,
+    sort_keys: Option<Vec<SortKey>>,
+    score_precision: Option<u32>,
+    global_idf: Option<bool>,
 }
 
 impl From<*mut pg_sys::List> for PrivateData {
@@ -75,7 +98,31 @@ impl PrivateData {
         if let Some(style) = pathkey {
             match style {
                 OrderByStyle::Score(_) => {}
-                OrderByStyle::Field(_, name) => self.sort_field = Some(name.clone()),
+                OrderByStyle::Field(_, name) => {
+                    self.sort_field = Some(name.clone());
+                    self.sort_nulls_first = Some(style.nulls_first());
+                }
+                OrderByStyle::ReducedField(_, name, reduction) => {
+                    self.sort_field = Some(name.clone());
+                    self.sort_reduction = Some(*reduction);
+                }
+                OrderByStyle::Expression(_, expression) => {
+                    self.sort_expression = Some(expression.clone())
+                }
+                OrderByStyle::Fields(keys) => {
+                    self.sort_keys = Some(
+                        keys.iter()
+                            .map(|(pathkey, name)| unsafe {
+                                let direction: SortDirection = (**pathkey).pk_strategy.into();
+                                SortKey {
+                                    field: name.clone(),
+                                    direction: direction.into(),
+                                    nulls_first: (**pathkey).pk_nulls_first,
+                                }
+                            })
+                            .collect(),
+                    );
+                }
             }
         }
     }
@@ -87,6 +134,62 @@ impl PrivateData {
     pub fn set_var_attname_lookup(&mut self, var_attname_lookup: *mut pg_sys::List) {
         self.var_attname_lookup = Some(var_attname_lookup);
     }
+
+    pub fn set_max_segments(&mut self, max_segments: Option<usize>) {
+        self.max_segments = max_segments;
+    }
+
+    pub fn set_score_tiebreak(&mut self, tiebreak: ScoreTieBreak) {
+        self.score_tiebreak = Some(tiebreak);
+    }
+
+    pub fn set_matches_nothing(&mut self, matches_nothing: bool) {
+        self.matches_nothing = Some(matches_nothing);
+    }
+
+    pub fn set_as_of_generation(&mut self, generation: Option<u64>) {
+        self.as_of_generation = generation;
+    }
+
+    pub fn set_score_percentile(&mut self, percentile: Option<f64>) {
+        self.score_percentile = percentile;
+    }
+
+    pub fn set_limit_with_ties(&mut self, with_ties: bool) {
+        self.limit_with_ties = Some(with_ties);
+    }
+
+    pub fn set_unscored_score_value(&mut self, value: UnscoredScoreValue) {
+        self.unscored_score_value = Some(value);
+    }
+
+    pub fn set_collapse_field(&mut self, field: Option<String>) {
+        self.collapse_field = field;
+    }
+
+    pub fn set_exists_only(&mut self, exists_only: bool) {
+        self.exists_only = Some(exists_only);
+    }
+
+    pub fn set_reverse_top_n(&mut self, reverse: bool) {
+        self.reverse_top_n = Some(reverse);
+    }
+
+    pub fn set_dedup_field(&mut self, field: Option<String>) {
+        self.dedup_field = field;
+    }
+
+    pub fn set_external_exprs(&mut self, exprs: Option<*mut pg_sys::List>) {
+        self.external_exprs = exprs;
+    }
+
+    pub fn set_score_precision(&mut self, decimal_places: Option<u32>) {
+        self.score_precision = decimal_places;
+    }
+
+    pub fn set_global_idf(&mut self, global_idf: bool) {
+        self.global_idf = Some(global_idf);
+    }
 }
 
 //
@@ -106,7 +209,7 @@ impl PrivateData {
         self.range_table_index
     }
 
-    pub fn quals(&self) -> Option<Qual> {
+    pub fn quals(&self, heaprel: &PgRelation, schema: &SearchIndexSchema) -> Option<Qual> {
         unsafe {
             self.restrict_info.and_then(|ri| {
                 extract_quals(
@@ -114,11 +217,18 @@ impl PrivateData {
                         .expect("rti should be set to get a Qual"),
                     ri.cast(),
                     anyelement_query_input_opoid(),
+                    heaprel,
+                    schema,
                 )
             })
         }
     }
 
+    /// The RestrictInfos our quals were extracted from, as the planner gave them to us
+    pub fn restrict_info(&self) -> Option<PgList<pg_sys::RestrictInfo>> {
+        self.restrict_info.map(|ri| unsafe { PgList::from_pg(ri) })
+    }
+
     pub fn limit(&self) -> Option<usize> {
         self.limit
     }
@@ -127,14 +237,188 @@ impl PrivateData {
         self.sort_field.clone()
     }
 
+    pub fn sort_expression(&self) -> Option<String> {
+        self.sort_expression.clone()
+    }
+
+    pub fn sort_reduction(&self) -> Option<SortReduction> {
+        self.sort_reduction
+    }
+
     pub fn sort_direction(&self) -> Option<SortDirection> {
         self.sort_direction
     }
 
+    pub fn sort_nulls_first(&self) -> Option<bool> {
+        self.sort_nulls_first
+    }
+
+    pub fn sort_keys(&self) -> Vec<SortKey> {
+        self.sort_keys.clone().unwrap_or_default()
+    }
+
     pub fn var_attname_lookup(&self) -> Option<PgList<pg_sys::Node>> {
         self.var_attname_lookup
             .map(|list| unsafe { PgList::from_pg(list) })
     }
+
+    pub fn max_segments(&self) -> Option<usize> {
+        self.max_segments
+    }
+
+    pub fn score_tiebreak(&self) -> Option<ScoreTieBreak> {
+        self.score_tiebreak
+    }
+
+    pub fn matches_nothing(&self) -> bool {
+        self.matches_nothing.unwrap_or(false)
+    }
+
+    pub fn as_of_generation(&self) -> Option<u64> {
+        self.as_of_generation
+    }
+
+    pub fn score_percentile(&self) -> Option<f64> {
+        self.score_percentile
+    }
+
+    pub fn limit_with_ties(&self) -> bool {
+        self.limit_with_ties.unwrap_or(false)
+    }
+
+    pub fn unscored_score_value(&self) -> Option<UnscoredScoreValue> {
+        self.unscored_score_value
+    }
+
+    pub fn collapse_field(&self) -> Option<String> {
+        self.collapse_field.clone()
+    }
+
+    pub fn exists_only(&self) -> bool {
+        self.exists_only.unwrap_or(false)
+    }
+
+    pub fn reverse_top_n(&self) -> bool {
+        self.reverse_top_n.unwrap_or(false)
+    }
+
+    pub fn dedup_field(&self) -> Option<String> {
+        self.dedup_field.clone()
+    }
+
+    pub fn external_exprs(&self) -> Option<*mut pg_sys::List> {
+        self.external_exprs
+    }
+
+    pub fn score_precision(&self) -> Option<u32> {
+        self.score_precision
+    }
+
+    pub fn global_idf(&self) -> bool {
+        self.global_idf.unwrap_or(false)
+    }
+}
+
+/// A one-line summary of what the scan was planned to do, decoded from its private data: the
+/// query its quals search for, how its results are sorted and limited, and the options that
+/// change how it searches.  Unset options are left out
+pub fn describe_private(privdat: &PrivateData) -> String {
+    let mut parts = Vec::new();
+
+    if let (Some(heaprelid), Some(indexrelid)) = (privdat.heaprelid, privdat.indexrelid) {
+        // # Safety
+        //
+        // The planner has already locked both relations, and `PgRelation::with_lock` raises an
+        // ERROR if either no longer exists
+        let (heaprel, indexrel) = unsafe {
+            (
+                PgRelation::with_lock(heaprelid, pg_sys::AccessShareLock as _),
+                PgRelation::with_lock(indexrelid, pg_sys::AccessShareLock as _),
+            )
+        };
+        let search_index =
+            open_search_index(&indexrel).expect("should be able to open search index");
+        if let Some(quals) = privdat.quals(&heaprel, &search_index.schema) {
+            let query = serde_json::to_string(&SearchQueryInput::from(quals))
+                .expect("query should serialize to json");
+            parts.push(format!("query={query}"));
+        }
+    }
+
+    let direction = privdat
+        .sort_direction
+        .map(|direction| direction.to_string());
+    if let Some(expression) = &privdat.sort_expression {
+        parts.push(format!(
+            "sort=expression '{expression}' {}",
+            direction.unwrap_or_default()
+        ));
+    } else if let Some(keys) = &privdat.sort_keys {
+        let keys = keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        parts.push(format!("sort={}", keys.join(", ")));
+    } else if let Some(field) = &privdat.sort_field {
+        let mut sort = format!("sort={field}");
+        if let Some(reduction) = privdat.sort_reduction {
+            sort.push_str(&format!(" {reduction}"));
+        }
+        if let Some(direction) = direction {
+            sort.push_str(&format!(" {direction}"));
+        }
+        match privdat.sort_nulls_first {
+            Some(true) => sort.push_str(" nulls first"),
+            Some(false) => sort.push_str(" nulls last"),
+            None => {}
+        }
+        parts.push(sort);
+    } else if let Some(direction) = direction {
+        parts.push(format!("sort=paradedb.score() {direction}"));
+    }
+
+    if let Some(limit) = privdat.limit {
+        parts.push(format!("limit={limit}"));
+    }
+    if let Some(tiebreak) = privdat.score_tiebreak {
+        if tiebreak != ScoreTieBreak::default() {
+            parts.push(format!("tiebreak={tiebreak}"));
+        }
+    }
+    if let Some(value) = privdat.unscored_score_value {
+        parts.push(format!("unscored_score_value={value}"));
+    }
+    if let Some(max_segments) = privdat.max_segments {
+        parts.push(format!("max_segments={max_segments}"));
+    }
+    if let Some(generation) = privdat.as_of_generation {
+        parts.push(format!("as_of_generation={generation}"));
+    }
+    if let Some(percentile) = privdat.score_percentile {
+        parts.push(format!("score_percentile={percentile}"));
+    }
+    if let Some(decimal_places) = privdat.score_precision {
+        parts.push(format!("score_precision={decimal_places}"));
+    }
+    if let Some(field) = &privdat.collapse_field {
+        parts.push(format!("collapse_field={field}"));
+    }
+    if let Some(field) = &privdat.dedup_field {
+        parts.push(format!("dedup_field={field}"));
+    }
+
+    let flags = [
+        ("matches_nothing", privdat.matches_nothing()),
+        ("exists_only", privdat.exists_only()),
+        ("limit_with_ties", privdat.limit_with_ties()),
+        ("reverse_top_n", privdat.reverse_top_n()),
+        ("global_idf", privdat.global_idf()),
+    ]
+    .into_iter()
+    .filter_map(|(name, set)| set.then_some(name))
+    .collect::<Vec<_>>();
+    if !flags.is_empty() {
+        parts.push(format!("flags={}", flags.join("|")));
+    }
+
+    parts.join(", ")
 }
 
 #[allow(non_snake_case)]
@@ -195,6 +479,26 @@ mod serialize {
         ser.push(unwrapOrNull(
             privdat.var_attname_lookup.map(|v| v.cast::<pg_sys::Node>()),
         ));
+        ser.push(makeString(privdat.max_segments));
+        ser.push(makeString(privdat.score_tiebreak));
+        ser.push(makeBoolean(privdat.matches_nothing));
+        ser.push(makeString(privdat.as_of_generation));
+        ser.push(makeString(privdat.score_percentile));
+        ser.push(makeBoolean(privdat.limit_with_ties));
+        ser.push(makeString(privdat.sort_expression));
+        ser.push(makeString(privdat.collapse_field));
+        ser.push(makeBoolean(privdat.exists_only));
+        ser.push(unwrapOrNull(privdat.external_exprs.map(|l| l.cast())));
+        ser.push(makeString(privdat.sort_reduction));
+        ser.push(makeString(privdat.unscored_score_value));
+        ser.push(makeBoolean(privdat.reverse_top_n));
+        ser.push(makeString(privdat.dedup_field));
+        ser.push(makeBoolean(privdat.sort_nulls_first));
+        ser.push(makeString(privdat.sort_keys.map(|keys| {
+            serde_json::to_string(&keys).expect("sort keys should serialize")
+        })));
+        ser.push(makeString(privdat.score_precision));
+        ser.push(makeBoolean(privdat.global_idf));
 
         ser
     }
@@ -236,6 +540,27 @@ mod deserialize {
             var_attname_lookup: input
                 .get_ptr(7)
                 .and_then(|n| nodecast!(List, T_List, n, true)),
+            max_segments: input.get_ptr(8).and_then(|n| decodeString(n)),
+            score_tiebreak: input.get_ptr(9).and_then(|n| decodeString(n)),
+            matches_nothing: input.get_ptr(10).and_then(|n| decodeBoolean(n)),
+            as_of_generation: input.get_ptr(11).and_then(|n| decodeString(n)),
+            score_percentile: input.get_ptr(12).and_then(|n| decodeString(n)),
+            limit_with_ties: input.get_ptr(13).and_then(|n| decodeBoolean(n)),
+            sort_expression: input.get_ptr(14).and_then(|n| decodeString(n)),
+            collapse_field: input.get_ptr(15).and_then(|n| decodeString(n)),
+            exists_only: input.get_ptr(16).and_then(|n| decodeBoolean(n)),
+            external_exprs: input.get_ptr(17).and_then(|n| nodecast!(List, T_List, n)),
+            sort_reduction: input.get_ptr(18).and_then(|n| decodeString(n)),
+            unscored_score_value: input.get_ptr(19).and_then(|n| decodeString(n)),
+            reverse_top_n: input.get_ptr(20).and_then(|n| decodeBoolean(n)),
+            dedup_field: input.get_ptr(21).and_then(|n| decodeString(n)),
+            sort_nulls_first: input.get_ptr(22).and_then(|n| decodeBoolean(n)),
+            sort_keys: input
+                .get_ptr(23)
+                .and_then(|n| decodeString::<String>(n))
+                .map(|json| serde_json::from_str(&json).expect("sort keys should deserialize")),
+            score_precision: input.get_ptr(24).and_then(|n| decodeString(n)),
+            global_idf: input.get_ptr(25).and_then(|n| decodeBoolean(n)),
         }
     }
 }
@@ -0,0 +1,176 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::nodecast;
+use pgrx::pg_sys::expression_tree_walker;
+use pgrx::{
+    direct_function_call, pg_extern, pg_guard, pg_sys, AnyElement, FromDatum, IntoDatum, JsonB,
+    PgList,
+};
+use std::collections::{HashMap, HashSet};
+use std::ptr::addr_of_mut;
+
+/// The row's score along with the values of the fast fields `names`, as a jsonb object like
+/// `{"score": 1.5, "features": {"views": 10, "ctr": 0.25}}`, read in the same pass that scores
+/// the row, such as to log features for a learning-to-rank model.  `NULL` when it isn't
+/// evaluated by our custom scan.
+#[pg_extern(name = "features", stable, parallel_safe, cost = 1)]
+fn features_from_relation(_relation_reference: AnyElement, _names: Vec<String>) -> Option<JsonB> {
+    None
+}
+
+pub fn features_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[c"paradedb.features(anyelement, text[])".into_datum()],
+        )
+        .expect("the `paradedb.features(anyelement, text[])` function should exist")
+    }
+}
+
+/// The field names of `funcexpr`, if it's a `paradedb.features()` call for the relation at `rti`
+unsafe fn feature_names(
+    funcexpr: *mut pg_sys::FuncExpr,
+    features_funcoid: pg_sys::Oid,
+    rti: pg_sys::Index,
+) -> Option<Vec<String>> {
+    if (*funcexpr).funcid != features_funcoid {
+        return None;
+    }
+
+    let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+    assert!(args.len() == 2, "features function must have 2 arguments");
+    let var = nodecast!(Var, T_Var, args.get_ptr(0).unwrap())?;
+    if (*var).varno as pg_sys::Index != rti {
+        return None;
+    }
+
+    let Some(names) = nodecast!(Const, T_Const, args.get_ptr(1).unwrap()) else {
+        panic!("`paradedb.features()`'s field names must be a literal")
+    };
+    Vec::<String>::from_datum((*names).constvalue, (*names).constisnull)
+}
+
+/// The field names of the `paradedb.features()` calls for the relation at `rti` in `node`
+pub unsafe fn uses_features(
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+    features_funcoid: pg_sys::Oid,
+) -> HashSet<Vec<String>> {
+    #[pg_guard]
+    unsafe extern "C" fn walker(node: *mut pg_sys::Node, data: *mut core::ffi::c_void) -> bool {
+        if node.is_null() {
+            return false;
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let data = &mut *data.cast::<Data>();
+            if let Some(names) = feature_names(funcexpr, data.features_funcoid, data.rti) {
+                data.names.insert(names);
+                return false;
+            }
+        }
+
+        expression_tree_walker(node, Some(walker), data)
+    }
+
+    struct Data {
+        rti: pg_sys::Index,
+        features_funcoid: pg_sys::Oid,
+        names: HashSet<Vec<String>>,
+    }
+
+    let mut data = Data {
+        rti,
+        features_funcoid,
+        names: HashSet::default(),
+    };
+
+    walker(node, addr_of_mut!(data).cast());
+    data.names
+}
+
+/// Replace the `paradedb.features()` calls for the relation at `rti` in `node` with the features
+/// of the row, from `features`, which are keyed by their field names
+pub unsafe fn inject_features(
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+    features_funcoid: pg_sys::Oid,
+    features: HashMap<Vec<String>, serde_json::Value>,
+) -> *mut pg_sys::Node {
+    struct Context {
+        rti: pg_sys::Index,
+        features_funcoid: pg_sys::Oid,
+        features: HashMap<Vec<String>, pg_sys::Datum>,
+    }
+
+    #[pg_guard]
+    unsafe extern "C" fn walker(
+        node: *mut pg_sys::Node,
+        data: *mut core::ffi::c_void,
+    ) -> *mut pg_sys::Node {
+        if node.is_null() {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let context = &*data.cast::<Context>();
+            if let Some(names) = feature_names(funcexpr, context.features_funcoid, context.rti) {
+                let features = context
+                    .features
+                    .get(&names)
+                    .expect("features should have been read");
+                let const_ = pg_sys::makeConst(
+                    pg_sys::JSONBOID,
+                    -1,
+                    pg_sys::InvalidOid,
+                    -1,
+                    *features,
+                    false,
+                    false,
+                );
+
+                return const_.cast();
+            }
+        }
+
+        #[cfg(not(any(feature = "pg16", feature = "pg17")))]
+        {
+            let fnptr = walker as usize as *const ();
+            let walker: unsafe extern "C" fn() -> *mut pg_sys::Node = std::mem::transmute(fnptr);
+            pg_sys::expression_tree_mutator(node, Some(walker), data)
+        }
+
+        #[cfg(any(feature = "pg16", feature = "pg17"))]
+        {
+            pg_sys::expression_tree_mutator_impl(node, Some(walker), data)
+        }
+    }
+
+    let mut context = Context {
+        rti,
+        features_funcoid,
+        features: features
+            .into_iter()
+            .map(|(names, features)| (names, JsonB(features).into_datum().unwrap()))
+            .collect(),
+    };
+
+    let data = addr_of_mut!(context);
+    walker(node, data.cast())
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::nodecast;
+use pgrx::pg_sys::expression_tree_walker;
+use pgrx::{direct_function_call, pg_extern, pg_guard, pg_sys, AnyElement, IntoDatum, JsonB};
+use std::collections::BTreeMap;
+use std::ptr::addr_of_mut;
+use tantivy::Score;
+
+/// How much each field contributed to the row's score, as a jsonb object of field names to scores
+#[pg_extern(name = "field_scores", stable, parallel_safe, cost = 1)]
+fn field_scores_from_relation(_relation_reference: AnyElement) -> Option<JsonB> {
+    None
+}
+
+pub fn field_scores_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[c"paradedb.field_scores(anyelement)".into_datum()],
+        )
+        .expect("the `paradedb.field_scores(anyelement)` function should exist")
+    }
+}
+
+pub unsafe fn uses_field_scores(
+    node: *mut pg_sys::Node,
+    mut field_scores_funcoid: pg_sys::Oid,
+) -> bool {
+    #[pg_guard]
+    unsafe extern "C" fn walker(node: *mut pg_sys::Node, data: *mut core::ffi::c_void) -> bool {
+        if node.is_null() {
+            return false;
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let field_scores_funcoid = data.cast::<pg_sys::Oid>();
+            if (*funcexpr).funcid == *field_scores_funcoid {
+                return true;
+            }
+        }
+
+        expression_tree_walker(node, Some(walker), data)
+    }
+
+    walker(node, addr_of_mut!(field_scores_funcoid).cast())
+}
+
+pub unsafe fn inject_field_scores(
+    node: *mut pg_sys::Node,
+    field_scores_funcoid: pg_sys::Oid,
+    field_scores: BTreeMap<String, Score>,
+) -> *mut pg_sys::Node {
+    struct Context {
+        field_scores_funcoid: pg_sys::Oid,
+        field_scores: pg_sys::Datum,
+    }
+
+    #[pg_guard]
+    unsafe extern "C" fn walker(
+        node: *mut pg_sys::Node,
+        data: *mut core::ffi::c_void,
+    ) -> *mut pg_sys::Node {
+        if node.is_null() {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let context = data.cast::<Context>();
+            if (*funcexpr).funcid == (*context).field_scores_funcoid {
+                let const_ = pg_sys::makeConst(
+                    pg_sys::JSONBOID,
+                    -1,
+                    pg_sys::InvalidOid,
+                    -1,
+                    (*context).field_scores,
+                    false,
+                    false,
+                );
+
+                return const_.cast();
+            }
+        }
+
+        #[cfg(not(any(feature = "pg16", feature = "pg17")))]
+        {
+            let fnptr = walker as usize as *const ();
+            let walker: unsafe extern "C" fn() -> *mut pg_sys::Node = std::mem::transmute(fnptr);
+            pg_sys::expression_tree_mutator(node, Some(walker), data)
+        }
+
+        #[cfg(any(feature = "pg16", feature = "pg17"))]
+        {
+            pg_sys::expression_tree_mutator_impl(node, Some(walker), data)
+        }
+    }
+
+    let field_scores = serde_json::to_value(field_scores).expect("scores should serialize to json");
+    let mut context = Context {
+        field_scores_funcoid,
+        field_scores: JsonB(field_scores).into_datum().unwrap(),
+    };
+
+    let data = addr_of_mut!(context);
+    walker(node, data.cast())
+}
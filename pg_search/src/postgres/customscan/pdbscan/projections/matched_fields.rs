@@ -0,0 +1,118 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::nodecast;
+use pgrx::pg_sys::expression_tree_walker;
+use pgrx::{direct_function_call, pg_extern, pg_guard, pg_sys, AnyElement, IntoDatum};
+use std::ptr::addr_of_mut;
+
+/// The names of the fields the query matched the row on, in order
+#[pg_extern(name = "matched_fields", stable, parallel_safe, cost = 1)]
+fn matched_fields_from_relation(_relation_reference: AnyElement) -> Option<Vec<String>> {
+    None
+}
+
+pub fn matched_fields_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[c"paradedb.matched_fields(anyelement)".into_datum()],
+        )
+        .expect("the `paradedb.matched_fields(anyelement)` function should exist")
+    }
+}
+
+pub unsafe fn uses_matched_fields(
+    node: *mut pg_sys::Node,
+    mut matched_fields_funcoid: pg_sys::Oid,
+) -> bool {
+    #[pg_guard]
+    unsafe extern "C" fn walker(node: *mut pg_sys::Node, data: *mut core::ffi::c_void) -> bool {
+        if node.is_null() {
+            return false;
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let matched_fields_funcoid = data.cast::<pg_sys::Oid>();
+            if (*funcexpr).funcid == *matched_fields_funcoid {
+                return true;
+            }
+        }
+
+        expression_tree_walker(node, Some(walker), data)
+    }
+
+    walker(node, addr_of_mut!(matched_fields_funcoid).cast())
+}
+
+pub unsafe fn inject_matched_fields(
+    node: *mut pg_sys::Node,
+    matched_fields_funcoid: pg_sys::Oid,
+    matched_fields: Vec<String>,
+) -> *mut pg_sys::Node {
+    struct Context {
+        matched_fields_funcoid: pg_sys::Oid,
+        matched_fields: pg_sys::Datum,
+    }
+
+    #[pg_guard]
+    unsafe extern "C" fn walker(
+        node: *mut pg_sys::Node,
+        data: *mut core::ffi::c_void,
+    ) -> *mut pg_sys::Node {
+        if node.is_null() {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let context = data.cast::<Context>();
+            if (*funcexpr).funcid == (*context).matched_fields_funcoid {
+                let const_ = pg_sys::makeConst(
+                    pg_sys::TEXTARRAYOID,
+                    -1,
+                    pg_sys::DEFAULT_COLLATION_OID,
+                    -1,
+                    (*context).matched_fields,
+                    false,
+                    false,
+                );
+
+                return const_.cast();
+            }
+        }
+
+        #[cfg(not(any(feature = "pg16", feature = "pg17")))]
+        {
+            let fnptr = walker as usize as *const ();
+            let walker: unsafe extern "C" fn() -> *mut pg_sys::Node = std::mem::transmute(fnptr);
+            pg_sys::expression_tree_mutator(node, Some(walker), data)
+        }
+
+        #[cfg(any(feature = "pg16", feature = "pg17"))]
+        {
+            pg_sys::expression_tree_mutator_impl(node, Some(walker), data)
+        }
+    }
+
+    let mut context = Context {
+        matched_fields_funcoid,
+        matched_fields: matched_fields.into_datum().unwrap(),
+    };
+
+    let data = addr_of_mut!(context);
+    walker(node, data.cast())
+}
@@ -15,17 +15,40 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+pub mod features;
+pub mod field_scores;
+pub mod matched_fields;
+pub mod rank;
+pub mod relevance_pct;
 pub mod score;
 pub mod snippet;
+pub mod sort_expression;
 
 use crate::nodecast;
+use crate::postgres::customscan::pdbscan::projections::features::features_funcoid;
+use crate::postgres::customscan::pdbscan::projections::field_scores::field_scores_funcoid;
+use crate::postgres::customscan::pdbscan::projections::matched_fields::matched_fields_funcoid;
+use crate::postgres::customscan::pdbscan::projections::rank::rank_funcoid;
+use crate::postgres::customscan::pdbscan::projections::relevance_pct::relevance_pct_funcoid;
 use crate::postgres::customscan::pdbscan::projections::score::score_funcoid;
-use crate::postgres::customscan::pdbscan::projections::snippet::snippet_funcoid;
+use crate::postgres::customscan::pdbscan::projections::snippet::{
+    snippet_funcoid, snippets_funcoid,
+};
+use crate::postgres::customscan::pdbscan::projections::sort_expression::sort_expression_funcoid;
 use pgrx::pg_sys::expression_tree_walker;
 use pgrx::{pg_guard, pg_sys, PgList};
 use std::ptr::addr_of_mut;
 
 pub unsafe fn maybe_needs_const_projections(node: *mut pg_sys::Node) -> bool {
+    uses_const_projection_funcs(node, true)
+}
+
+/// Like [`maybe_needs_const_projections`], but not counting `paradedb.score()`
+pub unsafe fn maybe_needs_const_projections_besides_scores(node: *mut pg_sys::Node) -> bool {
+    uses_const_projection_funcs(node, false)
+}
+
+unsafe fn uses_const_projection_funcs(node: *mut pg_sys::Node, include_scores: bool) -> bool {
     #[pg_guard]
     unsafe extern "C" fn walker(node: *mut pg_sys::Node, data: *mut core::ffi::c_void) -> bool {
         if node.is_null() {
@@ -34,8 +57,15 @@ pub unsafe fn maybe_needs_const_projections(node: *mut pg_sys::Node) -> bool {
 
         if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
             let data = &*data.cast::<Data>();
-            if (*funcexpr).funcid == data.score_funcoid
+            if (data.include_scores && (*funcexpr).funcid == data.score_funcoid)
                 || (*funcexpr).funcid == data.snipped_funcoid
+                || (*funcexpr).funcid == data.snippets_funcoid
+                || (*funcexpr).funcid == data.rank_funcoid
+                || (*funcexpr).funcid == data.sort_expression_funcoid
+                || (*funcexpr).funcid == data.field_scores_funcoid
+                || (*funcexpr).funcid == data.matched_fields_funcoid
+                || (*funcexpr).funcid == data.relevance_pct_funcoid
+                || (*funcexpr).funcid == data.features_funcoid
             {
                 return true;
             }
@@ -45,13 +75,29 @@ pub unsafe fn maybe_needs_const_projections(node: *mut pg_sys::Node) -> bool {
     }
 
     struct Data {
+        include_scores: bool,
         score_funcoid: pg_sys::Oid,
         snipped_funcoid: pg_sys::Oid,
+        snippets_funcoid: pg_sys::Oid,
+        rank_funcoid: pg_sys::Oid,
+        sort_expression_funcoid: pg_sys::Oid,
+        field_scores_funcoid: pg_sys::Oid,
+        matched_fields_funcoid: pg_sys::Oid,
+        relevance_pct_funcoid: pg_sys::Oid,
+        features_funcoid: pg_sys::Oid,
     }
 
     let mut data = Data {
+        include_scores,
         score_funcoid: score_funcoid(),
         snipped_funcoid: snippet_funcoid(),
+        snippets_funcoid: snippets_funcoid(),
+        rank_funcoid: rank_funcoid(),
+        sort_expression_funcoid: sort_expression_funcoid(),
+        field_scores_funcoid: field_scores_funcoid(),
+        matched_fields_funcoid: matched_fields_funcoid(),
+        relevance_pct_funcoid: relevance_pct_funcoid(),
+        features_funcoid: features_funcoid(),
     };
 
     let data = addr_of_mut!(data).cast();
@@ -20,8 +20,9 @@ use crate::nodecast;
 use pgrx::pg_sys::expression_tree_walker;
 use pgrx::{
     default, direct_function_call, pg_extern, pg_guard, pg_sys, AnyElement, FromDatum, IntoDatum,
-    PgList,
+    JsonB, PgList,
 };
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::ptr::addr_of_mut;
 use tantivy::snippet::SnippetGenerator;
@@ -29,6 +30,7 @@ use tantivy::DocAddress;
 
 const DEFAULT_SNIPPET_PREFIX: &str = "<b>";
 const DEFAULT_SNIPPET_POSTFIX: &str = "</b>";
+const DEFAULT_SNIPPET_MAX_NUM_CHARS: i32 = 150;
 
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub struct SnippetInfo {
@@ -48,6 +50,27 @@ fn snippet_from_relation(
     None
 }
 
+/// Per-field overrides of the options given to `paradedb.snippets()`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SnippetOptions {
+    start_tag: Option<String>,
+    end_tag: Option<String>,
+    max_num_chars: Option<i32>,
+}
+
+#[pg_extern(name = "snippets", stable, parallel_safe)]
+fn snippets_from_relation(
+    key: AnyElement,
+    fields: Vec<String>,
+    start_tag: default!(String, "'<b>'"),
+    end_tag: default!(String, "'</b>'"),
+    max_num_chars: default!(i32, "150"),
+    field_options: default!(JsonB, "'{}'"),
+) -> Option<JsonB> {
+    None
+}
+
 pub fn snippet_funcoid() -> pg_sys::Oid {
     unsafe {
         direct_function_call::<pg_sys::Oid>(
@@ -58,16 +81,102 @@ pub fn snippet_funcoid() -> pg_sys::Oid {
     }
 }
 
+pub fn snippets_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[c"paradedb.snippets(anyelement, text[], text, text, int, jsonb)".into_datum()],
+        )
+        .expect(
+            "the `paradedb.snippets(anyelement, text[], text, text, int, jsonb) type should exist",
+        )
+    }
+}
+
+/// The [`SnippetInfo`] of each field requested by a `paradedb.snippets()` call with the
+/// specified `args`, if the call is for the relation at `rti`
+unsafe fn snippets_info(
+    rti: pg_sys::Index,
+    args: &PgList<pg_sys::Node>,
+) -> Option<Vec<SnippetInfo>> {
+    // this should be equal to the number of args in the `snippets()` function above
+    assert!(args.len() == 6);
+
+    let key_arg = nodecast!(Var, T_Var, args.get_ptr(0).unwrap());
+    if !matches!(key_arg, Some(var) if (*var).varno as pg_sys::Index == rti) {
+        return None;
+    }
+
+    let fields_arg = nodecast!(Const, T_Const, args.get_ptr(1).unwrap());
+    let start_arg = nodecast!(Const, T_Const, args.get_ptr(2).unwrap());
+    let end_arg = nodecast!(Const, T_Const, args.get_ptr(3).unwrap());
+    let max_num_chars_arg = nodecast!(Const, T_Const, args.get_ptr(4).unwrap());
+    let options_arg = nodecast!(Const, T_Const, args.get_ptr(5).unwrap());
+
+    let (
+        Some(fields_arg),
+        Some(start_arg),
+        Some(end_arg),
+        Some(max_num_chars_arg),
+        Some(options_arg),
+    ) = (
+        fields_arg,
+        start_arg,
+        end_arg,
+        max_num_chars_arg,
+        options_arg,
+    )
+    else {
+        panic!("`paradedb.snippets()`'s arguments must be literals")
+    };
+
+    let fields = Vec::<String>::from_datum((*fields_arg).constvalue, (*fields_arg).constisnull)
+        .unwrap_or_default();
+    let start_tag = String::from_datum((*start_arg).constvalue, (*start_arg).constisnull)
+        .unwrap_or_else(|| DEFAULT_SNIPPET_PREFIX.to_string());
+    let end_tag = String::from_datum((*end_arg).constvalue, (*end_arg).constisnull)
+        .unwrap_or_else(|| DEFAULT_SNIPPET_POSTFIX.to_string());
+    let max_num_chars = i32::from_datum(
+        (*max_num_chars_arg).constvalue,
+        (*max_num_chars_arg).constisnull,
+    )
+    .unwrap_or(DEFAULT_SNIPPET_MAX_NUM_CHARS);
+    let mut field_options: HashMap<String, SnippetOptions> =
+        match JsonB::from_datum((*options_arg).constvalue, (*options_arg).constisnull) {
+            Some(JsonB(options)) => serde_json::from_value(options).unwrap_or_else(|e| {
+                panic!("`paradedb.snippets()`'s field_options are invalid: {e}")
+            }),
+            None => HashMap::new(),
+        };
+
+    Some(
+        fields
+            .into_iter()
+            .map(|field| {
+                let options = field_options.remove(&field).unwrap_or_default();
+                SnippetInfo {
+                    start_tag: options.start_tag.unwrap_or_else(|| start_tag.clone()),
+                    end_tag: options.end_tag.unwrap_or_else(|| end_tag.clone()),
+                    max_num_chars: options.max_num_chars.unwrap_or(max_num_chars).max(0) as usize,
+                    field,
+                }
+            })
+            .collect(),
+    )
+}
+
 pub unsafe fn uses_snippets(
     rti: pg_sys::Index,
     attname_lookup: &HashMap<(i32, pg_sys::AttrNumber), String>,
     node: *mut pg_sys::Node,
     snippet_funcoid: pg_sys::Oid,
+    snippets_funcoid: pg_sys::Oid,
 ) -> Vec<SnippetInfo> {
     struct Context<'a> {
         rti: pg_sys::Index,
         attname_lookup: &'a HashMap<(i32, pg_sys::AttrNumber), String>,
         snippet_funcoid: pg_sys::Oid,
+        snippets_funcoid: pg_sys::Oid,
         snippet_info: Vec<SnippetInfo>,
     }
 
@@ -116,6 +225,11 @@ pub unsafe fn uses_snippets(
                 } else {
                     panic!("`paradedb.snippet()`'s arguments must be literals")
                 }
+            } else if (*funcexpr).funcid == (*context).snippets_funcoid {
+                let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+                if let Some(snippet_info) = snippets_info((*context).rti, &args) {
+                    (*context).snippet_info.extend(snippet_info);
+                }
             }
         }
 
@@ -126,6 +240,7 @@ pub unsafe fn uses_snippets(
         rti,
         attname_lookup,
         snippet_funcoid,
+        snippets_funcoid,
         snippet_info: vec![],
     };
 
@@ -233,3 +348,106 @@ pub unsafe fn inject_snippet(
     let data = addr_of_mut!(context);
     walker(node, data.cast())
 }
+
+/// Replace every `paradedb.snippets()` call for the relation at `rti` with a `jsonb` Const of each
+/// requested field's snippet for the document at `doc_address`.  Fields without any highlighted
+/// terms have a `null` snippet.
+pub unsafe fn inject_snippets(
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+    snippets_funcoid: pg_sys::Oid,
+    search_reader: &SearchIndexReader,
+    snippet_generators: &HashMap<SnippetInfo, Option<SnippetGenerator>>,
+    doc_address: DocAddress,
+) -> *mut pg_sys::Node {
+    struct Context<'a> {
+        rti: pg_sys::Index,
+        snippets_funcoid: pg_sys::Oid,
+        search_reader: &'a SearchIndexReader,
+        snippet_generators: &'a HashMap<SnippetInfo, Option<SnippetGenerator>>,
+        doc_address: DocAddress,
+    }
+
+    #[pg_guard]
+    unsafe extern "C" fn walker(
+        node: *mut pg_sys::Node,
+        data: *mut core::ffi::c_void,
+    ) -> *mut pg_sys::Node {
+        if node.is_null() {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let context = data.cast::<Context>();
+
+            if (*funcexpr).funcid == (*context).snippets_funcoid {
+                let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+                if let Some(snippet_info) = snippets_info((*context).rti, &args) {
+                    // every field's snippet comes from the same stored document
+                    let doc = (*context)
+                        .search_reader
+                        .get_doc((*context).doc_address)
+                        .expect("should be able to retrieve doc for snippet generation");
+
+                    let mut snippets = serde_json::Map::new();
+                    for snippet_info in snippet_info {
+                        let generator = (*context)
+                            .snippet_generators
+                            .get(&snippet_info)
+                            .and_then(Option::as_ref)
+                            .expect("SnippetGenerator should have been created");
+
+                        let mut snippet = generator.snippet_from_doc(&doc);
+                        let html = if snippet.highlighted().is_empty() {
+                            serde_json::Value::Null
+                        } else {
+                            snippet.set_snippet_prefix_postfix(
+                                &snippet_info.start_tag,
+                                &snippet_info.end_tag,
+                            );
+                            serde_json::Value::String(snippet.to_html())
+                        };
+                        snippets.insert(snippet_info.field, html);
+                    }
+
+                    let json = JsonB(serde_json::Value::Object(snippets))
+                        .into_datum()
+                        .unwrap();
+                    let const_ = pg_sys::makeConst(
+                        pg_sys::JSONBOID,
+                        -1,
+                        pg_sys::InvalidOid,
+                        -1,
+                        json,
+                        false,
+                        false,
+                    );
+                    return const_.cast();
+                }
+            }
+        }
+
+        #[cfg(not(any(feature = "pg16", feature = "pg17")))]
+        {
+            let fnptr = walker as usize as *const ();
+            let walker: unsafe extern "C" fn() -> *mut pg_sys::Node = std::mem::transmute(fnptr);
+            pg_sys::expression_tree_mutator(node, Some(walker), data)
+        }
+
+        #[cfg(any(feature = "pg16", feature = "pg17"))]
+        {
+            pg_sys::expression_tree_mutator_impl(node, Some(walker), data)
+        }
+    }
+
+    let mut context = Context {
+        rti,
+        snippets_funcoid,
+        search_reader,
+        snippet_generators,
+        doc_address,
+    };
+
+    let data = addr_of_mut!(context);
+    walker(node, data.cast())
+}
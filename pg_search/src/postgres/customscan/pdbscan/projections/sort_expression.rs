@@ -0,0 +1,191 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::index::sort_expression::SortExpression;
+use crate::nodecast;
+use pgrx::pg_sys::expression_tree_walker;
+use pgrx::{
+    direct_function_call, pg_extern, pg_guard, pg_sys, AnyElement, FromDatum, IntoDatum, PgList,
+};
+use std::collections::HashMap;
+use std::ptr::addr_of_mut;
+
+/// The value of `expression` for the row, which sorts rows when it's used in an `ORDER BY`.  See
+/// [`SortExpression`] for what an expression may be made of.  `NULL` when it isn't evaluated by
+/// our custom scan.
+#[pg_extern(name = "sort_expression", stable, parallel_safe, cost = 1)]
+fn sort_expression_from_relation(_relation_reference: AnyElement, expression: &str) -> Option<f64> {
+    if let Err(e) = expression.parse::<SortExpression>() {
+        panic!("{e}");
+    }
+    None
+}
+
+pub fn sort_expression_funcoid() -> pg_sys::Oid {
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(
+            pg_sys::regprocedurein,
+            &[c"paradedb.sort_expression(anyelement, text)".into_datum()],
+        )
+        .expect("the `paradedb.sort_expression(anyelement, text)` function should exist")
+    }
+}
+
+/// The expression text of `funcexpr`, if it's a `paradedb.sort_expression()` call for the
+/// relation at `rti`
+unsafe fn sort_expression_text(
+    funcexpr: *mut pg_sys::FuncExpr,
+    sort_expression_funcoid: pg_sys::Oid,
+    rti: pg_sys::Index,
+) -> Option<String> {
+    if (*funcexpr).funcid != sort_expression_funcoid {
+        return None;
+    }
+
+    let args = PgList::<pg_sys::Node>::from_pg((*funcexpr).args);
+    assert!(
+        args.len() == 2,
+        "sort_expression function must have 2 arguments"
+    );
+    let var = nodecast!(Var, T_Var, args.get_ptr(0).unwrap())?;
+    if (*var).varno as pg_sys::Index != rti {
+        return None;
+    }
+
+    let Some(expression) = nodecast!(Const, T_Const, args.get_ptr(1).unwrap()) else {
+        panic!("`paradedb.sort_expression()`'s expression must be a literal")
+    };
+    String::from_datum((*expression).constvalue, (*expression).constisnull)
+}
+
+/// The expression of `node`, if it's a `paradedb.sort_expression()` call for the relation at `rti`
+pub unsafe fn is_sort_expression_func(node: *mut pg_sys::Node, rti: i32) -> Option<String> {
+    let funcexpr = nodecast!(FuncExpr, T_FuncExpr, node)?;
+    sort_expression_text(funcexpr, sort_expression_funcoid(), rti as pg_sys::Index)
+}
+
+/// The expressions of the `paradedb.sort_expression()` calls for the relation at `rti` in `node`,
+/// parsed and keyed by their text
+pub unsafe fn uses_sort_expressions(
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+    sort_expression_funcoid: pg_sys::Oid,
+) -> HashMap<String, SortExpression> {
+    #[pg_guard]
+    unsafe extern "C" fn walker(node: *mut pg_sys::Node, data: *mut core::ffi::c_void) -> bool {
+        if node.is_null() {
+            return false;
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let data = &mut *data.cast::<Data>();
+            if let Some(text) =
+                sort_expression_text(funcexpr, data.sort_expression_funcoid, data.rti)
+            {
+                let expression = text.parse().unwrap_or_else(|e| panic!("{e}"));
+                data.expressions.insert(text, expression);
+                return false;
+            }
+        }
+
+        expression_tree_walker(node, Some(walker), data)
+    }
+
+    struct Data {
+        rti: pg_sys::Index,
+        sort_expression_funcoid: pg_sys::Oid,
+        expressions: HashMap<String, SortExpression>,
+    }
+
+    let mut data = Data {
+        rti,
+        sort_expression_funcoid,
+        expressions: HashMap::default(),
+    };
+
+    walker(node, addr_of_mut!(data).cast());
+    data.expressions
+}
+
+/// Replace the `paradedb.sort_expression()` calls for the relation at `rti` in `node` with the
+/// values of their expressions, from `values`
+pub unsafe fn inject_sort_expressions(
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+    sort_expression_funcoid: pg_sys::Oid,
+    values: &HashMap<String, f64>,
+) -> *mut pg_sys::Node {
+    struct Context<'a> {
+        rti: pg_sys::Index,
+        sort_expression_funcoid: pg_sys::Oid,
+        values: &'a HashMap<String, f64>,
+    }
+
+    #[pg_guard]
+    unsafe extern "C" fn walker(
+        node: *mut pg_sys::Node,
+        data: *mut core::ffi::c_void,
+    ) -> *mut pg_sys::Node {
+        if node.is_null() {
+            return std::ptr::null_mut();
+        }
+
+        if let Some(funcexpr) = nodecast!(FuncExpr, T_FuncExpr, node) {
+            let context = &*data.cast::<Context>();
+            if let Some(text) =
+                sort_expression_text(funcexpr, context.sort_expression_funcoid, context.rti)
+            {
+                let value = context
+                    .values
+                    .get(&text)
+                    .expect("sort expression should have been evaluated");
+                let const_ = pg_sys::makeConst(
+                    pg_sys::FLOAT8OID,
+                    -1,
+                    pg_sys::Oid::INVALID,
+                    size_of::<f64>() as _,
+                    (*value).into_datum().unwrap(),
+                    false,
+                    true,
+                );
+
+                return const_.cast();
+            }
+        }
+
+        #[cfg(not(any(feature = "pg16", feature = "pg17")))]
+        {
+            let fnptr = walker as usize as *const ();
+            let walker: unsafe extern "C" fn() -> *mut pg_sys::Node = std::mem::transmute(fnptr);
+            pg_sys::expression_tree_mutator(node, Some(walker), data)
+        }
+
+        #[cfg(any(feature = "pg16", feature = "pg17"))]
+        {
+            pg_sys::expression_tree_mutator_impl(node, Some(walker), data)
+        }
+    }
+
+    let mut context = Context {
+        rti,
+        sort_expression_funcoid,
+        values,
+    };
+
+    let data = addr_of_mut!(context);
+    walker(node, data.cast())
+}
@@ -15,9 +15,20 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use crate::api::operator::anyelement_text_opoid;
 use crate::nodecast;
+use crate::postgres::types::TantivyValue;
 use crate::query::SearchQueryInput;
-use pgrx::{node_to_string, pg_sys, FromDatum, PgList};
+use crate::schema::{SearchFieldConfig, SearchFieldName, SearchIndexSchema};
+use pgrx::{
+    direct_function_call, node_to_string, pg_sys, FromDatum, IntoDatum, PgList, PgOid, PgRelation,
+};
+use std::cmp::Ordering;
+use std::ffi::CStr;
+use std::ops::Bound;
+use tantivy::query::RegexQuery;
+use tantivy::schema::OwnedValue;
+use tokenizers::SearchNormalizer;
 
 #[derive(Debug, Clone)]
 pub enum Qual {
@@ -27,25 +38,303 @@ pub enum Qual {
         opno: pg_sys::Oid,
         val: *mut pg_sys::Const,
     },
+    /// A Postgres regular expression operator, such as `~`, on a keyword field, translated into
+    /// a regex that must match a whole term
+    Regex {
+        field: String,
+        pattern: String,
+        negated: bool,
+    },
+    /// A field being distinct from a constant, as with `IS DISTINCT FROM`, which unlike `<>` is
+    /// also true of the rows where the field is NULL
+    DistinctFrom {
+        field: String,
+        val: *mut pg_sys::Const,
+    },
+    /// Any of the elements of an array field being equal to a constant, as in
+    /// `'value' = ANY(column)`
+    ElementTerm {
+        field: String,
+        val: *mut pg_sys::Const,
+    },
+    /// A comparison of a field against a constant, such as either half of a `BETWEEN`
+    ConstComparison {
+        field: String,
+        comparison: Comparison,
+        val: *mut pg_sys::Const,
+    },
+    /// A comparison of a field against a Param whose value is only known once the query runs,
+    /// such as the result of an InitPlan
+    ParamComparison {
+        field: String,
+        comparison: Comparison,
+        param: *mut pg_sys::Param,
+    },
+    /// A field being equal to any of the elements of an array Param whose value is only known
+    /// once the query runs, such as `id = ANY(ARRAY(SELECT ...))`
+    ParamTermSet {
+        field: String,
+        param: *mut pg_sys::Param,
+    },
+    /// A search for the value of an expression that's only known once the query runs, such as a
+    /// column of the outer query in a correlated `EXISTS`, which a nested loop supplies one row
+    /// at a time
+    ExternalQuery {
+        /// The field a text query searches, or `None` to search the index's default fields
+        field: Option<String>,
+        expr: *mut pg_sys::Node,
+        /// Is `expr` a text query to parse, rather than a `paradedb.searchqueryinput`?
+        is_text: bool,
+    },
+    /// A comparison of a field against an expression that's only known once the query runs, such
+    /// as `field = other.column` in a join, which a nested loop supplies one row at a time
+    ExternalComparison {
+        field: String,
+        comparison: Comparison,
+        expr: *mut pg_sys::Node,
+    },
     And(Vec<Qual>),
     Or(Vec<Qual>),
     Not(Box<Qual>),
 }
 
+#[derive(Debug, Copy, Clone)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    /// The comparison that's true when this one is, but with its two sides swapped, as in
+    /// `10 < column` being `column > 10`
+    fn commuted(self) -> Self {
+        match self {
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Le => Comparison::Ge,
+            Comparison::Eq => Comparison::Eq,
+            Comparison::Ge => Comparison::Le,
+            Comparison::Gt => Comparison::Lt,
+        }
+    }
+
+    /// The range of values of `field` that compare like this to `value`
+    fn into_range(self, field: String, value: OwnedValue) -> SearchQueryInput {
+        let is_datetime = matches!(value, OwnedValue::Date(_));
+        let (lower_bound, upper_bound) = match self {
+            Comparison::Lt => (Bound::Unbounded, Bound::Excluded(value)),
+            Comparison::Le => (Bound::Unbounded, Bound::Included(value)),
+            Comparison::Eq => (Bound::Included(value.clone()), Bound::Included(value)),
+            Comparison::Ge => (Bound::Included(value), Bound::Unbounded),
+            Comparison::Gt => (Bound::Excluded(value), Bound::Unbounded),
+        };
+        SearchQueryInput::Range {
+            field,
+            lower_bound,
+            upper_bound,
+            path: None,
+            is_datetime,
+        }
+    }
+}
+
+/// The value of a [`pg_sys::Param`], or of another expression we can't evaluate at plan time, as
+/// far as we know it
+pub enum ParamValue {
+    /// The query isn't running yet
+    Unknown,
+    Null,
+    Value(pg_sys::Datum),
+}
+
 impl From<Qual> for SearchQueryInput {
+    /// Comparisons against Params, and searches for expressions, don't narrow the search, as
+    /// their values aren't known yet.  Use [`Qual::into_search_query_input`] to supply them.
     fn from(value: Qual) -> Self {
-        match value {
+        value.into_search_query_input(&mut |_| ParamValue::Unknown)
+    }
+}
+
+impl Qual {
+    pub fn into_search_query_input(
+        self,
+        resolve: &mut dyn FnMut(*mut pg_sys::Node) -> ParamValue,
+    ) -> SearchQueryInput {
+        match self {
             Qual::Ignore => SearchQueryInput::All,
             Qual::OperatorExpression { val, .. } => unsafe {
                 SearchQueryInput::from_datum((*val).constvalue, (*val).constisnull)
                     .expect("rhs of @@@ operator Qual must not be null")
             },
+            Qual::Regex {
+                field,
+                pattern,
+                negated: false,
+            } => SearchQueryInput::Regex { field, pattern },
+            Qual::Regex {
+                field,
+                pattern,
+                negated: true,
+            } => SearchQueryInput::Boolean {
+                // a NULL doesn't satisfy `!~` either, so the document must have a value
+                must: vec![SearchQueryInput::Regex {
+                    field: field.clone(),
+                    pattern: "(?s).*".into(),
+                }],
+                should: Default::default(),
+                must_not: vec![SearchQueryInput::Regex { field, pattern }],
+                filter: Default::default(),
+                minimum_should_match: None,
+            },
+            Qual::DistinctFrom { field, val } => {
+                let value = unsafe {
+                    TantivyValue::try_from_datum((*val).constvalue, PgOid::from((*val).consttype))
+                }
+                .unwrap_or_else(|e| panic!("{e}"))
+                .tantivy_schema_value();
+                let is_datetime = matches!(value, OwnedValue::Date(_));
+                SearchQueryInput::Boolean {
+                    // the documents without a value don't have the term either, so they match
+                    must: vec![SearchQueryInput::All],
+                    should: Default::default(),
+                    must_not: vec![SearchQueryInput::Term {
+                        field: Some(field),
+                        value,
+                        path: None,
+                        is_datetime,
+                    }],
+                    filter: Default::default(),
+                    minimum_should_match: None,
+                }
+            }
+            Qual::ElementTerm { field, val } => unsafe {
+                // no element is equal to NULL
+                if (*val).constisnull {
+                    return SearchQueryInput::Empty;
+                }
+                let value =
+                    TantivyValue::try_from_datum((*val).constvalue, PgOid::from((*val).consttype))
+                        .unwrap_or_else(|e| panic!("{e}"))
+                        .tantivy_schema_value();
+                let is_datetime = matches!(value, OwnedValue::Date(_));
+                SearchQueryInput::Term {
+                    field: Some(field),
+                    value,
+                    path: None,
+                    is_datetime,
+                }
+            },
+            Qual::ConstComparison {
+                field,
+                comparison,
+                val,
+            } => unsafe {
+                // nothing compares to NULL
+                if (*val).constisnull {
+                    return SearchQueryInput::Empty;
+                }
+                let value =
+                    TantivyValue::try_from_datum((*val).constvalue, PgOid::from((*val).consttype))
+                        .unwrap_or_else(|e| panic!("{e}"))
+                        .tantivy_schema_value();
+                comparison.into_range(field, value)
+            },
+            Qual::ParamComparison {
+                field,
+                comparison,
+                param,
+            } => match resolve(param.cast()) {
+                ParamValue::Unknown => SearchQueryInput::All,
+                // nothing compares to NULL
+                ParamValue::Null => SearchQueryInput::Empty,
+                ParamValue::Value(datum) => {
+                    let value = unsafe {
+                        TantivyValue::try_from_datum(datum, PgOid::from((*param).paramtype))
+                    }
+                    .unwrap_or_else(|e| panic!("{e}"))
+                    .tantivy_schema_value();
+                    comparison.into_range(field, value)
+                }
+            },
+            Qual::ParamTermSet { field, param } => match resolve(param.cast()) {
+                ParamValue::Unknown => SearchQueryInput::All,
+                ParamValue::Null => SearchQueryInput::Empty,
+                ParamValue::Value(datum) => unsafe {
+                    let elemtype = pg_sys::get_element_type((*param).paramtype);
+                    let terms = array_elements(datum, elemtype)
+                        .into_iter()
+                        .map(|elem| {
+                            let value = TantivyValue::try_from_datum(elem, PgOid::from(elemtype))
+                                .unwrap_or_else(|e| panic!("{e}"))
+                                .tantivy_schema_value();
+                            let is_datetime = matches!(value, OwnedValue::Date(_));
+                            (field.clone(), value, None, is_datetime)
+                        })
+                        .collect();
+                    SearchQueryInput::TermSet { terms }
+                },
+            },
+            Qual::ExternalComparison {
+                field,
+                comparison,
+                expr,
+            } => match resolve(expr) {
+                ParamValue::Unknown => SearchQueryInput::All,
+                // nothing compares to NULL
+                ParamValue::Null => SearchQueryInput::Empty,
+                ParamValue::Value(datum) => {
+                    let value = unsafe {
+                        TantivyValue::try_from_datum(datum, PgOid::from(pg_sys::exprType(expr)))
+                    }
+                    .unwrap_or_else(|e| panic!("{e}"))
+                    .tantivy_schema_value();
+                    comparison.into_range(field, value)
+                }
+            },
+            Qual::ExternalQuery {
+                field,
+                expr,
+                is_text,
+            } => match resolve(expr) {
+                ParamValue::Unknown => SearchQueryInput::All,
+                // `@@@` is strict, so a NULL query matches nothing
+                ParamValue::Null => SearchQueryInput::Empty,
+                ParamValue::Value(datum) if is_text => {
+                    let query_string = unsafe { String::from_datum(datum, false) }
+                        .expect("text query should not be NULL");
+                    match field {
+                        Some(field) => SearchQueryInput::ParseWithField {
+                            field,
+                            query_string,
+                            lenient: None,
+                            conjunction_mode: None,
+                        },
+                        None => SearchQueryInput::Parse {
+                            query_string,
+                            lenient: None,
+                            conjunction_mode: None,
+                        },
+                    }
+                }
+                ParamValue::Value(datum) => unsafe { SearchQueryInput::from_datum(datum, false) }
+                    .expect("query should not be NULL"),
+            },
 
             Qual::And(quals) => {
-                let must = quals
-                    .into_iter()
-                    .map(SearchQueryInput::from)
-                    .collect::<Vec<_>>();
+                let must = intersect_ranges(
+                    quals
+                        .into_iter()
+                        .map(|qual| qual.into_search_query_input(resolve))
+                        .collect(),
+                );
+                if must
+                    .iter()
+                    .any(|query| matches!(query, SearchQueryInput::Empty))
+                {
+                    return SearchQueryInput::Empty;
+                }
 
                 match must.len() {
                     0 => panic!("Qual::And should have at least one item"),
@@ -54,34 +343,203 @@ impl From<Qual> for SearchQueryInput {
                         must,
                         should: Default::default(),
                         must_not: Default::default(),
+                        filter: Default::default(),
+                        minimum_should_match: None,
                     },
                 }
             }
             Qual::Or(quals) => {
+                // an alternative that can't match anything, like the half of a `BETWEEN
+                // SYMMETRIC` whose bounds are the wrong way around, can be left out
                 let should = quals
                     .into_iter()
-                    .map(SearchQueryInput::from)
+                    .map(|qual| qual.into_search_query_input(resolve))
+                    .filter(|query| !matches!(query, SearchQueryInput::Empty))
                     .collect::<Vec<_>>();
 
                 match should.len() {
-                    0 => panic!("Qual::Or should have at least one item"),
+                    0 => SearchQueryInput::Empty,
                     1 => should.into_iter().next().unwrap(),
                     _ => SearchQueryInput::Boolean {
                         must: Default::default(),
                         should,
                         must_not: Default::default(),
+                        filter: Default::default(),
+                        minimum_should_match: None,
                     },
                 }
             }
             Qual::Not(qual) => {
-                let must_not = vec![SearchQueryInput::from(*qual)];
+                let must_not = vec![(*qual).into_search_query_input(resolve)];
 
                 SearchQueryInput::Boolean {
                     must: Default::default(),
                     should: Default::default(),
                     must_not,
+                    filter: Default::default(),
+                    minimum_should_match: None,
+                }
+            }
+        }
+    }
+
+    /// Is this made up of nothing but plain Postgres operators, without any `@@@`?  Those alone
+    /// aren't worth using our index for, as a btree or trigram index has a better chance of
+    /// answering them.
+    pub fn lacks_search_operator(&self) -> bool {
+        match self {
+            Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ParamComparison { .. }
+            | Qual::ParamTermSet { .. }
+            | Qual::ExternalComparison { .. } => true,
+            Qual::And(quals) | Qual::Or(quals) => quals.iter().all(Qual::lacks_search_operator),
+            Qual::Not(qual) => qual.lacks_search_operator(),
+            Qual::Ignore | Qual::OperatorExpression { .. } | Qual::ExternalQuery { .. } => false,
+        }
+    }
+
+    /// Does this leave out a part of the clauses it came from, such as a search of another
+    /// relation, that then still needs to be checked against the rows we return?
+    pub fn has_ignore(&self) -> bool {
+        match self {
+            Qual::Ignore => true,
+            Qual::And(quals) | Qual::Or(quals) => quals.iter().any(Qual::has_ignore),
+            Qual::Not(qual) => qual.has_ignore(),
+            Qual::OperatorExpression { .. }
+            | Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ParamComparison { .. }
+            | Qual::ParamTermSet { .. }
+            | Qual::ExternalComparison { .. }
+            | Qual::ExternalQuery { .. } => false,
+        }
+    }
+
+    /// Every Param that this compares a field against
+    pub fn params(&self) -> Vec<*mut pg_sys::Param> {
+        match self {
+            Qual::ParamComparison { param, .. } | Qual::ParamTermSet { param, .. } => vec![*param],
+            Qual::And(quals) | Qual::Or(quals) => quals.iter().flat_map(Qual::params).collect(),
+            Qual::Not(qual) => qual.params(),
+            Qual::Ignore
+            | Qual::OperatorExpression { .. }
+            | Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ExternalComparison { .. }
+            | Qual::ExternalQuery { .. } => vec![],
+        }
+    }
+
+    /// The Params of [`Qual::params`] whose elements this searches for a field to equal
+    pub fn term_set_params(&self) -> Vec<*mut pg_sys::Param> {
+        match self {
+            Qual::ParamTermSet { param, .. } => vec![*param],
+            Qual::And(quals) | Qual::Or(quals) => {
+                quals.iter().flat_map(Qual::term_set_params).collect()
+            }
+            Qual::Not(qual) => qual.term_set_params(),
+            Qual::Ignore
+            | Qual::OperatorExpression { .. }
+            | Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ParamComparison { .. }
+            | Qual::ExternalComparison { .. }
+            | Qual::ExternalQuery { .. } => vec![],
+        }
+    }
+
+    /// Is one of the [`Qual::term_set_params`] under a [`Qual::Not`]?  A set of terms that turns
+    /// out to be too large to search for is searched for as every document instead, and its rows
+    /// filtered by the clause it came from, which only works when that can't match any fewer
+    pub fn has_negated_term_set(&self) -> bool {
+        match self {
+            Qual::Not(qual) => !qual.term_set_params().is_empty(),
+            Qual::And(quals) | Qual::Or(quals) => quals.iter().any(Qual::has_negated_term_set),
+            Qual::Ignore
+            | Qual::OperatorExpression { .. }
+            | Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ParamComparison { .. }
+            | Qual::ParamTermSet { .. }
+            | Qual::ExternalComparison { .. }
+            | Qual::ExternalQuery { .. } => false,
+        }
+    }
+
+    /// Every expression that this searches for the value of
+    pub fn external_exprs(&self) -> Vec<*mut pg_sys::Node> {
+        match self {
+            Qual::ExternalQuery { expr, .. } | Qual::ExternalComparison { expr, .. } => {
+                vec![*expr]
+            }
+            Qual::And(quals) | Qual::Or(quals) => {
+                quals.iter().flat_map(Qual::external_exprs).collect()
+            }
+            Qual::Not(qual) => qual.external_exprs(),
+            Qual::Ignore
+            | Qual::OperatorExpression { .. }
+            | Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ParamComparison { .. }
+            | Qual::ParamTermSet { .. } => vec![],
+        }
+    }
+
+    /// Does this compare a field against an expression that's only known once the query runs?
+    /// Unlike an `@@@` of one, such a clause can also be left for Postgres to check
+    pub fn has_external_comparison(&self) -> bool {
+        match self {
+            Qual::ExternalComparison { .. } => true,
+            Qual::And(quals) | Qual::Or(quals) => quals.iter().any(Qual::has_external_comparison),
+            Qual::Not(qual) => qual.has_external_comparison(),
+            Qual::Ignore
+            | Qual::OperatorExpression { .. }
+            | Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ParamComparison { .. }
+            | Qual::ParamTermSet { .. }
+            | Qual::ExternalQuery { .. } => false,
+        }
+    }
+
+    /// Replace the expressions [`Qual::external_exprs`] returns, in the same order, such as with
+    /// the copies of them the planner has prepared for the executor
+    pub fn replace_external_exprs(&mut self, exprs: &mut dyn Iterator<Item = *mut pg_sys::Node>) {
+        match self {
+            Qual::ExternalQuery { expr, .. } | Qual::ExternalComparison { expr, .. } => {
+                *expr = exprs
+                    .next()
+                    .expect("there should be an expression for every external query")
+            }
+            Qual::And(quals) | Qual::Or(quals) => {
+                for qual in quals {
+                    qual.replace_external_exprs(exprs);
                 }
             }
+            Qual::Not(qual) => qual.replace_external_exprs(exprs),
+            Qual::Ignore
+            | Qual::OperatorExpression { .. }
+            | Qual::Regex { .. }
+            | Qual::DistinctFrom { .. }
+            | Qual::ElementTerm { .. }
+            | Qual::ConstComparison { .. }
+            | Qual::ParamComparison { .. }
+            | Qual::ParamTermSet { .. } => {}
         }
     }
 }
@@ -90,10 +548,12 @@ pub unsafe fn extract_quals(
     rti: pg_sys::Index,
     node: *mut pg_sys::Node,
     pdbopoid: pg_sys::Oid,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
 ) -> Option<Qual> {
     match (*node).type_ {
         pg_sys::NodeTag::T_List => {
-            let mut quals = list(rti, node.cast(), pdbopoid)?;
+            let mut quals = list(rti, node.cast(), pdbopoid, heaprel, schema)?;
             if quals.len() == 1 {
                 quals.pop()
             } else {
@@ -111,15 +571,19 @@ pub unsafe fn extract_quals(
             } else {
                 (*ri).clause
             };
-            extract_quals(rti, clause.cast(), pdbopoid)
+            extract_quals(rti, clause.cast(), pdbopoid, heaprel, schema)
         }
 
-        pg_sys::NodeTag::T_OpExpr => opexpr(rti, node, pdbopoid),
+        pg_sys::NodeTag::T_OpExpr => opexpr(rti, node, pdbopoid, heaprel, schema),
+
+        pg_sys::NodeTag::T_ScalarArrayOpExpr => scalar_array_opexpr(rti, node, heaprel, schema),
+
+        pg_sys::NodeTag::T_DistinctExpr => distinct_expr(rti, node, heaprel, schema),
 
         pg_sys::NodeTag::T_BoolExpr => {
             let boolexpr = nodecast!(BoolExpr, T_BoolExpr, node)?;
             let args = PgList::<pg_sys::Node>::from_pg((*boolexpr).args);
-            let mut quals = list(rti, (*boolexpr).args, pdbopoid)?;
+            let mut quals = list(rti, (*boolexpr).args, pdbopoid, heaprel, schema)?;
 
             match (*boolexpr).boolop {
                 pg_sys::BoolExprType::AND_EXPR => Some(Qual::And(quals)),
@@ -141,11 +605,13 @@ unsafe fn list(
     rti: pg_sys::Index,
     list: *mut pg_sys::List,
     pdbopoid: pg_sys::Oid,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
 ) -> Option<Vec<Qual>> {
     let args = PgList::<pg_sys::Node>::from_pg(list);
     let mut quals = Vec::new();
     for child in args.iter_ptr() {
-        quals.push(extract_quals(rti, child, pdbopoid)?)
+        quals.push(extract_quals(rti, child, pdbopoid, heaprel, schema)?)
     }
     Some(quals)
 }
@@ -154,9 +620,37 @@ unsafe fn opexpr(
     rti: pg_sys::Index,
     node: *mut pg_sys::Node,
     pdbopoid: pg_sys::Oid,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
 ) -> Option<Qual> {
     let opexpr = nodecast!(OpExpr, T_OpExpr, node)?;
     let args = PgList::<pg_sys::Node>::from_pg((*opexpr).args);
+
+    if let Some(regex_op) = RegexOperator::from_opno((*opexpr).opno) {
+        return regex_opexpr(rti, &args, regex_op, heaprel, schema);
+    }
+
+    if let Some(qual) = external_query_opexpr(rti, (*opexpr).opno, &args, pdbopoid, heaprel) {
+        return Some(qual);
+    }
+
+    if let (Some(var), Some(param)) = (
+        nodecast!(Var, T_Var, args.get_ptr(0)?),
+        nodecast!(Param, T_Param, args.get_ptr(1)?),
+    ) {
+        return param_opexpr(rti, (*opexpr).opno, var, param, heaprel, schema);
+    }
+
+    if (*opexpr).opno != pdbopoid {
+        if let Some(qual) = const_opexpr(rti, (*opexpr).opno, &args, heaprel, schema) {
+            return Some(qual);
+        }
+        if let Some(qual) = external_comparison_opexpr(rti, (*opexpr).opno, &args, heaprel, schema)
+        {
+            return Some(qual);
+        }
+    }
+
     let (lhs, rhs) = (
         nodecast!(Var, T_Var, args.get_ptr(0)?),
         nodecast!(Const, T_Const, args.get_ptr(1)?),
@@ -185,3 +679,757 @@ unsafe fn opexpr(
         None
     }
 }
+
+/// Translate `column @@@ <expression>` into a [`Qual::ExternalQuery`] when the expression's value
+/// is only known once the query runs, such as when it's a column of another relation, or a Param.
+///
+/// The expression can't depend on the rows we're scanning, nor change from one evaluation to the
+/// next.
+unsafe fn external_query_opexpr(
+    rti: pg_sys::Index,
+    opno: pg_sys::Oid,
+    args: &PgList<pg_sys::Node>,
+    pdbopoid: pg_sys::Oid,
+    heaprel: &PgRelation,
+) -> Option<Qual> {
+    let is_text = opno == anyelement_text_opoid();
+    if !is_text && opno != pdbopoid {
+        return None;
+    }
+
+    let var = nodecast!(Var, T_Var, args.get_ptr(0)?)?;
+    let expr = args.get_ptr(1)?;
+    if (*var).varno as i32 != rti as i32 || nodecast!(Const, T_Const, expr).is_some() {
+        return None;
+    }
+    if pg_sys::contain_volatile_functions(expr) {
+        return None;
+    }
+    let vars = PgList::<pg_sys::Var>::from_pg(pull_vars(expr));
+    if vars.iter_ptr().any(|var| (*var).varno as i32 == rti as i32) {
+        return None;
+    }
+
+    // like a text literal, a text query is for the field the operator's left side names, unless
+    // that's the whole row
+    let field = if is_text && (*var).varattno > 0 {
+        let tupdesc = heaprel.tuple_desc();
+        Some(
+            tupdesc
+                .get((*var).varattno as usize - 1)?
+                .name()
+                .to_string(),
+        )
+    } else {
+        None
+    };
+
+    Some(Qual::ExternalQuery {
+        field,
+        expr,
+        is_text,
+    })
+}
+
+/// The Vars `expr` refers to, including those inside of any aggregates and placeholders
+pub unsafe fn pull_vars(expr: *mut pg_sys::Node) -> *mut pg_sys::List {
+    pg_sys::pull_var_clause(
+        expr,
+        (pg_sys::PVC_RECURSE_AGGREGATES
+            | pg_sys::PVC_RECURSE_WINDOWFUNCS
+            | pg_sys::PVC_RECURSE_PLACEHOLDERS) as _,
+    )
+}
+
+/// The Postgres regular expression operators on `text` that we can push down
+#[derive(Debug, Copy, Clone)]
+enum RegexOperator {
+    /// `~`
+    Match,
+    /// `~*`
+    IMatch,
+    /// `!~`
+    NotMatch,
+    /// `!~*`
+    NotIMatch,
+}
+
+impl RegexOperator {
+    unsafe fn from_opno(opno: pg_sys::Oid) -> Option<Self> {
+        [
+            (c"pg_catalog.~(text, text)", RegexOperator::Match),
+            (c"pg_catalog.~*(text, text)", RegexOperator::IMatch),
+            (c"pg_catalog.!~(text, text)", RegexOperator::NotMatch),
+            (c"pg_catalog.!~*(text, text)", RegexOperator::NotIMatch),
+        ]
+        .into_iter()
+        .find(|(signature, _)| opno == regoperator(signature))
+        .map(|(_, op)| op)
+    }
+
+    fn is_case_insensitive(&self) -> bool {
+        matches!(self, RegexOperator::IMatch | RegexOperator::NotIMatch)
+    }
+
+    fn is_negated(&self) -> bool {
+        matches!(self, RegexOperator::NotMatch | RegexOperator::NotIMatch)
+    }
+}
+
+unsafe fn regoperator(signature: &CStr) -> pg_sys::Oid {
+    direct_function_call::<pg_sys::Oid>(pg_sys::regoperatorin, &[signature.into_datum()])
+        .expect("builtin regular expression operators should exist")
+}
+
+/// Translate `column ~ 'pattern'`, and friends, into a [`Qual::Regex`] if the column is a
+/// keyword field of our index that keeps values of any length, and the pattern means the same
+/// thing to tantivy.
+///
+/// `SIMILAR TO` arrives here too, as Postgres rewrites it into a `~` with an anchored pattern.
+unsafe fn regex_opexpr(
+    rti: pg_sys::Index,
+    args: &PgList<pg_sys::Node>,
+    regex_op: RegexOperator,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<Qual> {
+    let lhs = args.get_ptr(0)?;
+    let var = match nodecast!(RelabelType, T_RelabelType, lhs) {
+        Some(relabel) => nodecast!(Var, T_Var, (*relabel).arg)?,
+        None => nodecast!(Var, T_Var, lhs)?,
+    };
+    let rhs = nodecast!(Const, T_Const, args.get_ptr(1)?)?;
+    if (*var).varno as i32 != rti as i32 || (*var).varattno < 1 || (*rhs).constisnull {
+        return None;
+    }
+
+    let tupdesc = heaprel.tuple_desc();
+    let field = tupdesc
+        .get((*var).varattno as usize - 1)?
+        .name()
+        .to_string();
+
+    // a case-sensitive match can only be answered by terms that kept their case, but a
+    // case-insensitive one doesn't care whether they were lowercased
+    match schema.keyword_field_normalizer(&field)? {
+        SearchNormalizer::Lowercase if !regex_op.is_case_insensitive() => return None,
+        _ => {}
+    }
+
+    // a value too long to be indexed would match neither `~` nor `!~`, so the field's terms
+    // must be all of its values
+    if !schema.keyword_field_keeps_long_values(&field) {
+        return None;
+    }
+
+    let pattern = String::from_datum((*rhs).constvalue, false)?;
+    let mut pattern = posix_to_tantivy_regex(&pattern)?;
+    if regex_op.is_case_insensitive() {
+        pattern = format!("(?i){pattern}");
+    }
+
+    // and tantivy must agree that it's a valid regex
+    let tantivy_field = schema.schema.get_field(&field).ok()?;
+    RegexQuery::from_pattern(&pattern, tantivy_field).ok()?;
+
+    Some(Qual::Regex {
+        field,
+        pattern,
+        negated: regex_op.is_negated(),
+    })
+}
+
+/// Rewrite a Postgres (POSIX "advanced") regular expression, which matches anywhere in a value
+/// unless anchored, as a tantivy regex, which must match a whole term.
+///
+/// Returns `None` for the constructs whose meaning differs between the two dialects, or which
+/// tantivy doesn't support, such as back references, lookaround, and anchors that aren't at the
+/// very start or end of the pattern.
+fn posix_to_tantivy_regex(pattern: &str) -> Option<String> {
+    let (anchored_start, rest) = match pattern.strip_prefix('^') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+
+    let chars = rest.chars().collect::<Vec<_>>();
+    let mut body = String::with_capacity(rest.len());
+    let mut anchored_end = false;
+    let mut in_bracket = false;
+    let mut depth = 0usize;
+    let mut top_level_alternation = false;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                let next = *chars.get(i + 1)?;
+                // back references, Postgres' own word-boundary and string anchors, and `\b`,
+                // which is a backspace to Postgres but a word boundary to tantivy
+                if next.is_ascii_digit() || "bBmMyYAZ".contains(next) {
+                    return None;
+                }
+                body.push(c);
+                body.push(next);
+                i += 2;
+                continue;
+            }
+            '[' if in_bracket => {
+                // only character classes, like `[:alpha:]`, nest
+                if chars.get(i + 1) != Some(&':') {
+                    return None;
+                }
+                let len = chars[i..].windows(2).position(|w| w == [':', ']'])? + 2;
+                body.extend(&chars[i..i + len]);
+                i += len;
+                continue;
+            }
+            '[' => {
+                in_bracket = true;
+                body.push(c);
+                // a `]` right after the opening bracket, or its negation, is a literal
+                if chars.get(i + 1) == Some(&'^') {
+                    body.push('^');
+                    i += 1;
+                }
+                if chars.get(i + 1) == Some(&']') {
+                    body.push_str("\\]");
+                    i += 1;
+                }
+                i += 1;
+                continue;
+            }
+            ']' if in_bracket => in_bracket = false,
+            // tantivy treats these as set operations inside a bracket expression
+            '&' | '-' | '~' if in_bracket && chars.get(i + 1) == Some(&c) => return None,
+            _ if in_bracket => {}
+            '(' => {
+                // only non-capturing groups, no lookaround nor embedded options
+                if chars.get(i + 1) == Some(&'?') && chars.get(i + 2) != Some(&':') {
+                    return None;
+                }
+                depth += 1;
+            }
+            ')' => depth = depth.checked_sub(1)?,
+            '|' if depth == 0 => top_level_alternation = true,
+            '$' if i == chars.len() - 1 => {
+                anchored_end = true;
+                break;
+            }
+            '^' | '$' => return None,
+            _ => {}
+        }
+        body.push(c);
+        i += 1;
+    }
+
+    if in_bracket || depth != 0 {
+        return None;
+    }
+
+    // an anchor only binds to the first or last alternative, which we can't express by wrapping
+    // the whole pattern
+    if top_level_alternation && (anchored_start || anchored_end) {
+        return None;
+    }
+
+    // Postgres' `.` matches a newline, as does tantivy's in `s` mode
+    Some(format!(
+        "(?s){}(?:{body}){}",
+        if anchored_start { "" } else { ".*" },
+        if anchored_end { "" } else { ".*" }
+    ))
+}
+
+/// Translate `column IS DISTINCT FROM <const>` into a [`Qual::DistinctFrom`] if the column is a
+/// field of our index whose terms are its exact values, so that a term query matches exactly the
+/// rows the column is equal to the constant in
+unsafe fn distinct_expr(
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<Qual> {
+    let expr = nodecast!(DistinctExpr, T_DistinctExpr, node)?;
+    let args = PgList::<pg_sys::Node>::from_pg((*expr).args);
+    let opname = pg_sys::get_opname((*expr).opno);
+    if args.len() != 2 || opname.is_null() || CStr::from_ptr(opname).to_bytes() != b"=" {
+        return None;
+    }
+
+    // the comparison is symmetric, so the column can be on either side
+    let unwrap_var = |node: *mut pg_sys::Node| match nodecast!(RelabelType, T_RelabelType, node) {
+        Some(relabel) => nodecast!(Var, T_Var, (*relabel).arg),
+        None => nodecast!(Var, T_Var, node),
+    };
+    let (var, val) = match (args.get_ptr(0)?, args.get_ptr(1)?) {
+        (lhs, rhs) if nodecast!(Const, T_Const, rhs).is_some() => {
+            (unwrap_var(lhs)?, nodecast!(Const, T_Const, rhs)?)
+        }
+        (lhs, rhs) => (unwrap_var(rhs)?, nodecast!(Const, T_Const, lhs)?),
+    };
+    // being distinct from NULL is the same as not being NULL, which a term can't tell us
+    if (*var).varno as i32 != rti as i32 || (*var).varattno < 1 || (*val).constisnull {
+        return None;
+    }
+
+    let tupdesc = heaprel.tuple_desc();
+    let att = tupdesc.get((*var).varattno as usize - 1)?;
+    let field = att.name().to_string();
+    let search_field = schema.get_search_field(&SearchFieldName(field.clone()))?;
+
+    // an array indexes each of its elements, but is compared as a whole
+    if pg_sys::get_element_type(att.atttypid) != pg_sys::InvalidOid {
+        return None;
+    }
+
+    // and the constant must be of the very type the field indexes, save for integers, which are
+    // all indexed alike, and text, whose terms mustn't have been lowercased
+    let is_integer = |oid| [pg_sys::INT2OID, pg_sys::INT4OID, pg_sys::INT8OID].contains(&oid);
+    let consttype = (*val).consttype;
+    let supported = match search_field.config {
+        SearchFieldConfig::Text { .. } => {
+            schema.keyword_field_normalizer(&field) == Some(SearchNormalizer::Raw)
+                && [pg_sys::TEXTOID, pg_sys::VARCHAROID].contains(&consttype)
+        }
+        SearchFieldConfig::Numeric { indexed: true, .. } => {
+            consttype == att.atttypid || (is_integer(consttype) && is_integer(att.atttypid))
+        }
+        SearchFieldConfig::Boolean { indexed: true, .. }
+        | SearchFieldConfig::Date { indexed: true, .. } => consttype == att.atttypid,
+        _ => false,
+    };
+    if !supported {
+        return None;
+    }
+
+    Some(Qual::DistinctFrom { field, val })
+}
+
+/// Translate `column <op> constant`, or `constant <op> column`, into a [`Qual::ConstComparison`]
+/// if the column is a numeric or date field of our index.
+///
+/// A `BETWEEN` arrives here as two such comparisons, and a `BETWEEN SYMMETRIC` as an `OR` of two
+/// pairs of them, one for each order its bounds could be in.
+unsafe fn const_opexpr(
+    rti: pg_sys::Index,
+    opno: pg_sys::Oid,
+    args: &PgList<pg_sys::Node>,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<Qual> {
+    let (lhs, rhs) = (args.get_ptr(0)?, args.get_ptr(1)?);
+    let (var, val, commuted) = match (
+        nodecast!(Var, T_Var, lhs),
+        nodecast!(Const, T_Const, rhs),
+        nodecast!(Const, T_Const, lhs),
+        nodecast!(Var, T_Var, rhs),
+    ) {
+        (Some(var), Some(val), ..) => (var, val, false),
+        (_, _, Some(val), Some(var)) => (var, val, true),
+        _ => return None,
+    };
+
+    let (field, comparison) = comparison_field(rti, opno, var, (*val).consttype, heaprel, schema)?;
+    Some(Qual::ConstComparison {
+        field,
+        comparison: if commuted {
+            comparison.commuted()
+        } else {
+            comparison
+        },
+        val,
+    })
+}
+
+/// Translate `column <op> $param` into a [`Qual::ParamComparison`] if the column is a numeric or
+/// date field of our index, and the Param is one that the executor computes, like the result of a
+/// scalar subquery.
+///
+/// It's up to the caller to make sure that the Param only takes a single value for the whole
+/// query, and to supply that value once it's known.
+unsafe fn param_opexpr(
+    rti: pg_sys::Index,
+    opno: pg_sys::Oid,
+    var: *mut pg_sys::Var,
+    param: *mut pg_sys::Param,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<Qual> {
+    if (*param).paramkind != pg_sys::ParamKind::PARAM_EXEC {
+        return None;
+    }
+
+    let (field, comparison) =
+        comparison_field(rti, opno, var, (*param).paramtype, heaprel, schema)?;
+    Some(Qual::ParamComparison {
+        field,
+        comparison,
+        param,
+    })
+}
+
+/// Translate `column <op> other.column`, or `other.column <op> column`, into a
+/// [`Qual::ExternalComparison`] if the column is a numeric or date field of our index, and the
+/// other side only refers to the columns of other relations, whose values a nested loop supplies.
+///
+/// Comparing two of our own columns, as in `column = column2`, depends on each row, which the
+/// index can't search for.
+unsafe fn external_comparison_opexpr(
+    rti: pg_sys::Index,
+    opno: pg_sys::Oid,
+    args: &PgList<pg_sys::Node>,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<Qual> {
+    let (lhs, rhs) = (args.get_ptr(0)?, args.get_ptr(1)?);
+    let is_ours =
+        |node| nodecast!(Var, T_Var, node).is_some_and(|var| (*var).varno as i32 == rti as i32);
+    let (var, expr, commuted) = if is_ours(lhs) {
+        (nodecast!(Var, T_Var, lhs)?, rhs, false)
+    } else if is_ours(rhs) {
+        (nodecast!(Var, T_Var, rhs)?, lhs, true)
+    } else {
+        return None;
+    };
+
+    if nodecast!(Const, T_Const, expr).is_some() || pg_sys::contain_volatile_functions(expr) {
+        return None;
+    }
+    let vars = PgList::<pg_sys::Var>::from_pg(pull_vars(expr));
+    if vars.is_empty() || vars.iter_ptr().any(|var| (*var).varno as i32 == rti as i32) {
+        return None;
+    }
+
+    let (field, comparison) =
+        comparison_field(rti, opno, var, pg_sys::exprType(expr), heaprel, schema)?;
+    Some(Qual::ExternalComparison {
+        field,
+        comparison: if commuted {
+            comparison.commuted()
+        } else {
+            comparison
+        },
+        expr,
+    })
+}
+
+/// The field `var` names, and how the operator `opno` compares it, if it's a numeric or date field
+/// of our index and a value of type `valtype` converts to the same kind of value the field indexes
+unsafe fn comparison_field(
+    rti: pg_sys::Index,
+    opno: pg_sys::Oid,
+    var: *mut pg_sys::Var,
+    valtype: pg_sys::Oid,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<(String, Comparison)> {
+    if (*var).varno as i32 != rti as i32 || (*var).varattno < 1 {
+        return None;
+    }
+
+    let opname = pg_sys::get_opname(opno);
+    if opname.is_null() {
+        return None;
+    }
+    let comparison = match CStr::from_ptr(opname).to_str().ok()? {
+        "<" => Comparison::Lt,
+        "<=" => Comparison::Le,
+        "=" => Comparison::Eq,
+        ">=" => Comparison::Ge,
+        ">" => Comparison::Gt,
+        _ => return None,
+    };
+
+    let tupdesc = heaprel.tuple_desc();
+    let att = tupdesc.get((*var).varattno as usize - 1)?;
+    let field = att.name().to_string();
+    let search_field = schema.get_search_field(&SearchFieldName(field.clone()))?;
+
+    // dates and times must be of the same type, as comparing a `timestamp` to a `timestamptz`
+    // depends on the session's time zone
+    let is_numeric = |oid| {
+        [
+            pg_sys::INT2OID,
+            pg_sys::INT4OID,
+            pg_sys::INT8OID,
+            pg_sys::FLOAT4OID,
+            pg_sys::FLOAT8OID,
+            pg_sys::NUMERICOID,
+        ]
+        .contains(&oid)
+    };
+    let is_datetime = |oid| {
+        [
+            pg_sys::DATEOID,
+            pg_sys::TIMESTAMPOID,
+            pg_sys::TIMESTAMPTZOID,
+        ]
+        .contains(&oid)
+    };
+    let supported = match search_field.config {
+        SearchFieldConfig::Numeric { indexed: true, .. } => {
+            is_numeric(att.atttypid) && is_numeric(valtype)
+        }
+        SearchFieldConfig::Date { indexed: true, .. } => {
+            is_datetime(att.atttypid) && att.atttypid == valtype
+        }
+        _ => false,
+    };
+    if !supported {
+        return None;
+    }
+
+    Some((field, comparison))
+}
+
+/// Narrow the ranges of the same field that must all match down to the single range they have in
+/// common, leaving every other query alone.  Once their bounds are combined, the ranges whose
+/// lower bound is above their upper bound, such as `column > 10 AND column <= 10`, are
+/// [`SearchQueryInput::Empty`]
+fn intersect_ranges(must: Vec<SearchQueryInput>) -> Vec<SearchQueryInput> {
+    let mut intersected: Vec<SearchQueryInput> = Vec::with_capacity(must.len());
+    'next: for query in must {
+        if let SearchQueryInput::Range {
+            field,
+            lower_bound,
+            upper_bound,
+            path: None,
+            is_datetime,
+        } = &query
+        {
+            for existing in &mut intersected {
+                if let SearchQueryInput::Range {
+                    field: existing_field,
+                    lower_bound: existing_lower,
+                    upper_bound: existing_upper,
+                    path: None,
+                    is_datetime: existing_is_datetime,
+                } = existing
+                {
+                    if existing_field != field || existing_is_datetime != is_datetime {
+                        continue;
+                    }
+                    if let (Some(lower), Some(upper)) = (
+                        tighter_bound(existing_lower, lower_bound, Ordering::Greater),
+                        tighter_bound(existing_upper, upper_bound, Ordering::Less),
+                    ) {
+                        *existing_lower = lower;
+                        *existing_upper = upper;
+                        continue 'next;
+                    }
+                }
+            }
+        }
+        intersected.push(query);
+    }
+
+    intersected
+        .into_iter()
+        .map(|query| match &query {
+            SearchQueryInput::Range {
+                lower_bound,
+                upper_bound,
+                ..
+            } if is_empty_range(lower_bound, upper_bound) => SearchQueryInput::Empty,
+            _ => query,
+        })
+        .collect()
+}
+
+/// Whichever of two lower bounds, if `tighter` is [`Ordering::Greater`], or of two upper bounds, if
+/// it's [`Ordering::Less`], leaves out more values.  `None` if their values can't be compared
+fn tighter_bound(
+    a: &Bound<OwnedValue>,
+    b: &Bound<OwnedValue>,
+    tighter: Ordering,
+) -> Option<Bound<OwnedValue>> {
+    match (a, b) {
+        (Bound::Unbounded, bound) | (bound, Bound::Unbounded) => Some(bound.clone()),
+        (Bound::Included(x) | Bound::Excluded(x), Bound::Included(y) | Bound::Excluded(y)) => {
+            match compare_values(x, y)? {
+                ordering if ordering == tighter => Some(a.clone()),
+                Ordering::Equal if matches!(a, Bound::Excluded(_)) => Some(a.clone()),
+                Ordering::Equal => Some(b.clone()),
+                _ => Some(b.clone()),
+            }
+        }
+    }
+}
+
+fn is_empty_range(lower: &Bound<OwnedValue>, upper: &Bound<OwnedValue>) -> bool {
+    match (lower, upper) {
+        (Bound::Included(x), Bound::Included(y)) => compare_values(x, y) == Some(Ordering::Greater),
+        (Bound::Included(x) | Bound::Excluded(x), Bound::Included(y) | Bound::Excluded(y)) => {
+            matches!(
+                compare_values(x, y),
+                Some(Ordering::Greater | Ordering::Equal)
+            )
+        }
+        _ => false,
+    }
+}
+
+/// How two of the values a field can be compared against order, if they're of kinds that can be
+fn compare_values(a: &OwnedValue, b: &OwnedValue) -> Option<Ordering> {
+    let as_f64 = |value: &OwnedValue| match value {
+        OwnedValue::I64(i) => Some(*i as f64),
+        OwnedValue::U64(u) => Some(*u as f64),
+        OwnedValue::F64(f) => Some(*f),
+        _ => None,
+    };
+    match (a, b) {
+        (OwnedValue::I64(a), OwnedValue::I64(b)) => Some(a.cmp(b)),
+        (OwnedValue::U64(a), OwnedValue::U64(b)) => Some(a.cmp(b)),
+        (OwnedValue::Date(a), OwnedValue::Date(b)) => Some(a.cmp(b)),
+        _ => as_f64(a)?.partial_cmp(&as_f64(b)?),
+    }
+}
+
+/// Translate `column = ANY($param)` into a [`Qual::ParamTermSet`] if the column is a numeric or
+/// date field of our index, and the Param is an array that the executor computes, like the
+/// result of an `ARRAY(SELECT ...)` subquery.
+///
+/// As with [`param_opexpr`], it's up to the caller to make sure the Param only takes a single
+/// value for the whole query, and to supply that value once it's known.
+unsafe fn scalar_array_opexpr(
+    rti: pg_sys::Index,
+    node: *mut pg_sys::Node,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<Qual> {
+    let saop = nodecast!(ScalarArrayOpExpr, T_ScalarArrayOpExpr, node)?;
+    let args = PgList::<pg_sys::Node>::from_pg((*saop).args);
+    if let Some(qual) = element_term(rti, saop, &args, heaprel, schema) {
+        return Some(qual);
+    }
+    let var = nodecast!(Var, T_Var, args.get_ptr(0)?)?;
+    let param = nodecast!(Param, T_Param, args.get_ptr(1)?)?;
+    if !(*saop).useOr
+        || (*param).paramkind != pg_sys::ParamKind::PARAM_EXEC
+        || (*var).varno as i32 != rti as i32
+        || (*var).varattno < 1
+    {
+        return None;
+    }
+
+    let opname = pg_sys::get_opname((*saop).opno);
+    if opname.is_null() || CStr::from_ptr(opname).to_bytes() != b"=" {
+        return None;
+    }
+
+    let elemtype = pg_sys::get_element_type((*param).paramtype);
+    if elemtype == pg_sys::InvalidOid {
+        return None;
+    }
+
+    let tupdesc = heaprel.tuple_desc();
+    let att = tupdesc.get((*var).varattno as usize - 1)?;
+    let field = att.name().to_string();
+    let search_field = schema.get_search_field(&SearchFieldName(field.clone()))?;
+
+    // unlike a range, a term only matches a value of the very type the field indexes, so the
+    // elements must be of the column's type, save for integers, which are all indexed alike
+    let is_integer = |oid| [pg_sys::INT2OID, pg_sys::INT4OID, pg_sys::INT8OID].contains(&oid);
+    let supported = match search_field.config {
+        SearchFieldConfig::Numeric { indexed: true, .. } => {
+            elemtype == att.atttypid || (is_integer(elemtype) && is_integer(att.atttypid))
+        }
+        SearchFieldConfig::Date { indexed: true, .. } => elemtype == att.atttypid,
+        _ => false,
+    };
+    if !supported {
+        return None;
+    }
+
+    Some(Qual::ParamTermSet { field, param })
+}
+
+/// Translate `<const> = ANY(column)` into a [`Qual::ElementTerm`] if the column is an array field
+/// of our index whose terms are the exact values of its elements, so that a term query matches
+/// exactly the rows with an element equal to the constant
+unsafe fn element_term(
+    rti: pg_sys::Index,
+    saop: *mut pg_sys::ScalarArrayOpExpr,
+    args: &PgList<pg_sys::Node>,
+    heaprel: &PgRelation,
+    schema: &SearchIndexSchema,
+) -> Option<Qual> {
+    let val = nodecast!(Const, T_Const, args.get_ptr(0)?)?;
+    let rhs = args.get_ptr(1)?;
+    let var = match nodecast!(RelabelType, T_RelabelType, rhs) {
+        Some(relabel) => nodecast!(Var, T_Var, (*relabel).arg)?,
+        None => nodecast!(Var, T_Var, rhs)?,
+    };
+    if !(*saop).useOr || (*var).varno as i32 != rti as i32 || (*var).varattno < 1 {
+        return None;
+    }
+
+    let opname = pg_sys::get_opname((*saop).opno);
+    if opname.is_null() || CStr::from_ptr(opname).to_bytes() != b"=" {
+        return None;
+    }
+
+    let tupdesc = heaprel.tuple_desc();
+    let att = tupdesc.get((*var).varattno as usize - 1)?;
+    let elemtype = pg_sys::get_element_type(att.atttypid);
+    if elemtype == pg_sys::InvalidOid {
+        return None;
+    }
+    let field = att.name().to_string();
+    let search_field = schema.get_search_field(&SearchFieldName(field.clone()))?;
+
+    // like with `IS DISTINCT FROM`, the constant must be of the very type the field indexes, save
+    // for integers, which are all indexed alike, and text, whose terms mustn't have been
+    // lowercased
+    let is_integer = |oid| [pg_sys::INT2OID, pg_sys::INT4OID, pg_sys::INT8OID].contains(&oid);
+    let is_text = |oid| [pg_sys::TEXTOID, pg_sys::VARCHAROID].contains(&oid);
+    let consttype = (*val).consttype;
+    let supported = match search_field.config {
+        SearchFieldConfig::Text { .. } => {
+            schema.keyword_field_normalizer(&field) == Some(SearchNormalizer::Raw)
+                && is_text(consttype)
+                && is_text(elemtype)
+        }
+        SearchFieldConfig::Numeric { indexed: true, .. } => {
+            consttype == elemtype || (is_integer(consttype) && is_integer(elemtype))
+        }
+        SearchFieldConfig::Boolean { indexed: true, .. }
+        | SearchFieldConfig::Date { indexed: true, .. } => consttype == elemtype,
+        _ => false,
+    };
+    if !supported {
+        return None;
+    }
+
+    Some(Qual::ElementTerm { field, val })
+}
+
+/// The non-NULL elements of the array `datum`, whose elements are of type `elemtype`.  A NULL
+/// element can't be equal to anything
+pub unsafe fn array_elements(datum: pg_sys::Datum, elemtype: pg_sys::Oid) -> Vec<pg_sys::Datum> {
+    let array = pg_sys::pg_detoast_datum(datum.cast_mut_ptr()).cast::<pg_sys::ArrayType>();
+    let mut typlen = 0;
+    let mut typbyval = false;
+    let mut typalign = 0;
+    pg_sys::get_typlenbyvalalign(elemtype, &mut typlen, &mut typbyval, &mut typalign);
+
+    let mut elems = std::ptr::null_mut();
+    let mut nulls = std::ptr::null_mut();
+    let mut nelems = 0;
+    pg_sys::deconstruct_array(
+        array,
+        elemtype,
+        typlen as _,
+        typbyval,
+        typalign,
+        &mut elems,
+        &mut nulls,
+        &mut nelems,
+    );
+
+    (0..nelems as usize)
+        .filter(|i| !*nulls.add(*i))
+        .map(|i| *elems.add(i))
+        .collect()
+}
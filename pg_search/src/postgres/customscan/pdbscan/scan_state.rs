@@ -15,19 +15,29 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::index::reader::{SearchIndexReader, SearchResults};
+use crate::gucs::UnscoredScoreValue;
+use crate::index::reader::{
+    ScoreTieBreak, SearchIndexReader, SearchIndexScore, SearchResults, SortKey,
+};
+use crate::index::sort_expression::SortExpression;
 use crate::postgres::customscan::builders::custom_path::SortDirection;
 use crate::postgres::customscan::builders::custom_state::CustomScanStateWrapper;
 use crate::postgres::customscan::pdbscan::exec_methods::ExecState;
 use crate::postgres::customscan::pdbscan::projections::snippet::SnippetInfo;
+use crate::postgres::customscan::pdbscan::qual_inspect::Qual;
 use crate::postgres::customscan::pdbscan::PdbScan;
 use crate::postgres::customscan::CustomScanState;
+use crate::postgres::types::TantivyValue;
+use crate::postgres::utils::u64_to_item_pointer;
 use crate::postgres::visibility_checker::VisibilityChecker;
 use crate::query::SearchQueryInput;
+use pgrx::itemptr::item_pointer_get_both;
 use pgrx::{name_data_to_str, pg_sys};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
 use tantivy::query::Query;
 use tantivy::snippet::SnippetGenerator;
+use tantivy::{DocAddress, Score};
 
 #[derive(Default)]
 pub struct PdbScanState {
@@ -41,15 +51,73 @@ pub struct PdbScanState {
     pub query: Option<Box<dyn Query>>,
 
     pub search_query_input: SearchQueryInput,
+    /// The quals, when they compare against Params whose values are only known once we're running
+    pub param_quals: Option<Qual>,
     pub search_reader: Option<SearchIndexReader>,
 
     pub search_results: SearchResults,
+    pub prefetch_distance: usize,
+    pub prefetched_results: VecDeque<(SearchIndexScore, DocAddress)>,
+    pub emit_duplicates: bool,
+    pub emitted_ctids: HashSet<u64>,
+    /// The values of the dedup field of the documents we've returned
+    pub dedup_keys: HashSet<Option<TantivyValue>>,
+    /// The values of the collapse field of the documents we've returned
+    pub collapse_keys: HashSet<Option<TantivyValue>>,
+    pub duplicate_count: usize,
 
     pub limit: Option<usize>,
     pub sort_field: Option<String>,
+    /// The fields we sort by one after the other, when sorting by more than one
+    pub sort_keys: Vec<SortKey>,
+    /// The text of the expression we sort by, and the expression it parses into
+    pub sort_expression: Option<(String, SortExpression)>,
+    /// Which of the values of a multivalued sort field each document is sorted by
+    pub sort_reduction: Option<SortReduction>,
     pub sort_direction: Option<SortDirection>,
+    /// Whether the documents without a value for the sort field come before those with one
+    pub sort_nulls_first: bool,
+    pub max_segments: Option<usize>,
+    pub score_tiebreak: ScoreTieBreak,
+    pub matches_nothing: bool,
+    pub as_of_generation: Option<u64>,
+    pub score_percentile: Option<f64>,
+    /// How many decimal places scores are rounded to, as `paradedb.score_precision` was planned
+    pub score_precision: Option<u32>,
+    /// Whether scoring only counts live documents, as `paradedb.global_idf` was planned
+    pub global_idf: bool,
+    pub limit_with_ties: bool,
+    pub reverse_top_n: bool,
+    /// The top-N, once it's been collected, when it's being returned in reverse
+    pub reversed_results: Vec<(SearchIndexScore, DocAddress)>,
+    /// The score of the rows of a top-N ordered by a field, which aren't scored, if it's projected
+    pub unscored_score_value: Option<UnscoredScoreValue>,
+    pub collapse_field: Option<String>,
+    pub dedup_field: Option<String>,
+    pub exists_only: bool,
     pub retry_count: usize,
     pub invisible_tuple_count: usize,
+    /// The pushed-down clauses with a set of terms, and the state they're evaluated with once the
+    /// scan has begun, which the rows are filtered by when one of the sets was too large to search
+    /// for
+    pub term_set_clauses: Option<*mut pg_sys::List>,
+    pub term_set_qual: Option<*mut pg_sys::ExprState>,
+    pub filter_term_sets: bool,
+    /// The pushed-down clauses that `paradedb.recheck_sample_rate` rechecks a sample of our rows
+    /// against, and the state they're evaluated with once the scan has begun
+    pub recheck_clauses: Option<*mut pg_sys::List>,
+    pub recheck_qual: Option<*mut pg_sys::ExprState>,
+    pub recheck_sample_rate: Option<f64>,
+    pub recheck_seed: u64,
+    pub recheck_count: usize,
+    pub recheck_failure_count: usize,
+    /// When the scan started, and how many rows it's returned, for `log_min_duration_scan`
+    pub started_at: Option<Instant>,
+    pub returned_tuple_count: usize,
+    /// How many of the rows we return under `EXPLAIN ANALYZE` to break the scores of down, and
+    /// the ctid and explanation of each of those we have so far
+    pub explain_scores: Option<usize>,
+    pub score_explanations: Vec<(u64, String)>,
 
     pub heaprel: Option<pg_sys::Relation>,
     pub indexrel: Option<pg_sys::Relation>,
@@ -59,9 +127,28 @@ pub struct PdbScanState {
     pub visibility_checker: Option<VisibilityChecker>,
 
     pub need_scores: bool,
+    pub need_rank: bool,
+    pub need_field_scores: bool,
+    pub need_matched_fields: bool,
+    pub need_relevance_pct: bool,
+    /// How many rows we've returned since the last rescan, to number them for `paradedb.rank()`
+    pub rank: i64,
+    /// The best score of the top-N we're returning, for `paradedb.relevance_pct()`
+    pub top_score: Option<Score>,
     pub snippet_generators: HashMap<SnippetInfo, Option<SnippetGenerator>>,
     pub score_funcoid: pg_sys::Oid,
+    pub rank_funcoid: pg_sys::Oid,
+    pub field_scores_funcoid: pg_sys::Oid,
+    pub matched_fields_funcoid: pg_sys::Oid,
+    pub relevance_pct_funcoid: pg_sys::Oid,
     pub snippet_funcoid: pg_sys::Oid,
+    pub snippets_funcoid: pg_sys::Oid,
+    pub sort_expression_funcoid: pg_sys::Oid,
+    /// The expressions of the `paradedb.sort_expression()` calls we project, keyed by their text
+    pub sort_expressions: HashMap<String, SortExpression>,
+    pub features_funcoid: pg_sys::Oid,
+    /// The field names of the `paradedb.features()` calls we project
+    pub features: HashSet<Vec<String>>,
     pub var_attname_lookup: HashMap<(i32, pg_sys::AttrNumber), String>,
 
     pub scan_func:
@@ -77,11 +164,48 @@ impl PdbScanState {
         self.need_scores
     }
 
+    #[inline(always)]
+    pub fn need_rank(&self) -> bool {
+        self.need_rank
+    }
+
+    #[inline(always)]
+    pub fn need_field_scores(&self) -> bool {
+        self.need_field_scores
+    }
+
+    #[inline(always)]
+    pub fn need_matched_fields(&self) -> bool {
+        self.need_matched_fields
+    }
+
+    #[inline(always)]
+    pub fn need_relevance_pct(&self) -> bool {
+        self.need_relevance_pct
+    }
+
+    #[inline(always)]
+    pub fn need_features(&self) -> bool {
+        !self.features.is_empty()
+    }
+
     #[inline(always)]
     pub fn need_snippets(&self) -> bool {
         !self.snippet_generators.is_empty()
     }
 
+    #[inline(always)]
+    pub fn sort_expression(&self) -> Option<&SortExpression> {
+        self.sort_expression
+            .as_ref()
+            .map(|(_, expression)| expression)
+    }
+
+    #[inline(always)]
+    pub fn need_sort_expressions(&self) -> bool {
+        !self.sort_expressions.is_empty()
+    }
+
     #[inline(always)]
     pub fn snapshot(&self) -> pg_sys::Snapshot {
         self.snapshot.unwrap()
@@ -106,4 +230,109 @@ impl PdbScanState {
     pub fn visibility_checker(&mut self) -> &mut VisibilityChecker {
         self.visibility_checker.as_mut().unwrap()
     }
+
+    /// Is the row with `ctid` one of the sample `paradedb.recheck_sample_rate` rechecks?
+    #[inline(always)]
+    pub fn should_recheck(&self, ctid: u64) -> bool {
+        match self.recheck_sample_rate {
+            None => false,
+            Some(rate) if rate >= 1.0 => true,
+            Some(rate) => {
+                let key = ScoreTieBreak::Random(self.recheck_seed)
+                    .key(ctid, crate::index::reader::SortDirection::Asc);
+                (key as f64 / u64::MAX as f64) < rate
+            }
+        }
+    }
+
+    /// Has a document with the same value of the collapse field, or of the dedup field, as the one
+    /// at `doc_address` already been returned?  If not, the document's values are remembered, as
+    /// it's about to be returned.  Documents without a value are all duplicates of each other.
+    ///
+    /// This is only asked of visible documents, so that a dead one never takes the place of the
+    /// live documents with its value
+    pub fn is_duplicate_key(&mut self, doc_address: DocAddress) -> bool {
+        if self.collapse_field.is_none() && self.dedup_field.is_none() {
+            return false;
+        }
+        let search_reader = self
+            .search_reader
+            .as_ref()
+            .expect("search reader should be open");
+
+        let collapse_value = self
+            .collapse_field
+            .as_ref()
+            .map(|field| search_reader.fast_field_value(field, doc_address));
+        if collapse_value
+            .as_ref()
+            .is_some_and(|value| self.collapse_keys.contains(value))
+        {
+            return true;
+        }
+
+        if let Some(field) = &self.dedup_field {
+            let value = search_reader.fast_field_value(field, doc_address);
+            if !self.dedup_keys.insert(value) {
+                self.duplicate_count += 1;
+                return true;
+            }
+        }
+        if let Some(value) = collapse_value {
+            self.collapse_keys.insert(value);
+        }
+        false
+    }
+
+    /// Replace the search results, forgetting any that were already read ahead from the old ones
+    pub fn set_search_results(&mut self, search_results: SearchResults) {
+        self.search_results = search_results;
+        self.prefetched_results.clear();
+    }
+
+    /// Return the next search result whose ctid hasn't already been returned.  The same document
+    /// should never live in more than one segment, but if it does we only want to emit it once.
+    ///
+    /// With `emit_duplicates` set, every hit is returned and reported, along with the ordinal of
+    /// the segment it came from, which is helpful when diagnosing how a duplicate got there.
+    pub fn next_search_result(&mut self) -> Option<(SearchIndexScore, DocAddress)> {
+        loop {
+            let (scored, doc_address) = self.next_prefetched_result()?;
+
+            if self.emit_duplicates {
+                let mut ipd = pg_sys::ItemPointerData::default();
+                u64_to_item_pointer(scored.ctid, &mut ipd);
+                let (blockno, offno) = item_pointer_get_both(ipd);
+                pgrx::notice!(
+                    "ctid ({blockno},{offno}) from segment {}",
+                    doc_address.segment_ord
+                );
+                return Some((scored, doc_address));
+            }
+
+            if self.emitted_ctids.insert(scored.ctid) {
+                return Some((scored, doc_address));
+            }
+        }
+    }
+
+    /// Return the next search result, having first asked Postgres to prefetch the heap pages of
+    /// up to `prefetch_distance` results that follow it, so their I/O overlaps with our
+    /// processing of this one
+    fn next_prefetched_result(&mut self) -> Option<(SearchIndexScore, DocAddress)> {
+        if self.prefetch_distance == 0 {
+            return self.search_results.next();
+        }
+
+        while self.prefetched_results.len() <= self.prefetch_distance {
+            match self.search_results.next() {
+                Some(result) => {
+                    self.visibility_checker().prefetch(result.0.ctid);
+                    self.prefetched_results.push_back(result);
+                }
+                None => break,
+            }
+        }
+        self.prefetched_results.pop_front()
+    }
 }
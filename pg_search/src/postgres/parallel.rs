@@ -15,7 +15,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use pgrx::{pg_guard, pg_sys};
+use crate::gucs;
+use crate::postgres::build;
+use pgrx::{pg_guard, pg_sys, PgList, PgRelation};
 use std::ptr::addr_of_mut;
 
 #[derive(Debug)]
@@ -141,3 +143,25 @@ fn get_bm25_scan_state(scan: &pg_sys::IndexScanDesc) -> Option<&mut Bm25Parallel
         bm25_shared_state(scan)
     }
 }
+
+/// Lower the number of workers each parallel scan of a BM25 index among the partial paths of `rel`
+/// asks for to `paradedb.max_scan_parallelism`.  The planner has already worked the number out
+/// from `max_parallel_workers_per_gather` and the size of the index, and the Gather it puts above
+/// the cheapest of these paths launches as many workers as it asks for.
+pub unsafe fn limit_parallel_workers(rel: *mut pg_sys::RelOptInfo) {
+    let Some(max_workers) = gucs::max_scan_parallelism() else {
+        return;
+    };
+
+    for path in PgList::<pg_sys::Path>::from_pg((*rel).partial_pathlist).iter_ptr() {
+        if (*path).type_ != pg_sys::NodeTag::T_IndexPath {
+            continue;
+        }
+
+        let indexinfo = (*path.cast::<pg_sys::IndexPath>()).indexinfo;
+        let index = PgRelation::open((*indexinfo).indexoid);
+        if (*index.rd_indam).ambuild == Some(build::ambuild) {
+            (*path).parallel_workers = (*path).parallel_workers.min(max_workers as i32);
+        }
+    }
+}
@@ -15,6 +15,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use crate::api::index::set_last_query_generation;
 use crate::index::reader::{SearchIndexReader, SearchResults};
 use crate::index::SearchIndex;
 use crate::postgres::index::open_search_index;
@@ -101,14 +102,19 @@ pub extern "C" fn amrescan(
             must: vec![search_query_input, key],
             should: vec![],
             must_not: vec![],
+            filter: Default::default(),
+            minimum_should_match: None,
         };
     }
 
     // Create the index and scan state
     let search_index = open_search_index(&indexrel).expect("should be able to open search index");
-    let search_reader = search_index
-        .get_reader()
-        .expect("SearchState should construct cleanly");
+    let search_reader = match search_query_input.as_of_generation() {
+        Some(generation) => search_index.get_reader_as_of(generation),
+        None => search_index.get_reader(),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
+    set_last_query_generation(search_reader.generation);
 
     unsafe {
         parallel::maybe_init_parallel_scan(scan, &search_reader.searcher);
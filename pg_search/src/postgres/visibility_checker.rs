@@ -55,6 +55,7 @@ pub struct VisibilityChecker {
     snapshot: pg_sys::Snapshot,
     last_buffer: pg_sys::Buffer,
     ipd: pg_sys::ItemPointerData,
+    last_prefetched: Option<pg_sys::BlockNumber>,
 }
 
 impl Drop for VisibilityChecker {
@@ -82,6 +83,26 @@ impl VisibilityChecker {
             snapshot,
             last_buffer: pg_sys::InvalidBuffer as pg_sys::Buffer,
             ipd: pg_sys::ItemPointerData::default(),
+            last_prefetched: None,
+        }
+    }
+
+    /// Ask Postgres to start reading the heap page that holds the specified `ctid` into shared
+    /// buffers, so that a later [`VisibilityChecker::exec_if_visible`] for it needn't wait on I/O.
+    ///
+    /// This is only a hint, and asking for the same page as the previous call does nothing
+    pub fn prefetch(&mut self, ctid: u64) {
+        let mut ipd = pg_sys::ItemPointerData::default();
+        utils::u64_to_item_pointer(ctid, &mut ipd);
+
+        let blockno = item_pointer_get_block_number(&ipd);
+        if self.last_prefetched == Some(blockno) {
+            return;
+        }
+        self.last_prefetched = Some(blockno);
+
+        unsafe {
+            pg_sys::PrefetchBuffer(self.relation, pg_sys::ForkNumber::MAIN_FORKNUM, blockno);
         }
     }
 
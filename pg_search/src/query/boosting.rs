@@ -0,0 +1,141 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::{DocId, DocSet, Score, SegmentReader, Term};
+
+/// A [`Query`] that matches the same documents as its `positive` query, but whose score
+/// is multiplied by `negative_boost` for documents that also match the `negative` query.
+///
+/// This is the equivalent of Lucene's "boosting query", and is used to demote, rather than
+/// exclude, documents.
+#[derive(Debug)]
+pub struct BoostingQuery {
+    positive: Box<dyn Query>,
+    negative: Box<dyn Query>,
+    negative_boost: Score,
+}
+
+impl BoostingQuery {
+    pub fn new(positive: Box<dyn Query>, negative: Box<dyn Query>, negative_boost: Score) -> Self {
+        Self {
+            positive,
+            negative,
+            negative_boost,
+        }
+    }
+}
+
+impl Clone for BoostingQuery {
+    fn clone(&self) -> Self {
+        Self {
+            positive: self.positive.box_clone(),
+            negative: self.negative.box_clone(),
+            negative_boost: self.negative_boost,
+        }
+    }
+}
+
+impl Query for BoostingQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(BoostingWeight {
+            positive: self.positive.weight(enable_scoring)?,
+            negative: self.negative.weight(enable_scoring)?,
+            negative_boost: self.negative_boost,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        // only the positive query contributes to matching, so only its terms are interesting
+        // to things like snippet generation
+        self.positive.query_terms(visitor);
+    }
+}
+
+struct BoostingWeight {
+    positive: Box<dyn Weight>,
+    negative: Box<dyn Weight>,
+    negative_boost: Score,
+}
+
+impl Weight for BoostingWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        Ok(Box::new(BoostingScorer {
+            positive: self.positive.scorer(reader, boost)?,
+            negative: self.negative.scorer(reader, 1.0)?,
+            negative_boost: self.negative_boost,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let positive = self.positive.explain(reader, doc)?;
+        let mut negative = self.negative.scorer(reader, 1.0)?;
+        if negative.seek(doc) != doc {
+            return Ok(positive);
+        }
+
+        let mut explanation = Explanation::new(
+            "Boosting, product of positive score and negative_boost",
+            positive.value() * self.negative_boost,
+        );
+        explanation.add_detail(positive);
+        explanation.add_const("negative_boost", self.negative_boost);
+        Ok(explanation)
+    }
+}
+
+struct BoostingScorer {
+    positive: Box<dyn Scorer>,
+    negative: Box<dyn Scorer>,
+    negative_boost: Score,
+}
+
+impl DocSet for BoostingScorer {
+    fn advance(&mut self) -> DocId {
+        self.positive.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.positive.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.positive.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.positive.size_hint()
+    }
+}
+
+impl Scorer for BoostingScorer {
+    fn score(&mut self) -> Score {
+        let doc = self.positive.doc();
+        let score = self.positive.score();
+
+        // the negative scorer only ever moves forward, alongside the positive scorer
+        if self.negative.doc() < doc {
+            self.negative.seek(doc);
+        }
+
+        if self.negative.doc() == doc {
+            score * self.negative_boost
+        } else {
+            score
+        }
+    }
+}
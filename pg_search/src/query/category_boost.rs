@@ -0,0 +1,173 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use tantivy::columnar::StrColumn;
+use tantivy::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::termdict::TermOrdinal;
+use tantivy::{DocId, DocSet, Score, SegmentReader, Term};
+
+/// A [`Query`] that matches the same documents as the query it wraps, but multiplies their scores
+/// by the boost of the category they're in, which is their value of the text fast field `field`.
+///
+/// Documents in a category without a boost, or without a category at all, keep their scores.
+#[derive(Debug)]
+pub struct CategoryBoostQuery {
+    query: Box<dyn Query>,
+    field: String,
+    boosts: BTreeMap<String, Score>,
+}
+
+impl CategoryBoostQuery {
+    pub fn new(query: Box<dyn Query>, field: String, boosts: BTreeMap<String, Score>) -> Self {
+        Self {
+            query,
+            field,
+            boosts,
+        }
+    }
+}
+
+impl Clone for CategoryBoostQuery {
+    fn clone(&self) -> Self {
+        Self {
+            query: self.query.box_clone(),
+            field: self.field.clone(),
+            boosts: self.boosts.clone(),
+        }
+    }
+}
+
+impl Query for CategoryBoostQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(CategoryBoostWeight {
+            weight: self.query.weight(enable_scoring)?,
+            field: self.field.clone(),
+            boosts: self.boosts.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+    }
+}
+
+struct CategoryBoostWeight {
+    weight: Box<dyn Weight>,
+    field: String,
+    boosts: BTreeMap<String, Score>,
+}
+
+impl CategoryBoostWeight {
+    /// The segment's category column, and the boost of each of its categories that has one, by
+    /// their term ordinal in it, so that a document's boost is found without reading its category
+    fn segment_boosts(
+        &self,
+        reader: &SegmentReader,
+    ) -> tantivy::Result<Option<(StrColumn, HashMap<TermOrdinal, Score>)>> {
+        let Some(column) = reader.fast_fields().str(&self.field)? else {
+            return Ok(None);
+        };
+
+        let mut boosts = HashMap::new();
+        for (category, boost) in &self.boosts {
+            if let Some(ord) = column.dictionary().term_ord(category)? {
+                boosts.insert(ord, *boost);
+            }
+        }
+        Ok(Some((column, boosts)))
+    }
+}
+
+impl Weight for CategoryBoostWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let scorer = self.weight.scorer(reader, boost)?;
+        match self.segment_boosts(reader)? {
+            Some((column, boosts)) if !boosts.is_empty() => Ok(Box::new(CategoryBoostScorer {
+                scorer,
+                column,
+                boosts,
+            })),
+            // none of the boosted categories are in this segment
+            _ => Ok(scorer),
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let explanation = self.weight.explain(reader, doc)?;
+        let Some((column, boosts)) = self.segment_boosts(reader)? else {
+            return Ok(explanation);
+        };
+        let Some(boost) = category_boost(&column, &boosts, doc) else {
+            return Ok(explanation);
+        };
+
+        let mut boosted = Explanation::new(
+            "CategoryBoost, product of score and the boost of the document's category",
+            explanation.value() * boost,
+        );
+        boosted.add_detail(explanation);
+        boosted.add_const("category_boost", boost);
+        Ok(boosted)
+    }
+}
+
+/// The boost of the category of `doc`, if it's in a category that has one.  A document with more
+/// than one value is in the category of its first
+#[inline]
+fn category_boost(
+    column: &StrColumn,
+    boosts: &HashMap<TermOrdinal, Score>,
+    doc: DocId,
+) -> Option<Score> {
+    let ord = column.term_ords(doc).next()?;
+    boosts.get(&ord).copied()
+}
+
+struct CategoryBoostScorer {
+    scorer: Box<dyn Scorer>,
+    column: StrColumn,
+    boosts: HashMap<TermOrdinal, Score>,
+}
+
+impl DocSet for CategoryBoostScorer {
+    fn advance(&mut self) -> DocId {
+        self.scorer.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.scorer.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for CategoryBoostScorer {
+    fn score(&mut self) -> Score {
+        let score = self.scorer.score();
+        match category_boost(&self.column, &self.boosts, self.scorer.doc()) {
+            Some(boost) => score * boost,
+            None => score,
+        }
+    }
+}
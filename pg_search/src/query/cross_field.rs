@@ -0,0 +1,74 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::Query;
+use tantivy::schema::{Field, Value};
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::{Searcher, TantivyDocument};
+
+/// The ctids of the documents matching `candidates` that hold `tokens` one after the other once
+/// the values of their `fields` are joined end to end, in order.
+///
+/// Each field keeps its own positions in the index, so a phrase that starts in one field and ends
+/// in the next can't be found from the postings alone.  Instead, the candidates' values are read
+/// back from the document store and analyzed again with `analyzer`, which all of the `fields`
+/// must share, into a single stream of tokens a phrase can cross the fields of.
+pub fn phrase_matches(
+    searcher: &Searcher,
+    candidates: &dyn Query,
+    fields: &[Field],
+    analyzer: &mut TextAnalyzer,
+    tokens: &[String],
+) -> tantivy::Result<Vec<u64>> {
+    let mut ctid_columns = HashMap::new();
+    let mut ctids = Vec::new();
+    for address in searcher.search(candidates, &DocSetCollector)? {
+        let document = searcher.doc::<TantivyDocument>(address)?;
+        let mut stream_tokens = Vec::new();
+        for field in fields {
+            for text in document.get_all(*field).filter_map(|value| value.as_str()) {
+                let mut stream = analyzer.token_stream(text);
+                while stream.advance() {
+                    stream_tokens.push(stream.token().text.clone());
+                }
+            }
+        }
+
+        if !stream_tokens
+            .windows(tokens.len())
+            .any(|window| window == tokens)
+        {
+            continue;
+        }
+
+        let column = match ctid_columns.entry(address.segment_ord) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+                searcher
+                    .segment_reader(address.segment_ord)
+                    .fast_fields()
+                    .u64("ctid")?,
+            ),
+        };
+        if let Some(ctid) = column.first(address.doc_id) {
+            ctids.push(ctid);
+        }
+    }
+    Ok(ctids)
+}
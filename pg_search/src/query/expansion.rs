@@ -0,0 +1,280 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use super::QueryError;
+use crate::gucs::{self, MaxTermExpansionsAction};
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA, SINK_STATE};
+use std::collections::BTreeSet;
+use tantivy::query::{PhrasePrefixQuery, Query, TermSetQuery};
+use tantivy::schema::Field;
+use tantivy::{Searcher, Term};
+use tantivy_fst::automaton::AlwaysMatch;
+use tantivy_fst::{Automaton, Regex};
+
+/// The terms a multi-term query expands into, which are those of its field that it matches
+pub enum Expansion<'a> {
+    /// The terms within `distance` edits of `term`, or of a prefix of it
+    Fuzzy {
+        term: &'a Term,
+        text: &'a str,
+        distance: u8,
+        transposition_cost_one: bool,
+        prefix: bool,
+    },
+    /// The terms that match a regular expression
+    Regex(&'a str),
+}
+
+/// Hold `query`, which searches `field` for the terms of `expansion`, to
+/// `paradedb.max_term_expansions`, by either raising an error or, if
+/// `paradedb.max_term_expansions_action` is `truncate`, only searching for as many of those terms
+/// as the cap allows, in term order
+pub fn limit_expansions(
+    query: Box<dyn Query>,
+    searcher: &Searcher,
+    field: Field,
+    expansion: Expansion,
+    description: &str,
+) -> Result<Box<dyn Query>, Box<dyn std::error::Error>> {
+    let Some(max_expansions) = gucs::max_term_expansions() else {
+        return Ok(query);
+    };
+
+    let terms = match expansion {
+        Expansion::Fuzzy {
+            term,
+            text,
+            distance,
+            transposition_cost_one,
+            prefix,
+        } => {
+            // tantivy doesn't support distances of more than 2, which the query will complain of
+            if distance > 2 {
+                return Ok(query);
+            }
+            let builder = LevenshteinAutomatonBuilder::new(distance, transposition_cost_one);
+            let dfa = if prefix {
+                builder.build_prefix_dfa(text)
+            } else {
+                builder.build_dfa(text)
+            };
+            // the terms of a JSON field start with the path of their value
+            let key = term.serialized_value_bytes();
+            let automaton = Prefixed {
+                prefix: key.strip_suffix(text.as_bytes()).unwrap_or_default(),
+                automaton: Dfa(dfa),
+            };
+            expansions(searcher, field, &automaton, max_expansions)?
+        }
+        Expansion::Regex(pattern) => {
+            let regex = Regex::new(pattern).map_err(|err| {
+                QueryError::RegexError(
+                    tantivy::TantivyError::InvalidArgument(format!("{err:?}")),
+                    pattern.into(),
+                )
+            })?;
+            expansions(searcher, field, &regex, max_expansions)?
+        }
+    };
+
+    if terms.len() <= max_expansions {
+        return Ok(query);
+    }
+    if gucs::max_term_expansions_action() == MaxTermExpansionsAction::Error {
+        return Err(QueryError::TooManyExpansions(description.into(), max_expansions).into());
+    }
+
+    pgrx::warning!(
+        "{description} expands into more terms than paradedb.max_term_expansions allows, so only the first {max_expansions} of them are searched"
+    );
+    Ok(first_terms(field, terms, max_expansions))
+}
+
+/// How many terms a phrase prefix query expands its prefix into, unless it's told otherwise
+const PHRASE_PREFIX_MAX_EXPANSIONS: usize = 50;
+
+/// A phrase prefix query of `field` for `terms`, the last of which is the prefix, held to
+/// `paradedb.max_term_expansions`.  A phrase prefix query already only searches for as many of
+/// the terms its prefix expands into as its `max_expansions` says, so it's only over the cap if
+/// that's more.  A lone prefix isn't held to its `max_expansions`, though.
+pub fn limit_phrase_prefix_expansions(
+    terms: Vec<Term>,
+    max_expansions: Option<u32>,
+    searcher: &Searcher,
+    field: Field,
+    description: &str,
+) -> Result<Box<dyn Query>, Box<dyn std::error::Error>> {
+    let prefix = terms
+        .last()
+        .cloned()
+        .expect("phrase prefix should have terms");
+    let is_lone_prefix = terms.len() == 1;
+    let mut query = PhrasePrefixQuery::new(terms);
+    let query_max_expansions = match max_expansions {
+        _ if is_lone_prefix => usize::MAX,
+        Some(max_expansions) => max_expansions as usize,
+        None => PHRASE_PREFIX_MAX_EXPANSIONS,
+    };
+    if let Some(max_expansions) = max_expansions {
+        query.set_max_expansions(max_expansions);
+    }
+    let max_expansions = match gucs::max_term_expansions() {
+        Some(max_expansions) if max_expansions < query_max_expansions => max_expansions,
+        _ => return Ok(Box::new(query)),
+    };
+
+    let automaton = Prefixed {
+        prefix: prefix.serialized_value_bytes(),
+        automaton: AlwaysMatch,
+    };
+    let expanded = expansions(searcher, field, &automaton, max_expansions)?;
+    if expanded.len() <= max_expansions {
+        return Ok(Box::new(query));
+    }
+    if gucs::max_term_expansions_action() == MaxTermExpansionsAction::Error {
+        return Err(QueryError::TooManyExpansions(description.into(), max_expansions).into());
+    }
+
+    pgrx::warning!(
+        "{description} expands into more terms than paradedb.max_term_expansions allows, so only the first {max_expansions} of them are searched"
+    );
+    if is_lone_prefix {
+        return Ok(first_terms(field, expanded, max_expansions));
+    }
+    query.set_max_expansions(max_expansions as u32);
+    Ok(Box::new(query))
+}
+
+/// A query for the first `max_expansions` of the `terms` of `field`
+fn first_terms(field: Field, terms: BTreeSet<Vec<u8>>, max_expansions: usize) -> Box<dyn Query> {
+    Box::new(TermSetQuery::new(
+        terms
+            .into_iter()
+            .take(max_expansions)
+            .map(|key| Term::from_field_bytes(field, &key)),
+    ))
+}
+
+/// The first `max_expansions + 1` terms of `field` that `automaton` matches in any of the segments
+/// of `searcher`, in term order, which is just enough to tell if there are more than the cap
+fn expansions<A>(
+    searcher: &Searcher,
+    field: Field,
+    automaton: &A,
+    max_expansions: usize,
+) -> tantivy::Result<BTreeSet<Vec<u8>>>
+where
+    A: Automaton,
+    A::State: Clone,
+{
+    let mut terms = BTreeSet::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader.inverted_index(field)?;
+        let mut stream = inverted_index.terms().search(automaton).into_stream()?;
+        let mut nterms = 0;
+        while nterms <= max_expansions && stream.advance() {
+            terms.insert(stream.key().to_vec());
+            nterms += 1;
+        }
+        while terms.len() > max_expansions + 1 {
+            terms.pop_last();
+        }
+    }
+    Ok(terms)
+}
+
+/// A Levenshtein automaton, as tantivy's fuzzy queries match terms with
+struct Dfa(DFA);
+
+impl Automaton for Dfa {
+    type State = u32;
+
+    fn start(&self) -> u32 {
+        self.0.initial_state()
+    }
+
+    fn is_match(&self, state: &u32) -> bool {
+        matches!(self.0.distance(*state), Distance::Exact(_))
+    }
+
+    fn can_match(&self, state: &u32) -> bool {
+        *state != SINK_STATE
+    }
+
+    fn accept(&self, state: &u32, byte: u8) -> u32 {
+        self.0.transition(*state, byte)
+    }
+}
+
+/// Matches the keys that start with `prefix` and go on with what `automaton` matches
+struct Prefixed<'a, A> {
+    prefix: &'a [u8],
+    automaton: A,
+}
+
+#[derive(Clone)]
+enum PrefixedState<S> {
+    /// How much of the prefix has been matched so far
+    Prefix(usize),
+    /// The state of the automaton, once the whole prefix has been matched
+    Rest(S),
+    /// The key doesn't start with the prefix
+    Dead,
+}
+
+impl<A> Automaton for Prefixed<'_, A>
+where
+    A: Automaton,
+    A::State: Clone,
+{
+    type State = PrefixedState<A::State>;
+
+    fn start(&self) -> Self::State {
+        if self.prefix.is_empty() {
+            PrefixedState::Rest(self.automaton.start())
+        } else {
+            PrefixedState::Prefix(0)
+        }
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        match state {
+            PrefixedState::Rest(state) => self.automaton.is_match(state),
+            _ => false,
+        }
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        match state {
+            PrefixedState::Prefix(_) => true,
+            PrefixedState::Rest(state) => self.automaton.can_match(state),
+            PrefixedState::Dead => false,
+        }
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        match state {
+            PrefixedState::Prefix(n) if self.prefix[*n] != byte => PrefixedState::Dead,
+            PrefixedState::Prefix(n) if n + 1 == self.prefix.len() => {
+                PrefixedState::Rest(self.automaton.start())
+            }
+            PrefixedState::Prefix(n) => PrefixedState::Prefix(n + 1),
+            PrefixedState::Rest(state) => PrefixedState::Rest(self.automaton.accept(state, byte)),
+            PrefixedState::Dead => PrefixedState::Dead,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde_json::{json, Value as JsonValue};
+use std::ops::Bound;
+use tantivy::query::{
+    AllQuery, BooleanQuery, EmptyQuery, EnableScoring, Occur, PhraseQuery, Query, TermQuery, Weight,
+};
+use tantivy::schema::Schema;
+use tantivy::Term;
+
+/// Describe `query` as JSON, so that users can see what their query became once it was parsed
+/// and its text tokenized.
+///
+/// Every node is an object whose `"type"` says what it is:
+///
+/// - `{"type": "all"}` matches every document
+/// - `{"type": "empty"}` matches no documents
+/// - `{"type": "boolean", "clauses": [{"occur": "must" | "should" | "must_not", "query": ...}]}`
+/// - `{"type": "term", "field": ..., "value": ...}`
+/// - `{"type": "phrase", "field": ..., "terms": [...], "slop": ...}`
+/// - `{"type": "range", "field": ..., "lower_bound": ..., "upper_bound": ...}`, where a bound is
+///   `{"included": ...}`, `{"excluded": ...}`, or `null` when the range is unbounded on that side
+/// - `{"type": "regex", "field": ..., "pattern": ...}`
+/// - `{"type": "fuzzy", "field": ..., "value": ..., "distance": ..., "transposition_cost_one": ...,
+///   "prefix": ...}`
+/// - `{"type": "other", "terms": [{"field": ..., "value": ...}]}` for any other query, with the
+///   terms it searches for, if any
+pub fn query_tree(query: &dyn Query, schema: &Schema) -> JsonValue {
+    if query.downcast_ref::<AllQuery>().is_some() {
+        json!({ "type": "all" })
+    } else if query.downcast_ref::<EmptyQuery>().is_some() {
+        json!({ "type": "empty" })
+    } else if let Some(boolean) = query.downcast_ref::<BooleanQuery>() {
+        let clauses = boolean
+            .clauses()
+            .iter()
+            .map(|(occur, query)| {
+                let occur = match occur {
+                    Occur::Must => "must",
+                    Occur::Should => "should",
+                    Occur::MustNot => "must_not",
+                };
+                json!({ "occur": occur, "query": query_tree(query.as_ref(), schema) })
+            })
+            .collect::<Vec<_>>();
+        json!({ "type": "boolean", "clauses": clauses })
+    } else if let Some(term) = query.downcast_ref::<TermQuery>() {
+        json!({
+            "type": "term",
+            "field": schema.get_field_name(term.term().field()),
+            "value": term_value_string(term.term()),
+        })
+    } else if let Some(phrase) = query.downcast_ref::<PhraseQuery>() {
+        let terms = phrase
+            .phrase_terms()
+            .iter()
+            .map(term_value_string)
+            .collect::<Vec<_>>();
+        json!({
+            "type": "phrase",
+            "field": schema.get_field_name(phrase.field()),
+            "terms": terms,
+            "slop": phrase.slop(),
+        })
+    } else if let Some(described) = query.downcast_ref::<DescribedQuery>() {
+        described.tree.clone()
+    } else {
+        let mut terms = Vec::new();
+        query.query_terms(&mut |term, _| {
+            terms.push(json!({
+                "field": schema.get_field_name(term.field()),
+                "value": term_value_string(term),
+            }))
+        });
+        json!({ "type": "other", "terms": terms })
+    }
+}
+
+/// The value of `term` as a user would have written it
+pub fn term_value_string(term: &Term) -> String {
+    let value = term.value();
+    if let Some(text) = value.as_str() {
+        text.to_string()
+    } else if let Some(n) = value.as_i64() {
+        n.to_string()
+    } else if let Some(n) = value.as_u64() {
+        n.to_string()
+    } else if let Some(n) = value.as_f64() {
+        n.to_string()
+    } else if let Some(b) = value.as_bool() {
+        b.to_string()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// A [`Query`] that searches just like the one it wraps, but carries the [`query_tree`] node
+/// that describes it, for the queries that don't expose what they were built with
+#[derive(Debug)]
+pub struct DescribedQuery {
+    query: Box<dyn Query>,
+    tree: JsonValue,
+}
+
+impl DescribedQuery {
+    pub fn range(
+        query: Box<dyn Query>,
+        field: &str,
+        lower_bound: &Bound<Term>,
+        upper_bound: &Bound<Term>,
+    ) -> Box<dyn Query> {
+        let bound_tree = |bound: &Bound<Term>| match bound {
+            Bound::Included(term) => json!({ "included": term_value_string(term) }),
+            Bound::Excluded(term) => json!({ "excluded": term_value_string(term) }),
+            Bound::Unbounded => JsonValue::Null,
+        };
+        let tree = json!({
+            "type": "range",
+            "field": field,
+            "lower_bound": bound_tree(lower_bound),
+            "upper_bound": bound_tree(upper_bound),
+        });
+        Box::new(Self { query, tree })
+    }
+
+    pub fn regex(query: Box<dyn Query>, field: &str, pattern: &str) -> Box<dyn Query> {
+        let tree = json!({ "type": "regex", "field": field, "pattern": pattern });
+        Box::new(Self { query, tree })
+    }
+
+    pub fn fuzzy(
+        query: Box<dyn Query>,
+        field: &str,
+        value: &str,
+        distance: u8,
+        transposition_cost_one: bool,
+        prefix: bool,
+    ) -> Box<dyn Query> {
+        let tree = json!({
+            "type": "fuzzy",
+            "field": field,
+            "value": value,
+            "distance": distance,
+            "transposition_cost_one": transposition_cost_one,
+            "prefix": prefix,
+        });
+        Box::new(Self { query, tree })
+    }
+}
+
+impl Clone for DescribedQuery {
+    fn clone(&self) -> Self {
+        Self {
+            query: self.query.box_clone(),
+            tree: self.tree.clone(),
+        }
+    }
+}
+
+impl Query for DescribedQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        self.query.weight(enable_scoring)
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+    }
+}
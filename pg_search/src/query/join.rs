@@ -0,0 +1,109 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeSet, HashMap};
+use tantivy::collector::DocSetCollector;
+use tantivy::columnar::{Column, StrColumn};
+use tantivy::fastfield::FastFieldReaders;
+use tantivy::query::Query;
+use tantivy::schema::OwnedValue;
+use tantivy::{DocId, Searcher};
+
+/// The distinct values of the fast field `field_name` among the documents matching `query`.
+///
+/// Documents modeled as parents and children are joined by these: a child document holds its
+/// parent's key in a fast field, so the children of a set of parents are those whose field holds
+/// one of their keys, and the parents of a set of children are those whose key is one of theirs.
+/// Documents without a value for the field don't join to anything.
+pub fn fast_field_values(
+    searcher: &Searcher,
+    query: &dyn Query,
+    field_name: &str,
+) -> tantivy::Result<Vec<OwnedValue>> {
+    let mut columns = HashMap::new();
+    let mut values = BTreeSet::new();
+    for address in searcher.search(query, &DocSetCollector)? {
+        let column = match columns.entry(address.segment_ord) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let segment_reader = searcher.segment_reader(address.segment_ord);
+                entry.insert(JoinColumn::open(segment_reader.fast_fields(), field_name)?)
+            }
+        };
+        if let Some(value) = column.value(address.doc_id) {
+            values.insert(value);
+        }
+    }
+    Ok(values
+        .into_iter()
+        .map(JoinValue::into_owned_value)
+        .collect())
+}
+
+/// A fast field that documents are joined by, which must hold integers or strings
+enum JoinColumn {
+    I64(Column<i64>),
+    U64(Column<u64>),
+    Str(StrColumn),
+}
+
+/// The value a document is joined by, which unlike an [`OwnedValue`] can be ordered
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum JoinValue {
+    I64(i64),
+    U64(u64),
+    Str(String),
+}
+
+impl JoinColumn {
+    fn open(ffr: &FastFieldReaders, field_name: &str) -> tantivy::Result<Self> {
+        if let Ok(Some(column)) = ffr.str(field_name) {
+            Ok(Self::Str(column))
+        } else if let Ok(column) = ffr.i64(field_name) {
+            Ok(Self::I64(column))
+        } else if let Ok(column) = ffr.u64(field_name) {
+            Ok(Self::U64(column))
+        } else {
+            Err(tantivy::TantivyError::SchemaError(format!(
+                "`{field_name}` must be an integer or text fast field to join documents by"
+            )))
+        }
+    }
+
+    fn value(&self, doc: DocId) -> Option<JoinValue> {
+        match self {
+            JoinColumn::I64(column) => column.first(doc).map(JoinValue::I64),
+            JoinColumn::U64(column) => column.first(doc).map(JoinValue::U64),
+            JoinColumn::Str(column) => {
+                let ord = column.term_ords(doc).next()?;
+                let mut value = String::new();
+                column.ord_to_str(ord, &mut value).ok()?;
+                Some(JoinValue::Str(value))
+            }
+        }
+    }
+}
+
+impl JoinValue {
+    fn into_owned_value(self) -> OwnedValue {
+        match self {
+            JoinValue::I64(value) => OwnedValue::I64(value),
+            JoinValue::U64(value) => OwnedValue::U64(value),
+            JoinValue::Str(value) => OwnedValue::Str(value),
+        }
+    }
+}
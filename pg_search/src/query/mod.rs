@@ -15,31 +15,73 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod boosting;
+mod category_boost;
+mod cross_field;
+mod expansion;
+mod explain;
+mod join;
+mod quantized;
 mod range;
+mod similarity;
+mod tiered;
+mod typed_values;
 
+use crate::gucs::{
+    self, EmptyQueryMode, MaxQueryTermsAction, OnInvalidQueryText, SameFieldClauses,
+};
+use crate::query::boosting::BoostingQuery;
+use crate::query::category_boost::CategoryBoostQuery;
+use crate::query::expansion::{limit_expansions, limit_phrase_prefix_expansions, Expansion};
+use crate::query::explain::DescribedQuery;
+pub use crate::query::explain::{query_tree, term_value_string};
+pub use crate::query::quantized::QuantizedScoreQuery;
 use crate::query::range::{Comparison, RangeField};
+pub use crate::query::similarity::Similarity;
+use crate::query::similarity::SimilarityQuery;
+use crate::query::tiered::{Recency, TieredQuery};
 use crate::schema::IndexRecordOption;
 use anyhow::Result;
 use core::panic;
 use pgrx::{pg_sys, PostgresType};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, ops::Bound};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Bound,
+};
 use tantivy::{
     collector::DocSetCollector,
     query::{
         AllQuery, BooleanQuery, BoostQuery, ConstScoreQuery, DisjunctionMaxQuery, EmptyQuery,
-        ExistsQuery, FastFieldRangeQuery, FuzzyTermQuery, MoreLikeThisQuery, PhrasePrefixQuery,
-        PhraseQuery, Query, QueryParser, RangeQuery, RegexQuery, TermQuery, TermSetQuery,
+        ExistsQuery, FastFieldRangeQuery, FuzzyTermQuery, MoreLikeThisQuery, PhraseQuery, Query,
+        QueryParser, RangeQuery, RegexQuery, TermQuery, TermSetQuery,
     },
     query_grammar::Occur,
     schema::{Field, FieldType, OwnedValue, DATE_TIME_PRECISION_INDEXED},
     Searcher, Term,
 };
 use thiserror::Error;
+use tokenizers::chain::AnalyzerChain;
+use tokenizers::manager::{language_from_str, language_to_str, stemmer_language};
 
 #[derive(Debug, PostgresType, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
 pub enum SearchQueryInput {
     All,
+    /// `value`, analyzed by the [`AnalyzerChain`] that the JSON `analyzer` describes rather than
+    /// the way `field` is.  A document must contain any of the terms it analyzes into, or with
+    /// `conjunction_mode`, all of them
+    Analyzed {
+        field: String,
+        value: String,
+        analyzer: serde_json::Value,
+        conjunction_mode: Option<bool>,
+        path: Option<String>,
+    },
+    AsOf {
+        generation: u64,
+        query: Box<SearchQueryInput>,
+    },
     Boolean {
         #[serde(default)]
         #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -52,21 +94,72 @@ pub enum SearchQueryInput {
         #[serde(default)]
         #[serde(skip_serializing_if = "Vec::is_empty")]
         must_not: Vec<SearchQueryInput>,
+
+        /// Like `must`, but without adding to the score
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        filter: Vec<SearchQueryInput>,
+
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        minimum_should_match: Option<MinimumShouldMatch>,
     },
     Boost {
         query: Box<SearchQueryInput>,
         boost: f32,
     },
+    Boosting {
+        positive: Box<SearchQueryInput>,
+        negative: Box<SearchQueryInput>,
+        negative_boost: f32,
+    },
+    /// The documents `query` matches, with the score of those whose value of the text fast field
+    /// `field` is one of the categories of `boosts` multiplied by that category's boost
+    CategoryBoost {
+        query: Box<SearchQueryInput>,
+        field: String,
+        boosts: BTreeMap<String, f32>,
+    },
+    Collapse {
+        field: String,
+        query: Box<SearchQueryInput>,
+    },
+    /// The documents `query` matches, but only the first one returned for each value of the fast
+    /// field `field`
+    Dedup {
+        field: String,
+        query: Box<SearchQueryInput>,
+    },
     ConstScore {
         query: Box<SearchQueryInput>,
         score: f32,
     },
+    /// `phrase`, as it would be found if the values of `fields` were one text, joined end to end
+    /// in order, so that it can start in one field and end in the next
+    CrossFieldPhrase {
+        fields: Vec<String>,
+        phrase: String,
+    },
     DisjunctionMax {
         disjuncts: Vec<SearchQueryInput>,
         tie_breaker: Option<f32>,
     },
     #[default]
     Empty,
+    /// `value`, analyzed the same way as `field`, with the score of documents that also match it
+    /// analyzed the way `cased_field` is, which should keep its case, multiplied by `boost`.
+    /// Either is searched for as a phrase if it analyzes into more than one token
+    ExactCase {
+        field: String,
+        cased_field: String,
+        value: String,
+        boost: f32,
+    },
+    /// The documents `query` matches, other than those of the rows with the `ctids`
+    Exclude {
+        query: Box<SearchQueryInput>,
+        ctids: Vec<u64>,
+    },
     Exists {
         field: String,
     },
@@ -92,6 +185,18 @@ pub enum SearchQueryInput {
         match_all_terms: Option<bool>,
         path: Option<String>,
     },
+    /// The parent documents of the documents matching `query`, which hold their parent's key in
+    /// the fast field `parent_field`
+    HasChild {
+        parent_field: String,
+        query: Box<SearchQueryInput>,
+    },
+    /// The child documents, which hold their parent's key in the fast field `parent_field`, of the
+    /// documents matching `query`
+    HasParent {
+        parent_field: String,
+        query: Box<SearchQueryInput>,
+    },
     MoreLikeThis {
         min_doc_frequency: Option<u64>,
         max_doc_frequency: Option<u64>,
@@ -127,6 +232,27 @@ pub enum SearchQueryInput {
         max_expansions: Option<u32>,
         path: Option<String>,
     },
+    PhrasePrefixText {
+        field: String,
+        value: String,
+        max_expansions: Option<u32>,
+        path: Option<String>,
+    },
+    PhraseWithTerms {
+        field: String,
+        phrases: Vec<String>,
+        slop: Option<u32>,
+        term_boost: Option<f32>,
+        term_boosts: Option<Vec<f32>>,
+        path: Option<String>,
+    },
+    Proximity {
+        field: String,
+        value: String,
+        slop: Option<u32>,
+        boost: Option<f32>,
+        path: Option<String>,
+    },
     Range {
         field: String,
         lower_bound: std::ops::Bound<tantivy::schema::OwnedValue>,
@@ -161,15 +287,46 @@ pub enum SearchQueryInput {
         field: String,
         pattern: String,
     },
+    Similarity {
+        query: Box<SearchQueryInput>,
+        similarity: Similarity,
+    },
+    /// `value`, stemmed the way words of `language` are, whichever way `field` was analyzed.  A
+    /// document must contain any of the stemmed terms, or with `conjunction_mode`, all of them
+    Stemmed {
+        field: String,
+        value: String,
+        language: String,
+        conjunction_mode: Option<bool>,
+        path: Option<String>,
+    },
+    /// `value`, or any of its `synonyms`, each analyzed the same way as `field`.  Those that
+    /// analyze into more than one token are searched for as phrases
+    Synonyms {
+        field: String,
+        value: String,
+        synonyms: Vec<String>,
+        path: Option<String>,
+    },
     Term {
         field: Option<String>,
         value: tantivy::schema::OwnedValue,
         path: Option<String>,
+        #[serde(default)]
         is_datetime: bool,
     },
     TermSet {
         terms: Vec<(String, tantivy::schema::OwnedValue, Option<String>, bool)>,
     },
+    /// The documents `exact` or `inexact` match, with those `exact` matches all scored above
+    /// those only `inexact` matches.  Within each tier, with a `recency_field`, scores halve every
+    /// `half_life` seconds a document's value of that date fast field is older than now
+    Tiered {
+        exact: Box<SearchQueryInput>,
+        inexact: Box<SearchQueryInput>,
+        recency_field: Option<String>,
+        half_life: Option<f64>,
+    },
     WithIndex {
         oid: pg_sys::Oid,
         query: Box<SearchQueryInput>,
@@ -183,21 +340,547 @@ impl SearchQueryInput {
                 must,
                 should,
                 must_not,
+                filter,
+                ..
             } => must
                 .iter()
                 .chain(should.iter())
                 .chain(must_not.iter())
+                .chain(filter.iter())
                 .any(Self::contains_more_like_this),
             SearchQueryInput::Boost { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::Boosting {
+                positive, negative, ..
+            } => Self::contains_more_like_this(positive) || Self::contains_more_like_this(negative),
+            SearchQueryInput::Tiered { exact, inexact, .. } => {
+                Self::contains_more_like_this(exact) || Self::contains_more_like_this(inexact)
+            }
+            SearchQueryInput::CategoryBoost { query, .. } => Self::contains_more_like_this(query),
             SearchQueryInput::ConstScore { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::HasChild { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::HasParent { query, .. } => Self::contains_more_like_this(query),
             SearchQueryInput::DisjunctionMax { disjuncts, .. } => {
                 disjuncts.iter().any(Self::contains_more_like_this)
             }
             SearchQueryInput::WithIndex { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::AsOf { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::Collapse { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::Dedup { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::Exclude { query, .. } => Self::contains_more_like_this(query),
+            SearchQueryInput::Similarity { query, .. } => Self::contains_more_like_this(query),
             SearchQueryInput::MoreLikeThis { .. } => true,
             _ => false,
         }
     }
+
+    /// The index generation this query has been pinned to with [`SearchQueryInput::AsOf`], if any
+    pub fn as_of_generation(&self) -> Option<u64> {
+        let own_generation = match self {
+            SearchQueryInput::AsOf { generation, .. } => Some(*generation),
+            _ => None,
+        };
+
+        self.subqueries()
+            .into_iter()
+            .filter_map(Self::as_of_generation)
+            .chain(own_generation)
+            .reduce(|generation, other| {
+                assert_eq!(
+                    generation, other,
+                    "a query can only be pinned to one index generation"
+                );
+                generation
+            })
+    }
+
+    /// The field this query's results are collapsed by with [`SearchQueryInput::Collapse`], if any
+    pub fn collapse_field(&self) -> Option<String> {
+        let own_field = match self {
+            SearchQueryInput::Collapse { field, .. } => Some(field.clone()),
+            _ => None,
+        };
+
+        self.subqueries()
+            .into_iter()
+            .filter_map(Self::collapse_field)
+            .chain(own_field)
+            .reduce(|field, other| {
+                assert_eq!(field, other, "a query can only be collapsed by one field");
+                field
+            })
+    }
+
+    /// The field this query's results are deduplicated by with [`SearchQueryInput::Dedup`], if any
+    pub fn dedup_field(&self) -> Option<String> {
+        let own_field = match self {
+            SearchQueryInput::Dedup { field, .. } => Some(field.clone()),
+            _ => None,
+        };
+
+        self.subqueries()
+            .into_iter()
+            .filter_map(Self::dedup_field)
+            .chain(own_field)
+            .reduce(|field, other| {
+                assert_eq!(
+                    field, other,
+                    "a query can only be deduplicated by one field"
+                );
+                field
+            })
+    }
+
+    fn subqueries(&self) -> Vec<&SearchQueryInput> {
+        match self {
+            SearchQueryInput::AsOf { query, .. }
+            | SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::CategoryBoost { query, .. }
+            | SearchQueryInput::Collapse { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::Dedup { query, .. }
+            | SearchQueryInput::Exclude { query, .. }
+            | SearchQueryInput::HasChild { query, .. }
+            | SearchQueryInput::HasParent { query, .. }
+            | SearchQueryInput::Similarity { query, .. }
+            | SearchQueryInput::WithIndex { query, .. } => vec![query],
+            SearchQueryInput::Boolean {
+                must,
+                should,
+                must_not,
+                filter,
+                ..
+            } => must
+                .iter()
+                .chain(should.iter())
+                .chain(must_not.iter())
+                .chain(filter.iter())
+                .collect(),
+            SearchQueryInput::Boosting {
+                positive, negative, ..
+            } => vec![positive, negative],
+            SearchQueryInput::Tiered { exact, inexact, .. } => vec![exact, inexact],
+            SearchQueryInput::DisjunctionMax { disjuncts, .. } => disjuncts.iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// This query as a tree, one node to a line, with each node's subqueries indented beneath it
+    /// and labelled by the role they play in it
+    pub fn explain_tree(&self) -> String {
+        let mut lines = Vec::new();
+        self.explain_node(0, None, &mut lines);
+        lines.join("\n")
+    }
+
+    fn explain_node(&self, depth: usize, role: Option<&str>, lines: &mut Vec<String>) {
+        let (label, children): (String, Vec<(&str, &SearchQueryInput)>) = match self {
+            SearchQueryInput::AsOf { generation, query } => (
+                format!("AsOf (generation={generation})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::Boolean {
+                must,
+                should,
+                must_not,
+                filter,
+                minimum_should_match,
+            } => (
+                match minimum_should_match {
+                    Some(MinimumShouldMatch::Count(count)) => {
+                        format!("Boolean (minimum_should_match={count})")
+                    }
+                    Some(MinimumShouldMatch::Percent(percent)) => {
+                        format!("Boolean (minimum_should_match={percent}%)")
+                    }
+                    None => "Boolean".into(),
+                },
+                must.iter()
+                    .map(|query| ("must", query))
+                    .chain(should.iter().map(|query| ("should", query)))
+                    .chain(must_not.iter().map(|query| ("must_not", query)))
+                    .chain(filter.iter().map(|query| ("filter", query)))
+                    .collect(),
+            ),
+            SearchQueryInput::Boost { query, boost } => (
+                format!("Boost (boost={boost})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::Boosting {
+                positive,
+                negative,
+                negative_boost,
+            } => (
+                format!("Boosting (negative_boost={negative_boost})"),
+                vec![
+                    ("positive", positive.as_ref()),
+                    ("negative", negative.as_ref()),
+                ],
+            ),
+            SearchQueryInput::CategoryBoost {
+                query,
+                field,
+                boosts,
+            } => (
+                format!(
+                    "CategoryBoost (field={field}, boosts={})",
+                    boosts
+                        .iter()
+                        .map(|(category, boost)| format!("{category}={boost}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::Collapse { field, query } => (
+                format!("Collapse (field={field})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::ConstScore { query, score } => (
+                format!("ConstScore (score={score})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::Dedup { field, query } => (
+                format!("Dedup (field={field})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::DisjunctionMax {
+                disjuncts,
+                tie_breaker,
+            } => (
+                match tie_breaker {
+                    Some(tie_breaker) => format!("DisjunctionMax (tie_breaker={tie_breaker})"),
+                    None => "DisjunctionMax".into(),
+                },
+                disjuncts.iter().map(|query| ("disjunct", query)).collect(),
+            ),
+            SearchQueryInput::Exclude { query, ctids } => (
+                format!("Exclude (ctids={})", ctids.len()),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::HasChild {
+                parent_field,
+                query,
+            } => (
+                format!("HasChild (parent_field={parent_field})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::HasParent {
+                parent_field,
+                query,
+            } => (
+                format!("HasParent (parent_field={parent_field})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::Similarity { query, similarity } => (
+                format!("Similarity (similarity={similarity:?})"),
+                vec![("query", query.as_ref())],
+            ),
+            SearchQueryInput::Tiered {
+                exact,
+                inexact,
+                recency_field,
+                half_life,
+            } => (
+                match (recency_field, half_life) {
+                    (Some(field), Some(half_life)) => {
+                        format!("Tiered (recency_field={field}, half_life={half_life})")
+                    }
+                    _ => "Tiered".into(),
+                },
+                vec![("exact", exact.as_ref()), ("inexact", inexact.as_ref())],
+            ),
+            SearchQueryInput::WithIndex { oid, query } => (
+                format!("WithIndex (oid={})", oid.as_u32()),
+                vec![("query", query.as_ref())],
+            ),
+            // the rest don't have subqueries, so are labelled with the arguments they serialize
+            leaf => {
+                let label = match serde_json::to_value(leaf).expect("query should serialize") {
+                    serde_json::Value::Object(variant) => variant
+                        .into_iter()
+                        .map(|(name, arguments)| match arguments {
+                            serde_json::Value::Object(arguments) => {
+                                let arguments = arguments
+                                    .into_iter()
+                                    .filter(|(_, value)| !value.is_null())
+                                    .map(|(key, value)| format!("{key}={value}"))
+                                    .collect::<Vec<_>>();
+                                format!("{name} ({})", arguments.join(", "))
+                            }
+                            _ => name,
+                        })
+                        .collect(),
+                    serde_json::Value::String(name) => name,
+                    other => other.to_string(),
+                };
+                (label, vec![])
+            }
+        };
+
+        let indent = "  ".repeat(depth);
+        match role {
+            Some(role) => lines.push(format!("{indent}{role}: {label}")),
+            None => lines.push(format!("{indent}{label}")),
+        }
+        for (role, child) in children {
+            child.explain_node(depth + 1, Some(role), lines);
+        }
+    }
+
+    /// The name of the text field this query searches, if it searches just one
+    fn text_field_mut(&mut self) -> Option<&mut String> {
+        match self {
+            SearchQueryInput::Analyzed { field, .. }
+            | SearchQueryInput::FuzzyTerm { field, .. }
+            | SearchQueryInput::FuzzyPhrase { field, .. }
+            | SearchQueryInput::ParseWithField { field, .. }
+            | SearchQueryInput::Phrase { field, .. }
+            | SearchQueryInput::PhrasePrefix { field, .. }
+            | SearchQueryInput::PhrasePrefixText { field, .. }
+            | SearchQueryInput::PhraseWithTerms { field, .. }
+            | SearchQueryInput::Proximity { field, .. }
+            | SearchQueryInput::Regex { field, .. }
+            | SearchQueryInput::Stemmed { field, .. }
+            | SearchQueryInput::Synonyms { field, .. }
+            | SearchQueryInput::Term {
+                field: Some(field), ..
+            } => Some(field),
+            _ => None,
+        }
+    }
+
+    /// Search each text field whose name matches the glob `pattern` the way this query searches
+    /// its one field, for a document that any of them match.  A glob that doesn't match any
+    /// field matches no documents
+    fn into_field_glob_query(
+        self,
+        pattern: &str,
+        field_lookup: &impl AsFieldType<String>,
+        parser: &mut QueryParser,
+        searcher: &Searcher,
+    ) -> Result<Box<dyn Query>, Box<dyn std::error::Error>> {
+        let schema = searcher.schema();
+        let mut subqueries = vec![];
+        for (field_type, field) in field_lookup.fields() {
+            let name = schema.get_field_name(field);
+            if !matches!(field_type, FieldType::Str(_)) || !glob_matches(pattern, name) {
+                continue;
+            }
+
+            let mut query = self.clone();
+            *query
+                .text_field_mut()
+                .expect("query should search a text field") = name.to_string();
+            subqueries.push((
+                Occur::Should,
+                query.into_tantivy_query(field_lookup, parser, searcher)?,
+            ));
+        }
+
+        if subqueries.is_empty() {
+            return Ok(Box::new(EmptyQuery));
+        }
+        Ok(Box::new(BooleanQuery::new(subqueries)))
+    }
+}
+
+/// Does `name` match the glob `pattern`, in which each `*` matches any run of characters?
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = name.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // there's no `*`, so the name must be the pattern itself
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+impl SearchQueryInput {
+    /// Can this query be proven to match no documents at all?
+    ///
+    /// `is_single_valued` reports if a field holds at most one value per document, which lets
+    /// two different required [`SearchQueryInput::Term`]s on that field be detected as a
+    /// contradiction.
+    pub fn matches_nothing(&self, is_single_valued: &dyn Fn(&str) -> bool) -> bool {
+        match self {
+            SearchQueryInput::Empty => true,
+            SearchQueryInput::TermSet { terms } => terms.is_empty(),
+            SearchQueryInput::AsOf { query, .. }
+            | SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::CategoryBoost { query, .. }
+            | SearchQueryInput::Collapse { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::Dedup { query, .. }
+            | SearchQueryInput::Exclude { query, .. }
+            | SearchQueryInput::HasChild { query, .. }
+            | SearchQueryInput::HasParent { query, .. }
+            | SearchQueryInput::Similarity { query, .. }
+            | SearchQueryInput::WithIndex { query, .. } => query.matches_nothing(is_single_valued),
+            SearchQueryInput::Boosting { positive, .. } => {
+                positive.matches_nothing(is_single_valued)
+            }
+            SearchQueryInput::DisjunctionMax { disjuncts, .. } => disjuncts
+                .iter()
+                .all(|query| query.matches_nothing(is_single_valued)),
+            SearchQueryInput::Tiered { exact, inexact, .. } => {
+                exact.matches_nothing(is_single_valued) && inexact.matches_nothing(is_single_valued)
+            }
+            SearchQueryInput::Boolean {
+                must,
+                should,
+                filter,
+                minimum_should_match,
+                ..
+            } => {
+                if must
+                    .iter()
+                    .chain(filter.iter())
+                    .any(|query| query.matches_nothing(is_single_valued))
+                {
+                    return true;
+                }
+
+                // more should clauses are required to match than could possibly match
+                let required_should = match minimum_should_match {
+                    Some(minimum_should_match) => minimum_should_match.resolve(should.len()),
+                    None if must.is_empty() && filter.is_empty() && !should.is_empty() => 1,
+                    None => 0,
+                };
+                let possible_should = should
+                    .iter()
+                    .filter(|query| !query.matches_nothing(is_single_valued))
+                    .count();
+                if required_should > possible_should {
+                    return true;
+                }
+
+                // look for the same single-valued field being required to have two different values
+                let mut required = HashMap::new();
+                for (field, value, is_datetime) in self.required_terms() {
+                    if !is_single_valued(field) {
+                        continue;
+                    }
+
+                    match required.entry(field) {
+                        std::collections::hash_map::Entry::Occupied(entry) => {
+                            let (other_value, other_is_datetime) = entry.get();
+                            if is_datetime == *other_is_datetime
+                                && term_values_conflict(value, other_value)
+                            {
+                                return true;
+                            }
+                        }
+                        std::collections::hash_map::Entry::Vacant(entry) => {
+                            entry.insert((value, is_datetime));
+                        }
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// The `(field, value, is_datetime)` of every [`SearchQueryInput::Term`] a document must
+    /// match in order to match this query
+    fn required_terms(&self) -> Vec<(&str, &OwnedValue, bool)> {
+        match self {
+            SearchQueryInput::Term {
+                field: Some(field),
+                value,
+                path: None,
+                is_datetime,
+            } => vec![(field.as_str(), value, *is_datetime)],
+            SearchQueryInput::Boolean { must, filter, .. } => must
+                .iter()
+                .chain(filter.iter())
+                .flat_map(Self::required_terms)
+                .collect(),
+            SearchQueryInput::AsOf { query, .. }
+            | SearchQueryInput::Boost { query, .. }
+            | SearchQueryInput::CategoryBoost { query, .. }
+            | SearchQueryInput::Collapse { query, .. }
+            | SearchQueryInput::ConstScore { query, .. }
+            | SearchQueryInput::Dedup { query, .. }
+            | SearchQueryInput::Exclude { query, .. }
+            | SearchQueryInput::Similarity { query, .. }
+            | SearchQueryInput::WithIndex { query, .. } => query.required_terms(),
+            _ => vec![],
+        }
+    }
+}
+
+/// How many of a [`SearchQueryInput::Boolean`]'s `should` clauses a document must match
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum MinimumShouldMatch {
+    /// An absolute number of clauses
+    Count(usize),
+    /// A percentage of the clauses, rounded down
+    Percent(f32),
+}
+
+impl MinimumShouldMatch {
+    /// The number of clauses that must match out of `nclauses`
+    pub fn resolve(&self, nclauses: usize) -> usize {
+        match self {
+            MinimumShouldMatch::Count(count) => *count,
+            MinimumShouldMatch::Percent(percent) => {
+                (nclauses as f64 * *percent as f64 / 100.0).floor() as usize
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for MinimumShouldMatch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<f32>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => {
+                    Ok(MinimumShouldMatch::Percent(percent))
+                }
+                _ => Err(format!(
+                    "minimum_should_match percentage must be between 0% and 100%, got '{s}'"
+                )),
+            },
+            None => s
+                .parse::<usize>()
+                .map(MinimumShouldMatch::Count)
+                .map_err(|_| {
+                    format!(
+                    "minimum_should_match must be a non-negative integer or a percentage, got '{s}'"
+                )
+                }),
+        }
+    }
+}
+
+/// Would a single-valued field be unable to hold both `a` and `b`?
+fn term_values_conflict(a: &OwnedValue, b: &OwnedValue) -> bool {
+    fn as_f64(value: &OwnedValue) -> Option<f64> {
+        match value {
+            OwnedValue::U64(v) => Some(*v as f64),
+            OwnedValue::I64(v) => Some(*v as f64),
+            OwnedValue::F64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    match (as_f64(a), as_f64(b)) {
+        (Some(a), Some(b)) => a != b,
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b) && a != b,
+    }
 }
 
 #[allow(dead_code)]
@@ -288,18 +971,103 @@ pub trait AsFieldType<T> {
 
 impl SearchQueryInput {
     pub fn into_tantivy_query(
-        self,
+        mut self,
         field_lookup: &impl AsFieldType<String>,
         parser: &mut QueryParser,
         searcher: &Searcher,
     ) -> Result<Box<dyn Query>, Box<dyn std::error::Error>> {
+        if let Some(pattern) = self
+            .text_field_mut()
+            .filter(|field| field.contains('*'))
+            .cloned()
+        {
+            return self.into_field_glob_query(&pattern, field_lookup, parser, searcher);
+        }
+
         match self {
             Self::All => Ok(Box::new(AllQuery)),
+            Self::Analyzed {
+                field,
+                value,
+                analyzer,
+                conjunction_mode,
+                path,
+            } => {
+                let value = checked_query_text(value)?;
+                let (field_type, field) = field_lookup
+                    .as_field_type(&field)
+                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+
+                let tokens = AnalyzerChain::from_json_value(&analyzer)?.analyze(&value);
+                let terms = tokens
+                    .into_iter()
+                    .map(|token| {
+                        value_to_term(
+                            field,
+                            &OwnedValue::Str(token),
+                            &field_type,
+                            path.as_deref(),
+                            false,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if terms.is_empty() {
+                    return Ok(no_terms_query(&value)?);
+                }
+
+                // a chain that doesn't produce the same terms the field was indexed with can't
+                // find anything, which is more likely a mistake than what the query was after
+                let is_indexed = |term: &Term| {
+                    searcher.segment_readers().iter().any(|segment_reader| {
+                        segment_reader
+                            .inverted_index(term.field())
+                            .ok()
+                            .and_then(|inverted_index| inverted_index.get_term_info(term).ok()?)
+                            .is_some()
+                    })
+                };
+                if searcher.num_docs() > 0 && !terms.iter().any(is_indexed) {
+                    pgrx::warning!(
+                        "none of the terms the analyzer chain turns '{value}' into are indexed in field '{}', so the chain may not be compatible with how the field is analyzed",
+                        searcher.schema().get_field_name(field)
+                    );
+                }
+
+                let occur = if conjunction_mode.unwrap_or(false) {
+                    Occur::Must
+                } else {
+                    Occur::Should
+                };
+                Ok(Box::new(BooleanQuery::new(
+                    terms
+                        .into_iter()
+                        .map(|term| {
+                            let query: Box<dyn Query> = Box::new(TermQuery::new(
+                                term,
+                                IndexRecordOption::WithFreqsAndPositions.into(),
+                            ));
+                            (occur, query)
+                        })
+                        .collect(),
+                )))
+            }
+            // the generation is handled by whoever opens the index reader
+            Self::AsOf { query, .. } => query.into_tantivy_query(field_lookup, parser, searcher),
+            // and the collapsing by whoever collects the results
+            Self::Collapse { query, .. } => {
+                query.into_tantivy_query(field_lookup, parser, searcher)
+            }
+            // and the deduplicating by whoever returns them
+            Self::Dedup { query, .. } => query.into_tantivy_query(field_lookup, parser, searcher),
             Self::Boolean {
                 must,
                 should,
                 must_not,
+                filter,
+                minimum_should_match,
             } => {
+                let minimum_should_match =
+                    minimum_should_match.map(|minimum| minimum.resolve(should.len()));
                 let mut subqueries = vec![];
                 for input in must {
                     subqueries.push((
@@ -319,16 +1087,144 @@ impl SearchQueryInput {
                         input.into_tantivy_query(field_lookup, parser, searcher)?,
                     ));
                 }
-                Ok(Box::new(BooleanQuery::new(subqueries)))
+                // a filter must match, but scores nothing, to leave the others' scores as they are
+                for input in filter {
+                    subqueries.push((
+                        Occur::Must,
+                        Box::new(ConstScoreQuery::new(
+                            input.into_tantivy_query(field_lookup, parser, searcher)?,
+                            0.0,
+                        )),
+                    ));
+                }
+                match minimum_should_match {
+                    Some(minimum) => Ok(Box::new(BooleanQuery::with_minimum_required_clauses(
+                        subqueries, minimum,
+                    ))),
+                    None => Ok(Box::new(BooleanQuery::new(subqueries))),
+                }
             }
             Self::Boost { query, boost } => Ok(Box::new(BoostQuery::new(
                 query.into_tantivy_query(field_lookup, parser, searcher)?,
                 boost,
             ))),
+            Self::Boosting {
+                positive,
+                negative,
+                negative_boost,
+            } => Ok(Box::new(BoostingQuery::new(
+                positive.into_tantivy_query(field_lookup, parser, searcher)?,
+                negative.into_tantivy_query(field_lookup, parser, searcher)?,
+                negative_boost,
+            ))),
+            Self::CategoryBoost {
+                query,
+                field,
+                boosts,
+            } => {
+                // the categories are read from the field's column, by their term ordinals in it
+                match field_lookup.as_field_type(&field) {
+                    Some((FieldType::Str(options), _)) if options.is_fast() => {}
+                    _ => return Err(Box::new(QueryError::CategoryBoostField(field))),
+                }
+                Ok(Box::new(CategoryBoostQuery::new(
+                    query.into_tantivy_query(field_lookup, parser, searcher)?,
+                    field,
+                    boosts,
+                )))
+            }
             Self::ConstScore { query, score } => Ok(Box::new(ConstScoreQuery::new(
                 query.into_tantivy_query(field_lookup, parser, searcher)?,
                 score,
             ))),
+            Self::CrossFieldPhrase { fields, phrase } => {
+                // the fields are joined into one stream of tokens by analyzing their stored
+                // values again, so they must be stored, and analyzed the same way
+                let mut tokenizer = None;
+                let mut search_fields = Vec::new();
+                for name in fields {
+                    let indexed = match field_lookup.as_field_type(&name) {
+                        Some((FieldType::Str(options), field)) if options.is_stored() => options
+                            .get_indexing_options()
+                            .map(|indexing| (field, indexing.tokenizer().to_string())),
+                        Some(_) => None,
+                        None => return Err(Box::new(QueryError::NonIndexedField(name))),
+                    };
+                    let Some((field, field_tokenizer)) = indexed else {
+                        return Err(Box::new(QueryError::CrossFieldPhraseField(name)));
+                    };
+                    if *tokenizer.get_or_insert_with(|| field_tokenizer.clone()) != field_tokenizer
+                    {
+                        return Err(Box::new(QueryError::CrossFieldPhraseField(name)));
+                    }
+                    search_fields.push(field);
+                }
+                let Some(first_field) = search_fields.first().copied() else {
+                    return Ok(Box::new(EmptyQuery));
+                };
+
+                let mut analyzer = searcher.index().tokenizer_for_field(first_field)?;
+                let tokens = {
+                    let mut stream = analyzer.token_stream(&phrase);
+                    let mut tokens = Vec::new();
+                    while stream.advance() {
+                        tokens.push(stream.token().text.clone());
+                    }
+                    tokens
+                };
+                if tokens.is_empty() {
+                    return Ok(no_terms_query(&phrase)?);
+                }
+
+                // only documents holding every token in one of the fields can hold the phrase
+                let mut distinct_tokens = tokens.clone();
+                distinct_tokens.sort();
+                distinct_tokens.dedup();
+                let candidates: Box<dyn Query> = Box::new(BooleanQuery::new(
+                    distinct_tokens
+                        .iter()
+                        .map(|token| {
+                            let in_any_field = BooleanQuery::new(
+                                search_fields
+                                    .iter()
+                                    .map(|field| {
+                                        let query: Box<dyn Query> = Box::new(TermQuery::new(
+                                            Term::from_field_text(*field, token),
+                                            IndexRecordOption::WithFreqs.into(),
+                                        ));
+                                        (Occur::Should, query)
+                                    })
+                                    .collect(),
+                            );
+                            let query: Box<dyn Query> = Box::new(in_any_field);
+                            (Occur::Must, query)
+                        })
+                        .collect(),
+                ));
+
+                let ctids = cross_field::phrase_matches(
+                    searcher,
+                    candidates.as_ref(),
+                    &search_fields,
+                    &mut analyzer,
+                    &tokens,
+                )?;
+                let ctid_field = field_lookup
+                    .as_u64(&"ctid".to_string())
+                    .ok_or_else(|| QueryError::NonIndexedField("ctid".into()))?;
+                let matches = TermSetQuery::new(
+                    ctids
+                        .into_iter()
+                        .map(|ctid| Term::from_field_u64(ctid_field, ctid)),
+                );
+                Ok(Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, candidates),
+                    (
+                        Occur::Must,
+                        Box::new(ConstScoreQuery::new(Box::new(matches), 0.0)),
+                    ),
+                ])))
+            }
             Self::DisjunctionMax {
                 disjuncts,
                 tie_breaker,
@@ -347,16 +1243,61 @@ impl SearchQueryInput {
                 }
             }
             Self::Empty => Ok(Box::new(EmptyQuery)),
+            Self::ExactCase {
+                field,
+                cased_field,
+                value,
+                boost,
+            } => {
+                let (field_type, field) = field_lookup
+                    .as_field_type(&field)
+                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+                let (cased_field_type, cased_field) = field_lookup
+                    .as_field_type(&cased_field)
+                    .ok_or_else(|| QueryError::NonIndexedField(cased_field))?;
+
+                let Some(query) = analyzed_text_query(searcher, field, &field_type, &value, None)?
+                else {
+                    return Ok(no_terms_query(&value)?);
+                };
+                let mut clauses = vec![(Occur::Must, query)];
+                if let Some(cased_query) =
+                    analyzed_text_query(searcher, cased_field, &cased_field_type, &value, None)?
+                {
+                    clauses.push((Occur::Should, Box::new(BoostQuery::new(cased_query, boost))));
+                }
+                Ok(Box::new(BooleanQuery::new(clauses)))
+            }
+            Self::Exclude { query, ctids } => {
+                let query = query.into_tantivy_query(field_lookup, parser, searcher)?;
+                if ctids.is_empty() {
+                    return Ok(query);
+                }
+
+                let ctid_field = field_lookup
+                    .as_u64(&"ctid".to_string())
+                    .ok_or_else(|| QueryError::NonIndexedField("ctid".into()))?;
+                let excluded = TermSetQuery::new(
+                    ctids
+                        .into_iter()
+                        .map(|ctid| Term::from_field_u64(ctid_field, ctid)),
+                );
+                Ok(Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, query),
+                    (Occur::MustNot, Box::new(excluded)),
+                ])))
+            }
             Self::Exists { field } => Ok(Box::new(ExistsQuery::new_exists_query(field))),
             Self::FastFieldRangeWeight {
                 field,
                 lower_bound,
                 upper_bound,
             } => {
+                let field_name = field;
                 let field = field_lookup
-                    .as_u64(&field)
-                    .or_else(|| field_lookup.as_i64(&field))
-                    .ok_or_else(|| QueryError::WrongFieldType(field.clone()))?;
+                    .as_u64(&field_name)
+                    .or_else(|| field_lookup.as_i64(&field_name))
+                    .ok_or_else(|| QueryError::WrongFieldType(field_name.clone()))?;
 
                 let new_lower_bound = match lower_bound {
                     Bound::Excluded(v) => Bound::Excluded(Term::from_field_u64(field, v)),
@@ -370,10 +1311,14 @@ impl SearchQueryInput {
                     Bound::Unbounded => Bound::Unbounded,
                 };
 
-                Ok(Box::new(FastFieldRangeQuery::new(
-                    new_lower_bound,
-                    new_upper_bound,
-                )))
+                let query =
+                    FastFieldRangeQuery::new(new_lower_bound.clone(), new_upper_bound.clone());
+                Ok(DescribedQuery::range(
+                    Box::new(query),
+                    &field_name,
+                    &new_lower_bound,
+                    &new_upper_bound,
+                ))
             }
             Self::FuzzyTerm {
                 field,
@@ -383,31 +1328,50 @@ impl SearchQueryInput {
                 prefix,
                 path,
             } => {
+                let field_name = field;
                 let (field_type, field) = field_lookup
-                    .as_field_type(&field)
-                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+                    .as_field_type(&field_name)
+                    .ok_or_else(|| QueryError::NonIndexedField(field_name.clone()))?;
                 let term = value_to_term(
                     field,
-                    &OwnedValue::Str(value),
+                    &OwnedValue::Str(value.clone()),
                     &field_type,
                     path.as_deref(),
                     false,
                 )?;
                 let distance = distance.unwrap_or(2);
                 let transposition_cost_one = transposition_cost_one.unwrap_or(true);
-                if prefix.unwrap_or(false) {
-                    Ok(Box::new(FuzzyTermQuery::new_prefix(
-                        term,
+                let prefix = prefix.unwrap_or(false);
+                let query: Box<dyn Query> = if prefix {
+                    Box::new(FuzzyTermQuery::new_prefix(
+                        term.clone(),
                         distance,
                         transposition_cost_one,
-                    )))
+                    ))
                 } else {
-                    Ok(Box::new(FuzzyTermQuery::new(
-                        term,
+                    Box::new(FuzzyTermQuery::new(
+                        term.clone(),
                         distance,
                         transposition_cost_one,
-                    )))
-                }
+                    ))
+                };
+                let query = DescribedQuery::fuzzy(
+                    query,
+                    &field_name,
+                    &value,
+                    distance,
+                    transposition_cost_one,
+                    prefix,
+                );
+                let expansion = Expansion::Fuzzy {
+                    term: &term,
+                    text: &value,
+                    distance,
+                    transposition_cost_one,
+                    prefix,
+                };
+                let description = format!("fuzzy term '{value}'");
+                limit_expansions(query, searcher, field, expansion, &description)
             }
             Self::FuzzyPhrase {
                 field,
@@ -418,14 +1382,16 @@ impl SearchQueryInput {
                 match_all_terms,
                 path,
             } => {
+                let value = checked_query_text(value)?;
                 let distance = distance.unwrap_or(2);
                 let transposition_cost_one = transposition_cost_one.unwrap_or(true);
                 let match_all_terms = match_all_terms.unwrap_or(false);
                 let prefix = prefix.unwrap_or(false);
 
+                let field_name = field;
                 let (field_type, field) = field_lookup
-                    .as_field_type(&field)
-                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+                    .as_field_type(&field_name)
+                    .ok_or_else(|| QueryError::NonIndexedField(field_name.clone()))?;
 
                 let mut analyzer = searcher.index().tokenizer_for_field(field)?;
                 let mut stream = analyzer.token_stream(&value);
@@ -435,20 +1401,42 @@ impl SearchQueryInput {
                     let token = stream.token().text.clone();
                     let term = value_to_term(
                         field,
-                        &OwnedValue::Str(token),
+                        &OwnedValue::Str(token.clone()),
                         &field_type,
                         path.as_deref(),
                         false,
                     )?;
                     let term_query: Box<dyn Query> = if prefix {
                         Box::new(FuzzyTermQuery::new_prefix(
-                            term,
+                            term.clone(),
                             distance,
                             transposition_cost_one,
                         ))
                     } else {
-                        Box::new(FuzzyTermQuery::new(term, distance, transposition_cost_one))
+                        Box::new(FuzzyTermQuery::new(
+                            term.clone(),
+                            distance,
+                            transposition_cost_one,
+                        ))
+                    };
+                    let term_query = DescribedQuery::fuzzy(
+                        term_query,
+                        &field_name,
+                        &token,
+                        distance,
+                        transposition_cost_one,
+                        prefix,
+                    );
+                    let expansion = Expansion::Fuzzy {
+                        term: &term,
+                        text: &token,
+                        distance,
+                        transposition_cost_one,
+                        prefix,
                     };
+                    let description = format!("fuzzy term '{token}'");
+                    let term_query =
+                        limit_expansions(term_query, searcher, field, expansion, &description)?;
                     let occur = if match_all_terms {
                         Occur::Must
                     } else {
@@ -458,8 +1446,49 @@ impl SearchQueryInput {
                     terms.push((occur, term_query));
                 }
 
+                if terms.is_empty() {
+                    return Ok(no_terms_query(&value)?);
+                }
                 Ok(Box::new(BooleanQuery::new(terms)))
             }
+            Self::HasChild {
+                parent_field,
+                query,
+            } => {
+                // parents are found by their keys, which their matching children hold
+                let children = query.into_tantivy_query(field_lookup, parser, searcher)?;
+                let keys = join::fast_field_values(searcher, children.as_ref(), &parent_field)?;
+                let key_field = searcher
+                    .schema()
+                    .get_field_name(field_lookup.key_field().1)
+                    .to_string();
+                Self::TermSet {
+                    terms: keys
+                        .into_iter()
+                        .map(|key| (key_field.clone(), key, None, false))
+                        .collect(),
+                }
+                .into_tantivy_query(field_lookup, parser, searcher)
+            }
+            Self::HasParent {
+                parent_field,
+                query,
+            } => {
+                // and children by their parents' keys
+                let key_field = searcher
+                    .schema()
+                    .get_field_name(field_lookup.key_field().1)
+                    .to_string();
+                let parents = query.into_tantivy_query(field_lookup, parser, searcher)?;
+                let keys = join::fast_field_values(searcher, parents.as_ref(), &key_field)?;
+                Self::TermSet {
+                    terms: keys
+                        .into_iter()
+                        .map(|key| (parent_field.clone(), key, None, false))
+                        .collect(),
+                }
+                .into_tantivy_query(field_lookup, parser, searcher)
+            }
             Self::MoreLikeThis {
                 min_doc_frequency,
                 max_doc_frequency,
@@ -543,42 +1572,206 @@ impl SearchQueryInput {
                 let (field_type, field) = field_lookup
                     .as_field_type(&field)
                     .ok_or_else(|| QueryError::NonIndexedField(field))?;
-                let terms = phrases.clone().into_iter().map(|phrase| {
-                    value_to_term(
+                let terms = phrases
+                    .clone()
+                    .into_iter()
+                    .map(|phrase| {
+                        value_to_term(
+                            field,
+                            &OwnedValue::Str(phrase),
+                            &field_type,
+                            path.as_deref(),
+                            false,
+                        )
+                        .unwrap()
+                    })
+                    .collect::<Vec<_>>();
+                let description = format!("phrase prefix '{}'", phrases.join(" "));
+                limit_phrase_prefix_expansions(terms, max_expansions, searcher, field, &description)
+            }
+            Self::PhrasePrefixText {
+                field,
+                value,
+                max_expansions,
+                path,
+            } => {
+                let value = checked_query_text(value)?;
+                let (field_type, field) = field_lookup
+                    .as_field_type(&field)
+                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+
+                // the text is analyzed the same way as the indexed field, and its last token
+                // is the prefix that's being typed
+                let mut analyzer = searcher.index().tokenizer_for_field(field)?;
+                let mut stream = analyzer.token_stream(&value);
+                let mut terms = Vec::new();
+
+                while stream.advance() {
+                    let token = stream.token().text.clone();
+                    terms.push(value_to_term(
                         field,
-                        &OwnedValue::Str(phrase),
+                        &OwnedValue::Str(token),
                         &field_type,
                         path.as_deref(),
                         false,
-                    )
-                    .unwrap()
-                });
-                let mut query = PhrasePrefixQuery::new(terms.collect());
-                if let Some(max_expansions) = max_expansions {
-                    query.set_max_expansions(max_expansions)
+                    )?);
                 }
-                Ok(Box::new(query))
+
+                if terms.is_empty() {
+                    return Ok(no_terms_query(&value)?);
+                }
+
+                let description = format!("phrase prefix '{value}'");
+                limit_phrase_prefix_expansions(terms, max_expansions, searcher, field, &description)
+            }
+            Self::PhraseWithTerms {
+                field,
+                phrases,
+                slop,
+                term_boost,
+                term_boosts,
+                path,
+            } => {
+                let (field_type, field) = field_lookup
+                    .as_field_type(&field)
+                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+                let terms = phrases
+                    .into_iter()
+                    .map(|phrase| {
+                        value_to_term(
+                            field,
+                            &OwnedValue::Str(phrase),
+                            &field_type,
+                            path.as_deref(),
+                            false,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // the phrase scores as it would on its own, and each of its terms adds to the
+                // score of every document it's in, by as much as its boost says
+                let mut clauses: Vec<(Occur, Box<dyn Query>)> = terms
+                    .iter()
+                    .enumerate()
+                    .map(|(i, term)| {
+                        let boost = term_boosts
+                            .as_ref()
+                            .and_then(|boosts| boosts.get(i).copied())
+                            .or(term_boost)
+                            .unwrap_or(1.0);
+                        let query = TermQuery::new(
+                            term.clone(),
+                            IndexRecordOption::WithFreqsAndPositions.into(),
+                        );
+                        let query = BoostQuery::new(Box::new(query), boost);
+                        (Occur::Should, Box::new(query) as Box<dyn Query>)
+                    })
+                    .collect();
+                if terms.len() > 1 {
+                    let mut phrase = PhraseQuery::new(terms);
+                    if let Some(slop) = slop {
+                        phrase.set_slop(slop);
+                    }
+                    clauses.push((Occur::Should, Box::new(phrase)));
+                }
+
+                Ok(Box::new(BooleanQuery::new(clauses)))
+            }
+            Self::Proximity {
+                field,
+                value,
+                slop,
+                boost,
+                path,
+            } => {
+                let value = checked_query_text(value)?;
+                let slop = slop.unwrap_or(2);
+                let boost = boost.unwrap_or(2.0);
+
+                let (field_type, field) = field_lookup
+                    .as_field_type(&field)
+                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+
+                let mut analyzer = searcher.index().tokenizer_for_field(field)?;
+                let mut stream = analyzer.token_stream(&value);
+                let mut terms = Vec::new();
+
+                while stream.advance() {
+                    let token = stream.token().text.clone();
+                    terms.push(value_to_term(
+                        field,
+                        &OwnedValue::Str(token),
+                        &field_type,
+                        path.as_deref(),
+                        false,
+                    )?);
+                }
+
+                if terms.is_empty() {
+                    return Ok(no_terms_query(&value)?);
+                }
+
+                // any of the terms matches, and the phrase of all of them only adds to the
+                // score of the documents where they're within `slop` of each other
+                let mut clauses: Vec<(Occur, Box<dyn Query>)> = terms
+                    .iter()
+                    .map(|term| {
+                        let query = TermQuery::new(
+                            term.clone(),
+                            IndexRecordOption::WithFreqsAndPositions.into(),
+                        );
+                        (Occur::Should, Box::new(query) as Box<dyn Query>)
+                    })
+                    .collect();
+                if terms.len() > 1 {
+                    let mut phrase = PhraseQuery::new(terms);
+                    phrase.set_slop(slop);
+                    let phrase = BoostQuery::new(Box::new(phrase), boost);
+                    clauses.push((Occur::Should, Box::new(phrase)));
+                }
+
+                Ok(Box::new(BooleanQuery::new(clauses)))
             }
             Self::Parse {
                 query_string,
                 lenient,
                 conjunction_mode,
             } => {
+                let query_string = checked_query_text(query_string)?;
                 if let Some(true) = conjunction_mode {
                     parser.set_conjunction_by_default();
                 }
 
-                match lenient {
+                // dates and numbers are searched for as they were indexed, and a lenient parse
+                // doesn't raise an error for a date it can't make sense of
+                let query_string = typed_values::normalize(
+                    &query_string,
+                    &|field| {
+                        field_lookup
+                            .as_field_type(&field.to_string())
+                            .map(|(field_type, _)| field_type)
+                    },
+                    lenient != Some(true),
+                )?;
+                let parsed_query: Box<dyn Query> = match lenient {
                     Some(true) => {
                         let (parsed_query, _) = parser.parse_query_lenient(&query_string);
-                        Ok(Box::new(parsed_query))
-                    }
-                    _ => {
-                        Ok(Box::new(parser.parse_query(&query_string).map_err(
-                            |err| QueryError::ParseError(err, query_string),
-                        )?))
+                        Box::new(parsed_query)
                     }
+                    _ => Box::new(
+                        parser
+                            .parse_query(&query_string)
+                            .map_err(|err| QueryError::ParseError(err, query_string.clone()))?,
+                    ),
+                };
+                if has_no_terms(parsed_query.as_ref()) {
+                    return Ok(no_terms_query(&query_string)?);
                 }
+                let parsed_query = match gucs::same_field_clauses() {
+                    SameFieldClauses::All => parsed_query,
+                    SameFieldClauses::Any => any_same_field_clause(parsed_query),
+                };
+                Ok(limit_query_terms(parsed_query, query_string)?)
             }
             Self::ParseWithField {
                 field,
@@ -630,6 +1823,13 @@ impl SearchQueryInput {
                 let (field_type, field) = field_lookup
                     .as_field_type(&field_name)
                     .ok_or_else(|| QueryError::WrongFieldType(field_name.clone()))?;
+                let (Some(lower_bound), Some(upper_bound)) = (
+                    coerce_bound(lower_bound, &field_type, true),
+                    coerce_bound(upper_bound, &field_type, false),
+                ) else {
+                    // a bound past the end of what the field can hold leaves nothing in range
+                    return Ok(Box::new(EmptyQuery));
+                };
 
                 let lower_bound = match lower_bound {
                     Bound::Included(value) => Bound::Included(value_to_term(
@@ -667,7 +1867,13 @@ impl SearchQueryInput {
                     Bound::Unbounded => Bound::Unbounded,
                 };
 
-                Ok(Box::new(RangeQuery::new(lower_bound, upper_bound)))
+                let query = RangeQuery::new(lower_bound.clone(), upper_bound.clone());
+                Ok(DescribedQuery::range(
+                    Box::new(query),
+                    &field_name,
+                    &lower_bound,
+                    &upper_bound,
+                ))
             }
             Self::RangeContains {
                 field,
@@ -1252,15 +2458,90 @@ impl SearchQueryInput {
                     ])))
                 }
             }
-            Self::Regex { field, pattern } => Ok(Box::new(
-                RegexQuery::from_pattern(
-                    &pattern,
-                    field_lookup
-                        .as_str(&field)
-                        .ok_or_else(|| QueryError::WrongFieldType(field.clone()))?,
+            Self::Regex { field, pattern } => {
+                let field_name = field;
+                let field = field_lookup
+                    .as_str(&field_name)
+                    .ok_or_else(|| QueryError::WrongFieldType(field_name.clone()))?;
+                let query = RegexQuery::from_pattern(&pattern, field)
+                    .map_err(|err| QueryError::RegexError(err, pattern.clone()))?;
+                let description = format!("regex '{pattern}'");
+                limit_expansions(
+                    DescribedQuery::regex(Box::new(query), &field_name, &pattern),
+                    searcher,
+                    field,
+                    Expansion::Regex(&pattern),
+                    &description,
                 )
-                .map_err(|err| QueryError::RegexError(err, pattern.clone()))?,
-            )),
+            }
+            Self::Similarity { query, similarity } => Ok(Box::new(SimilarityQuery::new(
+                query.into_tantivy_query(field_lookup, parser, searcher)?,
+                similarity,
+            ))),
+            Self::Stemmed {
+                field,
+                value,
+                language,
+                conjunction_mode,
+                path,
+            } => {
+                let stemmer = language_from_str(&language)
+                    .ok_or_else(|| QueryError::UnknownLanguage(language.clone()))?;
+
+                // terms stemmed in another language than the field's are unlikely to be indexed
+                if let Some((FieldType::Str(options), _)) = field_lookup.as_field_type(&field) {
+                    let field_stemmer = options
+                        .get_indexing_options()
+                        .and_then(|indexing| stemmer_language(indexing.tokenizer()));
+                    if field_stemmer != Some(stemmer) {
+                        pgrx::warning!(
+                            "field '{field}' isn't stemmed in {}, so terms stemmed in that language may not be found in it",
+                            language_to_str(&stemmer)
+                        );
+                    }
+                }
+
+                Self::Analyzed {
+                    field,
+                    value,
+                    analyzer: serde_json::json!({
+                        "filters": [{ "stemmer": language_to_str(&stemmer) }]
+                    }),
+                    conjunction_mode,
+                    path,
+                }
+                .into_tantivy_query(field_lookup, parser, searcher)
+            }
+            Self::Synonyms {
+                field,
+                value,
+                synonyms,
+                path,
+            } => {
+                let value = checked_query_text(value)?;
+                let synonyms = synonyms
+                    .into_iter()
+                    .map(checked_query_text)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let (field_type, field) = field_lookup
+                    .as_field_type(&field)
+                    .ok_or_else(|| QueryError::NonIndexedField(field))?;
+
+                // a synonym of more than one word only matches where they appear together
+                let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+                for text in std::iter::once(value.clone()).chain(synonyms) {
+                    if let Some(query) =
+                        analyzed_text_query(searcher, field, &field_type, &text, path.as_deref())?
+                    {
+                        clauses.push((Occur::Should, query));
+                    }
+                }
+
+                if clauses.is_empty() {
+                    return Ok(no_terms_query(&value)?);
+                }
+                Ok(Box::new(BooleanQuery::new(clauses)))
+            }
             Self::Term {
                 field,
                 value,
@@ -1272,6 +2553,10 @@ impl SearchQueryInput {
                     let (field_type, field) = field_lookup
                         .as_field_type(&field)
                         .ok_or_else(|| QueryError::NonIndexedField(field))?;
+                    if coerce_number(&value, &field_type).is_none() {
+                        // like `1.5` for an integer field, which no document can hold
+                        return Ok(Box::new(EmptyQuery));
+                    }
                     let term =
                         value_to_term(field, &value, &field_type, path.as_deref(), is_datetime)?;
 
@@ -1297,6 +2582,9 @@ impl SearchQueryInput {
                     let (field_type, field) = field_lookup
                         .as_field_type(&field_name)
                         .ok_or_else(|| QueryError::NonIndexedField(field_name))?;
+                    if coerce_number(&field_value, &field_type).is_none() {
+                        continue;
+                    }
                     terms.push(value_to_term(
                         field,
                         &field_value,
@@ -1308,6 +2596,36 @@ impl SearchQueryInput {
 
                 Ok(Box::new(TermSetQuery::new(terms)))
             }
+            Self::Tiered {
+                exact,
+                inexact,
+                recency_field,
+                half_life,
+            } => {
+                let recency = match (recency_field, half_life) {
+                    (Some(field), Some(half_life)) => {
+                        // ages are read from the field's column
+                        match field_lookup.as_field_type(&field) {
+                            Some((FieldType::Date(options), _)) if options.is_fast() => {}
+                            _ => return Err(Box::new(QueryError::RecencyField(field))),
+                        }
+                        let now = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .expect("the system clock should be after the unix epoch");
+                        Some(Recency {
+                            field,
+                            half_life,
+                            now: tantivy::DateTime::from_timestamp_micros(now.as_micros() as i64),
+                        })
+                    }
+                    _ => None,
+                };
+                Ok(Box::new(TieredQuery::new(
+                    exact.into_tantivy_query(field_lookup, parser, searcher)?,
+                    inexact.into_tantivy_query(field_lookup, parser, searcher)?,
+                    recency,
+                )))
+            }
             Self::WithIndex { query, .. } => {
                 query.into_tantivy_query(field_lookup, parser, searcher)
             }
@@ -1396,6 +2714,7 @@ pub fn value_to_term(
         }
     }
 
+    let value = &coerce_number(value, field_type).ok_or(QueryError::FieldTypeMismatch)?;
     Ok(match value {
         OwnedValue::Str(text) => Term::from_field_text(field, text),
         OwnedValue::PreTokStr(_) => panic!("pre-tokenized text cannot be converted to term"),
@@ -1422,6 +2741,72 @@ pub fn value_to_term(
     })
 }
 
+/// `value` as the kind of number `field_type` holds, so that an integer can be looked for in a
+/// float field and the other way around, or [`None`] if the field can't hold it exactly, like
+/// `1.5` in an integer field.  Anything other than a number, or for a field that doesn't hold
+/// numbers, is left as it is
+fn coerce_number(value: &OwnedValue, field_type: &FieldType) -> Option<OwnedValue> {
+    match (value, field_type) {
+        (OwnedValue::U64(u64), FieldType::I64(_)) => i64::try_from(*u64).ok().map(OwnedValue::I64),
+        (OwnedValue::U64(u64), FieldType::F64(_)) => Some(OwnedValue::F64(*u64 as f64)),
+        (OwnedValue::I64(i64), FieldType::U64(_)) => u64::try_from(*i64).ok().map(OwnedValue::U64),
+        (OwnedValue::I64(i64), FieldType::F64(_)) => Some(OwnedValue::F64(*i64 as f64)),
+        // NaN and the infinities have no fractional part of zero either
+        (OwnedValue::F64(f64), FieldType::I64(_)) => {
+            (f64.fract() == 0.0 && *f64 >= i64::MIN as f64 && *f64 < i64::MAX as f64)
+                .then_some(OwnedValue::I64(*f64 as i64))
+        }
+        (OwnedValue::F64(f64), FieldType::U64(_)) => {
+            (f64.fract() == 0.0 && *f64 >= 0.0 && *f64 < u64::MAX as f64)
+                .then_some(OwnedValue::U64(*f64 as u64))
+        }
+        _ => Some(value.clone()),
+    }
+}
+
+/// A range bound on a number, as a bound on the kind of number `field_type` holds.  A float that
+/// an integer field can't hold exactly is rounded away from the range, and then excluded, so that
+/// `> 1.5` and `>= 1.5` both become `> 1`, and `< 1.5` and `<= 1.5` both become `< 2`.
+///
+/// A number past either end of what the field can hold bounds nothing on the side of the range
+/// it's on, and leaves nothing in it on the other, in which case this is [`None`].  As in Postgres,
+/// NaN is past the top end
+fn coerce_bound(
+    bound: Bound<OwnedValue>,
+    field_type: &FieldType,
+    is_lower: bool,
+) -> Option<Bound<OwnedValue>> {
+    let (value, included) = match bound {
+        Bound::Included(value) => (value, true),
+        Bound::Excluded(value) => (value, false),
+        Bound::Unbounded => return Some(Bound::Unbounded),
+    };
+    let rounded = match coerce_number(&value, field_type) {
+        Some(value) if included => return Some(Bound::Included(value)),
+        Some(value) => return Some(Bound::Excluded(value)),
+        None => match value {
+            OwnedValue::F64(f64) => coerce_number(
+                &OwnedValue::F64(if is_lower { f64.floor() } else { f64.ceil() }),
+                field_type,
+            ),
+            _ => None,
+        },
+    };
+    match rounded {
+        Some(rounded) => Some(Bound::Excluded(rounded)),
+        None => {
+            // only integer fields can't hold a number, and the only integers they can't hold
+            // are the negative ones a u64 field can't, and the ones too large for an i64 field
+            let is_above = match value {
+                OwnedValue::F64(f64) => f64.is_nan() || f64 > 0.0,
+                OwnedValue::I64(_) => false,
+                _ => true,
+            };
+            (is_lower != is_above).then_some(Bound::Unbounded)
+        }
+    }
+}
+
 struct TantivyDateTime(pub tantivy::DateTime);
 impl TryFrom<&str> for TantivyDateTime {
     type Error = QueryError;
@@ -1438,6 +2823,201 @@ impl TryFrom<&str> for TantivyDateTime {
     }
 }
 
+/// A query for `text`, analyzed the same way as `field`: a term query if it analyzes into a single
+/// token, a phrase query if it analyzes into more, or [`None`] if it doesn't analyze into any
+fn analyzed_text_query(
+    searcher: &Searcher,
+    field: Field,
+    field_type: &FieldType,
+    text: &str,
+    path: Option<&str>,
+) -> Result<Option<Box<dyn Query>>, Box<dyn std::error::Error>> {
+    let mut analyzer = searcher.index().tokenizer_for_field(field)?;
+    let mut stream = analyzer.token_stream(text);
+    let mut terms = Vec::new();
+    while stream.advance() {
+        let token = stream.token().text.clone();
+        terms.push(value_to_term(
+            field,
+            &OwnedValue::Str(token),
+            field_type,
+            path,
+            false,
+        )?);
+    }
+
+    Ok(match terms.len() {
+        0 => None,
+        1 => Some(Box::new(TermQuery::new(
+            terms.pop().unwrap(),
+            IndexRecordOption::WithFreqsAndPositions.into(),
+        ))),
+        _ => Some(Box::new(PhraseQuery::new(terms))),
+    })
+}
+
+/// `text`, with the control characters in it, and the replacement characters that stand in for
+/// invalid unicode, either replaced by spaces or raised as an error, as
+/// `paradedb.on_invalid_query_text` says.  Tabs and line breaks are whitespace, and kept as is
+fn checked_query_text(text: String) -> Result<String, QueryError> {
+    let is_invalid = |c: char| {
+        (c.is_control() && !matches!(c, '\t' | '\n' | '\r')) || c == char::REPLACEMENT_CHARACTER
+    };
+    let Some(position) = text.chars().position(is_invalid) else {
+        return Ok(text);
+    };
+
+    match gucs::on_invalid_query_text() {
+        OnInvalidQueryText::Sanitize => Ok(text
+            .chars()
+            .map(|c| if is_invalid(c) { ' ' } else { c })
+            .collect()),
+        OnInvalidQueryText::Error => Err(QueryError::InvalidQueryText(
+            text.escape_debug().to_string(),
+            position + 1,
+        )),
+    }
+}
+
+/// Is `query` what the query parser makes of a query string that analyzes into no terms, which is
+/// an empty query or a boolean query of nothing but those?
+fn has_no_terms(query: &dyn Query) -> bool {
+    if query.is::<EmptyQuery>() {
+        return true;
+    }
+    match query.downcast_ref::<BooleanQuery>() {
+        Some(boolean) => boolean
+            .clauses()
+            .iter()
+            .all(|(_, clause)| has_no_terms(clause.as_ref())),
+        None => false,
+    }
+}
+
+/// The query for `text`, which analyzes into no terms, as `paradedb.empty_query_mode` says
+fn no_terms_query(text: &str) -> Result<Box<dyn Query>, QueryError> {
+    match gucs::empty_query_mode() {
+        EmptyQueryMode::MatchNone => Ok(Box::new(EmptyQuery)),
+        EmptyQueryMode::MatchAll => Ok(Box::new(AllQuery)),
+        EmptyQueryMode::Error => Err(QueryError::NoTerms(text.into())),
+    }
+}
+
+/// Rewrite the required clauses of `query`, and of the boolean queries nested in it, that only
+/// search one and the same field, like those of `field:a AND field:b`, into a single required
+/// clause that matches when any of them does, for `paradedb.same_field_clauses = 'any'`.  Clauses
+/// that search several fields, or none, like a range, stay as they are
+fn any_same_field_clause(query: Box<dyn Query>) -> Box<dyn Query> {
+    let Some(boolean) = query.downcast_ref::<BooleanQuery>() else {
+        return query;
+    };
+    let clauses = boolean
+        .clauses()
+        .iter()
+        .map(|(occur, clause)| (*occur, any_same_field_clause(clause.box_clone())))
+        .collect::<Vec<_>>();
+
+    let only_field = |query: &dyn Query| {
+        let mut fields = HashSet::new();
+        query.query_terms(&mut |term, _| {
+            fields.insert(term.field());
+        });
+        match fields.len() {
+            1 => fields.into_iter().next(),
+            _ => None,
+        }
+    };
+
+    // the required clauses of each field, in the order the fields first appear
+    let mut by_field: Vec<(Field, Vec<Box<dyn Query>>)> = vec![];
+    let mut others = vec![];
+    for (occur, clause) in clauses {
+        match (occur, only_field(clause.as_ref())) {
+            (Occur::Must, Some(field)) => match by_field.iter_mut().find(|(f, _)| *f == field) {
+                Some((_, same_field)) => same_field.push(clause),
+                None => by_field.push((field, vec![clause])),
+            },
+            _ => others.push((occur, clause)),
+        }
+    }
+
+    let mut combined = by_field
+        .into_iter()
+        .map(|(_, mut same_field)| {
+            let clause: Box<dyn Query> = if same_field.len() == 1 {
+                same_field.pop().unwrap()
+            } else {
+                Box::new(BooleanQuery::new(
+                    same_field
+                        .into_iter()
+                        .map(|clause| (Occur::Should, clause))
+                        .collect(),
+                ))
+            };
+            (Occur::Must, clause)
+        })
+        .collect::<Vec<_>>();
+    combined.extend(others);
+    Box::new(BooleanQuery::new(combined))
+}
+
+/// Hold a parsed query string to `paradedb.max_query_terms`, by either raising an error or, if
+/// `paradedb.max_query_terms_action` is `truncate`, keeping the clauses of the query in order
+/// until the next one would take it over the cap
+fn limit_query_terms(
+    query: Box<dyn Query>,
+    query_string: String,
+) -> Result<Box<dyn Query>, QueryError> {
+    let Some(max_terms) = gucs::max_query_terms() else {
+        return Ok(query);
+    };
+    let count_terms = |query: &dyn Query| {
+        let mut nterms = 0;
+        query.query_terms(&mut |_, _| nterms += 1);
+        nterms
+    };
+
+    let nterms = count_terms(query.as_ref());
+    if nterms <= max_terms {
+        return Ok(query);
+    }
+    let too_many_terms = || QueryError::TooManyTerms(query_string.clone(), nterms, max_terms);
+    if gucs::max_query_terms_action() == MaxQueryTermsAction::Error {
+        return Err(too_many_terms());
+    }
+
+    // only a query string of several clauses can be cut down to fewer of them, which may be
+    // nested in a single clause, like those grouped by `field:(...)`
+    let mut boolean = query
+        .downcast_ref::<BooleanQuery>()
+        .ok_or_else(too_many_terms)?;
+    while let [(Occur::Should | Occur::Must, clause)] = boolean.clauses() {
+        match clause.downcast_ref::<BooleanQuery>() {
+            Some(nested) => boolean = nested,
+            None => break,
+        }
+    }
+    let mut kept = vec![];
+    let mut kept_terms = 0;
+    for (occur, clause) in boolean.clauses() {
+        let clause_terms = count_terms(clause.as_ref());
+        if kept_terms + clause_terms > max_terms {
+            break;
+        }
+        kept_terms += clause_terms;
+        kept.push((*occur, clause.box_clone()));
+    }
+    if kept.is_empty() {
+        return Err(too_many_terms());
+    }
+
+    pgrx::warning!(
+        "query string has {nterms} terms, more than paradedb.max_query_terms allows, so only its first {} clauses, with {kept_terms} terms, are searched",
+        kept.len()
+    );
+    Ok(Box::new(BooleanQuery::new(kept)))
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Error)]
 enum QueryError {
@@ -1458,4 +3038,22 @@ enum QueryError {
            make sure to use column:term pairs, and to capitalize AND/OR."#
     )]
     ParseError(#[source] tantivy::query::QueryParserError, String),
+    #[error("query string '{0}' has {1} terms, but paradedb.max_query_terms only allows {2}")]
+    TooManyTerms(String, usize, usize),
+    #[error("{0} expands into more terms than paradedb.max_term_expansions allows, which is {1}")]
+    TooManyExpansions(String, usize),
+    #[error("field '{0}' can't be part of a cross-field phrase, which only searches stored text fields that all use the same tokenizer")]
+    CrossFieldPhraseField(String),
+    #[error("field '{0}' can't boost documents by category, which needs a text fast field")]
+    CategoryBoostField(String),
+    #[error("'{1}' isn't a date that field '{0}' can be searched for, which must look like 2024-01-01, 2024-01-01T12:00:00, or 2024-01-01T12:00:00Z")]
+    DateFormat(String, String),
+    #[error("'{0}' has no terms to search for once it's analyzed, and paradedb.empty_query_mode is 'error'")]
+    NoTerms(String),
+    #[error("query text '{0}' has a control character or invalid unicode at character {1}, and paradedb.on_invalid_query_text is 'error'")]
+    InvalidQueryText(String, usize),
+    #[error("field '{0}' can't decay scores by recency, which needs a datetime fast field")]
+    RecencyField(String),
+    #[error("'{0}' isn't a language that can be stemmed, which must be a code like 'fr' or a name like 'French'")]
+    UnknownLanguage(String),
 }
@@ -0,0 +1,130 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::{DocId, DocSet, Score, SegmentReader, Term};
+
+/// A [`Query`] that matches the same documents as the query it wraps, but rounds their scores
+/// to a fixed number of decimal places.
+///
+/// Scores computed on different platforms can differ in their last few bits, and rounding them
+/// means documents sort and compare the same everywhere.
+///
+/// They're rounded rather than scaled up to whole numbers, as both tantivy and `paradedb.score()`
+/// hold them as `f32`, which can't hold a score of more than about 16 scaled by a million
+/// exactly.  Rounding is done in `f64`, and converting back rounds to the nearest `f32`, both of
+/// which IEEE 754 defines exactly, so a rounded score is the same on every platform.
+#[derive(Debug)]
+pub struct QuantizedScoreQuery {
+    query: Box<dyn Query>,
+    scale: f64,
+}
+
+impl QuantizedScoreQuery {
+    pub fn new(query: Box<dyn Query>, decimal_places: u32) -> Self {
+        Self {
+            query,
+            scale: 10f64.powi(decimal_places as i32),
+        }
+    }
+
+    /// The query whose scores this rounds
+    pub fn query(&self) -> &dyn Query {
+        self.query.as_ref()
+    }
+}
+
+impl Clone for QuantizedScoreQuery {
+    fn clone(&self) -> Self {
+        Self {
+            query: self.query.box_clone(),
+            scale: self.scale,
+        }
+    }
+}
+
+impl Query for QuantizedScoreQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(QuantizedScoreWeight {
+            weight: self.query.weight(enable_scoring)?,
+            scale: self.scale,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+    }
+}
+
+/// Round `score` to the nearest multiple of `1 / scale`
+#[inline]
+fn quantize(score: Score, scale: f64) -> Score {
+    ((score as f64 * scale).round() / scale) as Score
+}
+
+struct QuantizedScoreWeight {
+    weight: Box<dyn Weight>,
+    scale: f64,
+}
+
+impl Weight for QuantizedScoreWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        Ok(Box::new(QuantizedScoreScorer {
+            scorer: self.weight.scorer(reader, boost)?,
+            scale: self.scale,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let explanation = self.weight.explain(reader, doc)?;
+        let mut quantized = Explanation::new(
+            "Quantized, rounded to a fixed number of decimal places",
+            quantize(explanation.value(), self.scale),
+        );
+        quantized.add_detail(explanation);
+        Ok(quantized)
+    }
+}
+
+struct QuantizedScoreScorer {
+    scorer: Box<dyn Scorer>,
+    scale: f64,
+}
+
+impl DocSet for QuantizedScoreScorer {
+    fn advance(&mut self) -> DocId {
+        self.scorer.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.scorer.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for QuantizedScoreScorer {
+    fn score(&mut self) -> Score {
+        quantize(self.scorer.score(), self.scale)
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::fieldnorm::FieldNormReader;
+use tantivy::postings::{Postings, SegmentPostings};
+use tantivy::query::{Bm25StatisticsProvider, EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::schema::IndexRecordOption;
+use tantivy::{DocId, DocSet, Score, SegmentReader, Term};
+
+/// The model used to score the documents that match a query
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum Similarity {
+    /// Okapi BM25, with tunable term frequency saturation (`k1`) and length normalization (`b`)
+    Bm25 { k1: f32, b: f32 },
+    /// Classic TF-IDF, as in Lucene's `ClassicSimilarity`
+    TfIdf,
+    /// Every matching document scores `1.0`
+    Boolean,
+}
+
+impl Similarity {
+    /// tantivy's own BM25 parameters
+    pub const DEFAULT_K1: f32 = 1.2;
+    pub const DEFAULT_B: f32 = 0.75;
+
+    fn idf(&self, doc_freq: u64, num_docs: u64) -> Score {
+        let doc_freq = doc_freq as f32;
+        let num_docs = num_docs as f32;
+        match self {
+            Similarity::Bm25 { .. } => (1.0 + (num_docs - doc_freq + 0.5) / (doc_freq + 0.5)).ln(),
+            Similarity::TfIdf => 1.0 + (num_docs / (doc_freq + 1.0)).ln(),
+            Similarity::Boolean => 1.0,
+        }
+    }
+
+    fn term_score(
+        &self,
+        idf: Score,
+        term_freq: u32,
+        fieldnorm: u32,
+        avg_fieldnorm: Score,
+    ) -> Score {
+        let term_freq = term_freq as f32;
+        let fieldnorm = fieldnorm.max(1) as f32;
+        match self {
+            Similarity::Bm25 { k1, b } => {
+                let norm = k1 * (1.0 - b + b * fieldnorm / avg_fieldnorm);
+                idf * (term_freq * (k1 + 1.0)) / (term_freq + norm)
+            }
+            Similarity::TfIdf => term_freq.sqrt() * idf * idf / fieldnorm.sqrt(),
+            Similarity::Boolean => 1.0,
+        }
+    }
+}
+
+impl std::str::FromStr for Similarity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "bm25" => Ok(Similarity::Bm25 {
+                k1: Self::DEFAULT_K1,
+                b: Self::DEFAULT_B,
+            }),
+            "tfidf" | "tf-idf" => Ok(Similarity::TfIdf),
+            "boolean" => Ok(Similarity::Boolean),
+            other => Err(format!(
+                "similarity must be one of 'bm25', 'tfidf', or 'boolean', got '{other}'"
+            )),
+        }
+    }
+}
+
+/// A [`Query`] that matches the same documents as its inner query, but scores them according to
+/// a [`Similarity`] rather than with tantivy's BM25.
+///
+/// Scores are computed from the terms the inner query reports through [`Query::query_terms`], so
+/// matches that don't come from terms, such as ranges, only contribute a score under
+/// [`Similarity::Boolean`].
+#[derive(Debug)]
+pub struct SimilarityQuery {
+    query: Box<dyn Query>,
+    similarity: Similarity,
+}
+
+impl SimilarityQuery {
+    pub fn new(query: Box<dyn Query>, similarity: Similarity) -> Self {
+        Self { query, similarity }
+    }
+}
+
+impl Clone for SimilarityQuery {
+    fn clone(&self) -> Self {
+        Self {
+            query: self.query.box_clone(),
+            similarity: self.similarity,
+        }
+    }
+}
+
+impl Query for SimilarityQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let mut terms = Vec::<Term>::new();
+        if self.similarity != Similarity::Boolean {
+            self.query.query_terms(&mut |term, _| {
+                if !terms.contains(term) {
+                    terms.push(term.clone());
+                }
+            });
+        }
+
+        let mut term_stats = Vec::with_capacity(terms.len());
+        if let EnableScoring::Enabled {
+            statistics_provider,
+            ..
+        } = enable_scoring
+        {
+            let num_docs = statistics_provider.total_num_docs()?.max(1);
+            for term in terms {
+                let doc_freq = statistics_provider.doc_freq(&term)?;
+                let num_tokens = statistics_provider.total_num_tokens(term.field())?;
+                term_stats.push(TermStats {
+                    idf: self.similarity.idf(doc_freq, num_docs),
+                    avg_fieldnorm: (num_tokens as f32 / num_docs as f32).max(1.0),
+                    term,
+                });
+            }
+        }
+
+        Ok(Box::new(SimilarityWeight {
+            weight: self.query.weight(enable_scoring)?,
+            similarity: self.similarity,
+            term_stats,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+    }
+}
+
+struct TermStats {
+    term: Term,
+    idf: Score,
+    avg_fieldnorm: Score,
+}
+
+struct SimilarityWeight {
+    weight: Box<dyn Weight>,
+    similarity: Similarity,
+    term_stats: Vec<TermStats>,
+}
+
+impl Weight for SimilarityWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let mut terms = Vec::with_capacity(self.term_stats.len());
+        for stats in &self.term_stats {
+            let Some(postings) = reader
+                .inverted_index(stats.term.field())?
+                .read_postings(&stats.term, IndexRecordOption::WithFreqs)?
+            else {
+                // the term isn't in this segment
+                continue;
+            };
+            let fieldnorms = reader
+                .get_fieldnorms_reader(stats.term.field())
+                .unwrap_or_else(|_| FieldNormReader::constant(reader.max_doc(), 1));
+
+            terms.push(SimilarityTerm {
+                postings,
+                fieldnorms,
+                idf: stats.idf,
+                avg_fieldnorm: stats.avg_fieldnorm,
+            });
+        }
+
+        Ok(Box::new(SimilarityScorer {
+            scorer: self.weight.scorer(reader, 1.0)?,
+            similarity: self.similarity,
+            terms,
+            boost,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(tantivy::TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+
+        Ok(Explanation::new(
+            format!("{:?} similarity", self.similarity),
+            scorer.score(),
+        ))
+    }
+}
+
+struct SimilarityTerm {
+    postings: SegmentPostings,
+    fieldnorms: FieldNormReader,
+    idf: Score,
+    avg_fieldnorm: Score,
+}
+
+struct SimilarityScorer {
+    scorer: Box<dyn Scorer>,
+    similarity: Similarity,
+    terms: Vec<SimilarityTerm>,
+    boost: Score,
+}
+
+impl DocSet for SimilarityScorer {
+    fn advance(&mut self) -> DocId {
+        self.scorer.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.scorer.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for SimilarityScorer {
+    fn score(&mut self) -> Score {
+        if self.similarity == Similarity::Boolean {
+            return self.boost;
+        }
+
+        let doc = self.scorer.doc();
+        let mut score = 0.0;
+        for term in &mut self.terms {
+            // the postings only ever move forward, alongside the inner scorer
+            if term.postings.doc() < doc {
+                term.postings.seek(doc);
+            }
+
+            if term.postings.doc() == doc {
+                score += self.similarity.term_score(
+                    term.idf,
+                    term.postings.term_freq(),
+                    term.fieldnorms.fieldnorm(doc),
+                    term.avg_fieldnorm,
+                );
+            }
+        }
+        score * self.boost
+    }
+}
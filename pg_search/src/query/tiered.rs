@@ -0,0 +1,212 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::columnar::Column;
+use tantivy::query::{BooleanQuery, EnableScoring, Explanation, Occur, Query, Scorer, Weight};
+use tantivy::{DateTime, DocId, DocSet, Score, SegmentReader, Term};
+
+/// How the scores of a [`TieredQuery`]'s documents decay with the age of their value of the date
+/// fast field `field`
+#[derive(Debug, Clone)]
+pub struct Recency {
+    pub field: String,
+    /// How old a document is when its score has halved, in seconds
+    pub half_life: f64,
+    /// The time documents' ages are measured from
+    pub now: DateTime,
+}
+
+/// A [`Query`] that matches the documents either its `exact` or its `inexact` query matches, and
+/// scores every document `exact` matches above every document only `inexact` matches.
+///
+/// Within a tier, a document's score is that of the query it matches, decayed by its [`Recency`]
+/// if there is one.  That score is squashed into `[0, 1)` and added to the tier, which is `1` for
+/// the exact matches and `0` for the others, so that no score within a tier can reach the next.
+#[derive(Debug)]
+pub struct TieredQuery {
+    exact: Box<dyn Query>,
+    inexact: Box<dyn Query>,
+    recency: Option<Recency>,
+}
+
+impl TieredQuery {
+    pub fn new(exact: Box<dyn Query>, inexact: Box<dyn Query>, recency: Option<Recency>) -> Self {
+        Self {
+            exact,
+            inexact,
+            recency,
+        }
+    }
+}
+
+impl Clone for TieredQuery {
+    fn clone(&self) -> Self {
+        Self {
+            exact: self.exact.box_clone(),
+            inexact: self.inexact.box_clone(),
+            recency: self.recency.clone(),
+        }
+    }
+}
+
+impl Query for TieredQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        // the exact query is searched for twice: as part of the union that finds every match,
+        // and on its own to tell which of them are exact
+        let matching = BooleanQuery::new(vec![
+            (Occur::Should, self.exact.box_clone()),
+            (Occur::Should, self.inexact.box_clone()),
+        ]);
+        Ok(Box::new(TieredWeight {
+            matching: matching.weight(enable_scoring)?,
+            exact: self.exact.weight(enable_scoring)?,
+            inexact: self.inexact.weight(enable_scoring)?,
+            recency: self.recency.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.exact.query_terms(visitor);
+        self.inexact.query_terms(visitor);
+    }
+}
+
+struct TieredWeight {
+    matching: Box<dyn Weight>,
+    exact: Box<dyn Weight>,
+    inexact: Box<dyn Weight>,
+    recency: Option<Recency>,
+}
+
+impl TieredWeight {
+    /// The segment's column of the recency field, if the query decays scores and the segment has
+    /// such a column
+    fn recency_column(&self, reader: &SegmentReader) -> Option<Column<DateTime>> {
+        let recency = self.recency.as_ref()?;
+        reader.fast_fields().date(&recency.field).ok()
+    }
+}
+
+impl Weight for TieredWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        Ok(Box::new(TieredScorer {
+            matching: self.matching.scorer(reader, boost)?,
+            exact: self.exact.scorer(reader, boost)?,
+            inexact: self.inexact.scorer(reader, boost)?,
+            recency: self
+                .recency
+                .as_ref()
+                .zip(self.recency_column(reader))
+                .map(|(recency, column)| (recency.clone(), column)),
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut exact = self.exact.scorer(reader, 1.0)?;
+        let (tier, explanation) = if exact.seek(doc) == doc {
+            (1.0, self.exact.explain(reader, doc)?)
+        } else {
+            (0.0, self.inexact.explain(reader, doc)?)
+        };
+
+        let mut within_tier = explanation.value();
+        let mut decayed = None;
+        if let Some((recency, column)) = self.recency.as_ref().zip(self.recency_column(reader)) {
+            let decay = recency_decay(recency, &column, doc);
+            within_tier *= decay;
+            decayed = Some(decay);
+        }
+
+        let mut tiered = Explanation::new(
+            "Tiered, the tier plus the score within it, squashed by score / (1 + score)",
+            tiered_score(tier, within_tier),
+        );
+        tiered.add_const("tier", tier);
+        tiered.add_detail(explanation);
+        if let Some(decay) = decayed {
+            tiered.add_const("recency_decay", decay);
+        }
+        Ok(tiered)
+    }
+}
+
+/// The factor the score of `doc` decays by with its age: `0.5` raised to its age in half-lives.
+/// Documents without a value, or from the future, don't decay
+#[inline]
+fn recency_decay(recency: &Recency, column: &Column<DateTime>, doc: DocId) -> Score {
+    let Some(value) = column.first(doc) else {
+        return 1.0;
+    };
+    let age = (recency.now.into_timestamp_micros() - value.into_timestamp_micros()).max(0) as f64
+        / 1_000_000.0;
+    0.5_f64.powf(age / recency.half_life) as Score
+}
+
+#[inline]
+fn tiered_score(tier: Score, within_tier: Score) -> Score {
+    let within_tier = within_tier.max(0.0);
+    tier + within_tier / (1.0 + within_tier)
+}
+
+struct TieredScorer {
+    matching: Box<dyn Scorer>,
+    exact: Box<dyn Scorer>,
+    inexact: Box<dyn Scorer>,
+    recency: Option<(Recency, Column<DateTime>)>,
+}
+
+impl DocSet for TieredScorer {
+    fn advance(&mut self) -> DocId {
+        self.matching.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.matching.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.matching.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.matching.size_hint()
+    }
+}
+
+impl Scorer for TieredScorer {
+    fn score(&mut self) -> Score {
+        let doc = self.matching.doc();
+
+        // the exact and inexact scorers only ever move forward, alongside the matching scorer
+        if self.exact.doc() < doc {
+            self.exact.seek(doc);
+        }
+        let (tier, mut within_tier) = if self.exact.doc() == doc {
+            (1.0, self.exact.score())
+        } else {
+            if self.inexact.doc() < doc {
+                self.inexact.seek(doc);
+            }
+            (0.0, self.inexact.score())
+        };
+
+        if let Some((recency, column)) = &self.recency {
+            within_tier *= recency_decay(recency, column, doc);
+        }
+        tiered_score(tier, within_tier)
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use super::QueryError;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use tantivy::schema::FieldType;
+
+/// Rewrite the values a query string searches date and integer fields for into the forms the
+/// query parser expects of them, which is how they were indexed.
+///
+/// A date field is searched for with an RFC 3339 timestamp, so a date like `2024-01-01`, or a
+/// timestamp without a time zone like `2024-01-01T12:00:00`, which is how `date` and `timestamp`
+/// columns are indexed, is taken to be in UTC.  A value that looks like a date but isn't in one of
+/// these forms, like `01/02/2024`, which could be the 1st of February or the 2nd of January, is an
+/// error, unless `strict` is false, in which case it's left for the query parser to make of it.
+///
+/// An integer field is searched for with a whole number, so a float like `4.0` is rewritten to `4`.
+pub fn normalize(
+    query_string: &str,
+    field_type: &dyn Fn(&str) -> Option<FieldType>,
+    strict: bool,
+) -> Result<String, QueryError> {
+    let chars = query_string.chars().collect::<Vec<_>>();
+    let mut normalized = String::with_capacity(query_string.len());
+    // the fields of the `field:( ... )` groups we're inside of, innermost last
+    let mut groups: Vec<Option<String>> = vec![];
+    let mut at_term_start = true;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            normalized.push(c);
+            at_term_start = true;
+            i += 1;
+        } else if c == '(' {
+            normalized.push(c);
+            groups.push(None);
+            at_term_start = true;
+            i += 1;
+        } else if c == ')' {
+            normalized.push(c);
+            groups.pop();
+            at_term_start = false;
+            i += 1;
+        } else if at_term_start && (c == '+' || c == '-') {
+            normalized.push(c);
+            i += 1;
+        } else if at_term_start {
+            let name_end = (i..chars.len())
+                .find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '_' || chars[j] == '.'))
+                .unwrap_or(chars.len());
+            let group_field = groups.iter().rev().flatten().next().cloned();
+            if name_end > i && chars.get(name_end) == Some(&':') {
+                let field = chars[i..name_end].iter().collect::<String>();
+                normalized.push_str(&field);
+                normalized.push(':');
+                i = name_end + 1;
+                if chars.get(i) == Some(&'(') {
+                    normalized.push('(');
+                    groups.push(Some(field));
+                    i += 1;
+                    continue;
+                }
+                i = value(&chars, i, &field, field_type, strict, &mut normalized)?;
+            } else if let Some(field) = group_field {
+                i = value(&chars, i, &field, field_type, strict, &mut normalized)?;
+            } else {
+                i = copy_word(&chars, i, &mut normalized);
+            }
+            at_term_start = false;
+        } else {
+            i = copy_word(&chars, i, &mut normalized);
+        }
+    }
+
+    Ok(normalized)
+}
+
+/// Copy the value `field` is searched for, starting at `start`, into `normalized`, rewritten if
+/// need be, and return where it ends
+fn value(
+    chars: &[char],
+    start: usize,
+    field: &str,
+    field_type: &dyn Fn(&str) -> Option<FieldType>,
+    strict: bool,
+    normalized: &mut String,
+) -> Result<usize, QueryError> {
+    let Some(field_type) = field_type(field) else {
+        return Ok(copy_word(chars, start, normalized));
+    };
+    let rewrite = |text: &str| rewrite(field, &field_type, text, strict);
+
+    match chars.get(start) {
+        Some('"') => {
+            let end = quoted_end(chars, start);
+            let text_end = if end > start + 1 && chars[end - 1] == '"' {
+                end - 1
+            } else {
+                end
+            };
+            let text = chars[start + 1..text_end].iter().collect::<String>();
+            match rewrite(&text)? {
+                Some(rewritten) => {
+                    normalized.push('"');
+                    normalized.push_str(&rewritten);
+                    normalized.push('"');
+                }
+                None => normalized.extend(&chars[start..end]),
+            }
+            Ok(end)
+        }
+        Some(open @ ('[' | '{')) => {
+            let Some(close) = (start..chars.len()).find(|&j| chars[j] == ']' || chars[j] == '}')
+            else {
+                return Ok(copy_word(chars, start, normalized));
+            };
+            let range = chars[start + 1..close].iter().collect::<String>();
+            normalized.push(*open);
+            for (n, bound) in range.split(" TO ").enumerate() {
+                if n > 0 {
+                    normalized.push_str(" TO ");
+                }
+                let trimmed = bound.trim();
+                match rewrite(trimmed.trim_matches('"'))? {
+                    Some(rewritten) => normalized.push_str(&bound.replacen(trimmed, &rewritten, 1)),
+                    None => normalized.push_str(bound),
+                }
+            }
+            normalized.push(chars[close]);
+            Ok(close + 1)
+        }
+        _ => {
+            let mut i = start;
+            for comparison in [">=", "<=", ">", "<"] {
+                if chars[i..].starts_with(&comparison.chars().collect::<Vec<_>>()) {
+                    normalized.push_str(comparison);
+                    i += comparison.len();
+                    break;
+                }
+            }
+            let end = word_end(chars, i);
+            // a boost, like the `^2` of `2024-01-01^2`, isn't part of the value
+            let value_end = (i..end).find(|&j| chars[j] == '^').unwrap_or(end);
+            let text = chars[i..value_end].iter().collect::<String>();
+            match rewrite(&text)? {
+                Some(rewritten) if matches!(field_type, FieldType::Date(_)) => {
+                    normalized.push('"');
+                    normalized.push_str(&rewritten);
+                    normalized.push('"');
+                }
+                Some(rewritten) => normalized.push_str(&rewritten),
+                None => normalized.push_str(&text),
+            }
+            normalized.extend(&chars[value_end..end]);
+            Ok(end)
+        }
+    }
+}
+
+/// What `text` should be rewritten to for the query parser to find it in `field`, or `None` if it
+/// should be left as it is
+fn rewrite(
+    field: &str,
+    field_type: &FieldType,
+    text: &str,
+    strict: bool,
+) -> Result<Option<String>, QueryError> {
+    match field_type {
+        FieldType::Date(_) => {
+            let looks_like_date = text.starts_with(|c: char| c.is_ascii_digit())
+                && text.contains(['-', '/', '.'])
+                && text
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-/.:+".contains(c));
+            if !looks_like_date {
+                return Ok(None);
+            }
+            match parse_datetime(text) {
+                Some(datetime) => Ok(Some(datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true))),
+                None if strict => Err(QueryError::DateFormat(field.into(), text.into())),
+                None => Ok(None),
+            }
+        }
+        FieldType::I64(_) | FieldType::U64(_) => match text.parse::<f64>() {
+            Ok(number) if text.contains('.') && number.fract() == 0.0 && number.is_finite() => {
+                Ok(Some(format!("{number:.0}")))
+            }
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// `text` as a point in time, if it's a date, a timestamp without a time zone, both of which are
+/// in UTC, or an RFC 3339 timestamp
+fn parse_datetime(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    // chrono accepts single digit months and days, which aren't what anyone indexes dates with
+    let is_iso_date = |date: &str| {
+        let bytes = date.as_bytes();
+        bytes.len() == 10 && bytes[4] == b'-' && bytes[7] == b'-'
+    };
+    if is_iso_date(text) {
+        return NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|datetime| datetime.and_utc());
+    }
+    if text.len() > 10 && is_iso_date(&text[..10]) {
+        for format in [
+            "%Y-%m-%dT%H:%M:%S%.f",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%dT%H:%M",
+        ] {
+            if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+                return Some(datetime.and_utc());
+            }
+        }
+    }
+    None
+}
+
+/// Where the quoted text starting at `start` ends, after its closing quote
+fn quoted_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Where the word starting at `start` ends, at the next whitespace or closing parenthesis
+fn word_end(chars: &[char], start: usize) -> usize {
+    (start..chars.len())
+        .find(|&j| chars[j].is_whitespace() || chars[j] == ')')
+        .unwrap_or(chars.len())
+}
+
+/// Copy the word, or quoted text, starting at `start` into `normalized` as it is, and return where
+/// it ends
+fn copy_word(chars: &[char], start: usize, normalized: &mut String) -> usize {
+    let end = if chars[start] == '"' {
+        quoted_end(chars, start)
+    } else {
+        word_end(chars, start).max(start + 1)
+    };
+    normalized.extend(&chars[start..end]);
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+    use tantivy::schema::{DateOptions, FieldType, NumericOptions, TextOptions};
+
+    fn field_type(name: &str) -> Option<FieldType> {
+        match name {
+            "created" => Some(FieldType::Date(DateOptions::default())),
+            "rating" => Some(FieldType::I64(NumericOptions::default())),
+            "description" => Some(FieldType::Str(TextOptions::default())),
+            _ => None,
+        }
+    }
+
+    fn normalized(query_string: &str) -> String {
+        normalize(query_string, &field_type, true).expect("query string should normalize")
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(
+            normalized("created:2024-01-01"),
+            r#"created:"2024-01-01T00:00:00Z""#
+        );
+        assert_eq!(
+            normalized("created:2024-01-01T12:30:00"),
+            r#"created:"2024-01-01T12:30:00Z""#
+        );
+        assert_eq!(
+            normalized("created:2024-01-01T12:30:00+02:00"),
+            r#"created:"2024-01-01T10:30:00Z""#
+        );
+        assert_eq!(
+            normalized("created:[2024-01-01 TO 2024-02-01}"),
+            "created:[2024-01-01T00:00:00Z TO 2024-02-01T00:00:00Z}"
+        );
+        assert_eq!(
+            normalized("created:>=2024-01-01 AND description:2024-01-01"),
+            r#"created:>="2024-01-01T00:00:00Z" AND description:2024-01-01"#
+        );
+        assert_eq!(
+            normalized("created:(2024-01-01 OR 2024-02-01)"),
+            r#"created:("2024-01-01T00:00:00Z" OR "2024-02-01T00:00:00Z")"#
+        );
+        assert_eq!(
+            normalized("created:[* TO 2024-01-01]"),
+            "created:[* TO 2024-01-01T00:00:00Z]"
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_dates() {
+        for query_string in [
+            "created:01/02/2024",
+            "created:2024-1-2",
+            "created:2024-13-01",
+        ] {
+            assert!(
+                normalize(query_string, &field_type, true).is_err(),
+                "{query_string}"
+            );
+            assert_eq!(
+                normalize(query_string, &field_type, false).unwrap(),
+                query_string
+            );
+        }
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(normalized("rating:4.0"), "rating:4");
+        assert_eq!(normalized("rating:4.5"), "rating:4.5");
+        assert_eq!(normalized("rating:[1.0 TO 3]"), "rating:[1 TO 3]");
+        assert_eq!(normalized("+rating:-2.0^2"), "+rating:-2^2");
+        assert_eq!(normalized("description:4.0"), "description:4.0");
+    }
+}
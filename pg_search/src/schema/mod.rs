@@ -701,6 +701,81 @@ impl SearchIndexSchema {
             .is_some()
     }
 
+    /// Is the field a numeric or boolean fast field, whose values a [`SortExpression`] can read?
+    ///
+    /// [`SortExpression`]: crate::index::sort_expression::SortExpression
+    pub fn is_field_numeric_fast(&self, name: &str) -> bool {
+        matches!(
+            self.get_search_field(&SearchFieldName(name.to_string()))
+                .map(|search_field| &search_field.config),
+            Some(SearchFieldConfig::Numeric { fast: true, .. })
+                | Some(SearchFieldConfig::Boolean { fast: true, .. })
+        )
+    }
+
+    /// Is the field a text, numeric, boolean, or date fast field, whose values results can be
+    /// collapsed by?
+    pub fn is_field_fast(&self, name: &str) -> bool {
+        matches!(
+            self.get_search_field(&SearchFieldName(name.to_string()))
+                .map(|search_field| &search_field.config),
+            Some(SearchFieldConfig::Text { fast: true, .. })
+                | Some(SearchFieldConfig::Numeric { fast: true, .. })
+                | Some(SearchFieldConfig::Boolean { fast: true, .. })
+                | Some(SearchFieldConfig::Date { fast: true, .. })
+        )
+    }
+
+    /// Does the field index at most one term per value?  This is true of the scalar field
+    /// types and of text fields that aren't tokenized.
+    ///
+    /// Note that the Postgres column behind the field could still be an array, in which case a
+    /// document can have more than one value for the field.  That's for the caller to check.
+    pub fn is_field_single_term(&self, name: &str) -> bool {
+        let Some(search_field) = self.get_search_field(&SearchFieldName(name.to_string())) else {
+            return false;
+        };
+
+        matches!(
+            search_field.config,
+            SearchFieldConfig::Text {
+                tokenizer: SearchTokenizer::Raw(_),
+                ..
+            } | SearchFieldConfig::Numeric { .. }
+                | SearchFieldConfig::Boolean { .. }
+                | SearchFieldConfig::Date { .. }
+                | SearchFieldConfig::Ctid
+        )
+    }
+
+    /// If the field is an indexed text field whose terms are the column's whole values, changed
+    /// at most by lowercasing, returns the [`SearchNormalizer`] that describes that change.
+    pub fn keyword_field_normalizer(&self, name: &str) -> Option<SearchNormalizer> {
+        let search_field = self.get_search_field(&SearchFieldName(name.to_string()))?;
+
+        match &search_field.config {
+            SearchFieldConfig::Text {
+                indexed: true,
+                tokenizer,
+                ..
+            } => tokenizer.keyword_normalizer(),
+            _ => None,
+        }
+    }
+
+    /// Whether the field's tokenizer indexes every value, however long, rather than dropping
+    /// those past its `remove_long` limit.
+    pub fn keyword_field_keeps_long_values(&self, name: &str) -> bool {
+        let Some(search_field) = self.get_search_field(&SearchFieldName(name.to_string())) else {
+            return false;
+        };
+
+        match &search_field.config {
+            SearchFieldConfig::Text { tokenizer, .. } => tokenizer.keeps_long_values(),
+            _ => false,
+        }
+    }
+
     fn is_field_sortable(&self, name: &str, desired_normalizer: SearchNormalizer) -> Option<()> {
         let search_field = self.get_search_field(&SearchFieldName(name.to_string()))?;
 
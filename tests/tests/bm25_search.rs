@@ -27,7 +27,7 @@ use pretty_assertions::assert_eq;
 use rstest::*;
 use sqlx::{types::BigDecimal, PgConnection};
 use std::str::FromStr;
-use tantivy::Index;
+use tantivy::{Index, TantivyDocument};
 
 #[rstest]
 async fn basic_search_query(mut conn: PgConnection) -> Result<(), sqlx::Error> {
@@ -1233,3 +1233,99 @@ fn json_array_term(mut conn: PgConnection) {
         .fetch(&mut conn);
     assert_eq!(rows, vec![(1,)]);
 }
+
+#[rstest]
+fn debug_emit_duplicates(mut conn: PgConnection) -> Result<()> {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    // craft a duplicate of the row with id 1, by writing a second document for its ctid into a
+    // new segment behind Postgres' back
+    let (blockno, offno): (i64, i64) = r#"
+    SELECT (ctid::text::point)[0]::int8, (ctid::text::point)[1]::int8
+    FROM paradedb.bm25_search WHERE id = 1"#
+        .fetch_one(&mut conn);
+    let index_dir_path = pg_search_index_directory_path(&mut conn, "bm25_search_bm25_index");
+    let index = Index::open_in_dir(index_dir_path.join("tantivy"))?;
+    let ctid_field = index.schema().get_field("ctid")?;
+    let mut writer = index.writer::<TantivyDocument>(15_000_000)?;
+    let mut duplicate = TantivyDocument::new();
+    duplicate.add_u64(ctid_field, ((blockno as u64) << 16) | offno as u64);
+    writer.add_document(duplicate)?;
+    writer.commit()?;
+    writer.wait_merging_threads()?;
+
+    let query = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.all()
+    ORDER BY id"#;
+
+    // normally the duplicate is only emitted once
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows.len(), 41);
+    assert_eq!(rows.iter().filter(|(id,)| *id == 1).count(), 1);
+
+    // but when debugging, every hit is visible
+    "SET paradedb.debug_emit_duplicates = true".execute(&mut conn);
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows.len(), 42);
+    assert_eq!(rows.iter().filter(|(id,)| *id == 1).count(), 2);
+
+    "RESET paradedb.debug_emit_duplicates".execute(&mut conn);
+    Ok(())
+}
+
+#[rstest]
+fn scans_see_index_ddl_without_reconnecting(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE ddl_items (id SERIAL PRIMARY KEY, description TEXT, category TEXT);
+    INSERT INTO ddl_items (description, category) VALUES
+        ('red socks', 'clothing'),
+        ('blue shoes', 'footwear');
+
+    CALL paradedb.create_bm25(
+        index_name => 'ddl_items',
+        table_name => 'ddl_items',
+        key_field => 'id',
+        text_fields => paradedb.field('description')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT id FROM ddl_items WHERE description @@@ 'socks' ORDER BY id";
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    // a REINDEX writes the index to a new relfilenode, which the next scan opens
+    let before: (i64,) =
+        "SELECT pg_relation_filenode('ddl_items_bm25_index')::int8".fetch_one(&mut conn);
+    "INSERT INTO ddl_items (description, category) VALUES ('green socks', 'clothing')"
+        .execute(&mut conn);
+    "REINDEX INDEX ddl_items_bm25_index".execute(&mut conn);
+    let after: (i64,) =
+        "SELECT pg_relation_filenode('ddl_items_bm25_index')::int8".fetch_one(&mut conn);
+    assert_ne!(before, after);
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+
+    // an index recreated with other fields is the one the next scan searches
+    r#"
+    CALL paradedb.drop_bm25('ddl_items');
+    CALL paradedb.create_bm25(
+        index_name => 'ddl_items',
+        table_name => 'ddl_items',
+        key_field => 'id',
+        text_fields => paradedb.field('description') || paradedb.field('category')
+    );
+    "#
+    .execute(&mut conn);
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM ddl_items WHERE category @@@ 'clothing' ORDER BY id".fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+
+    // and renaming the index keeps it usable
+    "ALTER INDEX ddl_items_bm25_index RENAME TO ddl_items_renamed_idx".execute(&mut conn);
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+}
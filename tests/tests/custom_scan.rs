@@ -307,3 +307,1469 @@ fn join_issue_1776(mut conn: PgConnection) {
     assert_eq!(results[1], (6, "White jogging shoes".into(), "Alice Johnson".into(), 4.921624, 2.4849067));
     assert_eq!(results[2], (36,"White jogging shoes".into(), "Alice Johnson".into(), 4.921624, 2.4849067));
 }
+
+#[rstest]
+fn max_segments_limits_segments_searched(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+        CREATE TABLE segmented (id serial8 NOT NULL PRIMARY KEY, body text);
+        CALL paradedb.create_bm25(
+            index_name => 'segmented',
+            table_name => 'segmented',
+            key_field => 'id',
+            text_fields => paradedb.field('body')
+        );
+
+        SET paradedb.statement_parallelism = 1;
+        INSERT INTO segmented (body) SELECT 'sample' FROM generate_series(1, 10);
+        INSERT INTO segmented (body) SELECT 'sample' FROM generate_series(1, 10);
+        INSERT INTO segmented (body) SELECT 'sample' FROM generate_series(1, 10);
+    "#
+    .execute(&mut conn);
+
+    let all =
+        "SELECT id FROM segmented WHERE body @@@ 'sample' ORDER BY id".fetch::<(i64,)>(&mut conn);
+    assert_eq!(all.len(), 30);
+
+    "SET paradedb.max_segments = 1".execute(&mut conn);
+
+    let (plan,) = "EXPLAIN (FORMAT JSON) SELECT id FROM segmented WHERE body @@@ 'sample'"
+        .fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.pointer("/0/Plan").unwrap();
+    eprintln!("{plan:#?}");
+    assert_eq!(
+        plan.get("Custom Plan Provider"),
+        Some(&Value::String(String::from("ParadeDB Scan")))
+    );
+    assert_eq!(plan.get("Max Segments"), Some(&Value::from(1)));
+
+    // each INSERT statement above produced its own segment, so only one statement's rows come back
+    let some =
+        "SELECT id FROM segmented WHERE body @@@ 'sample' ORDER BY id".fetch::<(i64,)>(&mut conn);
+    assert_eq!(some.len(), 10);
+    assert_eq!(some.last().unwrap().0 - some.first().unwrap().0, 9);
+
+    "SET paradedb.max_segments = 0".execute(&mut conn);
+    let all =
+        "SELECT id FROM segmented WHERE body @@@ 'sample' ORDER BY id".fetch::<(i64,)>(&mut conn);
+    assert_eq!(all.len(), 30);
+}
+
+#[rstest]
+fn contradictory_quals_match_nothing(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    for query in [
+        // an empty set of terms
+        "SELECT id FROM paradedb.bm25_search WHERE id @@@ paradedb.term_set(ARRAY[]::searchqueryinput[])",
+        // a single-valued field can't equal two different values
+        "SELECT id FROM paradedb.bm25_search WHERE id @@@ paradedb.term('rating', 1) AND id @@@ paradedb.term('rating', 2)",
+    ] {
+        let (plan,) = format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+        let plan = plan.pointer("/0/Plan").unwrap();
+        eprintln!("{plan:#?}");
+        assert_eq!(
+            plan.get("Custom Plan Provider"),
+            Some(&Value::String(String::from("ParadeDB Scan")))
+        );
+        assert_eq!(plan.get("Matches Nothing"), Some(&Value::Bool(true)));
+        assert_eq!(plan.get("Total Cost"), Some(&Value::from(0.0)));
+        assert_eq!(plan.get("Actual Rows"), Some(&Value::from(0)));
+
+        let rows = query.fetch::<(i32,)>(&mut conn);
+        assert!(rows.is_empty());
+    }
+
+    // the same value twice isn't a contradiction
+    let query = "SELECT id FROM paradedb.bm25_search WHERE id @@@ paradedb.term('rating', 4) AND id @@@ paradedb.term('rating', 4)";
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(plan.pointer("/0/Plan/Matches Nothing"), None);
+    assert!(!query.fetch::<(i32,)>(&mut conn).is_empty());
+
+    // and neither are two different terms in a tokenized text field
+    let query = "SELECT id FROM paradedb.bm25_search WHERE id @@@ paradedb.term('description', 'running') AND id @@@ paradedb.term('description', 'shoes')";
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![(3,)]);
+}
+
+#[rstest]
+fn heap_prefetch_does_not_change_results(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE prefetch_test (id serial8 not null primary key, body text);
+    INSERT INTO prefetch_test (body)
+        SELECT CASE WHEN x % 3 = 0 THEN 'fizz' ELSE 'buzz' END || ' ' || repeat('padding ', 20)
+        FROM generate_series(1, 5000) x;
+    CALL paradedb.create_bm25(
+        index_name => 'prefetch_test',
+        table_name => 'prefetch_test',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+
+    -- leave some dead tuples behind so the top-n scan has to retry
+    DELETE FROM prefetch_test WHERE id % 7 = 0;
+    "#
+    .execute(&mut conn);
+
+    let queries = [
+        "SELECT id FROM prefetch_test WHERE body @@@ 'fizz' ORDER BY id",
+        "SELECT id FROM prefetch_test WHERE body @@@ 'fizz' ORDER BY paradedb.score(id) DESC, id LIMIT 100",
+        "SELECT id FROM prefetch_test WHERE body @@@ 'fizz OR buzz' ORDER BY id LIMIT 2500",
+    ];
+
+    for query in queries {
+        "SET effective_io_concurrency = 0".execute(&mut conn);
+        let expected = query.fetch::<(i64,)>(&mut conn);
+        assert!(!expected.is_empty());
+
+        for effective_io_concurrency in [1, 16, 256] {
+            format!("SET effective_io_concurrency = {effective_io_concurrency}").execute(&mut conn);
+            let actual = query.fetch::<(i64,)>(&mut conn);
+            assert_eq!(
+                actual, expected,
+                "effective_io_concurrency = {effective_io_concurrency}"
+            );
+        }
+    }
+}
+
+#[rstest]
+fn multiple_field_snippets_project(mut conn: PgConnection) {
+    use serde_json::{json, Value};
+
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let (id, snippets) =
+        "SELECT id, paradedb.snippets(id, ARRAY['description', 'category']) FROM paradedb.bm25_search WHERE description @@@ 'keyboard' OR category @@@ 'electronics' ORDER BY id LIMIT 1 OFFSET 1"
+            .fetch_one::<(i32, Value)>(&mut conn);
+    assert_eq!(id, 2);
+    assert_eq!(
+        snippets,
+        json!({
+            "description": "Plastic <b>Keyboard</b>",
+            "category": "<b>Electronics</b>",
+        })
+    );
+
+    // a field without any matching terms has no snippet
+    let (id, snippets) =
+        "SELECT id, paradedb.snippets(id, ARRAY['description', 'category']) FROM paradedb.bm25_search WHERE description @@@ 'keyboard' ORDER BY paradedb.score(id) DESC LIMIT 1"
+            .fetch_one::<(i32, Value)>(&mut conn);
+    assert_eq!(id, 2);
+    assert_eq!(
+        snippets,
+        json!({
+            "description": "Plastic <b>Keyboard</b>",
+            "category": null,
+        })
+    );
+
+    // and tags can be set for individual fields
+    let (id, snippets) =
+        r#"SELECT id, paradedb.snippets(id, ARRAY['description', 'category'], '<em>', '</em>', field_options => '{"category": {"start_tag": "[", "end_tag": "]"}}') FROM paradedb.bm25_search WHERE description @@@ 'keyboard' OR category @@@ 'electronics' ORDER BY id LIMIT 1 OFFSET 1"#
+            .fetch_one::<(i32, Value)>(&mut conn);
+    assert_eq!(id, 2);
+    assert_eq!(
+        snippets,
+        json!({
+            "description": "Plastic <em>Keyboard</em>",
+            "category": "[Electronics]",
+        })
+    );
+}
+
+#[rstest]
+fn regex_operators_push_down(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE parts (id serial PRIMARY KEY, body text, sku text, tag text);
+    INSERT INTO parts (body, sku, tag) VALUES
+        ('widget', 'AB-100', 'Blue'),
+        ('widget', 'AB-200', 'blue'),
+        ('widget', 'XY-100', 'Red'),
+        ('widget', NULL, 'Green'),
+        ('gadget', 'AB-300', 'Blue');
+    ALTER TABLE parts ADD COLUMN code text;
+    UPDATE parts SET code = sku;
+
+    CALL paradedb.create_bm25(
+        index_name => 'parts',
+        table_name => 'parts',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+            || paradedb.field('sku', tokenizer => paradedb.tokenizer('raw', lowercase => false, remove_long => 1073741823))
+            || paradedb.field('tag', tokenizer => paradedb.tokenizer('raw', remove_long => 1073741823))
+            || paradedb.field('code', tokenizer => paradedb.tokenizer('raw', lowercase => false))
+    );
+    "#
+    .execute(&mut conn);
+
+    // the custom scan sits below the Sort
+    let is_custom_scan = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        plan.to_string().contains("ParadeDB Scan")
+    };
+
+    for (query, expected) in [
+        // anchored at both ends
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND sku ~ '^AB-.*$' ORDER BY id",
+            vec![(1,), (2,)],
+        ),
+        // unanchored matches anywhere in the value
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND sku ~ '100' ORDER BY id",
+            vec![(1,), (3,)],
+        ),
+        // a NULL doesn't match a negated regex either
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND sku !~ '^AB-.*$' ORDER BY id",
+            vec![(3,)],
+        ),
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND sku SIMILAR TO 'AB-%' ORDER BY id",
+            vec![(1,), (2,)],
+        ),
+        // case-insensitive matches work on both exact and lowercased terms
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND sku ~* '^ab-1' ORDER BY id",
+            vec![(1,)],
+        ),
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND tag ~* '^BLUE$' ORDER BY id",
+            vec![(1,), (2,)],
+        ),
+    ] {
+        assert!(is_custom_scan(query, &mut conn), "{query}");
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+
+    for (query, expected) in [
+        // the lowercased terms of `tag` can't answer a case-sensitive match
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND tag ~ '^Blue$' ORDER BY id",
+            vec![(1,)],
+        ),
+        // `code` drops the values past the default `remove_long` limit, which Postgres would
+        // still match
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND code ~ '^AB-.*$' ORDER BY id",
+            vec![(1,), (2,)],
+        ),
+        // tantivy has no lookahead
+        (
+            "SELECT id FROM parts WHERE body @@@ 'widget' AND sku ~ '^(?=AB).*00$' ORDER BY id",
+            vec![(1,), (2,)],
+        ),
+        // nor can a regex alone use the custom scan
+        (
+            "SELECT id FROM parts WHERE sku ~ '^AB-.*$' ORDER BY id",
+            vec![(1,), (2,), (5,)],
+        ),
+    ] {
+        assert!(!is_custom_scan(query, &mut conn), "{query}");
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+}
+
+#[rstest]
+fn init_plan_comparisons_push_down(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE listings (id serial PRIMARY KEY, body text, price int, listed_at timestamp);
+    INSERT INTO listings (body, price, listed_at) VALUES
+        ('widget', 10, '2024-01-01'),
+        ('widget', 20, '2024-02-01'),
+        ('widget', 30, '2024-03-01'),
+        ('widget', NULL, NULL),
+        ('gadget', 10, '2024-01-01');
+    CREATE TABLE thresholds (id int, price int, listed_at timestamp);
+    INSERT INTO thresholds VALUES (1, 20, '2024-02-01'), (2, NULL, NULL);
+
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('price'),
+        datetime_fields => paradedb.field('listed_at')
+    );
+    "#
+    .execute(&mut conn);
+
+    // the custom scan sits below the Sort
+    let is_custom_scan = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        plan.to_string().contains("ParadeDB Scan")
+    };
+
+    for (query, expected) in [
+        (
+            "SELECT id FROM listings WHERE body @@@ 'widget' AND price <= (SELECT price FROM thresholds WHERE id = 1) ORDER BY id",
+            vec![(1,), (2,)],
+        ),
+        (
+            "SELECT id FROM listings WHERE body @@@ 'widget' AND price > (SELECT price FROM thresholds WHERE id = 1) ORDER BY id",
+            vec![(3,)],
+        ),
+        (
+            "SELECT id FROM listings WHERE body @@@ 'widget' AND listed_at >= (SELECT listed_at FROM thresholds WHERE id = 1) ORDER BY id",
+            vec![(2,), (3,)],
+        ),
+        // nothing compares equal to NULL
+        (
+            "SELECT id FROM listings WHERE body @@@ 'widget' AND price = (SELECT price FROM thresholds WHERE id = 2) ORDER BY id",
+            vec![],
+        ),
+    ] {
+        assert!(is_custom_scan(query, &mut conn), "{query}");
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+
+    // a correlated subquery is re-run for every row, so it can't become part of the search
+    let query = "SELECT id FROM listings WHERE body @@@ 'widget' AND price <= (SELECT t.price FROM thresholds t WHERE t.id = listings.id) ORDER BY id";
+    assert!(!is_custom_scan(query, &mut conn), "{query}");
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![(1,)]);
+}
+
+#[rstest]
+fn constant_comparisons_push_down_as_ranges(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE listings (id serial PRIMARY KEY, body text, price int);
+    INSERT INTO listings (body, price) VALUES
+        ('widget', 5),
+        ('widget', 10),
+        ('widget', 50),
+        ('widget', 100),
+        ('widget', 150),
+        ('widget', NULL),
+        ('gadget', 50);
+
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('price')
+    );
+    "#
+    .execute(&mut conn);
+
+    for (clause, expected, range) in [
+        // the bounds of a symmetric BETWEEN are put the right way around
+        (
+            "price BETWEEN SYMMETRIC 100 AND 10",
+            vec![2, 3, 4],
+            r#""lower_bound":{"Included":10},"upper_bound":{"Included":100}"#,
+        ),
+        (
+            "price BETWEEN SYMMETRIC 10 AND 100",
+            vec![2, 3, 4],
+            r#""lower_bound":{"Included":10},"upper_bound":{"Included":100}"#,
+        ),
+        // a half-open interval
+        (
+            "price > 10 AND price <= 100",
+            vec![3, 4],
+            r#""lower_bound":{"Excluded":10},"upper_bound":{"Included":100}"#,
+        ),
+        (
+            "10 < price AND 100 >= price",
+            vec![3, 4],
+            r#""lower_bound":{"Excluded":10},"upper_bound":{"Included":100}"#,
+        ),
+        // the tighter of two bounds on the same side wins
+        (
+            "price >= 10 AND price > 10 AND price < 150 AND price <= 100",
+            vec![3, 4],
+            r#""lower_bound":{"Excluded":10},"upper_bound":{"Included":100}"#,
+        ),
+    ] {
+        let query = format!("SELECT id FROM listings WHERE body @@@ 'widget' AND {clause}");
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+        eprintln!("{plan:#?}");
+        let tantivy_query = plan
+            .pointer("/0/Plan/Tantivy Query")
+            .and_then(Value::as_str)
+            .unwrap_or_else(|| panic!("{query} should use the custom scan"));
+        assert!(tantivy_query.contains(range), "{query}: {tantivy_query}");
+
+        let mut ids = query
+            .fetch::<(i32,)>(&mut conn)
+            .into_iter()
+            .map(|(id,)| id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, expected, "{query}");
+    }
+
+    // a BETWEEN whose bounds are the wrong way around matches nothing, as in Postgres
+    let query = "SELECT id FROM listings WHERE body @@@ 'widget' AND price BETWEEN 100 AND 10";
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![]);
+    let query =
+        "SELECT id FROM listings WHERE body @@@ 'widget' AND price > 10 AND price <= 10 ORDER BY id";
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![]);
+}
+
+#[rstest]
+fn is_distinct_from_pushes_down(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE tickets (id serial PRIMARY KEY, body text, status text, priority int, tag text);
+    INSERT INTO tickets (body, status, priority, tag) VALUES
+        ('printer jam', 'open', 1, 'Printer'),
+        ('printer jam', 'closed', 2, 'printer'),
+        ('printer jam', NULL, NULL, NULL),
+        ('printer jam', 'Closed', 3, 'Office'),
+        ('paper jam', 'open', 1, 'Office');
+
+    CALL paradedb.create_bm25(
+        index_name => 'tickets',
+        table_name => 'tickets',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+            || paradedb.field('status', tokenizer => paradedb.tokenizer('raw', lowercase => false))
+            || paradedb.field('tag', tokenizer => paradedb.tokenizer('raw')),
+        numeric_fields => paradedb.field('priority')
+    );
+    "#
+    .execute(&mut conn);
+
+    // the custom scan sits below the Sort
+    let is_custom_scan = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        plan.to_string().contains("ParadeDB Scan")
+    };
+
+    // unlike `<>`, the rows where the column is NULL are distinct from the constant too
+    for (query, expected) in [
+        (
+            "SELECT id FROM tickets WHERE body @@@ 'printer' AND status IS DISTINCT FROM 'closed' ORDER BY id",
+            vec![(1,), (3,), (4,)],
+        ),
+        (
+            "SELECT id FROM tickets WHERE body @@@ 'printer' AND 'closed' IS DISTINCT FROM status ORDER BY id",
+            vec![(1,), (3,), (4,)],
+        ),
+        (
+            "SELECT id FROM tickets WHERE body @@@ 'printer' AND priority IS DISTINCT FROM 2 ORDER BY id",
+            vec![(1,), (3,), (4,)],
+        ),
+        (
+            "SELECT id FROM tickets WHERE body @@@ 'printer' AND status IS NOT DISTINCT FROM 'closed' ORDER BY id",
+            vec![(2,)],
+        ),
+    ] {
+        assert!(is_custom_scan(query, &mut conn), "{query}");
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+
+    // a lowercased field can't tell `Printer` from `printer`, and being distinct from NULL is
+    // being NOT NULL, so neither is searched for
+    for (query, expected) in [
+        (
+            "SELECT id FROM tickets WHERE body @@@ 'printer' AND tag IS DISTINCT FROM 'printer' ORDER BY id",
+            vec![(1,), (3,), (4,)],
+        ),
+        (
+            "SELECT id FROM tickets WHERE body @@@ 'printer' AND status IS DISTINCT FROM NULL ORDER BY id",
+            vec![(1,), (2,), (4,)],
+        ),
+    ] {
+        assert!(!is_custom_scan(query, &mut conn), "{query}");
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+}
+
+#[rstest]
+fn array_element_equality_pushes_down(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE articles (id serial PRIMARY KEY, body text, tags text[], ratings int[]);
+    INSERT INTO articles (body, tags, ratings) VALUES
+        ('database news', '{postgres,search}', '{1,5}'),
+        ('database news', '{Postgres,rust}', '{2}'),
+        ('database news', '{mysql,"full text search"}', '{}'),
+        ('database news', NULL, NULL),
+        ('sports news', '{postgres}', '{5}');
+
+    CALL paradedb.create_bm25(
+        index_name => 'articles',
+        table_name => 'articles',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+            || paradedb.field('tags', tokenizer => paradedb.tokenizer('raw', lowercase => false)),
+        numeric_fields => paradedb.field('ratings')
+    );
+    "#
+    .execute(&mut conn);
+
+    let is_custom_scan = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        plan.to_string().contains("ParadeDB Scan")
+    };
+
+    // an array matches if any one of its elements is equal to the value, as a whole, and the
+    // arrays without such an element, or without any elements, don't
+    for (query, expected) in [
+        (
+            "SELECT id FROM articles WHERE body @@@ 'database' AND 'postgres' = ANY(tags) ORDER BY id",
+            vec![(1,)],
+        ),
+        (
+            "SELECT id FROM articles WHERE body @@@ 'database' AND 'full text search' = ANY(tags) ORDER BY id",
+            vec![(3,)],
+        ),
+        (
+            "SELECT id FROM articles WHERE body @@@ 'database' AND 'text' = ANY(tags) ORDER BY id",
+            vec![],
+        ),
+        (
+            "SELECT id FROM articles WHERE body @@@ 'database' AND ('rust' = ANY(tags) OR 'mysql' = ANY(tags)) ORDER BY id",
+            vec![(2,), (3,)],
+        ),
+        (
+            "SELECT id FROM articles WHERE body @@@ 'news' AND 5 = ANY(ratings) ORDER BY id",
+            vec![(1,), (5,)],
+        ),
+    ] {
+        assert!(is_custom_scan(query, &mut conn), "{query}");
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+
+    // comparing every element isn't something a term can answer
+    let query =
+        "SELECT id FROM articles WHERE body @@@ 'database' AND 'postgres' = ALL(tags) ORDER BY id";
+    assert!(!is_custom_scan(query, &mut conn), "{query}");
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![]);
+}
+
+#[rstest]
+fn term_set_subqueries_push_down(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE listings (id serial PRIMARY KEY, body text, price int);
+    INSERT INTO listings (body, price) VALUES
+        ('widget', 10),
+        ('widget', 20),
+        ('widget', 30),
+        ('gadget', 10);
+    CREATE TABLE small_set (id int);
+    INSERT INTO small_set VALUES (1), (3), (4), (NULL);
+    CREATE TABLE empty_set (id int);
+
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('price')
+    );
+    "#
+    .execute(&mut conn);
+
+    let is_custom_scan = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        plan.to_string().contains("ParadeDB Scan")
+    };
+
+    for (query, expected) in [
+        // the NULL in the set matches nothing, and neither does the "gadget"
+        (
+            "SELECT id FROM listings WHERE body @@@ 'widget' AND id = ANY(ARRAY(SELECT id FROM small_set)) ORDER BY id",
+            vec![(1,), (3,)],
+        ),
+        // a bigint set still finds an int field
+        (
+            "SELECT id FROM listings WHERE body @@@ 'widget' AND price = ANY(ARRAY(SELECT 10::bigint UNION SELECT 30)) ORDER BY id",
+            vec![(1,), (3,)],
+        ),
+        (
+            "SELECT id FROM listings WHERE body @@@ 'widget' AND id = ANY(ARRAY(SELECT id FROM empty_set)) ORDER BY id",
+            vec![],
+        ),
+    ] {
+        assert!(is_custom_scan(query, &mut conn), "{query}");
+        assert_eq!(query.fetch::<(i32,)>(&mut conn), expected, "{query}");
+    }
+
+    // too many terms to search for, so the query is left to Postgres
+    let query = "SELECT id FROM listings WHERE body @@@ 'widget' AND id = ANY(ARRAY(SELECT generate_series(2, 100001))) ORDER BY id";
+    assert!(!is_custom_scan(query, &mut conn), "{query}");
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![(2,), (3,)]);
+
+    // the planner expects a few hundred even numbers, but there are far more, so the rows we find
+    // are filtered by the set instead
+    let query = "SELECT id FROM listings WHERE body @@@ 'widget' AND id = ANY(ARRAY(SELECT g FROM generate_series(1, 40000) g WHERE g % 2 = 0)) ORDER BY id";
+    assert!(is_custom_scan(query, &mut conn), "{query}");
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![(2,)]);
+}
+
+#[rstest]
+fn partial_index_requires_implied_predicate(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE posts (id serial PRIMARY KEY, body text, active boolean);
+    INSERT INTO posts (body, active) VALUES
+        ('hello world', true),
+        ('hello there', false),
+        ('hello again', true),
+        ('goodbye world', true);
+
+    CALL paradedb.create_bm25(
+        index_name => 'posts',
+        table_name => 'posts',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        predicates => 'active'
+    );
+    "#
+    .execute(&mut conn);
+
+    // the custom scan sits below the Sort
+    let is_custom_scan = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        plan.to_string().contains("ParadeDB Scan")
+    };
+
+    // the predicate's clause is answered by the index itself
+    let query = "SELECT id FROM posts WHERE body @@@ 'hello' AND active ORDER BY id";
+    assert!(is_custom_scan(query, &mut conn), "{query}");
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![(1,), (3,)]);
+
+    // without the predicate the query could also match rows the index doesn't have
+    let query = "SELECT id FROM posts WHERE body @@@ 'hello' ORDER BY id";
+    assert!(!is_custom_scan(query, &mut conn), "{query}");
+
+    let query = "SELECT id FROM posts WHERE body @@@ 'hello' AND NOT active ORDER BY id";
+    assert!(!is_custom_scan(query, &mut conn), "{query}");
+
+    // rows that stop satisfying the predicate leave the results
+    "UPDATE posts SET active = false WHERE id = 3".execute(&mut conn);
+    let query = "SELECT id FROM posts WHERE body @@@ 'hello' AND active ORDER BY id";
+    assert_eq!(query.fetch::<(i32,)>(&mut conn), vec![(1,)]);
+}
+
+#[rstest]
+fn group_by_count_pushes_down(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE items (id SERIAL PRIMARY KEY, category TEXT, rating INT, body TEXT);
+    INSERT INTO items (category, rating, body) VALUES
+        ('shoes', 1, 'red running shoes'),
+        ('shoes', 2, 'blue running shoes'),
+        ('shoes', 2, 'old shoes'),
+        ('boots', 3, 'running boots'),
+        ('boots', 3, 'hiking boots'),
+        (NULL, 4, 'running socks'),
+        ('hats', 5, 'a running hat');
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('body') || paradedb.field('category', fast => true),
+        numeric_fields => paradedb.field('rating', fast => true)
+    );
+
+    DELETE FROM items WHERE body = 'a running hat';
+    "#
+    .execute(&mut conn);
+
+    let plan_of = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        plan.to_string()
+    };
+    let naive_counts = |query: &str, conn: &mut PgConnection| {
+        "SET paradedb.enable_custom_scan = false".execute(conn);
+        let counts = query.fetch::<(Option<String>, i64)>(conn);
+        "SET paradedb.enable_custom_scan = true".execute(conn);
+        counts
+    };
+
+    // the deleted row isn't counted, and rows without a category are grouped together
+    let query = "SELECT category, count(*) FROM items WHERE body @@@ 'running' GROUP BY category ORDER BY category";
+    assert!(plan_of(query, &mut conn).contains("ParadeDB Aggregate Scan"));
+    let counts = query.fetch::<(Option<String>, i64)>(&mut conn);
+    assert_eq!(
+        counts,
+        vec![
+            (Some("boots".into()), 1),
+            (Some("shoes".into()), 2),
+            (None, 1),
+        ]
+    );
+    assert_eq!(counts, naive_counts(query, &mut conn));
+
+    // the target list can repeat and reorder the grouped column and the count
+    let query = "SELECT count(*), category FROM items WHERE body @@@ 'shoes' GROUP BY category";
+    assert!(plan_of(query, &mut conn).contains("ParadeDB Aggregate Scan"));
+    assert_eq!(
+        query.fetch::<(i64, String)>(&mut conn),
+        vec![(3, "shoes".into())]
+    );
+
+    // nullable numeric columns read as 0 when they have no value, so they aren't pushed down
+    let query = "SELECT rating, count(*) FROM items WHERE body @@@ 'running' GROUP BY rating";
+    assert!(!plan_of(query, &mut conn).contains("ParadeDB Aggregate Scan"));
+
+    // nor is grouping by a column that isn't a fast field
+    let query = "SELECT body, count(*) FROM items WHERE body @@@ 'running' GROUP BY body";
+    assert!(!plan_of(query, &mut conn).contains("ParadeDB Aggregate Scan"));
+
+    // nor are other aggregates, or filtering the groups
+    let query = "SELECT category, max(id) FROM items WHERE body @@@ 'running' GROUP BY category";
+    assert!(!plan_of(query, &mut conn).contains("ParadeDB Aggregate Scan"));
+    let query = "SELECT category, count(*) FROM items WHERE body @@@ 'running' GROUP BY category HAVING count(*) > 1";
+    assert!(!plan_of(query, &mut conn).contains("ParadeDB Aggregate Scan"));
+    assert_eq!(
+        query.fetch::<(Option<String>, i64)>(&mut conn),
+        vec![(Some("shoes".into()), 2)]
+    );
+
+    // and a `count(*)` that isn't the built-in one could count anything
+    r#"
+    CREATE SCHEMA counting;
+    CREATE AGGREGATE counting.count(*) (sfunc = int8inc, stype = int8, initcond = '0');
+    "#
+    .execute(&mut conn);
+    let query =
+        "SELECT category, counting.count(*) FROM items WHERE body @@@ 'running' GROUP BY category";
+    assert!(!plan_of(query, &mut conn).contains("ParadeDB Aggregate Scan"));
+}
+
+#[rstest]
+fn agg_max_buckets_caps_high_cardinality_groups(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE items (id SERIAL PRIMARY KEY, code INT NOT NULL, category TEXT, body TEXT);
+    INSERT INTO items (code, category, body)
+    SELECT i, 'category ' || i, 'running shoes' FROM generate_series(1, 200) i;
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('body') || paradedb.field('category', fast => true),
+        numeric_fields => paradedb.field('code', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    let by_category =
+        "SELECT category, count(*) FROM items WHERE body @@@ 'running' GROUP BY category";
+    let by_code = "SELECT code, count(*) FROM items WHERE body @@@ 'running' GROUP BY code";
+    let numeric_histogram = "SELECT * FROM paradedb.numeric_histogram('items_bm25_index'::regclass, paradedb.term('body', 'running'), 'code', 1)";
+    let score_histogram = "SELECT * FROM paradedb.score_histogram('items_bm25_index'::regclass, paradedb.term('body', 'running'), buckets => 101)";
+
+    // the default cap is well above what these need
+    assert_eq!(by_category.fetch::<(String, i64)>(&mut conn).len(), 200);
+    assert_eq!(by_code.fetch::<(i32, i64)>(&mut conn).len(), 200);
+    assert_eq!(
+        numeric_histogram.fetch::<(f64, f64, i64)>(&mut conn).len(),
+        200
+    );
+
+    // but each of them fails once it needs more buckets than are allowed
+    "SET paradedb.agg_max_buckets = 100".execute(&mut conn);
+    for query in [by_category, by_code, numeric_histogram, score_histogram] {
+        let error = query.execute_result(&mut conn).unwrap_err().to_string();
+        assert!(
+            error.contains("paradedb.agg_max_buckets"),
+            "{query}: {error}"
+        );
+    }
+
+    // a query that fits under the cap isn't affected
+    let few = "SELECT category, count(*) FROM items WHERE body @@@ 'running' AND code <= 100 GROUP BY category";
+    assert_eq!(few.fetch::<(String, i64)>(&mut conn).len(), 100);
+
+    // and 0 lifts the cap
+    "SET paradedb.agg_max_buckets = 0".execute(&mut conn);
+    assert_eq!(by_category.fetch::<(String, i64)>(&mut conn).len(), 200);
+    assert_eq!(by_code.fetch::<(i32, i64)>(&mut conn).len(), 200);
+}
+
+#[rstest]
+fn distinct_limit_stops_early(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE items (id SERIAL PRIMARY KEY, category TEXT, body TEXT);
+    INSERT INTO items (category, body)
+    SELECT 'category ' || (i % 10), 'running shoes' FROM generate_series(1, 1000) i;
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('body') || paradedb.field('category', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    fn find<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+        match value {
+            Value::Object(map) => map
+                .get(key)
+                .or_else(|| map.values().find_map(|value| find(value, key))),
+            Value::Array(values) => values.iter().find_map(|value| find(value, key)),
+            _ => None,
+        }
+    }
+    let explain = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        plan
+    };
+    let all: Vec<(String,)> =
+        "SELECT DISTINCT category FROM items WHERE body @@@ 'shoes'".fetch(&mut conn);
+    assert_eq!(all.len(), 10);
+
+    // only as many rows are looked at as it takes to find the distinct values the LIMIT needs
+    let query = "SELECT DISTINCT category FROM items WHERE body @@@ 'shoes' LIMIT 5";
+    let plan = explain(query, &mut conn);
+    assert!(plan.to_string().contains("ParadeDB Aggregate Scan"));
+    assert_eq!(find(&plan, "Distinct Limit"), Some(&Value::from(5)));
+    let visited = find(&plan, "   Documents Visited")
+        .and_then(|visited| visited.as_u64())
+        .expect("plan should show the documents visited");
+    assert!(visited < 100, "{visited}");
+
+    let mut some: Vec<(String,)> = query.fetch(&mut conn);
+    assert_eq!(some.len(), 5);
+    some.sort();
+    some.dedup();
+    assert_eq!(some.len(), 5);
+    assert!(some.iter().all(|category| all.contains(category)));
+
+    // an OFFSET needs that many more
+    let query = "SELECT DISTINCT category FROM items WHERE body @@@ 'shoes' LIMIT 3 OFFSET 4";
+    assert_eq!(
+        find(&explain(query, &mut conn), "Distinct Limit"),
+        Some(&Value::from(7))
+    );
+    assert_eq!(query.fetch::<(String,)>(&mut conn).len(), 3);
+
+    // but any of them won't do when they're ordered
+    let query =
+        "SELECT DISTINCT category FROM items WHERE body @@@ 'shoes' ORDER BY category LIMIT 5";
+    let plan = explain(query, &mut conn);
+    assert!(plan.to_string().contains("ParadeDB Aggregate Scan"));
+    assert_eq!(find(&plan, "Distinct Limit"), None);
+    let mut sorted = all.clone();
+    sorted.sort();
+    assert_eq!(query.fetch::<(String,)>(&mut conn), sorted[..5].to_vec());
+}
+
+#[rstest]
+fn high_cardinality_group_by_streams_groups(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE items (id SERIAL PRIMARY KEY, category TEXT, body TEXT);
+    INSERT INTO items (category, body)
+    SELECT CASE WHEN i % 7 = 0 THEN NULL ELSE 'category ' || (i % 5000) END, 'running shoes'
+    FROM generate_series(1, 20000) i;
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('body') || paradedb.field('category', fast => true)
+    );
+
+    -- a second segment, with values the first one also has, and some it doesn't
+    INSERT INTO items (category, body)
+    SELECT 'category ' || (i % 6000), 'running shoes' FROM generate_series(1, 12000) i;
+    DELETE FROM items WHERE id % 11 = 0;
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT category, count(*) FROM items WHERE body @@@ 'shoes' GROUP BY category";
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    eprintln!("{plan:#?}");
+    let plan = plan.to_string();
+    assert!(plan.contains("ParadeDB Aggregate Scan"));
+    assert!(plan.contains(r#""Streaming Groups":true"#));
+    assert!(plan.contains(r#""Groups":6001"#));
+
+    // the groups come out in the order of their values, with the rows without one last
+    let counts = query.fetch::<(Option<String>, i64)>(&mut conn);
+    assert_eq!(counts.len(), 6001);
+    assert_eq!(
+        counts.last().map(|(category, _)| category.clone()),
+        Some(None)
+    );
+    let values = counts
+        .iter()
+        .filter_map(|(category, _)| category.clone())
+        .collect::<Vec<_>>();
+    assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+
+    "SET paradedb.enable_custom_scan = false".execute(&mut conn);
+    let mut naive = format!("{query} ORDER BY category").fetch::<(Option<String>, i64)>(&mut conn);
+    "SET paradedb.enable_custom_scan = true".execute(&mut conn);
+    let mut sorted = counts.clone();
+    sorted.sort();
+    naive.sort();
+    assert_eq!(sorted, naive);
+
+    // and can be read a few at a time through a cursor
+    "BEGIN".execute(&mut conn);
+    format!("DECLARE groups CURSOR FOR {query}").execute(&mut conn);
+    let first = "FETCH 100 FROM groups".fetch::<(Option<String>, i64)>(&mut conn);
+    let next = "FETCH 100 FROM groups".fetch::<(Option<String>, i64)>(&mut conn);
+    "COMMIT".execute(&mut conn);
+    assert_eq!(first, counts[..100].to_vec());
+    assert_eq!(next, counts[100..200].to_vec());
+}
+
+#[rstest]
+fn correlated_exists_searches_each_outer_row(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE items (id serial PRIMARY KEY, body text);
+    INSERT INTO items (body) VALUES
+        ('running shoes'),
+        ('hiking boots'),
+        ('running socks'),
+        ('wool socks');
+    CREATE TABLE categories (id int, name text);
+    INSERT INTO categories VALUES (1, 'running'), (2, 'boots'), (3, 'hats'), (4, NULL);
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT name FROM categories c WHERE EXISTS (SELECT 1 FROM items i WHERE i.body @@@ c.name) ORDER BY name";
+    assert_eq!(
+        query.fetch::<(Option<String>,)>(&mut conn),
+        vec![(Some("boots".into()),), (Some("running".into()),)]
+    );
+
+    // every outer row gets a search of its own, which stops at the first match, even though
+    // "running" matches two items
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    eprintln!("{plan:#?}");
+    fn find_scan(node: &Value) -> Option<&Value> {
+        if node.get("Custom Plan Provider") == Some(&Value::from("ParadeDB Scan")) {
+            return Some(node);
+        }
+        node.get("Plans")?.as_array()?.iter().find_map(find_scan)
+    }
+    let scan = find_scan(&plan[0]["Plan"]).expect("should use a custom scan");
+    assert_eq!(scan["Exists Only"], Value::from(true));
+    assert_eq!(scan["Actual Loops"], Value::from(4));
+    assert!(scan["Actual Rows"].as_f64().unwrap() <= 1.0);
+
+    // `NOT EXISTS` needs every outer row searched too, and a NULL matches nothing
+    let query = "SELECT name FROM categories c WHERE NOT EXISTS (SELECT 1 FROM items i WHERE i.body @@@ c.name) ORDER BY name";
+    assert_eq!(
+        query.fetch::<(Option<String>,)>(&mut conn),
+        vec![(Some("hats".into()),), (None,)]
+    );
+
+    // and a search of our own narrows what the outer rows find
+    let query = "SELECT name FROM categories c WHERE EXISTS (SELECT 1 FROM items i WHERE i.body @@@ c.name AND i.body @@@ 'socks') ORDER BY name";
+    assert_eq!(
+        query.fetch::<(Option<String>,)>(&mut conn),
+        vec![(Some("running".into()),)]
+    );
+}
+
+#[rstest]
+fn correlated_equality_searches_each_outer_row(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE items (id serial PRIMARY KEY, rating int, body text);
+    INSERT INTO items (rating, body) VALUES
+        (1, 'running shoes'),
+        (2, 'hiking boots'),
+        (2, 'running socks'),
+        (3, 'wool socks'),
+        (3, 'running hat');
+    CREATE TABLE wanted (rating int);
+    INSERT INTO wanted VALUES (2), (3), (4), (NULL);
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('rating')
+    );
+
+    SET enable_hashjoin = off;
+    SET enable_mergejoin = off;
+    SET enable_material = off;
+    "#
+    .execute(&mut conn);
+
+    fn find_scan(node: &Value) -> Option<&Value> {
+        if node.get("Custom Plan Provider") == Some(&Value::from("ParadeDB Scan")) {
+            return Some(node);
+        }
+        node.get("Plans")?.as_array()?.iter().find_map(find_scan)
+    }
+    let naive = |query: &str, conn: &mut PgConnection| {
+        "SET paradedb.enable_custom_scan = false".execute(conn);
+        let rows = query.fetch::<(i32, String)>(conn);
+        "SET paradedb.enable_custom_scan = true".execute(conn);
+        rows
+    };
+
+    // each outer row's rating is searched for along with the text, and a NULL matches nothing
+    let query = "SELECT w.rating, i.body FROM wanted w JOIN items i ON i.rating = w.rating WHERE i.body @@@ 'running' ORDER BY w.rating, i.body";
+    assert_eq!(
+        query.fetch::<(i32, String)>(&mut conn),
+        vec![(2, "running socks".into()), (3, "running hat".into())]
+    );
+    assert_eq!(
+        query.fetch::<(i32, String)>(&mut conn),
+        naive(query, &mut conn)
+    );
+
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    eprintln!("{plan:#?}");
+    let scan = find_scan(&plan[0]["Plan"]).expect("should use a custom scan");
+    assert_eq!(scan["Actual Loops"], Value::from(4));
+    assert!(scan["Actual Rows"].as_f64().unwrap() <= 1.0);
+
+    // the comparison can be written either way around, and in a correlated `EXISTS`
+    let query = "SELECT w.rating, 'found'::text FROM wanted w WHERE EXISTS (SELECT 1 FROM items i WHERE w.rating = i.rating AND i.body @@@ 'socks') ORDER BY w.rating";
+    assert_eq!(
+        query.fetch::<(i32, String)>(&mut conn),
+        vec![(2, "found".into()), (3, "found".into())]
+    );
+    assert_eq!(
+        query.fetch::<(i32, String)>(&mut conn),
+        naive(query, &mut conn)
+    );
+
+    // comparing two of our own columns depends on each row, so Postgres checks it
+    let query = "SELECT i.rating, i.body FROM items i WHERE i.rating = i.id AND i.body @@@ 'running' ORDER BY i.body";
+    assert_eq!(
+        query.fetch::<(i32, String)>(&mut conn),
+        vec![(1, "running shoes".into())]
+    );
+}
+
+#[rstest]
+fn log_min_duration_scan_logs_slow_scans(mut conn: PgConnection) {
+    use cmd_lib::run_fun;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    // sqlx doesn't hand us the messages the server sends along with a result, but psql prints
+    // them, so run the query through psql against this test's database
+    let (database,): (String,) = "SELECT current_database()".fetch_one(&mut conn);
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL should be set");
+    let (server, _) = database_url
+        .rsplit_once('/')
+        .expect("DATABASE_URL should name a database");
+    let pg_config = std::env::var("PG_CONFIG").expect("PG_CONFIG should be set");
+    let bindir = run_fun!($pg_config --bindir).expect("pg_config should report its bindir");
+    let psql_messages = |settings: &str| {
+        let output = Command::new(PathBuf::from(bindir.trim()).join("psql"))
+            .arg("-X")
+            .arg(format!("{server}/{database}"))
+            .arg("-c")
+            .arg(format!(
+                "SET client_min_messages = log; SET enable_indexscan = off; {settings}; \
+                 SELECT id FROM paradedb.bm25_search WHERE description @@@ 'keyboard' ORDER BY id"
+            ))
+            .output()
+            .expect("psql should run");
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8_lossy(&output.stderr).to_string()
+    };
+
+    // a tiny threshold logs the scan, with what it searched for and found
+    let messages = psql_messages("SET paradedb.log_min_duration_scan = 0");
+    let line = messages
+        .lines()
+        .find(|line| line.contains("ParadeDB Scan on index bm25_search_bm25_index"))
+        .unwrap_or_else(|| panic!("the scan should have been logged: {messages}"));
+    assert!(line.starts_with("LOG:"), "{line}");
+    let (count,): (i64,) =
+        "SELECT count(*) FROM paradedb.bm25_search WHERE description @@@ 'keyboard'"
+            .fetch_one(&mut conn);
+    let found = format!("matched {count} documents and returned {count} rows");
+    assert!(line.contains(&found), "{line}");
+    assert!(line.contains(r#""query_string":"keyboard""#), "{line}");
+    assert!(line.contains(" ms, "), "{line}");
+
+    // scans quicker than the threshold aren't
+    let messages = psql_messages("SET paradedb.log_min_duration_scan = '1h'");
+    assert!(!messages.contains("ParadeDB Scan on index"), "{messages}");
+    let messages = psql_messages("RESET paradedb.log_min_duration_scan");
+    assert!(!messages.contains("ParadeDB Scan on index"), "{messages}");
+}
+
+#[rstest]
+fn require_covering_rejects_columns_outside_the_index(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE items (id SERIAL PRIMARY KEY, category TEXT, rating INT, body TEXT);
+    INSERT INTO items (category, rating, body) VALUES
+        ('shoes', 1, 'red running shoes'),
+        ('boots', 3, 'running boots'),
+        ('hats', 5, 'a hat');
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('body') || paradedb.field('category', fast => true),
+        numeric_fields => paradedb.field('rating', fast => true)
+    );
+
+    SET enable_indexscan = off;
+    SET paradedb.require_covering = true;
+    "#
+    .execute(&mut conn);
+
+    // the key field and fast fields are all held by the index, as is what's searched
+    let rows: Vec<(i32, String, i32, f32)> = r#"
+    SELECT id, category, rating, paradedb.score(id) FROM items
+    WHERE body @@@ 'running' ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(
+        rows.iter()
+            .map(|(id, category, rating, _)| (*id, category.as_str(), *rating))
+            .collect::<Vec<_>>(),
+        vec![(1, "shoes", 1), (2, "boots", 3)]
+    );
+    let (count,): (i64,) =
+        "SELECT count(*) FROM items WHERE body @@@ 'running'".fetch_one(&mut conn);
+    assert_eq!(count, 2);
+
+    // but a column that isn't a fast field would have to be read from the heap
+    let error = "SELECT id, body FROM items WHERE body @@@ 'running'"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("the index doesn't hold the values of body"),
+        "{error}"
+    );
+    let error = "SELECT items FROM items WHERE body @@@ 'running'"
+        .execute_result(&mut conn)
+        .unwrap_err();
+    assert!(error.to_string().contains("items.*"), "{error}");
+
+    // which is fine once covering isn't required
+    "SET paradedb.require_covering = false".execute(&mut conn);
+    let rows: Vec<(i32, String)> =
+        "SELECT id, body FROM items WHERE body @@@ 'running' ORDER BY id".fetch(&mut conn);
+    assert_eq!(rows.len(), 2);
+}
+
+#[rstest]
+fn materialized_view_uses_custom_scan(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE posts (id serial PRIMARY KEY, body text, active boolean);
+    INSERT INTO posts (body, active) VALUES
+        ('hello world', true),
+        ('hello there', false),
+        ('hello again', true),
+        ('goodbye world', true);
+
+    CREATE MATERIALIZED VIEW active_posts AS SELECT id, body FROM posts WHERE active;
+    CREATE UNIQUE INDEX ON active_posts (id);
+
+    CALL paradedb.create_bm25(
+        index_name => 'active_posts',
+        table_name => 'active_posts',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT id, paradedb.score(id) FROM active_posts WHERE body @@@ 'hello'";
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert!(plan.to_string().contains("ParadeDB Scan"), "{plan:#?}");
+
+    let ids = |conn: &mut PgConnection| {
+        let mut ids = "SELECT id FROM active_posts WHERE body @@@ 'hello'".fetch::<(i32,)>(conn);
+        ids.sort();
+        ids
+    };
+    assert_eq!(ids(&mut conn), vec![(1,), (3,)]);
+
+    // the index only sees changes to the table once the view is refreshed
+    "INSERT INTO posts (body, active) VALUES ('hello again and again', true)".execute(&mut conn);
+    "UPDATE posts SET active = false WHERE id = 1".execute(&mut conn);
+    assert_eq!(ids(&mut conn), vec![(1,), (3,)]);
+
+    "REFRESH MATERIALIZED VIEW active_posts".execute(&mut conn);
+    assert_eq!(ids(&mut conn), vec![(3,), (5,)]);
+
+    "UPDATE posts SET active = true WHERE id = 1".execute(&mut conn);
+    "REFRESH MATERIALIZED VIEW CONCURRENTLY active_posts".execute(&mut conn);
+    assert_eq!(ids(&mut conn), vec![(1,), (3,), (5,)]);
+}
+
+#[rstest]
+fn explain_verbose_shows_query_tree(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE id @@@ paradedb.boolean(
+        must => ARRAY[paradedb.boost(2.0, paradedb.term('description', 'shoes'))],
+        should => ARRAY[paradedb.boolean(should => ARRAY[
+            paradedb.range('rating', int4range(3, 5)),
+            paradedb.phrase('description', ARRAY['running', 'shoes'])
+        ])]
+    )"#;
+
+    // only a verbose EXPLAIN renders the tree
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(plan.pointer("/0/Plan/Query Tree"), None);
+
+    let (plan,) =
+        format!("EXPLAIN (VERBOSE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let tree = plan
+        .pointer("/0/Plan/Query Tree")
+        .and_then(Value::as_str)
+        .unwrap_or_else(|| panic!("{plan:#?}"));
+    let lines = tree.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 6, "{tree}");
+    assert_eq!(lines[0], "Boolean");
+    assert_eq!(lines[1], "  must: Boost (boost=2)");
+    assert_eq!(
+        lines[2],
+        r#"    query: Term (field="description", is_datetime=false, value="shoes")"#
+    );
+    assert_eq!(lines[3], "  should: Boolean");
+    assert!(
+        lines[4].starts_with(r#"    should: Range (field="rating", "#),
+        "{tree}"
+    );
+    assert_eq!(
+        lines[5],
+        r#"    should: Phrase (field="description", phrases=["running","shoes"])"#
+    );
+}
+
+#[rstest]
+fn explain_verbose_describes_private_data(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    "SET enable_indexscan TO off".execute(&mut conn);
+
+    let query = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes'
+    ORDER BY rating DESC
+    LIMIT 5"#;
+
+    // only a verbose EXPLAIN decodes the private data
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(plan.pointer("/0/Plan/Plans/0/Private Data"), None);
+
+    let (plan,) =
+        format!("EXPLAIN (VERBOSE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let private = plan
+        .pointer("/0/Plan/Plans/0/Private Data")
+        .and_then(Value::as_str)
+        .unwrap_or_else(|| panic!("{plan:#?}"));
+    assert!(
+        private.starts_with(
+            r#"query={"ParseWithField":{"field":"description","query_string":"shoes""#
+        ),
+        "{private}"
+    );
+    assert!(
+        private.contains(", sort=rating desc nulls first, limit=5"),
+        "{private}"
+    );
+}
+
+#[rstest]
+fn recheck_sample_rate_finds_lossy_matches(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    // a numeric is indexed as a float, which can't tell 1 from a hair above it
+    r#"
+    CREATE TABLE prices (id serial PRIMARY KEY, body text, price numeric);
+    INSERT INTO prices (body, price) VALUES
+        ('widget', 1.00000000000000000001),
+        ('widget', 0.5),
+        ('widget', 2);
+
+    CALL paradedb.create_bm25(
+        index_name => 'prices',
+        table_name => 'prices',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('price')
+    );
+    "#
+    .execute(&mut conn);
+    "SET enable_indexscan TO off".execute(&mut conn);
+    "SET paradedb.recheck_sample_rate TO 1".execute(&mut conn);
+
+    let recheck_counts = |query: &str, conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        let node = plan
+            .pointer("/0/Plan")
+            .unwrap_or_else(|| panic!("{plan:#?}"));
+        let count = |name: &str| {
+            node.get(name)
+                .and_then(Value::as_u64)
+                .unwrap_or_else(|| panic!("{name} should be in {plan:#?}"))
+        };
+        (count("   Rechecked Tuples"), count("   Recheck Failures"))
+    };
+
+    // the index says the first row is at most 1, and Postgres doesn't
+    let lossy = "SELECT id FROM prices WHERE body @@@ 'widget' AND price <= 1";
+    assert_eq!(recheck_counts(lossy, &mut conn), (2, 1));
+
+    let clean = "SELECT id FROM prices WHERE body @@@ 'widget' AND price >= 2";
+    assert_eq!(recheck_counts(clean, &mut conn), (1, 0));
+
+    // nothing is rechecked by default
+    "RESET paradedb.recheck_sample_rate".execute(&mut conn);
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {lossy}").fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(plan.pointer("/0/Plan/   Recheck Failures"), None);
+}
+
+#[rstest]
+fn order_by_expression_index_column(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE books (id serial PRIMARY KEY, title text);
+    INSERT INTO books (title) VALUES
+        ('banana bread'),
+        ('Apple pie'),
+        ('cherry tart'),
+        ('Date loaf');
+
+    CREATE INDEX books_idx ON books
+    USING bm25 (id, title, (lower(title)))
+    WITH (
+        key_field = 'id',
+        text_fields = '{"title": {}, "lower": {"fast": true, "tokenizer": {"type": "raw"}}}'
+    );
+
+    SET enable_indexscan = off;
+    "#
+    .execute(&mut conn);
+
+    // the index holds the value of `lower(title)`, so it can sort by it itself
+    let query = r#"
+    SELECT id, title FROM books
+    WHERE title @@@ 'bread OR pie OR tart OR loaf'
+    ORDER BY lower(title)
+    LIMIT 3"#;
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    eprintln!("{plan:#?}");
+    let plan = plan.to_string();
+    assert!(plan.contains("ParadeDB Scan"), "{plan}");
+    assert!(!plan.contains(r#""Node Type":"Sort""#), "{plan}");
+    assert!(plan.contains(r#""   Sort Field":"lower""#), "{plan}");
+
+    let rows: Vec<(i32, String)> = query.fetch(&mut conn);
+    assert_eq!(
+        rows,
+        vec![
+            (2, "Apple pie".into()),
+            (1, "banana bread".into()),
+            (3, "cherry tart".into())
+        ]
+    );
+
+    // a different expression isn't what the index holds, so Postgres sorts by it
+    let query = r#"
+    SELECT id, title FROM books
+    WHERE title @@@ 'bread OR pie OR tart OR loaf'
+    ORDER BY upper(title)
+    LIMIT 3"#;
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert!(
+        plan.to_string().contains(r#""Node Type":"Sort""#),
+        "{plan:#?}"
+    );
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(2,), (1,), (3,)]);
+}
+
+#[rstest]
+fn max_scan_parallelism_caps_parallel_index_scans(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    r#"
+    CREATE TABLE parallel_items (id serial PRIMARY KEY, body text);
+    INSERT INTO parallel_items (body) SELECT 'sample ' || x FROM generate_series(1, 10000) x;
+    CREATE INDEX parallel_items_idx ON parallel_items
+    USING bm25 (id, body) WITH (key_field = 'id', text_fields = '{"body": {}}');
+    ALTER TABLE parallel_items SET (parallel_workers = 4);
+
+    SET paradedb.enable_custom_scan = false;
+    SET enable_seqscan = off;
+    SET enable_bitmapscan = off;
+    SET max_parallel_workers_per_gather = 4;
+    SET parallel_setup_cost = 0;
+    SET parallel_tuple_cost = 0;
+    SET min_parallel_index_scan_size = 0;
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT id FROM parallel_items WHERE body @@@ 'sample'";
+    let workers_planned = |conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        plan.pointer("/0/Plan/Workers Planned")
+            .and_then(Value::as_i64)
+    };
+
+    // without a cap, the scan gets as many workers as the table asks for
+    assert_eq!(workers_planned(&mut conn), Some(4));
+
+    "SET paradedb.max_scan_parallelism = 2".execute(&mut conn);
+    assert_eq!(workers_planned(&mut conn), Some(2));
+
+    // a cap above what the scan would ask for anyway changes nothing
+    "SET paradedb.max_scan_parallelism = 8".execute(&mut conn);
+    assert_eq!(workers_planned(&mut conn), Some(4));
+
+    // the workers of a capped scan still find every match between them
+    "SET paradedb.max_scan_parallelism = 2".execute(&mut conn);
+    let (count,) = format!("SELECT count(*) FROM ({query}) matches").fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 10000);
+}
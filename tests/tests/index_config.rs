@@ -751,3 +751,443 @@ fn delete_index_aborted_maintains_tantivy_files(mut conn: PgConnection) {
         index_dir
     );
 }
+
+#[rstest]
+fn index_schema_matches_definition(mut conn: PgConnection) {
+    "CALL paradedb.create_bm25_test_table(table_name => 'index_config', schema_name => 'paradedb')"
+        .execute(&mut conn);
+
+    r#"
+    CALL paradedb.create_bm25(
+	    index_name => 'index_config',
+	    table_name => 'index_config',
+	    schema_name => 'paradedb',
+	    key_field => 'id',
+	    text_fields => paradedb.field('description', fast => true, stored => true, tokenizer => paradedb.tokenizer('en_stem')),
+	    numeric_fields => paradedb.field('rating', fast => false),
+	    boolean_fields => paradedb.field('in_stock', indexed => false)
+    )"#
+    .execute(&mut conn);
+
+    let rows: Vec<(String, String, bool, bool, bool, Option<String>)> =
+        "SELECT * FROM paradedb.index_schema('paradedb.index_config_bm25_index'::regclass)"
+            .fetch(&mut conn);
+
+    assert_eq!(
+        rows,
+        vec![
+            ("ctid".into(), "U64".into(), true, true, true, None),
+            (
+                "description".into(),
+                "Str".into(),
+                true,
+                true,
+                true,
+                Some("en_stem[remove_long=255,lowercase=true]".into())
+            ),
+            ("id".into(), "I64".into(), true, true, true, None),
+            ("in_stock".into(), "Bool".into(), false, true, true, None),
+            ("rating".into(), "I64".into(), true, true, false, None),
+        ]
+    );
+
+    // it works with a plain oid too
+    let (count,) = r#"
+    SELECT count(*) FROM paradedb.index_schema(
+        (SELECT oid FROM pg_class WHERE relname = 'index_config_bm25_index')
+    )"#
+    .fetch_one::<(i64,)>(&mut conn);
+    assert_eq!(count, 5);
+}
+
+#[rstest]
+fn warmup_touches_every_segment(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    // a second segment
+    "INSERT INTO paradedb.bm25_search (description, rating, category) VALUES ('Bright red shoes', 4, 'Footwear')"
+        .execute(&mut conn);
+
+    let mut warmed: Vec<(String, i64, i64)> =
+        "SELECT * FROM paradedb.warmup('paradedb.bm25_search_bm25_index'::regclass)"
+            .fetch(&mut conn);
+    warmed.sort();
+
+    let mut segments: Vec<(String, i64)> =
+        "SELECT segno, num_docs FROM paradedb.index_info('paradedb.bm25_search_bm25_index')"
+            .fetch(&mut conn);
+    segments.sort();
+
+    assert_eq!(
+        warmed
+            .iter()
+            .map(|(segno, num_docs, _)| (segno.clone(), *num_docs))
+            .collect::<Vec<_>>(),
+        segments
+    );
+    assert_eq!(warmed.len(), 2);
+    assert_eq!(
+        warmed.iter().map(|(_, num_docs, _)| num_docs).sum::<i64>(),
+        42
+    );
+    assert!(warmed.iter().all(|(_, _, byte_size)| *byte_size > 0));
+
+    // and the index is no worse for it
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'shoes' ORDER BY id"
+            .fetch(&mut conn);
+    assert_eq!(rows.len(), 4);
+}
+
+#[rstest]
+fn segment_info_counts_live_and_deleted_docs(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    // a second segment
+    "INSERT INTO paradedb.bm25_search (description, rating, category) VALUES ('Bright red shoes', 4, 'Footwear')"
+        .execute(&mut conn);
+
+    let totals = |conn: &mut PgConnection| {
+        let segments: Vec<(String, i64, i64, i64)> =
+            "SELECT * FROM paradedb.segment_info('paradedb.bm25_search_bm25_index'::regclass)"
+                .fetch(conn);
+        assert!(segments.iter().all(|(_, _, _, byte_size)| *byte_size > 0));
+        let num_docs = segments
+            .iter()
+            .map(|(_, num_docs, _, _)| num_docs)
+            .sum::<i64>();
+        let num_deleted = segments
+            .iter()
+            .map(|(_, _, deleted, _)| deleted)
+            .sum::<i64>();
+        (segments.len(), num_docs, num_deleted)
+    };
+    assert_eq!(totals(&mut conn), (2, 42, 0));
+
+    // deleted rows are only marked deleted in the index once they're vacuumed
+    "DELETE FROM paradedb.bm25_search WHERE id <= 5".execute(&mut conn);
+    assert_eq!(totals(&mut conn), (2, 42, 0));
+    "VACUUM paradedb.bm25_search".execute(&mut conn);
+    assert_eq!(totals(&mut conn), (2, 37, 5));
+
+    // and it agrees with index_info
+    let mut segments: Vec<(String, i64, i64, i64)> =
+        "SELECT * FROM paradedb.segment_info('paradedb.bm25_search_bm25_index'::regclass)"
+            .fetch(&mut conn);
+    segments.sort();
+    let mut info: Vec<(String, i64, i64, i64)> = "SELECT segno, num_docs, num_deleted, byte_size FROM paradedb.index_info('paradedb.bm25_search_bm25_index')"
+        .fetch(&mut conn);
+    info.sort();
+    assert_eq!(segments, info);
+}
+
+/// Merge all of an index's segments into one, as a background merge would, which leaves out the
+/// documents that had been deleted from them
+fn merge_all_segments(conn: &mut PgConnection, index_name: &str) {
+    let index_dir_path = pg_search_index_directory_path(conn, index_name);
+    let index =
+        tantivy::Index::open_in_dir(index_dir_path.join("tantivy")).expect("index should open");
+    let mut writer = index
+        .writer::<tantivy::TantivyDocument>(15_000_000)
+        .expect("index writer should open");
+    let segment_ids = index
+        .searchable_segment_ids()
+        .expect("segments should be listed");
+    writer
+        .merge(&segment_ids)
+        .wait()
+        .expect("segments should merge");
+    writer
+        .wait_merging_threads()
+        .expect("merging threads should finish");
+}
+
+#[rstest]
+fn global_idf_scores_are_unchanged_by_merges(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    // a second segment, and deleted documents that match the query in the first
+    "INSERT INTO paradedb.bm25_search (description, rating, category) VALUES ('Bright red shoes', 4, 'Footwear')"
+        .execute(&mut conn);
+    r#"
+    DELETE FROM paradedb.bm25_search WHERE id IN (
+        SELECT id FROM paradedb.bm25_search WHERE description @@@ 'shoes' ORDER BY id LIMIT 2
+    );
+    VACUUM paradedb.bm25_search;
+    "#
+    .execute(&mut conn);
+
+    let scores = |conn: &mut PgConnection| {
+        "SELECT id, paradedb.score(id) FROM paradedb.bm25_search WHERE description @@@ 'shoes OR keyboard' ORDER BY id"
+            .fetch::<(i32, f32)>(conn)
+    };
+    let same = |a: &[(i32, f32)], b: &[(i32, f32)]| {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|((a_id, a), (b_id, b))| a_id == b_id && (a - b).abs() < 1e-6)
+    };
+
+    let before = scores(&mut conn);
+    "SET paradedb.global_idf = true".execute(&mut conn);
+    let global_before = scores(&mut conn);
+    // tantivy's own statistics still count the deleted documents
+    assert!(
+        !same(&before, &global_before),
+        "{before:?} {global_before:?}"
+    );
+
+    merge_all_segments(&mut conn, "bm25_search_bm25_index");
+    let (segments,): (i64,) =
+        "SELECT count(*) FROM paradedb.segment_info('paradedb.bm25_search_bm25_index'::regclass)"
+            .fetch_one(&mut conn);
+    assert_eq!(segments, 1);
+
+    // once they've been merged away, both agree with the scores from before the merge
+    let global_after = scores(&mut conn);
+    assert!(
+        same(&global_before, &global_after),
+        "{global_before:?} {global_after:?}"
+    );
+    "SET paradedb.global_idf = false".execute(&mut conn);
+    let after = scores(&mut conn);
+    assert!(same(&global_before, &after), "{global_before:?} {after:?}");
+}
+
+#[rstest]
+fn term_stats_counts_documents_and_occurrences(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT, rating INT);
+    INSERT INTO notes (body, rating) SELECT 'apple apple banana', 1 FROM generate_series(1, 3);
+    INSERT INTO notes (body, rating) SELECT 'banana', 2 FROM generate_series(1, 5);
+
+    CALL paradedb.create_bm25(
+        index_name => 'notes',
+        table_name => 'notes',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('rating')
+    );
+
+    -- in another segment
+    INSERT INTO notes (body, rating) VALUES ('cherry apple', 2);
+    "#
+    .execute(&mut conn);
+
+    let mut stats: Vec<(String, String, i64, i64)> = r#"
+    SELECT * FROM paradedb.term_stats(
+        'notes_bm25_index'::regclass,
+        paradedb.boolean(should => ARRAY[
+            paradedb.parse('body:apple OR body:banana OR body:durian'),
+            paradedb.term('rating', 2)
+        ])
+    )"#
+    .fetch(&mut conn);
+    stats.sort();
+
+    assert_eq!(
+        stats,
+        vec![
+            ("body".into(), "apple".into(), 4, 7),
+            ("body".into(), "banana".into(), 8, 8),
+            ("body".into(), "durian".into(), 0, 0),
+            ("rating".into(), "2".into(), 6, 6),
+        ]
+    );
+}
+
+#[rstest]
+fn estimate_selectivity_approximates_true_selectivity(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO notes (body)
+    SELECT concat_ws(' ',
+        CASE WHEN i % 2 = 0 THEN 'red' END,
+        CASE WHEN i % 5 = 0 THEN 'blue' END,
+        CASE WHEN i % 10 = 0 THEN 'green' END,
+        'plain'
+    ) FROM generate_series(1, 1000) i;
+
+    CALL paradedb.create_bm25(
+        index_name => 'notes',
+        table_name => 'notes',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    "ANALYZE notes".execute(&mut conn);
+
+    for query in [
+        "paradedb.term('body', 'red')",
+        "paradedb.term('body', 'green')",
+        "paradedb.boolean(should => ARRAY[paradedb.term('body', 'red'), paradedb.term('body', 'blue')])",
+        "paradedb.boolean(must => ARRAY[paradedb.term('body', 'red')], must_not => ARRAY[paradedb.term('body', 'green')])",
+        "paradedb.all()",
+        "paradedb.term('body', 'purple')",
+    ] {
+        let (estimate, actual): (f64, f64) = format!(
+            "SELECT paradedb.estimate_selectivity('notes_bm25_index'::regclass, {query}),
+                    (SELECT count(*) FROM notes WHERE id @@@ {query})::float8 / 1000"
+        )
+        .fetch_one(&mut conn);
+        assert!(
+            (estimate - actual).abs() <= 0.05,
+            "{query}: estimated {estimate}, but the true selectivity is {actual}"
+        );
+    }
+}
+
+#[rstest]
+fn explain_query_describes_parsed_query(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT, rating INT);
+    INSERT INTO notes (body, rating) VALUES ('Running Shoes', 4);
+
+    CALL paradedb.create_bm25(
+        index_name => 'notes',
+        table_name => 'notes',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('rating')
+    );
+    "#
+    .execute(&mut conn);
+
+    let (tree,): (serde_json::Value,) = r#"
+    SELECT paradedb.explain_query(
+        'notes_bm25_index'::regclass,
+        paradedb.boolean(
+            must => ARRAY[paradedb.parse('body:"Running Shoes"'), paradedb.term('rating', 4)],
+            must_not => ARRAY[paradedb.parse('body:sandals')]
+        )
+    )::json"#
+        .fetch_one(&mut conn);
+
+    assert_eq!(
+        tree,
+        serde_json::json!({
+            "type": "boolean",
+            "clauses": [
+                {
+                    "occur": "must",
+                    "query": {
+                        "type": "phrase",
+                        "field": "body",
+                        "terms": ["running", "shoes"],
+                        "slop": 0
+                    }
+                },
+                {
+                    "occur": "must",
+                    "query": { "type": "term", "field": "rating", "value": "4" }
+                },
+                {
+                    "occur": "must_not",
+                    "query": { "type": "term", "field": "body", "value": "sandals" }
+                }
+            ]
+        })
+    );
+
+    let (tree,): (serde_json::Value,) = r#"
+    SELECT paradedb.explain_query(
+        'notes_bm25_index'::regclass,
+        paradedb.boolean(
+            should => ARRAY[
+                paradedb.range('rating', int4range(2, NULL, '[)')),
+                paradedb.regex('body', 'run.*'),
+                paradedb.fuzzy_term('body', 'shoez', distance => 1)
+            ]
+        )
+    )::json"#
+        .fetch_one(&mut conn);
+
+    assert_eq!(
+        tree,
+        serde_json::json!({
+            "type": "boolean",
+            "clauses": [
+                {
+                    "occur": "should",
+                    "query": {
+                        "type": "range",
+                        "field": "rating",
+                        "lower_bound": { "included": "2" },
+                        "upper_bound": null
+                    }
+                },
+                {
+                    "occur": "should",
+                    "query": { "type": "regex", "field": "body", "pattern": "run.*" }
+                },
+                {
+                    "occur": "should",
+                    "query": {
+                        "type": "fuzzy",
+                        "field": "body",
+                        "value": "shoez",
+                        "distance": 1,
+                        "transposition_cost_one": true,
+                        "prefix": false
+                    }
+                }
+            ]
+        })
+    );
+
+    let (tree,): (serde_json::Value,) =
+        "SELECT paradedb.explain_query('notes_bm25_index'::regclass, paradedb.all())::json"
+            .fetch_one(&mut conn);
+    assert_eq!(tree, serde_json::json!({ "type": "all" }));
+}
+
+#[rstest]
+fn analyze_query_returns_the_tokens_of_a_fields_analyzer(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE notes (id SERIAL PRIMARY KEY, body TEXT, stemmed TEXT);
+    INSERT INTO notes (body, stemmed) VALUES ('Running Shoes', 'Running Shoes');
+
+    CALL paradedb.create_bm25(
+        index_name => 'notes',
+        table_name => 'notes',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+            || paradedb.field('stemmed', tokenizer => paradedb.tokenizer('default', stemmer => 'English'))
+    );
+    "#
+    .execute(&mut conn);
+
+    // the stemmed field lowercases and stems its tokens
+    let tokens: Vec<(String, i32)> = "
+    SELECT * FROM paradedb.analyze_query('notes_bm25_index'::regclass, 'stemmed', 'Running Shoes')"
+        .fetch(&mut conn);
+    assert_eq!(tokens, vec![("run".into(), 0), ("shoe".into(), 1)]);
+
+    // which is what the same tokenizer does outside of the index
+    let expected: Vec<(String, i32)> = "
+    SELECT * FROM paradedb.tokenize(paradedb.tokenizer('default', stemmer => 'English'), 'Running Shoes')"
+        .fetch(&mut conn);
+    assert_eq!(tokens, expected);
+
+    // and the other field only lowercases them
+    let tokens: Vec<(String, i32)> = "
+    SELECT * FROM paradedb.analyze_query('notes_bm25_index'::regclass, 'body', 'Running Shoes')"
+        .fetch(&mut conn);
+    assert_eq!(tokens, vec![("running".into(), 0), ("shoes".into(), 1)]);
+
+    // the tokens are the terms a query over the field searches for
+    let (count,): (i64,) = "
+    SELECT COUNT(*) FROM notes WHERE id @@@ paradedb.term('stemmed', 'shoe')"
+        .fetch_one(&mut conn);
+    assert_eq!(count, 1);
+
+    let result =
+        "SELECT * FROM paradedb.analyze_query('notes_bm25_index'::regclass, 'missing', 'shoes')"
+            .fetch_result::<(String, i32)>(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("field `missing` is not part of the index"));
+}
@@ -177,3 +177,69 @@ async fn test_statement_level_locking(database: Db) -> Result<()> {
 
     Ok(())
 }
+
+/// Inserting one row at a time gives the index a new segment for every row, which keeps it
+/// merging segments in the background.  A scan running alongside should always see one coherent
+/// set of segments, rather than both the segments being merged and the segment they merge into.
+#[rstest]
+#[tokio::test]
+async fn test_scans_during_merges_see_consistent_counts(database: Db) -> Result<()> {
+    let mut conn = database.connection().await;
+
+    "CREATE EXTENSION pg_search;
+    CREATE TABLE merge_items (
+      id SERIAL PRIMARY KEY,
+      description TEXT
+    );
+    CALL paradedb.create_bm25(
+        table_name => 'merge_items',
+        index_name => 'merge_items',
+        schema_name => 'public',
+        key_field => 'id',
+        text_fields => paradedb.field('description')
+    );"
+    .execute(&mut conn);
+
+    let mut writer = database.connection().await;
+    let mut reader = database.connection().await;
+
+    let writes = async move {
+        for i in 0..100 {
+            format!("INSERT INTO merge_items (description) VALUES ('merge item {i}')")
+                .execute_async(&mut writer)
+                .await;
+        }
+    };
+
+    // the heap and the index are counted as of the same snapshot, and the index's total isn't
+    // deduplicated by ctid, so a document counted twice would show
+    let reads = async move {
+        for _ in 0..300 {
+            let (heap, scanned, total): (i64, i64, i64) = sqlx::query_as(
+                "SELECT
+                    (SELECT count(*) FROM merge_items WHERE description LIKE 'merge%'),
+                    (SELECT count(*) FROM merge_items WHERE description @@@ 'merge'),
+                    (SELECT coalesce(max(total), 0) FROM paradedb.search_with_total(
+                        'merge_items_bm25_index'::regclass,
+                        paradedb.term('description', 'merge'),
+                        1
+                    ))",
+            )
+            .fetch_one(&mut reader)
+            .await
+            .expect("scanning during merges should succeed");
+            assert_eq!(scanned, heap);
+            assert_eq!(total, heap);
+        }
+    };
+
+    join!(writes, reads);
+
+    let count: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM merge_items WHERE description @@@ 'merge'")
+            .fetch_one(&mut conn)
+            .await?;
+    assert_eq!(count, 100);
+
+    Ok(())
+}
@@ -24,6 +24,7 @@ use fixtures::*;
 use pretty_assertions::assert_eq;
 use rstest::*;
 use sqlx::{PgConnection, Row};
+use std::collections::HashMap;
 
 #[rstest]
 fn boolean_tree(mut conn: PgConnection) {
@@ -1101,3 +1102,2518 @@ async fn prepared_statement_replanning(mut conn: PgConnection) {
             .collect();
     }
 }
+
+#[rstest]
+fn boosting_demotes_negative_matches(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let plain: Vec<(i32, f32)> = r#"
+    SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.term('description', 'shoes')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(
+        plain.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![3, 4, 5]
+    );
+
+    let boosted: Vec<(i32, f32)> = r#"
+    SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.boosting(
+        positive => paradedb.term('description', 'shoes'),
+        negative => paradedb.term('description', 'running'),
+        negative_boost => 0.1
+    )
+    ORDER BY id"#
+        .fetch(&mut conn);
+
+    // documents matching the negative query are still returned, but with a demoted score
+    assert_eq!(
+        boosted.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![3, 4, 5]
+    );
+    for ((id, plain_score), (_, boosted_score)) in plain.iter().zip(boosted.iter()) {
+        if *id == 3 {
+            assert!((boosted_score - plain_score * 0.1).abs() < f32::EPSILON);
+        } else {
+            assert_eq!(boosted_score, plain_score);
+        }
+    }
+
+    // and the demoted document now ranks last
+    let ranked: Vec<(i32,)> = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.boosting(
+        positive => paradedb.term('description', 'shoes'),
+        negative => paradedb.term('description', 'running'),
+        negative_boost => 0.1
+    )
+    ORDER BY paradedb.score(id) DESC LIMIT 3"#
+        .fetch(&mut conn);
+    assert_eq!(ranked.last(), Some(&(3,)));
+
+    let result = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.boosting(
+        paradedb.term('description', 'shoes'),
+        paradedb.term('description', 'running'),
+        -1.0
+    )"#
+    .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn boolean_minimum_should_match(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let matching_ids = |minimum_should_match: &str, conn: &mut PgConnection| {
+        format!(
+            r#"
+            SELECT id FROM paradedb.bm25_search
+            WHERE bm25_search @@@ paradedb.boolean(
+                should => ARRAY[
+                    paradedb.term('description', 'shoes'),
+                    paradedb.term('description', 'running'),
+                    paradedb.term('description', 'white')
+                ],
+                minimum_should_match => {minimum_should_match}
+            )
+            ORDER BY id"#
+        )
+        .fetch::<(i32,)>(conn)
+        .into_iter()
+        .map(|(id,)| id)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(matching_ids("NULL", &mut conn), vec![3, 4, 5]);
+    assert_eq!(matching_ids("'1'", &mut conn), vec![3, 4, 5]);
+    assert_eq!(matching_ids("'2'", &mut conn), vec![3, 4]);
+    assert_eq!(matching_ids("'3'", &mut conn), Vec::<i32>::new());
+    assert_eq!(matching_ids("'4'", &mut conn), Vec::<i32>::new());
+
+    // percentages of the should clauses are rounded down
+    assert_eq!(matching_ids("'50%'", &mut conn), vec![3, 4, 5]);
+    assert_eq!(matching_ids("'67%'", &mut conn), vec![3, 4]);
+    assert_eq!(matching_ids("'100%'", &mut conn), Vec::<i32>::new());
+
+    // with a must clause, should clauses are otherwise optional
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.boolean(
+        must => ARRAY[paradedb.term('description', 'shoes')],
+        should => ARRAY[paradedb.term('description', 'running'), paradedb.term('description', 'white')]
+    )
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(3,), (4,), (5,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.boolean(
+        must => ARRAY[paradedb.term('description', 'shoes')],
+        should => ARRAY[paradedb.term('description', 'running'), paradedb.term('description', 'white')],
+        minimum_should_match => '1'
+    )
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(3,), (4,)]);
+
+    // and invalid values are rejected
+    let result = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.boolean(
+        should => ARRAY[paradedb.term('description', 'shoes')],
+        minimum_should_match => '150%'
+    )"#
+    .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn snapshot_as_of_older_generation(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let (generation,): (i64,) =
+        "SELECT paradedb.index_generation('paradedb.bm25_search_bm25_index'::regclass)"
+            .fetch_one(&mut conn);
+
+    let pinned_query = format!(
+        r#"
+        SELECT id FROM paradedb.bm25_search
+        WHERE bm25_search @@@ paradedb.snapshot(
+            query => paradedb.term('description', 'shoes'),
+            as_of => {generation}
+        )
+        ORDER BY id"#
+    );
+    let before: Vec<(i32,)> = pinned_query.fetch(&mut conn);
+    assert_eq!(before, vec![(3,), (4,), (5,)]);
+
+    "INSERT INTO paradedb.bm25_search (description, rating, category) VALUES ('Bright red shoes', 4, 'Footwear')"
+        .execute(&mut conn);
+
+    let (new_generation,): (i64,) =
+        "SELECT paradedb.index_generation('paradedb.bm25_search_bm25_index'::regclass)"
+            .fetch_one(&mut conn);
+    assert!(new_generation > generation);
+
+    // the new row is visible to queries against the current generation...
+    let current: Vec<(i32,)> = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.term('description', 'shoes')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(current.len(), 4);
+
+    // ... but not to the query pinned to the older one
+    let after: Vec<(i32,)> = pinned_query.fetch(&mut conn);
+    assert_eq!(after, before);
+
+    // generations that never existed are an error
+    let result = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.snapshot(
+        query => paradedb.term('description', 'shoes'),
+        as_of => 999999999
+    )"#
+    .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn collapse_keeps_best_per_value(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE listings (id SERIAL PRIMARY KEY, seller TEXT, title TEXT);
+    INSERT INTO listings (seller, title) VALUES
+        ('alice', 'rust book'),
+        ('alice', 'rust rust rust'),
+        ('bob', 'rust'),
+        ('bob', 'a long title about rust and other things'),
+        ('carol', 'rust rust'),
+        ('dave', 'python');
+
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('title') || paradedb.field('seller', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    let all: Vec<(i32, String, f32)> = r#"
+        SELECT id, seller, paradedb.score(id) FROM listings WHERE title @@@ 'rust'"#
+        .fetch(&mut conn);
+    let mut best = std::collections::HashMap::<String, f32>::new();
+    for (_, seller, score) in &all {
+        let entry = best.entry(seller.clone()).or_insert(*score);
+        *entry = entry.max(*score);
+    }
+    assert_eq!(best.len(), 3);
+
+    let query = r#"
+        SELECT id, seller, paradedb.score(id) FROM listings
+        WHERE id @@@ paradedb.collapse(paradedb.parse('title:rust'), collapse => 'seller')
+        ORDER BY paradedb.score(id) DESC"#;
+
+    let collapsed: Vec<(i32, String, f32)> = format!("{query} LIMIT 10").fetch(&mut conn);
+    let mut sellers = collapsed
+        .iter()
+        .map(|(_, seller, _)| seller.clone())
+        .collect::<Vec<_>>();
+    sellers.sort();
+    assert_eq!(sellers, vec!["alice", "bob", "carol"]);
+    for (_, seller, score) in &collapsed {
+        assert_eq!(*score, best[seller]);
+    }
+    assert!(collapsed.windows(2).all(|w| w[0].2 >= w[1].2));
+
+    // the limit counts the collapsed results
+    let top: Vec<(i32, String, f32)> = format!("{query} LIMIT 2").fetch(&mut conn);
+    assert_eq!(top, collapsed[..2].to_vec());
+
+    let (plan,): (serde_json::Value,) =
+        format!("EXPLAIN (FORMAT JSON) {query} LIMIT 2").fetch_one(&mut conn);
+    assert!(plan.to_string().contains("Collapse Field"));
+
+    // only fast fields can be collapsed by
+    let result = r#"
+    SELECT id FROM listings
+    WHERE id @@@ paradedb.collapse(paradedb.parse('title:rust'), collapse => 'title')"#
+        .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn dedup_keeps_top_scoring_row_per_key(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, product_code TEXT, title TEXT);
+    INSERT INTO products (product_code, title) VALUES
+        ('A1', 'shoe'),
+        ('A1', 'shoe shoe shoe'),
+        ('B2', 'shoe shoe'),
+        ('B2', 'a long title about a shoe and other things'),
+        ('C3', 'shoe shoe shoe shoe'),
+        ('C3', 'shoe shoe shoe shoe shoe'),
+        ('D4', 'boot');
+
+    CALL paradedb.create_bm25(
+        index_name => 'products',
+        table_name => 'products',
+        key_field => 'id',
+        text_fields => paradedb.field('title') || paradedb.field('product_code', fast => true)
+    );
+
+    -- the best match for C3 can't be seen, so the next best represents it
+    DELETE FROM products WHERE id = 6;
+    "#
+    .execute(&mut conn);
+
+    let all: Vec<(i32, String, f32)> = r#"
+        SELECT id, product_code, paradedb.score(id) FROM products WHERE title @@@ 'shoe'"#
+        .fetch(&mut conn);
+    let mut best = std::collections::HashMap::<String, (i32, f32)>::new();
+    for (id, code, score) in &all {
+        let entry = best.entry(code.clone()).or_insert((*id, *score));
+        if *score > entry.1 {
+            *entry = (*id, *score);
+        }
+    }
+    assert_eq!(best.len(), 3);
+
+    let query = r#"
+        SELECT id, product_code, paradedb.score(id) FROM products
+        WHERE id @@@ paradedb.dedup(paradedb.parse('title:shoe'), dedup_by => 'product_code')"#;
+
+    // with and without a top-N, only the highest-scoring row for each key is returned
+    for query in [
+        format!("{query} ORDER BY paradedb.score(id) DESC LIMIT 10"),
+        query.to_string(),
+    ] {
+        let deduped: Vec<(i32, String, f32)> = query.fetch(&mut conn);
+        assert_eq!(deduped.len(), 3, "{query}: {deduped:?}");
+        for (id, code, score) in &deduped {
+            assert_eq!((*id, *score), best[code], "{query}: {deduped:?}");
+        }
+    }
+    assert!(best.values().any(|(id, _)| *id == 5));
+
+    // the limit counts the deduplicated rows
+    let deduped: Vec<(i32, String, f32)> =
+        format!("{query} ORDER BY paradedb.score(id) DESC LIMIT 10").fetch(&mut conn);
+    let top: Vec<(i32, String, f32)> =
+        format!("{query} ORDER BY paradedb.score(id) DESC LIMIT 2").fetch(&mut conn);
+    assert_eq!(top, deduped[..2].to_vec());
+
+    let (plan,): (serde_json::Value,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query} ORDER BY paradedb.score(id) DESC LIMIT 10")
+            .fetch_one(&mut conn);
+    assert!(plan.to_string().contains("Dedup Field"));
+    assert!(
+        plan.to_string().contains(r#""   Duplicates Removed":2"#),
+        "{plan}"
+    );
+
+    // only fast fields can be deduplicated by
+    let result = r#"
+    SELECT id FROM products
+    WHERE id @@@ paradedb.dedup(paradedb.parse('title:shoe'), dedup_by => 'title')"#
+        .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn raw_query_from_json(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.raw_query('{"Term": {"field": "description", "value": "shoes"}}')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(3,), (4,), (5,)]);
+
+    // fields that aren't part of the index are an error...
+    let result = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.raw_query('{"Term": {"field": "nonexistent", "value": "shoes"}}')"#
+        .execute_result(&mut conn);
+    assert!(result.unwrap_err().to_string().contains("nonexistent"));
+
+    // ... and so are unknown keys and invalid JSON
+    let result = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.raw_query('{"Term": {"field": "description", "valu": "shoes"}}')"#
+        .execute_result(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("invalid raw query"));
+    let result = r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE bm25_search @@@ paradedb.raw_query('{"Term": ')"#
+        .execute_result(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("invalid raw query"));
+}
+
+#[rstest]
+fn search_json_returns_hits_in_score_order(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let expected: Vec<(i32, f32, i32)> = r#"
+    SELECT id, paradedb.score(id), rating FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard'
+    ORDER BY paradedb.score(id) DESC, id
+    LIMIT 4"#
+        .fetch(&mut conn);
+    assert_eq!(expected.len(), 4);
+
+    let query = r#"
+    SELECT paradedb.search_json(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        paradedb.parse('description:shoes OR description:keyboard'),
+        limit => 4,
+        fields => ARRAY['rating']
+    )::json"#;
+    let (hits,): (serde_json::Value,) = query.fetch_one(&mut conn);
+    let hits = hits.as_array().unwrap();
+    assert_eq!(hits.len(), 4);
+
+    let scores = hits
+        .iter()
+        .map(|hit| hit["score"].as_f64().unwrap())
+        .collect::<Vec<_>>();
+    assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+    for (hit, (_, score, _)) in hits.iter().zip(&expected) {
+        assert!((hit["score"].as_f64().unwrap() - *score as f64).abs() < 1e-5);
+        let (_, _, rating) = expected
+            .iter()
+            .find(|(id, _, _)| hit["key"] == serde_json::json!(id))
+            .expect("every hit should be one of the top rows");
+        assert_eq!(hit["fields"], serde_json::json!({ "rating": rating }));
+    }
+
+    // deleted rows are skipped
+    let first_key = hits[0]["key"].as_i64().unwrap();
+    format!("DELETE FROM paradedb.bm25_search WHERE id = {first_key}").execute(&mut conn);
+    let (after,): (serde_json::Value,) = query.fetch_one(&mut conn);
+    let after = after.as_array().unwrap();
+    assert_eq!(after.len(), 4);
+    assert!(after
+        .iter()
+        .all(|hit| hit["key"].as_i64() != Some(first_key)));
+
+    // only fast fields can be returned
+    let result = r#"
+    SELECT paradedb.search_json(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        paradedb.parse('description:shoes'),
+        limit => 4,
+        fields => ARRAY['description']
+    )"#
+    .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn search_with_total_returns_page_and_count(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let (count,): (i64,) = r#"
+    SELECT count(*) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'"#
+        .fetch_one(&mut conn);
+    assert!(count > 4);
+
+    let expected: Vec<(i32, f32)> = r#"
+    SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'
+    ORDER BY paradedb.score(id) DESC, ctid
+    LIMIT 3 OFFSET 1"#
+        .fetch(&mut conn);
+
+    let query = r#"
+    SELECT b.id, s.score, s.total FROM paradedb.search_with_total(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        paradedb.parse('description:shoes OR description:keyboard OR description:plastic'),
+        "limit" => 3,
+        "offset" => 1
+    ) s
+    JOIN paradedb.bm25_search b ON b.ctid = s.ctid
+    ORDER BY s.score DESC, s.ctid"#;
+    let page: Vec<(i32, f32, i64)> = query.fetch(&mut conn);
+    assert_eq!(page.len(), 3);
+    for ((id, score, total), (expected_id, expected_score)) in page.iter().zip(&expected) {
+        assert_eq!(id, expected_id);
+        assert!((score - expected_score).abs() < 1e-5);
+        assert_eq!(*total, count);
+    }
+
+    // deleted rows are neither returned nor counted
+    let (first_id, _, _) = page[0];
+    format!("DELETE FROM paradedb.bm25_search WHERE id = {first_id}").execute(&mut conn);
+    let after: Vec<(i32, f32, i64)> = query.fetch(&mut conn);
+    assert_eq!(after.len(), 3);
+    assert!(after
+        .iter()
+        .all(|(id, _, total)| *id != first_id && *total == count - 1));
+}
+
+#[rstest]
+fn max_result_window_caps_offset_plus_limit(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    "SET paradedb.max_result_window = 10".execute(&mut conn);
+
+    // a page that ends inside the window is returned
+    r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'
+    ORDER BY paradedb.score(id) DESC
+    LIMIT 5 OFFSET 5"#
+        .fetch::<(i32,)>(&mut conn);
+
+    // but one that ends past it is an error, whether or not it's sorted
+    for query in [
+        "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'shoes' ORDER BY paradedb.score(id) DESC LIMIT 5 OFFSET 6",
+        "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'shoes' LIMIT 11",
+    ] {
+        let error = query.execute_result(&mut conn).unwrap_err().to_string();
+        assert!(
+            error.contains("is more than paradedb.max_result_window allows, which is 10"),
+            "{error}"
+        );
+        assert!(error.contains("search_after"), "{error}");
+    }
+
+    // and so does search_with_total
+    let error = r#"
+    SELECT * FROM paradedb.search_with_total(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        paradedb.parse('description:shoes'),
+        "limit" => 5,
+        "offset" => 6
+    )"#
+    .execute_result(&mut conn)
+    .unwrap_err()
+    .to_string();
+    assert!(
+        error.contains("is more than paradedb.max_result_window allows"),
+        "{error}"
+    );
+
+    // queries that don't search the index aren't capped
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM paradedb.bm25_search ORDER BY id LIMIT 20".fetch(&mut conn);
+    assert!(rows.len() > 10);
+
+    "RESET paradedb.max_result_window".execute(&mut conn);
+    r#"
+    SELECT id FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'
+    ORDER BY paradedb.score(id) DESC
+    LIMIT 5 OFFSET 6"#
+        .fetch::<(i32,)>(&mut conn);
+}
+
+#[rstest]
+fn search_tids_returns_index_hits_without_reading_the_table(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let expected: Vec<(String, f32)> = r#"
+    SELECT ctid::text, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'
+    ORDER BY paradedb.score(id) DESC, ctid
+    LIMIT 4"#
+        .fetch(&mut conn);
+    assert_eq!(expected.len(), 4);
+
+    let query = r#"
+    SELECT ctid::text, score FROM paradedb.search_tids(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        paradedb.parse('description:shoes OR description:keyboard OR description:plastic'),
+        "limit" => 4
+    )"#;
+    let tids: Vec<(String, f32)> = query.fetch(&mut conn);
+    assert_eq!(tids.len(), expected.len());
+    for ((ctid, score), (expected_ctid, expected_score)) in tids.iter().zip(&expected) {
+        assert_eq!(ctid, expected_ctid);
+        assert!((score - expected_score).abs() < 1e-5);
+    }
+
+    // the table isn't read, so a deleted row that hasn't been vacuumed is still returned
+    format!(
+        "DELETE FROM paradedb.bm25_search WHERE ctid = '{}'",
+        expected[0].0
+    )
+    .execute(&mut conn);
+    let after: Vec<(String, f32)> = query.fetch(&mut conn);
+    assert_eq!(after, tids);
+
+    "VACUUM paradedb.bm25_search".execute(&mut conn);
+    let vacuumed: Vec<(String, f32)> = query.fetch(&mut conn);
+    assert!(vacuumed.iter().all(|(ctid, _)| *ctid != expected[0].0));
+}
+
+#[rstest]
+fn same_field_clauses_all_or_any(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM paradedb.bm25_search WHERE bm25_search @@@ '{query}' ORDER BY id")
+            .fetch::<(i32,)>(conn)
+    };
+    let both = ids("description:running AND description:shoes", &mut conn);
+    let either = ids("description:running OR description:shoes", &mut conn);
+    assert!(!both.is_empty());
+    assert!(both.len() < either.len());
+
+    // by default, every required clause must match the field
+    let query = "description:running AND description:shoes";
+    assert_eq!(ids(query, &mut conn), both);
+
+    // with 'any', one of a field's required clauses is enough
+    "SET paradedb.same_field_clauses = 'any'".execute(&mut conn);
+    assert_eq!(ids(query, &mut conn), either);
+    assert_eq!(
+        ids("+description:running +description:shoes", &mut conn),
+        either
+    );
+
+    // but the clauses over each field are still all required
+    let across_fields = "description:running AND description:shoes AND category:footwear";
+    let expected = ids(
+        "(description:running OR description:shoes) AND category:footwear",
+        &mut conn,
+    );
+    assert_eq!(ids(across_fields, &mut conn), expected);
+
+    "SET paradedb.same_field_clauses = 'all'".execute(&mut conn);
+    let expected = ids(
+        "(description:running AND description:shoes) AND category:footwear",
+        &mut conn,
+    );
+    assert_eq!(ids(across_fields, &mut conn), expected);
+
+    assert!("SET paradedb.same_field_clauses = 'some'"
+        .execute_result(&mut conn)
+        .is_err());
+}
+
+#[rstest]
+fn on_invalid_query_text_sanitizes_or_errors(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let ids = |condition: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM paradedb.bm25_search WHERE {condition} ORDER BY id")
+            .fetch_result::<(i32,)>(conn)
+    };
+    let expected = ids("description @@@ 'running shoes'", &mut conn).unwrap();
+    assert!(!expected.is_empty());
+
+    // Postgres already rejects text that isn't valid utf8, so what reaches a query is a control
+    // character, or the replacement character that bytes it couldn't decode were turned into
+    let malformed = [
+        "description @@@ ('running' || chr(1) || 'shoes')",
+        "description @@@ convert_from(decode('72756e6e696e67efbfbd73686f6573', 'hex'), 'UTF8')",
+        "id @@@ paradedb.parse('description:running' || chr(27) || 'description:shoes')",
+        "id @@@ paradedb.fuzzy_phrase('description', 'running' || chr(65533) || 'shoes', distance => 0)",
+    ];
+
+    // by default, they're searched for as spaces
+    for condition in malformed {
+        assert_eq!(ids(condition, &mut conn).unwrap(), expected, "{condition}");
+    }
+    assert_eq!(
+        ids("description @@@ E'running\tshoes'", &mut conn).unwrap(),
+        expected
+    );
+
+    // or they raise an error that says where they are
+    "SET paradedb.on_invalid_query_text = 'error'".execute(&mut conn);
+    for condition in malformed {
+        let error = ids(condition, &mut conn).unwrap_err().to_string();
+        assert!(
+            error.contains("control character or invalid unicode at character")
+                && error.contains("paradedb.on_invalid_query_text is 'error'"),
+            "{condition}: {error}"
+        );
+    }
+    let error = ids(
+        "description @@@ ('running' || chr(1) || 'shoes')",
+        &mut conn,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(error.contains("at character 8"), "{error}");
+
+    // but whitespace is always fine
+    assert_eq!(
+        ids("description @@@ E'running\tshoes'", &mut conn).unwrap(),
+        expected
+    );
+    assert_eq!(
+        ids("description @@@ 'running shoes'", &mut conn).unwrap(),
+        expected
+    );
+}
+
+#[rstest]
+fn explain_analyze_breaks_down_scores(mut conn: PgConnection) {
+    use serde_json::Value;
+
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = r#"
+    SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard'
+    ORDER BY paradedb.score(id) DESC
+    LIMIT 5"#;
+    let explanations = |plan: &Value| {
+        let scan = plan
+            .pointer("/0/Plan/Plans/0")
+            .and_then(Value::as_object)
+            .expect("plan should have a custom scan under its limit");
+        scan.iter()
+            .filter(|(key, _)| key.starts_with("Score of ("))
+            .map(|(key, value)| {
+                let explanation: Value = serde_json::from_str(value.as_str().unwrap())
+                    .expect("score explanation should be json");
+                (key.clone(), explanation)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // nothing is explained by default
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert!(explanations(&plan).is_empty(), "{plan:#?}");
+
+    "SET paradedb.explain_scores = 2".execute(&mut conn);
+    let (plan,) =
+        format!("EXPLAIN (ANALYZE, FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    eprintln!("{plan:#?}");
+    let explained = explanations(&plan);
+    assert_eq!(explained.len(), 2);
+
+    // the sampled rows are the first ones returned, and each is explained with its own score
+    let expected: Vec<(String, f32)> = r#"
+    SELECT ctid::text, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard'
+    ORDER BY paradedb.score(id) DESC
+    LIMIT 2"#
+        .fetch(&mut conn);
+    for (ctid, score) in &expected {
+        let (_, explanation) = explained
+            .iter()
+            .find(|(key, _)| *key == format!("Score of {ctid}"))
+            .unwrap_or_else(|| panic!("row {ctid} should have its score explained"));
+        let value = explanation["value"].as_f64().unwrap() as f32;
+        assert!((value - score).abs() < 1e-5, "{explanation:#?}");
+    }
+
+    // a plain EXPLAIN doesn't run the query, so there's nothing to explain
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    assert!(explanations(&plan).is_empty(), "{plan:#?}");
+}
+
+#[rstest]
+fn score_histogram_counts_every_match(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let scores: Vec<(f32,)> = r#"
+    SELECT paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'"#
+        .fetch(&mut conn);
+    assert!(scores.len() > 4);
+    let lowest = scores.iter().map(|(s,)| *s).fold(f32::INFINITY, f32::min);
+    let highest = scores
+        .iter()
+        .map(|(s,)| *s)
+        .fold(f32::NEG_INFINITY, f32::max);
+    assert!(highest > lowest);
+
+    let histogram: Vec<(i32, f32, f32, i64)> = r#"
+    SELECT * FROM paradedb.score_histogram(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        paradedb.parse('description:shoes OR description:keyboard OR description:plastic'),
+        buckets => 4
+    ) ORDER BY bucket"#
+        .fetch(&mut conn);
+    assert_eq!(histogram.len(), 4);
+    assert_eq!(
+        histogram
+            .iter()
+            .map(|(bucket, ..)| *bucket)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+
+    // the buckets cover every score, one after the other, and count every match once
+    assert!((histogram[0].1 - lowest).abs() < 1e-5, "{histogram:?}");
+    assert!((histogram[3].2 - highest).abs() < 1e-5, "{histogram:?}");
+    for window in histogram.windows(2) {
+        assert!((window[0].2 - window[1].1).abs() < 1e-5, "{histogram:?}");
+    }
+    let total: i64 = histogram.iter().map(|(.., count)| count).sum();
+    assert_eq!(total, scores.len() as i64);
+    for (_, lower, upper, count) in &histogram {
+        let expected = scores
+            .iter()
+            .filter(|(s,)| s >= lower && (s < upper || (*upper - highest).abs() < 1e-5))
+            .count();
+        assert!(
+            (*count - expected as i64).abs() <= 1,
+            "{histogram:?} {scores:?}"
+        );
+    }
+    assert!(histogram[0].3 > 0 && histogram[3].3 > 0, "{histogram:?}");
+
+    let result = r#"
+    SELECT * FROM paradedb.score_histogram(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        paradedb.parse('description:shoes'),
+        buckets => 0
+    )"#
+    .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn numeric_histogram_buckets_fast_field_values(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE widgets (id SERIAL PRIMARY KEY, name TEXT, price INT);
+    INSERT INTO widgets (name, price) VALUES
+        ('widget', 5), ('widget', 12), ('widget', 18), ('widget', 20),
+        ('widget', 47), ('widget', NULL), ('gadget', 15);
+    CALL paradedb.create_bm25(
+        index_name => 'widgets',
+        table_name => 'widgets',
+        key_field => 'id',
+        text_fields => paradedb.field('name'),
+        numeric_fields => paradedb.field('price')
+    );
+    "#
+    .execute(&mut conn);
+
+    let histogram = |args: &str, conn: &mut PgConnection| {
+        format!(
+            "SELECT * FROM paradedb.numeric_histogram('widgets_bm25_index'::regclass, paradedb.term('name', 'widget'), 'price', {args})"
+        )
+        .fetch::<(f64, f64, i64)>(conn)
+    };
+
+    // every bucket between the lowest value's and the highest's is returned, and rows without a
+    // value or that don't match aren't counted
+    assert_eq!(
+        histogram("10", &mut conn),
+        vec![
+            (0.0, 10.0, 1),
+            (10.0, 20.0, 2),
+            (20.0, 30.0, 1),
+            (30.0, 40.0, 0),
+            (40.0, 50.0, 1)
+        ]
+    );
+
+    // the offset shifts the bucket boundaries
+    assert_eq!(
+        histogram(r#""offset" => 5, "interval" => 10"#, &mut conn),
+        vec![
+            (5.0, 15.0, 2),
+            (15.0, 25.0, 2),
+            (25.0, 35.0, 0),
+            (35.0, 45.0, 0),
+            (45.0, 55.0, 1)
+        ]
+    );
+
+    // deleted rows aren't counted either
+    "DELETE FROM widgets WHERE price = 47".execute(&mut conn);
+    assert_eq!(
+        histogram("10", &mut conn),
+        vec![(0.0, 10.0, 1), (10.0, 20.0, 2), (20.0, 30.0, 1)]
+    );
+
+    let error = "SELECT * FROM paradedb.numeric_histogram('widgets_bm25_index'::regclass, paradedb.all(), 'price', 0)"
+        .execute_result(&mut conn)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("interval must be greater than zero"),
+        "{error}"
+    );
+    let error = "SELECT * FROM paradedb.numeric_histogram('widgets_bm25_index'::regclass, paradedb.all(), 'name', 10)"
+        .execute_result(&mut conn)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("is not a numeric fast field"), "{error}");
+}
+
+#[rstest]
+fn score_matrix_matches_individual_scores(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let queries = [
+        "description:shoes OR description:keyboard",
+        "description:keyboard^2 OR description:shoes OR category:electronics",
+    ];
+
+    // every candidate matches both queries
+    let candidates: Vec<(i32,)> = format!(
+        "SELECT id FROM paradedb.bm25_search WHERE description @@@ '{}' ORDER BY id LIMIT 3",
+        queries[0]
+    )
+    .fetch(&mut conn);
+    assert_eq!(candidates.len(), 3);
+    let ids = candidates
+        .iter()
+        .map(|(id,)| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut expected = Vec::new();
+    for (query_idx, query) in queries.iter().enumerate() {
+        let scores: Vec<(i32, f32)> = format!(
+            "SELECT id, paradedb.score(id) FROM paradedb.bm25_search WHERE id @@@ paradedb.parse('{query}')"
+        )
+        .fetch(&mut conn);
+        for (id,) in &candidates {
+            let (_, score) = scores
+                .iter()
+                .find(|(i, _)| i == id)
+                .expect("candidate should match");
+            expected.push((query_idx as i32 + 1, *id, *score));
+        }
+    }
+    expected.sort_by_key(|(query_idx, id, _)| (*query_idx, *id));
+
+    let matrix: Vec<(i32, i32, f32)> = format!(
+        r#"
+    SELECT m.query_idx, b.id, m.score FROM paradedb.score_matrix(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        ARRAY[paradedb.parse('{}'), paradedb.parse('{}')],
+        ARRAY(SELECT ctid FROM paradedb.bm25_search WHERE id IN ({ids}))
+    ) m
+    JOIN paradedb.bm25_search b ON b.ctid = m.ctid
+    ORDER BY m.query_idx, b.id"#,
+        queries[0], queries[1]
+    )
+    .fetch(&mut conn);
+    assert_eq!(matrix.len(), 6, "{matrix:?}");
+    for ((query_idx, id, score), (expected_idx, expected_id, expected_score)) in
+        matrix.iter().zip(&expected)
+    {
+        assert_eq!((query_idx, id), (expected_idx, expected_id));
+        assert!(
+            (score - expected_score).abs() < 1e-5,
+            "{matrix:?} {expected:?}"
+        );
+    }
+
+    // a candidate a query doesn't match has no score for it
+    let (count,): (i64,) = r#"
+    SELECT count(*) FROM paradedb.score_matrix(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        ARRAY[paradedb.parse('description:shoes')],
+        ARRAY(SELECT ctid FROM paradedb.bm25_search WHERE NOT description @@@ 'shoes')
+    )"#
+    .fetch_one(&mut conn);
+    assert_eq!(count, 0);
+}
+
+#[rstest]
+fn search_many_matches_individual_searches(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let queries = [
+        "description:shoes OR description:keyboard",
+        "category:electronics",
+        "description:nonexistent",
+    ];
+
+    let mut expected = Vec::new();
+    for (query_idx, query) in queries.iter().enumerate() {
+        let page: Vec<(String, f32)> = format!(
+            r#"
+        SELECT ctid::text, score FROM paradedb.search_with_total(
+            'paradedb.bm25_search_bm25_index'::regclass,
+            paradedb.parse('{query}'),
+            "limit" => 3
+        )"#
+        )
+        .fetch(&mut conn);
+        expected.extend(
+            page.into_iter()
+                .enumerate()
+                .map(|(rank, (ctid, score))| (query_idx as i32 + 1, ctid, score, rank as i64 + 1)),
+        );
+    }
+    assert_eq!(expected.len(), 6);
+
+    // all the queries are searched in the one call
+    let many: Vec<(i32, String, f32, i64)> = format!(
+        r#"
+    SELECT query_idx, ctid::text, score, rank FROM paradedb.search_many(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        ARRAY[paradedb.parse('{}'), paradedb.parse('{}'), paradedb.parse('{}')],
+        "limit" => 3
+    )
+    ORDER BY query_idx, rank"#,
+        queries[0], queries[1], queries[2]
+    )
+    .fetch(&mut conn);
+    assert_eq!(many.len(), expected.len(), "{many:?}");
+    for ((query_idx, ctid, score, rank), (e_idx, e_ctid, e_score, e_rank)) in
+        many.iter().zip(&expected)
+    {
+        assert_eq!((query_idx, ctid, rank), (e_idx, e_ctid, e_rank));
+        assert!((score - e_score).abs() < 1e-5, "{many:?} {expected:?}");
+    }
+
+    // the one reader they share can't be at two generations at once
+    let result = r#"
+    SELECT * FROM paradedb.search_many(
+        'paradedb.bm25_search_bm25_index'::regclass,
+        ARRAY[
+            paradedb.snapshot(paradedb.parse('description:shoes'), 1),
+            paradedb.snapshot(paradedb.parse('description:shoes'), 2)
+        ],
+        "limit" => 3
+    )"#
+    .execute_result(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("can't be pinned to different index generations"));
+}
+
+#[rstest]
+fn max_query_terms_caps_query_strings(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM paradedb.bm25_search WHERE description @@@ '{query}' ORDER BY id")
+            .fetch_result::<(i32,)>(conn)
+    };
+    let long_query = "shoes OR keyboard OR plastic OR hat OR ceramic";
+    let all = ids(long_query, &mut conn).unwrap();
+
+    "SET paradedb.max_query_terms = 3".execute(&mut conn);
+
+    // queries under the cap are unaffected
+    assert_eq!(
+        ids("shoes OR keyboard", &mut conn).unwrap(),
+        ids("keyboard OR shoes", &mut conn).unwrap()
+    );
+    assert!(!ids("shoes OR keyboard OR plastic", &mut conn)
+        .unwrap()
+        .is_empty());
+
+    // those over it are an error by default
+    let err = ids(long_query, &mut conn).unwrap_err();
+    assert!(
+        err.to_string().contains("paradedb.max_query_terms"),
+        "{err}"
+    );
+
+    // or are cut down to the clauses that fit
+    "SET paradedb.max_query_terms_action = 'truncate'".execute(&mut conn);
+    let truncated = ids(long_query, &mut conn).unwrap();
+    assert_eq!(
+        truncated,
+        ids("shoes OR keyboard OR plastic", &mut conn).unwrap()
+    );
+    assert!(truncated.len() < all.len());
+
+    // which a single phrase over the cap can't be
+    assert!(ids(r#""white shoes for the summer""#, &mut conn).is_err());
+
+    "RESET paradedb.max_query_terms".execute(&mut conn);
+    assert_eq!(ids(long_query, &mut conn).unwrap(), all);
+}
+
+#[rstest]
+fn max_term_expansions_caps_multi_term_queries(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE parts (id SERIAL PRIMARY KEY, name TEXT);
+    INSERT INTO parts (name)
+    SELECT 'item' || lpad(n::text, 3, '0') FROM generate_series(0, 99) n;
+
+    CALL paradedb.create_bm25(
+        index_name => 'parts',
+        table_name => 'parts',
+        key_field => 'id',
+        text_fields => paradedb.field('name')
+    );
+    "#
+    .execute(&mut conn);
+
+    let names = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT name FROM parts WHERE id @@@ {query} ORDER BY name")
+            .fetch_result::<(String,)>(conn)
+            .map(|rows| rows.into_iter().map(|(name,)| name).collect::<Vec<_>>())
+    };
+    let prefix =
+        "paradedb.fuzzy_term(field => 'name', value => 'item0', distance => 0, prefix => true)";
+    assert_eq!(names(prefix, &mut conn).unwrap().len(), 100);
+
+    "SET paradedb.max_term_expansions = 10".execute(&mut conn);
+
+    // queries that expand into few enough terms are unaffected
+    assert_eq!(
+        names("paradedb.regex('name', 'item00[0-4]')", &mut conn).unwrap(),
+        vec!["item000", "item001", "item002", "item003", "item004"]
+    );
+
+    // those that expand into more are an error by default
+    for query in [
+        prefix,
+        "paradedb.regex('name', 'item.*')",
+        "paradedb.phrase_prefix('name', ARRAY['item0'])",
+    ] {
+        let err = names(query, &mut conn).unwrap_err();
+        assert!(
+            err.to_string().contains("paradedb.max_term_expansions"),
+            "{query}: {err}"
+        );
+    }
+
+    // or only search for the first of their terms
+    "SET paradedb.max_term_expansions_action = 'truncate'".execute(&mut conn);
+    let expected = (0..10).map(|n| format!("item{n:03}")).collect::<Vec<_>>();
+    assert_eq!(names(prefix, &mut conn).unwrap(), expected);
+    assert_eq!(
+        names("paradedb.regex('name', 'item.*')", &mut conn).unwrap(),
+        expected
+    );
+    assert_eq!(
+        names("paradedb.phrase_prefix('name', ARRAY['item0'])", &mut conn)
+            .unwrap()
+            .len(),
+        10
+    );
+
+    "RESET paradedb.max_term_expansions".execute(&mut conn);
+    assert_eq!(names(prefix, &mut conn).unwrap().len(), 100);
+}
+
+#[rstest]
+fn collapse_skips_dead_rows_of_a_group(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE listings (id SERIAL PRIMARY KEY, seller TEXT, title TEXT);
+    INSERT INTO listings (seller, title) VALUES
+        ('alice', 'rust rust rust'),
+        ('alice', 'rust book'),
+        ('bob', 'rust rust'),
+        ('bob', 'a long title about rust and other things');
+
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('title') || paradedb.field('seller', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = r#"
+        SELECT id FROM listings
+        WHERE id @@@ paradedb.collapse(paradedb.parse('title:rust'), collapse => 'seller')
+        ORDER BY paradedb.score(id) DESC
+        LIMIT 10"#;
+    let ids = |conn: &mut PgConnection| {
+        let mut ids = query
+            .fetch::<(i32,)>(conn)
+            .into_iter()
+            .map(|(id,)| id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+    assert_eq!(ids(&mut conn), vec![1, 3]);
+
+    // the best row of each seller is no longer visible, but is still in the index until it's
+    // vacuumed, and the seller's next best row takes its place
+    "DELETE FROM listings WHERE id = 1".execute(&mut conn);
+    "UPDATE listings SET title = 'python' WHERE id = 3".execute(&mut conn);
+    assert_eq!(ids(&mut conn), vec![2, 4]);
+}
+
+#[rstest]
+fn phrase_prefix_type_ahead(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE cities (
+        id SERIAL PRIMARY KEY,
+        name TEXT
+    );
+    INSERT INTO cities (name) VALUES
+        ('New York City'),
+        ('New Yorkshire Pudding'),
+        ('New Yorker Magazine'),
+        ('Old York'),
+        ('New Zealand');
+    "#
+    .execute(&mut conn);
+
+    r#"
+    CALL paradedb.create_bm25(
+        table_name => 'cities',
+        index_name => 'cities_index',
+        key_field => 'id',
+        text_fields => '{"name": {"record": "position"}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    // a single completion, after the text is tokenized like the field
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM cities
+    WHERE cities @@@ paradedb.phrase_prefix('name', 'NEW YORKS')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+
+    // several completions of the same prefix, but only when they follow the phrase
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM cities
+    WHERE cities @@@ paradedb.phrase_prefix('name', 'new yor')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,), (3,)]);
+
+    // the prefix only expands to the first `max_expansion` terms: "york" and "yorker"
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM cities
+    WHERE cities @@@ paradedb.phrase_prefix('name', 'new yor', max_expansion => 2)
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,)]);
+
+    // a lone token is just a prefix
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM cities
+    WHERE cities @@@ paradedb.phrase_prefix('name', 'yo')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,), (3,), (4,)]);
+
+    // and text without any tokens matches nothing
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM cities
+    WHERE cities @@@ paradedb.phrase_prefix('name', '  ')
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![]);
+}
+
+#[rstest]
+fn similarity_models(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE similarities (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO similarities (body) VALUES
+        ('cheese'),
+        ('cheese cheese bread bread bread bread bread bread'),
+        ('bread');
+
+    CALL paradedb.create_bm25(
+        table_name => 'similarities',
+        index_name => 'similarities_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let scored = |query: &str, conn: &mut PgConnection| -> Vec<(i32, f32)> {
+        format!(
+            r#"
+            SELECT id, paradedb.score(id) FROM similarities
+            WHERE id @@@ {query}
+            ORDER BY paradedb.score(id) DESC, id"#
+        )
+        .fetch(conn)
+    };
+
+    // with BM25's default parameters the scores are the same as without a similarity at all,
+    // and the shorter document wins
+    let plain = scored("paradedb.term('body', 'cheese')", &mut conn);
+    let bm25 = scored(
+        "paradedb.similarity(paradedb.term('body', 'cheese'), 'bm25')",
+        &mut conn,
+    );
+    assert_eq!(
+        plain.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(
+        bm25.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    for ((_, plain), (_, bm25)) in plain.iter().zip(bm25.iter()) {
+        assert!((plain - bm25).abs() <= plain.abs() * 1e-4);
+    }
+
+    // without length normalization, the document that repeats the term wins
+    let unnormalized = scored(
+        "paradedb.similarity(paradedb.term('body', 'cheese'), 'bm25', b => 0)",
+        &mut conn,
+    );
+    assert_eq!(
+        unnormalized.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+
+    // classic TF-IDF penalizes the longer document too
+    let tfidf = scored(
+        "paradedb.similarity(paradedb.term('body', 'cheese'), 'tfidf')",
+        &mut conn,
+    );
+    assert_eq!(
+        tfidf.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert!(tfidf[0].1 > tfidf[1].1);
+
+    // and when every match scores the same, only the tiebreak on id orders them
+    let boolean = scored(
+        "paradedb.similarity(paradedb.term('body', 'cheese'), 'boolean')",
+        &mut conn,
+    );
+    assert_eq!(boolean, vec![(1, 1.0), (2, 1.0)]);
+
+    // k1 and b only mean something to BM25
+    let result = r#"
+    SELECT id FROM similarities
+    WHERE id @@@ paradedb.similarity(paradedb.term('body', 'cheese'), 'boolean', k1 => 2.0)"#
+        .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn proximity_boosts_adjacent_terms(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (
+        id SERIAL PRIMARY KEY,
+        description TEXT
+    );
+    INSERT INTO products (description) VALUES
+        ('wireless keyboard with a long cable'),
+        ('keyboard cable with a long wireless'),
+        ('wireless ergonomic keyboard with long cable'),
+        ('wireless mouse');
+    "#
+    .execute(&mut conn);
+
+    r#"
+    CALL paradedb.create_bm25(
+        table_name => 'products',
+        index_name => 'products_index',
+        key_field => 'id',
+        text_fields => '{"description": {"record": "position"}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let mut scores = |proximity: &str| {
+        format!(
+            "SELECT id, paradedb.score(id) FROM products
+            WHERE products @@@ {proximity}
+            ORDER BY id"
+        )
+        .fetch::<(i32, f32)>(&mut conn)
+        .into_iter()
+        .collect::<HashMap<_, _>>()
+    };
+
+    // every document with any of the terms matches, but the ones where they're close together
+    // rank higher than the one with the same terms far apart
+    let scores_by_id = scores("paradedb.proximity('description', 'wireless keyboard')");
+    assert_eq!(scores_by_id.len(), 4, "{scores_by_id:?}");
+    assert!(scores_by_id[&1] > scores_by_id[&2], "{scores_by_id:?}");
+    assert!(scores_by_id[&3] > scores_by_id[&2], "{scores_by_id:?}");
+    assert!(scores_by_id[&2] > scores_by_id[&4], "{scores_by_id:?}");
+
+    // without any slop, only adjacent terms are boosted
+    let scores_by_id = scores("paradedb.proximity('description', 'wireless keyboard', slop => 0)");
+    assert!(scores_by_id[&1] > scores_by_id[&3], "{scores_by_id:?}");
+    assert!(
+        (scores_by_id[&3] - scores_by_id[&2]).abs() < 1e-5,
+        "{scores_by_id:?}"
+    );
+
+    // and a larger boost widens the gap
+    let default_boost = scores("paradedb.proximity('description', 'wireless keyboard')");
+    let large_boost = scores("paradedb.proximity('description', 'wireless keyboard', boost => 10)");
+    assert!(
+        large_boost[&1] - large_boost[&2] > default_boost[&1] - default_boost[&2],
+        "{default_boost:?} {large_boost:?}"
+    );
+
+    let result = r#"
+    SELECT id FROM products
+    WHERE products @@@ paradedb.proximity('description', 'a', slop => -1)"#
+        .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn phrase_with_terms_ranks_exact_phrases_first(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (
+        id SERIAL PRIMARY KEY,
+        description TEXT
+    );
+    INSERT INTO products (description) VALUES
+        ('running in waterproof shoes'),
+        ('running shoes for the rain'),
+        ('waterproof shoes'),
+        ('running socks'),
+        ('rain jacket');
+    "#
+    .execute(&mut conn);
+
+    r#"
+    CALL paradedb.create_bm25(
+        table_name => 'products',
+        index_name => 'products_index',
+        key_field => 'id',
+        text_fields => '{"description": {"record": "position"}}'
+    );
+    "#
+    .execute(&mut conn);
+
+    let mut scores = |query: &str| {
+        format!(
+            "SELECT id, paradedb.score(id) FROM products
+            WHERE products @@@ {query}
+            ORDER BY id"
+        )
+        .fetch::<(i32, f32)>(&mut conn)
+        .into_iter()
+        .collect::<HashMap<_, _>>()
+    };
+
+    // the exact phrase ranks highest, but documents with only some of its terms still match
+    let scores_by_id =
+        scores("paradedb.phrase_with_terms('description', ARRAY['running', 'shoes'])");
+    assert_eq!(scores_by_id.len(), 4, "{scores_by_id:?}");
+    assert!(!scores_by_id.contains_key(&5), "{scores_by_id:?}");
+    for id in [1, 3, 4] {
+        assert!(scores_by_id[&2] > scores_by_id[&id], "{scores_by_id:?}");
+    }
+
+    // the boost of each term decides which loose matches rank higher
+    let scores_by_id = scores(
+        "paradedb.phrase_with_terms('description', ARRAY['running', 'shoes'], term_boosts => ARRAY[0.1, 5.0])",
+    );
+    assert!(scores_by_id[&3] > scores_by_id[&4], "{scores_by_id:?}");
+    let scores_by_id = scores(
+        "paradedb.phrase_with_terms('description', ARRAY['running', 'shoes'], term_boosts => ARRAY[5.0, 0.1])",
+    );
+    assert!(scores_by_id[&4] > scores_by_id[&3], "{scores_by_id:?}");
+
+    // with slop, the phrase also matches when its terms are apart
+    let scores_by_id = scores(
+        "paradedb.phrase_with_terms('description', ARRAY['running', 'shoes'], slop => 3, term_boost => 0)",
+    );
+    assert!(scores_by_id[&1] > 0.0, "{scores_by_id:?}");
+    assert!(scores_by_id[&3].abs() < 1e-5, "{scores_by_id:?}");
+
+    let result = r#"
+    SELECT id FROM products
+    WHERE products @@@ paradedb.phrase_with_terms('description', ARRAY['running', 'shoes'], term_boosts => ARRAY[1.0])"#
+        .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn synonyms_match_documents_with_only_a_synonym(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE listings (id SERIAL PRIMARY KEY, description TEXT);
+    INSERT INTO listings (description) VALUES
+        ('a red car for sale'),
+        ('vintage Automobile, barely driven'),
+        ('a sports utility vehicle'),
+        ('a utility shed'),
+        ('a red bicycle');
+
+    CREATE TABLE listing_synonyms (term TEXT, synonym TEXT);
+    INSERT INTO listing_synonyms VALUES
+        ('car', 'automobile'),
+        ('car', 'utility vehicle'),
+        ('bike', 'bicycle');
+
+    CALL paradedb.create_bm25(
+        table_name => 'listings',
+        index_name => 'listings_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('description')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| {
+        let mut ids = format!("SELECT id FROM listings WHERE id @@@ {query}")
+            .fetch::<(i32,)>(conn)
+            .into_iter()
+            .map(|(id,)| id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    // the synonyms are analyzed like the field, and one of more than one word is a phrase, so
+    // the shed doesn't match
+    assert_eq!(
+        ids(
+            "paradedb.synonyms('description', 'car', 'listing_synonyms'::regclass)",
+            &mut conn
+        ),
+        vec![1, 2, 3]
+    );
+
+    // the value itself needn't match anything
+    assert_eq!(
+        ids(
+            "paradedb.synonyms('description', 'bike', 'listing_synonyms'::regclass)",
+            &mut conn
+        ),
+        vec![5]
+    );
+
+    // and a value without synonyms is only searched for itself
+    assert_eq!(
+        ids(
+            "paradedb.synonyms('description', 'red', 'listing_synonyms'::regclass)",
+            &mut conn
+        ),
+        vec![1, 5]
+    );
+
+    // synonyms only map one way
+    assert_eq!(
+        ids(
+            "paradedb.synonyms('description', 'automobile', 'listing_synonyms'::regclass)",
+            &mut conn
+        ),
+        vec![2]
+    );
+}
+
+#[rstest]
+fn field_globs_search_every_matching_field(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE articles (id SERIAL PRIMARY KEY, body_en TEXT, body_fr TEXT, body_de TEXT, title TEXT);
+    INSERT INTO articles (body_en, body_fr, body_de, title) VALUES
+        ('the cat sleeps', 'le chien dort', 'der hund schläft', 'pets'),
+        ('the dog barks', 'le chat dort', 'der hund bellt', 'pets'),
+        ('the bird sings', 'l''oiseau chante', 'die katze schläft', 'pets'),
+        ('the fish swims', 'le poisson nage', 'der fisch schwimmt', 'cat');
+
+    CALL paradedb.create_bm25(
+        index_name => 'articles',
+        table_name => 'articles',
+        key_field => 'id',
+        text_fields => paradedb.field('body_en') || paradedb.field('body_fr') ||
+            paradedb.field('body_de') || paradedb.field('title')
+    );
+    "#
+    .execute(&mut conn);
+
+    // the title isn't a body field, so the fourth article isn't found
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM articles WHERE id @@@ paradedb.term('body_*', 'cat') ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM articles
+    WHERE id @@@ paradedb.boolean(should => ARRAY[
+        paradedb.term('body_*', 'cat'),
+        paradedb.term('body_*', 'chat'),
+        paradedb.term('body_*', 'katze')
+    ])
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,), (3,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM articles WHERE id @@@ paradedb.fuzzy_term('body_*', 'hunt') ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM articles WHERE id @@@ paradedb.phrase('*_fr', ARRAY['le', 'chat']) ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,)]);
+
+    // a glob that matches no field matches nothing
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM articles WHERE id @@@ paradedb.term('summary_*', 'cat') ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![]);
+}
+
+#[rstest]
+fn has_child_and_has_parent_join_documents(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, parent_id INT, name TEXT, color TEXT);
+    INSERT INTO products (parent_id, name, color) VALUES
+        (NULL, 'running sneaker', NULL),
+        (NULL, 'leather boot', NULL),
+        (NULL, 'canvas sneaker', NULL),
+        (1, 'running sneaker', 'red'),
+        (1, 'running sneaker', 'blue'),
+        (2, 'leather boot', 'red'),
+        (3, 'canvas sneaker', 'green');
+
+    CALL paradedb.create_bm25(
+        index_name => 'products',
+        table_name => 'products',
+        key_field => 'id',
+        text_fields => paradedb.field('name') || paradedb.field('color'),
+        numeric_fields => paradedb.field('parent_id', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    // only the parents are returned, not the red children that matched
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE id @@@ paradedb.has_child('parent_id', paradedb.term('color', 'red'))
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (2,)]);
+
+    // and the children of the sneakers, but neither of the sneakers themselves
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE id @@@ paradedb.has_parent('parent_id', paradedb.term('name', 'sneaker'))
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(4,), (5,), (7,)]);
+
+    // the joins combine with the rest of a query
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE id @@@ paradedb.boolean(must => ARRAY[
+        paradedb.term('name', 'sneaker'),
+        paradedb.has_child('parent_id', paradedb.term('color', 'red'))
+    ])
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,)]);
+
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM products
+    WHERE id @@@ paradedb.has_child('parent_id', paradedb.term('color', 'purple'))
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![]);
+}
+
+#[rstest]
+fn exact_case_ranks_exact_case_matches_first(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE fruits (
+        id SERIAL PRIMARY KEY,
+        title TEXT,
+        title_cased TEXT GENERATED ALWAYS AS (title) STORED
+    );
+    INSERT INTO fruits (title) VALUES
+        ('apple makes pie'),
+        ('Apple makes phones'),
+        ('apple sells cider'),
+        ('Apple sells watches'),
+        ('orange sells juice');
+
+    CALL paradedb.create_bm25(
+        table_name => 'fruits',
+        index_name => 'fruits_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('title')
+            || paradedb.field('title_cased', tokenizer => paradedb.tokenizer('default', lowercase => false))
+    );
+    "#
+    .execute(&mut conn);
+
+    // every document with either case matches, but those with the exact case rank first
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM fruits
+    WHERE id @@@ paradedb.exact_case('title', 'Apple', 'title_cased')
+    ORDER BY paradedb.score(id) DESC, id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(2,), (4,), (1,), (3,)]);
+
+    // without the boost, the two cases are scored alike
+    let rows: Vec<(i32, f32)> = r#"
+    SELECT id, paradedb.score(id) FROM fruits
+    WHERE id @@@ paradedb.exact_case('title', 'Apple', 'title_cased', boost => 0.0)
+    ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0].1, rows[1].1);
+
+    // a lowercase value only boosts the lowercase documents
+    let rows: Vec<(i32,)> = r#"
+    SELECT id FROM fruits
+    WHERE id @@@ paradedb.exact_case('title', 'apple', 'title_cased')
+    ORDER BY paradedb.score(id) DESC, id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,), (2,), (4,)]);
+}
+
+#[rstest]
+fn analyzed_applies_a_query_time_analyzer_chain(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE desserts (id SERIAL PRIMARY KEY, title TEXT, folded TEXT);
+    INSERT INTO desserts (title, folded) VALUES
+        ('Crème brûlée', 'Crème brûlée'),
+        ('Creme caramel', 'Creme caramel'),
+        ('Apple pie', 'Apple pie');
+
+    CALL paradedb.create_bm25(
+        table_name => 'desserts',
+        index_name => 'desserts_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('title')
+            || paradedb.field('folded', tokenizer => paradedb.tokenizer('source_code'))
+    );
+    "#
+    .execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM desserts WHERE id @@@ {query} ORDER BY id")
+            .fetch::<(i32,)>(conn)
+            .into_iter()
+            .map(|(id,)| id)
+            .collect::<Vec<_>>()
+    };
+    let folding = r#"'{"tokenizer": {"type": "whitespace", "lowercase": false}, "filters": ["lowercase", "ascii_folding"]}'"#;
+
+    // the accents are folded away at index time, so only a chain that folds them matches
+    assert_eq!(
+        ids(
+            &format!("paradedb.analyzed('folded', 'CRÈME', {folding})"),
+            &mut conn
+        ),
+        vec![1, 2]
+    );
+    assert_eq!(
+        ids(
+            r#"paradedb.analyzed('folded', 'CRÈME', '{"filters": ["lowercase"]}')"#,
+            &mut conn
+        ),
+        Vec::<i32>::new()
+    );
+
+    // a field that keeps its accents is matched by a chain that keeps them too
+    assert_eq!(
+        ids(
+            r#"paradedb.analyzed('title', 'BRÛLÉE', '{"filters": ["lowercase"]}')"#,
+            &mut conn
+        ),
+        vec![1]
+    );
+    assert_eq!(
+        ids(
+            &format!("paradedb.analyzed('title', 'brûlée', {folding})"),
+            &mut conn
+        ),
+        Vec::<i32>::new()
+    );
+
+    // every term must match in conjunction mode
+    assert_eq!(
+        ids(
+            &format!(
+                "paradedb.analyzed('folded', 'Crème Brûlée', {folding}, conjunction_mode => true)"
+            ),
+            &mut conn
+        ),
+        vec![1]
+    );
+
+    // an edge n-gram chain matches the words its grams start
+    assert_eq!(
+        ids(
+            r#"paradedb.analyzed('title', 'Apples', '{"filters": [{"edge_ngram": {"min_gram": 5, "max_gram": 5}}]}')"#,
+            &mut conn
+        ),
+        vec![3]
+    );
+
+    // and a chain that isn't valid is an error
+    let result = r#"SELECT id FROM desserts WHERE id @@@ paradedb.analyzed('title', 'pie', '{"filters": ["uppercase"]}')"#
+        .fetch_result::<(i32,)>(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("unknown analyzer chain filter"));
+}
+
+#[rstest]
+fn stemmed_searches_with_the_language_of_the_query(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE articles (id SERIAL PRIMARY KEY, body_fr TEXT, body TEXT);
+    INSERT INTO articles (body_fr, body) VALUES
+        ('Le chat noir dort', 'Le chat noir dort'),
+        ('Les chiens aboient', 'Les chiens aboient'),
+        ('Un oiseau chante', 'Un oiseau chante');
+
+    CALL paradedb.create_bm25(
+        table_name => 'articles',
+        index_name => 'articles_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('body_fr', tokenizer => paradedb.tokenizer('default', stemmer => 'French'))
+            || paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM articles WHERE id @@@ {query} ORDER BY id")
+            .fetch::<(i32,)>(conn)
+            .into_iter()
+            .map(|(id,)| id)
+            .collect::<Vec<_>>()
+    };
+
+    // the plurals are stemmed into the singulars the field was indexed with
+    assert_eq!(
+        ids(
+            "paradedb.stemmed('body_fr', 'chats', language => 'fr')",
+            &mut conn
+        ),
+        vec![1]
+    );
+    assert_eq!(
+        ids(
+            "paradedb.stemmed('body_fr', 'CHIEN', language => 'French')",
+            &mut conn
+        ),
+        vec![2]
+    );
+    assert_eq!(
+        ids("paradedb.term('body_fr', 'chats')", &mut conn),
+        Vec::<i32>::new()
+    );
+
+    // every stemmed term must match in conjunction mode
+    assert_eq!(
+        ids(
+            "paradedb.stemmed('body_fr', 'chats chiens', language => 'fr')",
+            &mut conn
+        ),
+        vec![1, 2]
+    );
+    assert_eq!(
+        ids(
+            "paradedb.stemmed('body_fr', 'chats noirs', language => 'fr', conjunction_mode => true)",
+            &mut conn
+        ),
+        vec![1]
+    );
+
+    // a field that isn't stemmed in French only warns and is searched anyway
+    assert_eq!(
+        ids(
+            "paradedb.stemmed('body', 'chat', language => 'fr')",
+            &mut conn
+        ),
+        vec![1]
+    );
+
+    // and a language without a stemmer is an error
+    let result = "SELECT id FROM articles WHERE id @@@ paradedb.stemmed('body_fr', 'chat', language => 'xx')"
+        .fetch_result::<(i32,)>(&mut conn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("invalid language `xx`"));
+}
+
+#[rstest]
+fn exclude_leaves_out_excluded_ctids(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let all: Vec<(String, f32)> = r#"
+    SELECT ctid::text, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE id @@@ paradedb.parse('description:shoes OR description:keyboard')
+    ORDER BY paradedb.score(id) DESC, ctid"#
+        .fetch(&mut conn);
+    assert!(all.len() > 4);
+
+    // exclude the very best matches, which would otherwise come first
+    let (seen, rest) = all.split_at(3);
+    let exclude = seen
+        .iter()
+        .map(|(ctid, _)| format!("'{ctid}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let rows: Vec<(String, f32)> = format!(
+        r#"
+    SELECT ctid::text, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE id @@@ paradedb.exclude(
+        paradedb.parse('description:shoes OR description:keyboard'),
+        exclude => ARRAY[{exclude}]::tid[]
+    )
+    ORDER BY paradedb.score(id) DESC, ctid"#
+    )
+    .fetch(&mut conn);
+    assert!(rows
+        .iter()
+        .all(|(ctid, _)| !seen.iter().any(|(s, _)| s == ctid)));
+
+    // the rest are returned as they were, with the same scores
+    assert_eq!(rows.len(), rest.len());
+    for ((ctid, score), (expected_ctid, expected_score)) in rows.iter().zip(rest) {
+        assert_eq!(ctid, expected_ctid);
+        assert!((score - expected_score).abs() < 1e-5);
+    }
+
+    // and so is the top N
+    let top: Vec<(String,)> = format!(
+        r#"
+    SELECT ctid::text FROM paradedb.bm25_search
+    WHERE id @@@ paradedb.exclude(
+        paradedb.parse('description:shoes OR description:keyboard'),
+        exclude => ARRAY[{exclude}]::tid[]
+    )
+    ORDER BY paradedb.score(id) DESC
+    LIMIT 1"#
+    )
+    .fetch(&mut conn);
+    assert_eq!(top.len(), 1);
+    assert!(!seen.iter().any(|(ctid, _)| *ctid == top[0].0));
+
+    // excluding nothing excludes nothing
+    let (count,): (i64,) = r#"
+    SELECT count(*) FROM paradedb.bm25_search
+    WHERE id @@@ paradedb.exclude(
+        paradedb.parse('description:shoes OR description:keyboard'),
+        exclude => ARRAY[]::tid[]
+    )"#
+    .fetch_one(&mut conn);
+    assert_eq!(count, all.len() as i64);
+}
+
+#[rstest]
+fn boolean_filter_leaves_scores_unchanged(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let scores = |query: &str, conn: &mut PgConnection| {
+        format!(
+            "SELECT id, paradedb.score(id) FROM paradedb.bm25_search WHERE id @@@ {query} ORDER BY id"
+        )
+        .fetch::<(i32, f32)>(conn)
+    };
+
+    let alone = scores(
+        "paradedb.parse('description:shoes OR description:keyboard')",
+        &mut conn,
+    );
+    let (ratings,): (Vec<i32>,) = r#"
+    SELECT array_agg(id ORDER BY id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard' AND rating >= 4"#
+        .fetch_one(&mut conn);
+    assert!(!ratings.is_empty() && ratings.len() < alone.len());
+
+    // the filter narrows down the matches, but each keeps its score
+    let filtered = scores(
+        r#"paradedb.boolean(
+            must => ARRAY[paradedb.parse('description:shoes OR description:keyboard')],
+            filter => ARRAY[paradedb.range('rating', int4range(4, NULL))]
+        )"#,
+        &mut conn,
+    );
+    assert_eq!(
+        filtered.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        ratings
+    );
+    for (id, score) in &filtered {
+        let (_, expected) = alone.iter().find(|(i, _)| i == id).unwrap();
+        assert!((score - expected).abs() < 1e-5, "{filtered:?} {alone:?}");
+    }
+
+    // unlike the same range as a must clause, which adds to the score
+    let must = scores(
+        r#"paradedb.boolean(
+            must => ARRAY[
+                paradedb.parse('description:shoes OR description:keyboard'),
+                paradedb.range('rating', int4range(4, NULL))
+            ]
+        )"#,
+        &mut conn,
+    );
+    assert_eq!(must.len(), filtered.len());
+    assert!(must
+        .iter()
+        .zip(&filtered)
+        .all(|((_, must), (_, filtered))| must > filtered));
+
+    // with nothing else to score them, the filtered documents score nothing
+    let only_filter = scores(
+        "paradedb.boolean(filter => ARRAY[paradedb.range('rating', int4range(4, NULL))])",
+        &mut conn,
+    );
+    assert!(!only_filter.is_empty());
+    assert!(only_filter.iter().all(|(_, score)| *score == 0.0));
+}
+
+#[rstest]
+fn numeric_query_values_are_coerced_to_the_field_type(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE prices (id SERIAL PRIMARY KEY, price FLOAT8, qty INT);
+    INSERT INTO prices (price, qty) VALUES (10.0, 1), (10.5, 2), (11.0, 3), (12.0, 4);
+    CALL paradedb.create_bm25(
+        index_name => 'prices_idx',
+        table_name => 'prices',
+        key_field => 'id',
+        numeric_fields => paradedb.field('price') || paradedb.field('qty')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM prices WHERE id @@@ {query} ORDER BY id").fetch::<(i32,)>(conn)
+    };
+
+    // an integer against a float field
+    assert_eq!(ids("paradedb.term('price', 10)", &mut conn), vec![(1,)]);
+    assert_eq!(
+        ids(
+            "paradedb.term_set(ARRAY[paradedb.term('price', 11), paradedb.term('price', 12)])",
+            &mut conn
+        ),
+        vec![(3,), (4,)]
+    );
+
+    // a float against an integer field, which only matches if it's a whole number
+    assert_eq!(
+        ids("paradedb.term('qty', 3.0::float8)", &mut conn),
+        vec![(3,)]
+    );
+    assert_eq!(ids("paradedb.term('qty', 3.5::float8)", &mut conn), vec![]);
+    assert_eq!(
+        ids("paradedb.term('qty', 3.0::numeric)", &mut conn),
+        vec![(3,)]
+    );
+
+    // and float range bounds against an integer field round away from the range
+    assert_eq!(
+        ids("paradedb.range('qty', numrange(1.5, 3.5))", &mut conn),
+        vec![(2,), (3,)]
+    );
+    assert_eq!(
+        ids("paradedb.range('qty', numrange(2.0, 3.0, '[]'))", &mut conn),
+        vec![(2,), (3,)]
+    );
+    assert_eq!(
+        ids("paradedb.range('price', int4range(11, NULL))", &mut conn),
+        vec![(3,), (4,)]
+    );
+
+    // while bounds past the end of what an integer field holds bound nothing on their side of
+    // the range, and leave nothing in it on the other
+    assert_eq!(
+        ids("paradedb.range('qty', numrange(NULL, 1e30))", &mut conn),
+        vec![(1,), (2,), (3,), (4,)]
+    );
+    assert_eq!(
+        ids("paradedb.range('qty', numrange(-1e30, 2.5))", &mut conn),
+        vec![(1,), (2,)]
+    );
+    assert_eq!(
+        ids("paradedb.range('qty', numrange(1e30, NULL))", &mut conn),
+        vec![]
+    );
+    assert_eq!(
+        ids("paradedb.range('qty', numrange(NULL, -1e30))", &mut conn),
+        vec![]
+    );
+}
+
+#[rstest]
+fn cross_field_phrase_crosses_field_boundary(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE people (id SERIAL PRIMARY KEY, first_name TEXT, last_name TEXT, bio TEXT, age INT);
+    INSERT INTO people (first_name, last_name, bio, age) VALUES
+        ('Mary Jane', 'Watson', 'an actress', 30),
+        ('Watson', 'Mary Jane', 'a doctor', 40),
+        ('Mary', 'Jane', 'watson was her maiden name', 50),
+        ('Jane', 'Mary Watson', 'a writer', 60);
+
+    CALL paradedb.create_bm25(
+        index_name => 'people',
+        table_name => 'people',
+        key_field => 'id',
+        text_fields => paradedb.field('first_name') || paradedb.field('last_name')
+            || paradedb.field('bio', tokenizer => paradedb.tokenizer('en_stem')),
+        numeric_fields => paradedb.field('age')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM people WHERE id @@@ {query} ORDER BY id").fetch::<(i32,)>(conn)
+    };
+
+    // the phrase starts in the first name and ends in the last
+    assert_eq!(
+        ids(
+            "paradedb.cross_field_phrase(ARRAY['first_name', 'last_name'], 'jane watson')",
+            &mut conn
+        ),
+        vec![(1,)]
+    );
+    // in the order the fields are listed
+    assert_eq!(
+        ids(
+            "paradedb.cross_field_phrase(ARRAY['last_name', 'first_name'], 'jane watson')",
+            &mut conn
+        ),
+        vec![(2,)]
+    );
+    // and phrases within one of the fields are found too
+    assert_eq!(
+        ids(
+            "paradedb.cross_field_phrase(ARRAY['first_name', 'last_name'], 'mary jane')",
+            &mut conn
+        ),
+        vec![(1,), (2,), (3,)]
+    );
+    // unlike a phrase on either field alone
+    assert_eq!(
+        ids(
+            "paradedb.phrase('last_name', ARRAY['jane', 'watson'])",
+            &mut conn
+        ),
+        vec![]
+    );
+
+    // fields that aren't text, or are tokenized differently, can't be joined
+    for fields in ["ARRAY['first_name', 'age']", "ARRAY['last_name', 'bio']"] {
+        let result = format!(
+            "SELECT id FROM people WHERE id @@@ paradedb.cross_field_phrase({fields}, 'jane watson')"
+        )
+        .execute_result(&mut conn);
+        let error = result.unwrap_err().to_string();
+        assert!(
+            error.contains("can't be part of a cross-field phrase"),
+            "{error}"
+        );
+    }
+}
+
+#[rstest]
+fn query_string_dates_match_the_indexed_date(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE events (id SERIAL PRIMARY KEY, name TEXT, happened_on DATE, happened_at TIMESTAMP);
+    INSERT INTO events (name, happened_on, happened_at) VALUES
+        ('launch', '2024-01-01', '2024-01-01 09:30:00'),
+        ('review', '2024-01-02', '2024-01-02 14:00:00'),
+        ('party', '2024-02-01', '2024-02-01 20:15:00');
+    CALL paradedb.create_bm25(
+        index_name => 'events_idx',
+        table_name => 'events',
+        key_field => 'id',
+        text_fields => paradedb.field('name'),
+        datetime_fields => paradedb.field('happened_on') || paradedb.field('happened_at')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ids = |query_string: &str, conn: &mut PgConnection| {
+        format!("SELECT id FROM events WHERE id @@@ paradedb.parse('{query_string}') ORDER BY id")
+            .fetch::<(i32,)>(conn)
+    };
+
+    assert_eq!(ids("happened_on:2024-01-01", &mut conn), vec![(1,)]);
+    assert_eq!(
+        ids("happened_at:2024-01-02T14:00:00", &mut conn),
+        vec![(2,)]
+    );
+    assert_eq!(
+        ids("happened_at:\"2024-02-01T21:15:00+01:00\"", &mut conn),
+        vec![(3,)]
+    );
+    assert_eq!(
+        ids("happened_on:[2024-01-01 TO 2024-02-01}", &mut conn),
+        vec![(1,), (2,)]
+    );
+    assert_eq!(
+        ids("name:party OR happened_on:2024-01-02", &mut conn),
+        vec![(2,), (3,)]
+    );
+    assert_eq!(
+        "SELECT id FROM events WHERE id @@@ paradedb.parse_with_field('happened_on', '2024-01-01 OR 2024-02-01') ORDER BY id"
+            .fetch::<(i32,)>(&mut conn),
+        vec![(1,), (3,)]
+    );
+
+    // a date that could be read more than one way is an error
+    let error = "SELECT id FROM events WHERE id @@@ paradedb.parse('happened_on:01/02/2024')"
+        .execute_result(&mut conn)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("isn't a date"), "{error}");
+}
+
+#[rstest]
+fn matches_annotates_outer_rows(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE wanted (id SERIAL PRIMARY KEY, word TEXT);
+    INSERT INTO wanted (word) VALUES ('shoes'), ('keyboard'), ('spaceship');
+
+    CREATE TABLE docs (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO docs (body) SELECT 'running shoes number ' || x || repeat(' padding', 20) FROM generate_series(1, 5000) x;
+    INSERT INTO docs (body) VALUES ('a mechanical keyboard');
+    CALL paradedb.create_bm25(
+        index_name => 'docs',
+        table_name => 'docs',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let annotated: Vec<(String, bool)> = r#"
+    SELECT w.word, m.matched
+    FROM wanted w,
+    LATERAL (SELECT paradedb.matches('docs_bm25_index'::regclass, paradedb.term('body', w.word)) AS matched) m
+    ORDER BY w.id"#
+        .fetch(&mut conn);
+    assert_eq!(
+        annotated,
+        vec![
+            ("shoes".into(), true),
+            ("keyboard".into(), true),
+            ("spaceship".into(), false)
+        ]
+    );
+
+    // a deleted row is no match, even while it's still in the index
+    "DELETE FROM docs WHERE body @@@ 'keyboard'".execute(&mut conn);
+    let (matched,): (bool,) =
+        "SELECT paradedb.matches('docs_bm25_index'::regclass, paradedb.term('body', 'keyboard'))"
+            .fetch_one(&mut conn);
+    assert!(!matched);
+
+    // and the search stops at the first visible match, so it only reads the heap page that's on,
+    // rather than those of the thousands of other matches
+    "BEGIN".execute(&mut conn);
+    let (pages,): (i64,) =
+        "SELECT pg_relation_size('docs') / current_setting('block_size')::bigint"
+            .fetch_one(&mut conn);
+    let (before,): (i64,) =
+        "SELECT pg_stat_get_xact_blocks_fetched('docs'::regclass)".fetch_one(&mut conn);
+    let (matched,): (bool,) =
+        "SELECT paradedb.matches('docs_bm25_index'::regclass, paradedb.term('body', 'shoes'))"
+            .fetch_one(&mut conn);
+    let (after,): (i64,) =
+        "SELECT pg_stat_get_xact_blocks_fetched('docs'::regclass)".fetch_one(&mut conn);
+    "COMMIT".execute(&mut conn);
+    assert!(matched);
+    assert!(pages > 10, "{pages}");
+    assert!(after - before <= 1, "fetched {} blocks", after - before);
+}
+
+#[rstest]
+fn category_boost_multiplies_scores_by_category(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE listings (id SERIAL PRIMARY KEY, title TEXT, tier TEXT);
+    INSERT INTO listings (title, tier) VALUES
+        ('cozy cottage', 'premium'),
+        ('cottage cottage cottage', 'basic'),
+        ('cottage by the sea', NULL);
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('title') || paradedb.field('tier', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    let scores = |query: &str, conn: &mut PgConnection| {
+        format!("SELECT id, paradedb.score(id) FROM listings WHERE id @@@ {query} ORDER BY id")
+            .fetch::<(i32, f32)>(conn)
+    };
+
+    // without the boost, the basic listing mentions cottage the most and ranks first
+    let plain = scores("paradedb.term('title', 'cottage')", &mut conn);
+    assert!(plain[1].1 > plain[0].1, "{plain:?}");
+
+    let boosted = scores(
+        r#"paradedb.category_boost(paradedb.term('title', 'cottage'), 'tier', '{"premium": 2.0, "unlisted": 0.5}')"#,
+        &mut conn,
+    );
+    assert_eq!(boosted.len(), 3);
+    assert!(
+        (boosted[0].1 - plain[0].1 * 2.0).abs() < 1e-5,
+        "{boosted:?}"
+    );
+    // the basic listing and the one without a tier keep their scores
+    assert_eq!(boosted[1], plain[1]);
+    assert_eq!(boosted[2], plain[2]);
+
+    let (top,): (i32,) = r#"
+    SELECT id FROM listings
+    WHERE id @@@ paradedb.category_boost(paradedb.term('title', 'cottage'), 'tier', '{"premium": 2.0}')
+    ORDER BY paradedb.score(id) DESC LIMIT 1"#
+        .fetch_one(&mut conn);
+    assert_eq!(top, 1);
+
+    // the categories are read from a fast field
+    let error = r#"
+    SELECT id FROM listings
+    WHERE id @@@ paradedb.category_boost(paradedb.term('title', 'cottage'), 'title', '{"premium": 2.0}')"#
+        .execute_result(&mut conn)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("needs a text fast field"), "{error}");
+}
+
+#[rstest]
+fn tiered_ranks_exact_matches_above_recent_inexact_ones(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE posts (id SERIAL PRIMARY KEY, title TEXT, published_at TIMESTAMP);
+    INSERT INTO posts (title, published_at) VALUES
+        ('running shoes', now() - interval '2 years'),
+        ('runnin shoes', now()),
+        ('running boots', now() - interval '1 day'),
+        ('walking shoes', now());
+    CALL paradedb.create_bm25(
+        index_name => 'posts',
+        table_name => 'posts',
+        key_field => 'id',
+        text_fields => paradedb.field('title'),
+        datetime_fields => paradedb.field('published_at')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ranked = |query: &str, conn: &mut PgConnection| {
+        format!(
+            "SELECT id, paradedb.score(id) FROM posts WHERE id @@@ {query} ORDER BY paradedb.score(id) DESC, id"
+        )
+        .fetch::<(i32, f32)>(conn)
+    };
+    let tiered = "paradedb.tiered(
+        exact => paradedb.term('title', 'running'),
+        inexact => paradedb.fuzzy_term('title', 'running'),
+        recency_field => 'published_at',
+        half_life => interval '30 days'
+    )";
+
+    // the old exact match still outranks the new inexact one, and the newer exact match ranks first
+    let rows = ranked(tiered, &mut conn);
+    assert_eq!(
+        rows.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![3, 1, 2],
+        "{rows:?}"
+    );
+    // the exact tier scores between 1 and 2, and the inexact tier below 1
+    assert!(
+        rows[..2]
+            .iter()
+            .all(|(_, score)| (1.0..2.0).contains(score)),
+        "{rows:?}"
+    );
+    assert!((0.0..1.0).contains(&rows[2].1), "{rows:?}");
+
+    // without the recency decay, the exact matches tie on their scores
+    let rows = ranked(
+        "paradedb.tiered(paradedb.term('title', 'running'), paradedb.fuzzy_term('title', 'running'))",
+        &mut conn,
+    );
+    assert_eq!(
+        rows.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 3, 2],
+        "{rows:?}"
+    );
+    assert_eq!(rows[0].1, rows[1].1);
+
+    // the ages are read from a datetime fast field
+    let error = "
+    SELECT id FROM posts
+    WHERE id @@@ paradedb.tiered(
+        paradedb.term('title', 'running'),
+        paradedb.fuzzy_term('title', 'running'),
+        recency_field => 'title',
+        half_life => interval '30 days'
+    )"
+    .execute_result(&mut conn)
+    .unwrap_err()
+    .to_string();
+    assert!(error.contains("needs a datetime fast field"), "{error}");
+
+    let error = "
+    SELECT id FROM posts
+    WHERE id @@@ paradedb.tiered(
+        paradedb.term('title', 'running'),
+        paradedb.fuzzy_term('title', 'running'),
+        recency_field => 'published_at'
+    )"
+    .execute_result(&mut conn)
+    .unwrap_err()
+    .to_string();
+    assert!(error.contains("must be given together"), "{error}");
+}
+
+#[rstest]
+fn exists_boost_ranks_documents_with_the_field_higher(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE listings (id SERIAL PRIMARY KEY, title TEXT, rating INT);
+    INSERT INTO listings (title, rating) VALUES
+        ('red shoes', NULL),
+        ('red shoes', 4),
+        ('red shoes', NULL),
+        ('blue shoes', 5);
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('title'),
+        numeric_fields => paradedb.field('rating')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ranked = |query: &str, conn: &mut PgConnection| {
+        format!(
+            "SELECT id, paradedb.score(id) FROM listings WHERE id @@@ {query} ORDER BY paradedb.score(id) DESC, id"
+        )
+        .fetch::<(i32, f32)>(conn)
+    };
+
+    // the listings are otherwise equal, so the one with a rating ranks first only for the boost
+    let plain = ranked("paradedb.term('title', 'red')", &mut conn);
+    assert_eq!(plain.len(), 3);
+    assert!(
+        plain.iter().all(|(_, score)| *score == plain[0].1),
+        "{plain:?}"
+    );
+
+    let boosted = ranked(
+        "paradedb.boolean(
+            must => ARRAY[paradedb.term('title', 'red')],
+            should => ARRAY[paradedb.exists_boost('rating', 1.5)]
+        )",
+        &mut conn,
+    );
+    assert_eq!(
+        boosted.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![2, 1, 3],
+        "{boosted:?}"
+    );
+    // the boost is added to the score, and documents without the field keep theirs
+    assert!(
+        (boosted[0].1 - (plain[0].1 + 1.5)).abs() < 1e-5,
+        "{boosted:?}"
+    );
+    assert_eq!(boosted[1].1, plain[0].1);
+    assert_eq!(boosted[2].1, plain[0].1);
+
+    let error = "SELECT id FROM listings WHERE id @@@ paradedb.exists_boost('rating', -1)"
+        .execute_result(&mut conn)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("must be zero or greater"), "{error}");
+}
+
+#[rstest]
+fn last_query_generation_reports_the_serving_generation(mut conn: PgConnection) {
+    // nothing has been searched yet
+    let (generation,): (Option<i64>,) =
+        "SELECT paradedb.last_query_generation()".fetch_one(&mut conn);
+    assert_eq!(generation, None);
+
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let current_generation = |conn: &mut PgConnection| {
+        "SELECT paradedb.index_generation('paradedb.bm25_search_bm25_index'::regclass)"
+            .fetch_one::<(i64,)>(conn)
+            .0
+    };
+    let last_query_generation = |conn: &mut PgConnection| {
+        "SELECT paradedb.last_query_generation()"
+            .fetch_one::<(Option<i64>,)>(conn)
+            .0
+    };
+    let query = "SELECT id FROM paradedb.bm25_search WHERE description @@@ 'shoes'";
+
+    query.fetch::<(i32,)>(&mut conn);
+    let before = current_generation(&mut conn);
+    assert_eq!(last_query_generation(&mut conn), Some(before));
+
+    "INSERT INTO paradedb.bm25_search (description, rating, category) VALUES ('Bright red shoes', 4, 'Footwear')"
+        .execute(&mut conn);
+    let after = current_generation(&mut conn);
+    assert!(after > before);
+
+    // the write isn't reported until a query has been served by it
+    assert_eq!(last_query_generation(&mut conn), Some(before));
+    query.fetch::<(i32,)>(&mut conn);
+    assert_eq!(last_query_generation(&mut conn), Some(after));
+
+    // a query pinned to an older generation reports that one
+    format!(
+        r#"
+        SELECT id FROM paradedb.bm25_search
+        WHERE bm25_search @@@ paradedb.snapshot(
+            query => paradedb.term('description', 'shoes'),
+            as_of => {before}
+        )"#
+    )
+    .fetch::<(i32,)>(&mut conn);
+    assert_eq!(last_query_generation(&mut conn), Some(before));
+}
+
+#[rstest]
+fn empty_query_mode_decides_what_no_terms_match(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    // the default tokenizer keeps nothing of punctuation, just as a stopword filter would keep
+    // nothing of a query of only stopwords
+    let query = r#"
+    SELECT count(*) FROM paradedb.bm25_search
+    WHERE id @@@ paradedb.parse_with_field('description', ',,, ...')"#;
+
+    let (count,): (i64,) = query.fetch_one(&mut conn);
+    assert_eq!(count, 0);
+
+    "SET paradedb.empty_query_mode = 'match_none'".execute(&mut conn);
+    let (count,): (i64,) = query.fetch_one(&mut conn);
+    assert_eq!(count, 0);
+
+    "SET paradedb.empty_query_mode = 'match_all'".execute(&mut conn);
+    let (count,): (i64,) = query.fetch_one(&mut conn);
+    assert_eq!(count, 41);
+
+    // which leaves the rest of a query to decide what matches
+    let (count,): (i64,) = r#"
+    SELECT count(*) FROM paradedb.bm25_search
+    WHERE id @@@ paradedb.boolean(must => ARRAY[
+        paradedb.parse('description:shoes'),
+        paradedb.parse_with_field('description', ',,, ...')
+    ])"#
+    .fetch_one(&mut conn);
+    assert_eq!(count, 3);
+
+    "SET paradedb.empty_query_mode = 'error'".execute(&mut conn);
+    let error = query.execute_result(&mut conn).unwrap_err();
+    assert!(error.to_string().contains("has no terms"), "{error}");
+
+    // a query with terms isn't affected
+    let (count,): (i64,) =
+        "SELECT count(*) FROM paradedb.bm25_search WHERE description @@@ 'shoes'"
+            .fetch_one(&mut conn);
+    assert_eq!(count, 3);
+}
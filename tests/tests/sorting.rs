@@ -119,3 +119,1239 @@ fn sort_by_row_return_scores(mut conn: PgConnection) {
     assert_eq!(plan.get("   Sort Field"), None);
     assert_eq!(plan.get("Scores"), Some(&Value::Bool(true)));
 }
+
+#[rstest]
+fn sort_by_non_indexed_column_still_searches(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT, price INT);
+    INSERT INTO products (description, price)
+    SELECT 'shoes number ' || n, (n * 37) % 100 FROM generate_series(1, 100) n;
+    INSERT INTO products (description, price)
+    SELECT 'keyboard number ' || n, n FROM generate_series(1, 100) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'products',
+        index_name => 'products_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('description')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT id, price FROM products WHERE description @@@ 'shoes' ORDER BY price DESC, id LIMIT 3";
+
+    // the filter is searched for by the custom scan, and its matches are sorted above it
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let sort = plan.pointer("/0/Plan/Plans/0").unwrap();
+    assert_eq!(
+        sort.get("Node Type"),
+        Some(&Value::from("Sort")),
+        "{plan:#?}"
+    );
+    let scan = sort.pointer("/Plans/0").unwrap();
+    assert_eq!(
+        scan.get("Custom Plan Provider"),
+        Some(&Value::from("ParadeDB Scan")),
+        "{plan:#?}"
+    );
+    assert_eq!(scan.get("   Sort Field"), None, "{plan:#?}");
+
+    let rows: Vec<(i32, i32)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(27, 99), (54, 98), (81, 97)]);
+
+    "SET paradedb.unpushed_order_by = 'error'".execute(&mut conn);
+    assert!(query.execute_result(&mut conn).is_err());
+
+    // an ORDER BY that can be pushed down is still allowed
+    "SELECT id FROM products WHERE description @@@ 'shoes' ORDER BY paradedb.score(id) DESC LIMIT 3"
+        .execute(&mut conn);
+}
+
+#[rstest]
+fn sort_by_fast_fields_in_mixed_directions(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT, price INT, rating INT);
+    INSERT INTO products (description, price, rating)
+    SELECT 'shoes number ' || n, n % 5, CASE WHEN n % 11 = 0 THEN NULL ELSE n % 7 END
+    FROM generate_series(1, 200) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'products',
+        index_name => 'products_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('description'),
+        numeric_fields => paradedb.field('price', fast => true) || paradedb.field('rating', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = "SELECT price, rating FROM products WHERE description @@@ 'shoes' ORDER BY price ASC, rating DESC LIMIT 50";
+
+    // both keys are sorted by the index, so there's nothing left for Postgres to sort
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let scan = plan.pointer("/0/Plan/Plans/0").unwrap();
+    assert_eq!(
+        scan.get("Custom Plan Provider"),
+        Some(&Value::from("ParadeDB Scan")),
+        "{plan:#?}"
+    );
+    assert_eq!(
+        scan.get("   Sort Keys"),
+        Some(&Value::from(
+            "price asc nulls last, rating desc nulls first"
+        )),
+        "{plan:#?}"
+    );
+    assert!(scan.get("Plans").is_none(), "{plan:#?}");
+
+    let sorted_by_index: Vec<(i32, Option<i32>)> = query.fetch(&mut conn);
+    "SET paradedb.enable_custom_scan = false".execute(&mut conn);
+    let sorted_by_postgres: Vec<(i32, Option<i32>)> = query.fetch(&mut conn);
+    assert_eq!(sorted_by_index.len(), 50);
+    assert_eq!(sorted_by_index, sorted_by_postgres);
+}
+
+#[rstest]
+fn top_n_through_projecting_subquery(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, description TEXT, rating INT);
+    INSERT INTO products (description, rating)
+    SELECT 'shoes number ' || n, n % 7 FROM generate_series(1, 200) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'products',
+        index_name => 'products_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('description'),
+        numeric_fields => paradedb.field('rating', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    // the subquery has an ORDER BY, so it isn't pulled up, and it's only the outer query that
+    // says how many of its rows are needed
+    let query = r#"
+    SELECT id, rating * 2 AS doubled
+    FROM (SELECT id, rating FROM products WHERE description @@@ 'shoes' ORDER BY rating DESC) s
+    LIMIT 5"#;
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    eprintln!("{plan:#?}");
+    let plan = plan.to_string();
+    assert!(plan.contains("ParadeDB Scan"), "{plan}");
+    assert!(plan.contains(r#""   Top N Limit":5"#), "{plan}");
+    assert!(!plan.contains(r#""Node Type":"Sort""#), "{plan}");
+
+    let through_subquery: Vec<(i32, i32)> = query.fetch(&mut conn);
+    let flattened: Vec<(i32, i32)> = r#"
+    SELECT id, rating * 2 FROM products WHERE description @@@ 'shoes'
+    ORDER BY rating DESC LIMIT 5"#
+        .fetch(&mut conn);
+    assert_eq!(through_subquery.len(), 5);
+    assert_eq!(through_subquery, flattened);
+
+    // an outer query that puts the rows in another order needs all of them
+    let query = r#"
+    SELECT id, rating * 2 AS doubled
+    FROM (SELECT id, rating FROM products WHERE description @@@ 'shoes' ORDER BY rating DESC) s
+    ORDER BY id
+    LIMIT 5"#;
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.to_string();
+    assert!(!plan.contains("Top N Limit"), "{plan}");
+    let rows: Vec<(i32, i32)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(1, 2), (2, 4), (3, 6), (4, 8), (5, 10)]);
+}
+
+#[rstest]
+fn score_tiebreak_random_seed(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    // two documents score highly, and every other document ties with a score of 1.0
+    let query = r#"
+        SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+        WHERE id @@@ paradedb.boolean(should => ARRAY[
+            paradedb.boost(10.0, paradedb.term('description', 'keyboard')),
+            paradedb.const_score(1.0, paradedb.all())
+        ])
+        ORDER BY paradedb.score(id) DESC
+        LIMIT 10
+    "#;
+
+    "SET paradedb.score_tiebreak = 'random(1)'".execute(&mut conn);
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.pointer("/0/Plan/Plans/0").unwrap();
+    eprintln!("{plan:#?}");
+    assert_eq!(
+        plan.get("   Tie Break"),
+        Some(&Value::String(String::from("random(1)")))
+    );
+
+    let seed1: Vec<(i32, f32)> = query.fetch(&mut conn);
+    let seed1_again: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(
+        seed1, seed1_again,
+        "the same seed should produce the same order"
+    );
+
+    "SET paradedb.score_tiebreak = 'random(2)'".execute(&mut conn);
+    let seed2: Vec<(i32, f32)> = query.fetch(&mut conn);
+
+    for rows in [&seed1, &seed2] {
+        assert_eq!(rows.len(), 10);
+        // score is still the primary ordering
+        assert!(rows.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert!(rows[2..].iter().all(|(_, score)| *score == 1.0));
+    }
+    assert_eq!(seed1[..2], seed2[..2]);
+    assert_ne!(
+        seed1[2..].iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        seed2[2..].iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        "different seeds should rotate the tied documents"
+    );
+
+    "SET paradedb.score_tiebreak = 'bogus'".execute(&mut conn);
+    assert!(query.execute_result(&mut conn).is_err());
+}
+
+#[rstest]
+fn ties_are_ordered_by_ctid(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE listings (id SERIAL PRIMARY KEY, body TEXT, rating INT);
+    CALL paradedb.create_bm25(
+        index_name => 'listings',
+        table_name => 'listings',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('rating')
+    );
+
+    INSERT INTO listings (body, rating) SELECT 'house', n % 3 FROM generate_series(1, 40) n;
+    INSERT INTO listings (body, rating) SELECT 'house', n % 3 FROM generate_series(1, 40) n;
+    UPDATE listings SET rating = rating WHERE id % 7 = 0;
+    INSERT INTO listings (body, rating) SELECT 'house', n % 3 FROM generate_series(1, 40) n;
+    "#
+    .execute(&mut conn);
+
+    let by_score = r#"
+        SELECT id, ctid::text FROM listings
+        WHERE id @@@ paradedb.all()
+        ORDER BY paradedb.score(id) DESC
+        LIMIT 25"#;
+    let by_field = r#"
+        SELECT id, ctid::text FROM listings
+        WHERE body @@@ 'house'
+        ORDER BY rating DESC
+        LIMIT 25"#;
+
+    for (query, expected) in [
+        (
+            by_score,
+            "SELECT id, ctid::text FROM listings ORDER BY ctid LIMIT 25",
+        ),
+        (
+            by_field,
+            "SELECT id, ctid::text FROM listings ORDER BY rating DESC, ctid LIMIT 25",
+        ),
+    ] {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+        let plan = plan.pointer("/0/Plan/Plans/0").unwrap();
+        assert_eq!(
+            plan.get("   Top N Limit"),
+            Some(&Value::from(25)),
+            "{query}"
+        );
+
+        let first: Vec<(i32, String)> = query.fetch(&mut conn);
+        for _ in 0..5 {
+            let again: Vec<(i32, String)> = query.fetch(&mut conn);
+            assert_eq!(first, again, "{query}");
+        }
+        let expected: Vec<(i32, String)> = expected.fetch(&mut conn);
+        assert_eq!(first, expected, "{query}");
+    }
+
+    // which can be left to the order they're found in instead
+    "SET paradedb.score_tiebreak = 'none'".execute(&mut conn);
+    let rows: Vec<(i32, String)> = by_score.fetch(&mut conn);
+    assert_eq!(rows.len(), 25);
+}
+
+#[rstest]
+fn limit_one_is_the_first_of_the_top_n(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    // a second segment, whose documents tie with those of the first
+    r#"
+    INSERT INTO paradedb.bm25_search (description, category, rating, in_stock)
+    VALUES ('extra keyboard', 'Electronics', 4, true), ('extra socks', 'Clothing', 3, true);
+    "#
+    .execute(&mut conn);
+
+    let query = |scoring: &str, order: &str, limit: usize| {
+        format!(
+            "SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+            WHERE id @@@ {scoring}
+            ORDER BY paradedb.score(id) {order}
+            LIMIT {limit}"
+        )
+    };
+
+    let (plan,) = format!(
+        "EXPLAIN (FORMAT JSON) {}",
+        query("paradedb.all()", "DESC", 1)
+    )
+    .fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.pointer("/0/Plan/Plans/0").unwrap();
+    assert_eq!(plan.get("   Top N Limit"), Some(&Value::from(1)));
+
+    for tiebreak in ["none", "random(1)", "random(7)"] {
+        format!("SET paradedb.score_tiebreak = '{tiebreak}'").execute(&mut conn);
+        for scoring in [
+            // every document ties
+            "paradedb.all()",
+            // a few documents score differently, but as many others tie
+            "paradedb.boolean(should => ARRAY[
+                paradedb.term('description', 'keyboard'),
+                paradedb.const_score(1.0, paradedb.all())
+            ])",
+        ] {
+            for order in ["DESC", "ASC"] {
+                let best: Vec<(i32, f32)> = query(scoring, order, 1).fetch(&mut conn);
+                let top_n: Vec<(i32, f32)> = query(scoring, order, 10).fetch(&mut conn);
+                assert_eq!(best.len(), 1);
+                assert_eq!(best[0], top_n[0], "{tiebreak} {order} {scoring}");
+            }
+        }
+    }
+
+    "RESET paradedb.score_tiebreak".execute(&mut conn);
+    let best: Vec<(i32, f32)> =
+        query("paradedb.term('description', 'nothing')", "DESC", 1).fetch(&mut conn);
+    assert_eq!(best, vec![]);
+}
+
+#[rstest]
+fn unscored_score_value_policies(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = r#"
+        SELECT rating, paradedb.score(id) FROM paradedb.bm25_search
+        WHERE description @@@ 'shoes OR keyboard'
+        ORDER BY rating DESC
+        LIMIT 3
+    "#;
+    let sort_field = |conn: &mut PgConnection| {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(conn);
+        eprintln!("{plan:#?}");
+        let plan = plan.pointer("/0/Plan/Plans/0").unwrap().clone();
+        (
+            plan.get("   Sort Field").cloned(),
+            plan.get("   Unscored Score Value").cloned(),
+        )
+    };
+
+    // by default, the ORDER BY isn't pushed down so that every row is scored
+    assert_eq!(sort_field(&mut conn), (None, None));
+    let scored: Vec<(i32, Option<f32>)> = query.fetch(&mut conn);
+    assert_eq!(scored.len(), 3);
+    assert!(scored.iter().all(|(_, score)| score.unwrap() > 0.0));
+    let ratings = scored.iter().map(|(rating, _)| *rating).collect::<Vec<_>>();
+
+    for (value, expected) in [("null", None), ("one", Some(1.0))] {
+        format!("SET paradedb.unscored_score_value = '{value}'").execute(&mut conn);
+        assert_eq!(
+            sort_field(&mut conn),
+            (Some(Value::from("rating")), Some(Value::from(value)))
+        );
+        let rows: Vec<(i32, Option<f32>)> = query.fetch(&mut conn);
+        assert_eq!(
+            rows.iter().map(|(rating, _)| *rating).collect::<Vec<_>>(),
+            ratings
+        );
+        assert!(
+            rows.iter().all(|(_, score)| *score == expected),
+            "{value}: {rows:?}"
+        );
+    }
+
+    "SET paradedb.unscored_score_value = 'error'".execute(&mut conn);
+    assert!(query.execute_result(&mut conn).is_err());
+
+    // which only matters when the score is projected
+    let rows: Vec<(i32,)> = r#"
+        SELECT rating FROM paradedb.bm25_search
+        WHERE description @@@ 'shoes OR keyboard'
+        ORDER BY rating DESC
+        LIMIT 3"#
+        .fetch(&mut conn);
+    assert_eq!(
+        rows.into_iter().map(|(rating,)| rating).collect::<Vec<_>>(),
+        ratings
+    );
+
+    assert!("SET paradedb.unscored_score_value = 'bogus'"
+        .execute_result(&mut conn)
+        .is_err());
+    "RESET paradedb.unscored_score_value".execute(&mut conn);
+}
+
+#[rstest]
+fn score_precision_quantizes_scores(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = r#"
+        SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+        WHERE description @@@ 'shoes OR keyboard OR plastic OR running'
+        ORDER BY paradedb.score(id) DESC, id
+        LIMIT 10
+    "#;
+
+    let unrounded: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert!(
+        unrounded
+            .iter()
+            .any(|(_, score)| (score * 1000.0).round() / 1000.0 != *score),
+        "some scores should need rounding for this test to mean anything"
+    );
+
+    "SET paradedb.score_precision = 3".execute(&mut conn);
+    let rounded: Vec<(i32, f32)> = query.fetch(&mut conn);
+    let rounded_again: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(rounded, rounded_again);
+
+    // the same documents come back in the same order, with their scores rounded
+    assert_eq!(
+        rounded.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        unrounded.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+    );
+    for ((_, rounded), (_, unrounded)) in rounded.iter().zip(unrounded.iter()) {
+        assert_eq!(
+            *rounded,
+            ((*unrounded as f64 * 1000.0).round() / 1000.0) as f32
+        );
+    }
+
+    // and the "top N" scan, which sorts by score itself, agrees
+    let top_n: Vec<(i32, f32)> = r#"
+        SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+        WHERE description @@@ 'shoes OR keyboard OR plastic OR running'
+        ORDER BY paradedb.score(id) DESC
+        LIMIT 10
+    "#
+    .fetch(&mut conn);
+    let mut top_n_scores = top_n.iter().map(|(_, score)| *score).collect::<Vec<_>>();
+    let rounded_scores = rounded.iter().map(|(_, score)| *score).collect::<Vec<_>>();
+    assert_eq!(top_n_scores, rounded_scores);
+    top_n_scores.dedup();
+    assert!(top_n_scores.windows(2).all(|w| w[0] > w[1]));
+}
+
+#[rstest]
+fn score_precision_is_fixed_when_planned(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    r#"
+    SET plan_cache_mode = force_generic_plan;
+    SET paradedb.score_precision = 3;
+    PREPARE scored AS
+        SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+        WHERE description @@@ 'shoes OR keyboard OR plastic OR running'
+        ORDER BY paradedb.score(id) DESC, id
+        LIMIT 10;
+    "#
+    .execute(&mut conn);
+    let planned: Vec<(i32, f32)> = "EXECUTE scored".fetch(&mut conn);
+
+    // the cached plan keeps rounding scores the way it was planned to
+    "SET paradedb.score_precision = -1".execute(&mut conn);
+    let executed: Vec<(i32, f32)> = "EXECUTE scored".fetch(&mut conn);
+    assert_eq!(planned, executed);
+    assert!(executed
+        .iter()
+        .all(|(_, score)| *score == ((*score as f64 * 1000.0).round() / 1000.0) as f32));
+}
+
+#[rstest]
+fn score_percentile_cutoff(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE percentiles (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO percentiles (body)
+    SELECT repeat('cheese ', (n % 10) + 1) FROM generate_series(1, 1000) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'percentiles',
+        index_name => 'percentiles_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = r#"
+        SELECT id, paradedb.score(id) FROM percentiles
+        WHERE body @@@ 'cheese'
+        ORDER BY paradedb.score(id) DESC
+    "#;
+    let all: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(all.len(), 1000);
+
+    // documents that mention "cheese" the most score the highest, and they're a tenth of them
+    "SET paradedb.score_percentile = 90".execute(&mut conn);
+    let top_decile: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(top_decile.len(), 100);
+    assert!(top_decile.iter().all(|(id, _)| id % 10 == 9));
+    assert!(top_decile.windows(2).all(|w| w[0].1 >= w[1].1));
+
+    let cutoff = top_decile.last().unwrap().1;
+    assert!(all
+        .iter()
+        .filter(|(id, _)| id % 10 != 9)
+        .all(|(_, score)| *score < cutoff));
+
+    // the cutoff still applies when only some of them are wanted
+    let limited: Vec<(i32, f32)> = format!("{query} LIMIT 10").fetch(&mut conn);
+    assert_eq!(limited.len(), 10);
+    assert!(limited.iter().all(|(id, _)| id % 10 == 9));
+
+    // and documents with the same score as the cutoff are never split up
+    "SET paradedb.score_percentile = 95".execute(&mut conn);
+    let top_half_decile: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(top_half_decile.len(), 100);
+
+    "RESET paradedb.score_percentile".execute(&mut conn);
+}
+
+#[rstest]
+fn score_percentile_counts_deleted_rows_until_vacuum(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE percentiles (id SERIAL PRIMARY KEY, body TEXT) WITH (autovacuum_enabled = false);
+    INSERT INTO percentiles (body)
+    SELECT repeat('cheese ', (n % 10) + 1) FROM generate_series(1, 1000) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'percentiles',
+        index_name => 'percentiles_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+
+    SET paradedb.score_percentile = 90;
+    "#
+    .execute(&mut conn);
+
+    let query = r#"
+        SELECT id, paradedb.score(id) FROM percentiles
+        WHERE body @@@ 'cheese'
+        ORDER BY paradedb.score(id) DESC
+    "#;
+
+    // half of the top decile is deleted, but the index still matches them, so the cutoff is
+    // the same and only the half that's left of it is returned
+    "DELETE FROM percentiles WHERE id % 10 = 9 AND id <= 500".execute(&mut conn);
+    let rows: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(rows.len(), 50);
+    assert!(rows.iter().all(|(id, _)| id % 10 == 9 && *id > 500));
+
+    // once they're vacuumed away, the cutoff is the top decile of what's left, which reaches
+    // into the documents that mention "cheese" one time fewer
+    "VACUUM percentiles".execute(&mut conn);
+    let rows: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(rows.len(), 150);
+    assert!(rows.iter().all(|(id, _)| id % 10 == 9 || id % 10 == 8));
+
+    "RESET paradedb.score_percentile".execute(&mut conn);
+}
+
+#[rstest]
+fn limit_with_partially_pushed_order_by_includes_ties(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE ties (id SERIAL PRIMARY KEY, body TEXT, rank INT);
+    INSERT INTO ties (body, rank)
+    SELECT CASE WHEN n <= 3 THEN 'cheese cheese' ELSE 'cheese' END, 20 - n
+    FROM generate_series(1, 10) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'ties',
+        index_name => 'ties_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    // only the score can be sorted by the index, so Postgres sorts by `rank` itself, which it
+    // can only get right if it sees every document tied at the boundary of the limit
+    let query = r#"
+        SELECT id FROM ties
+        WHERE body @@@ 'cheese'
+        ORDER BY paradedb.score(id) DESC, rank ASC
+        LIMIT 5
+    "#;
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(3,), (2,), (1,), (10,), (9,)]);
+
+    // the same holds when the limit falls exactly at the end of a group of ties...
+    let rows: Vec<(i32,)> = query.replace("LIMIT 5", "LIMIT 3").fetch(&mut conn);
+    assert_eq!(rows, vec![(3,), (2,), (1,)]);
+
+    // ... and when it's in the middle of the first one
+    let rows: Vec<(i32,)> = query.replace("LIMIT 5", "LIMIT 1").fetch(&mut conn);
+    assert_eq!(rows, vec![(3,)]);
+
+    let (plan,): (Value,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one(&mut conn);
+    let plan = plan.to_string();
+    assert!(plan.contains("Top N Limit"));
+    assert!(plan.contains("Limit With Ties"));
+}
+
+#[rstest]
+fn limit_ties_cuts_or_includes_boundary_ties(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE ties (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO ties (body)
+    SELECT CASE WHEN n <= 3 THEN 'cheese cheese' ELSE 'cheese' END
+    FROM generate_series(1, 10) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'ties',
+        index_name => 'ties_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = r#"
+        SELECT id FROM ties
+        WHERE body @@@ 'cheese'
+        ORDER BY paradedb.score(id) DESC
+        LIMIT 2
+    "#;
+    let ids = |query: &str, conn: &mut PgConnection| {
+        let mut ids = query
+            .fetch::<(i32,)>(conn)
+            .into_iter()
+            .map(|(id,)| id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    // by default the limit is a hard cut through the documents tied at its boundary
+    assert_eq!(ids(query, &mut conn).len(), 2);
+
+    // but all of them can be included instead
+    "SET paradedb.limit_ties = 'include'".execute(&mut conn);
+    assert_eq!(ids(query, &mut conn), vec![1, 2, 3]);
+    assert_eq!(
+        ids(&query.replace("LIMIT 2", "LIMIT 4"), &mut conn),
+        (1..=10).collect::<Vec<_>>()
+    );
+
+    // which changes nothing when the limit falls at the end of a group of ties
+    assert_eq!(
+        ids(&query.replace("LIMIT 2", "LIMIT 3"), &mut conn),
+        vec![1, 2, 3]
+    );
+
+    // a plan that doesn't use our scan stops at the limit, as the query itself says, both
+    // while the scan is disabled and once it's enabled again
+    "SET paradedb.enable_custom_scan = false".execute(&mut conn);
+    assert_eq!(ids(query, &mut conn).len(), 2);
+    "SET paradedb.enable_custom_scan = true".execute(&mut conn);
+    assert_eq!(ids(query, &mut conn), vec![1, 2, 3]);
+
+    "SET paradedb.limit_ties = 'cut'".execute(&mut conn);
+    assert_eq!(ids(query, &mut conn).len(), 2);
+
+    // only `cut` and `include` are understood
+    assert!("SET paradedb.limit_ties = 'maybe'"
+        .execute_result(&mut conn)
+        .is_err());
+}
+
+#[rstest]
+fn reverse_top_n_returns_the_same_documents_reversed(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE counts (id SERIAL PRIMARY KEY, body TEXT);
+    INSERT INTO counts (body) SELECT repeat('cheese ', n) FROM generate_series(1, 10) n;
+
+    CALL paradedb.create_bm25(
+        table_name => 'counts',
+        index_name => 'counts_idx',
+        key_field => 'id',
+        text_fields => paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = r#"
+        SELECT id, paradedb.score(id) FROM counts
+        WHERE body @@@ 'cheese'
+        ORDER BY paradedb.score(id) DESC
+        LIMIT 4
+    "#;
+
+    let forward: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(
+        forward.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![10, 9, 8, 7]
+    );
+
+    // the same documents, with the same scores, but from the worst to the best
+    "SET paradedb.reverse_top_n = true".execute(&mut conn);
+    let reversed: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(reversed, forward.into_iter().rev().collect::<Vec<_>>());
+
+    // an OFFSET has to skip over the best documents, so isn't reversed
+    let offset: Vec<(i32,)> = "SELECT id FROM counts WHERE body @@@ 'cheese' ORDER BY paradedb.score(id) DESC LIMIT 2 OFFSET 1"
+        .fetch(&mut conn);
+    assert_eq!(offset, vec![(9,), (8,)]);
+
+    "SET paradedb.reverse_top_n = false".execute(&mut conn);
+    let forward: Vec<(i32, f32)> = query.fetch(&mut conn);
+    assert_eq!(
+        forward.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![10, 9, 8, 7]
+    );
+}
+
+#[rstest]
+fn rank_numbers_rows_in_score_order(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = r#"
+        SELECT id, paradedb.score(id), paradedb.rank(id) FROM paradedb.bm25_search
+        WHERE description @@@ 'keyboard OR shoes'
+        ORDER BY paradedb.score(id) DESC"#;
+
+    let rows: Vec<(i32, f32, Option<i64>)> = format!("{query} LIMIT 4").fetch(&mut conn);
+    assert_eq!(rows.len(), 4);
+    assert_eq!(
+        rows.iter().map(|(_, _, rank)| *rank).collect::<Vec<_>>(),
+        vec![Some(1), Some(2), Some(3), Some(4)]
+    );
+    assert!(rows.windows(2).all(|w| w[0].1 >= w[1].1));
+
+    // the rows skipped by an OFFSET are still counted
+    let page: Vec<(i32, f32, Option<i64>)> = format!("{query} LIMIT 2 OFFSET 2").fetch(&mut conn);
+    assert_eq!(page, rows[2..].to_vec());
+
+    // without a LIMIT, Postgres sorts the rows itself, after they've left the scan
+    let unranked: Vec<(i32, f32, Option<i64>)> = query.fetch(&mut conn);
+    assert!(unranked.len() >= 4);
+    assert!(unranked.iter().all(|(_, _, rank)| rank.is_none()));
+}
+
+#[rstest]
+fn relevance_pct_scales_scores_to_the_top_score(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+
+    let query = r#"
+        SELECT id, paradedb.score(id), paradedb.relevance_pct(id) FROM paradedb.bm25_search
+        WHERE description @@@ 'keyboard OR shoes'
+        ORDER BY paradedb.score(id) DESC"#;
+
+    let rows: Vec<(i32, f32, Option<f32>)> = format!("{query} LIMIT 4").fetch(&mut conn);
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0].2, Some(100.0));
+    let top_score = rows[0].1;
+    for (id, score, pct) in &rows {
+        let pct = pct.expect("a top-N by score should have relevance percentages");
+        assert!(
+            (pct - score / top_score * 100.0).abs() < 1e-3,
+            "{id}: {pct}"
+        );
+        assert!(pct <= 100.0, "{id}: {pct}");
+    }
+    assert!(rows.iter().any(|(_, _, pct)| pct.unwrap() < 100.0));
+
+    // when every row scores the same, they're all the top score
+    let rows: Vec<(i32, Option<f32>)> = r#"
+        SELECT id, paradedb.relevance_pct(id) FROM paradedb.bm25_search
+        WHERE id @@@ paradedb.const_score(1.0, paradedb.term('description', 'shoes'))
+        ORDER BY paradedb.score(id) DESC LIMIT 3"#
+        .fetch(&mut conn);
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().all(|(_, pct)| *pct == Some(100.0)), "{rows:?}");
+
+    // without a LIMIT, there's no top-N to compare to
+    let rows: Vec<(i32, f32, Option<f32>)> = query.fetch(&mut conn);
+    assert!(rows.iter().all(|(_, _, pct)| pct.is_none()));
+}
+
+#[rstest]
+fn sort_by_expression(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE articles (id SERIAL PRIMARY KEY, body TEXT, views INT, likes INT);
+    INSERT INTO articles (body, views, likes) VALUES
+        ('rust', 1000, 1),
+        ('rust', 10, 50),
+        ('rust', 500, 20),
+        ('rust', NULL, 5),
+        ('python', 9999, 99);
+
+    CALL paradedb.create_bm25(
+        index_name => 'articles',
+        table_name => 'articles',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('views') || paradedb.field('likes')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = |expression: &str, order: &str| {
+        format!(
+            "SELECT id, paradedb.sort_expression(id, '{expression}') AS sort_key FROM articles
+            WHERE body @@@ 'rust'
+            ORDER BY sort_key {order}"
+        )
+    };
+
+    // a missing value counts as 0
+    let weighted = "views / 1000.0 * 0.3 + likes / 50.0 * 0.7";
+    let rows: Vec<(i32, f64)> = format!("{} LIMIT 3", query(weighted, "DESC")).fetch(&mut conn);
+    assert_eq!(
+        rows.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![2, 3, 1]
+    );
+    let expected = [0.003 + 0.7, 0.15 + 0.28, 0.3 + 0.014];
+    for ((_, sort_key), expected) in rows.iter().zip(expected) {
+        assert!(
+            (sort_key - expected).abs() < 1e-9,
+            "{sort_key} != {expected}"
+        );
+    }
+
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {} LIMIT 3", query(weighted, "DESC"))
+        .fetch_one::<(Value,)>(&mut conn);
+    eprintln!("{plan:#?}");
+    let plan = plan.pointer("/0/Plan/Plans/0").unwrap();
+    assert_eq!(
+        plan.get("   Sort Expression"),
+        Some(&Value::String(weighted.into()))
+    );
+
+    let rows: Vec<(i32, f64)> = format!("{} LIMIT 2", query(weighted, "ASC")).fetch(&mut conn);
+    assert_eq!(
+        rows.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![4, 1]
+    );
+
+    // without a LIMIT, Postgres sorts the values the scan computed
+    let rows: Vec<(i32, f64)> = query(weighted, "DESC").fetch(&mut conn);
+    assert_eq!(
+        rows.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![2, 3, 1, 4]
+    );
+
+    // dividing by 0 is 0, including when the divisor is a missing value
+    let rows: Vec<(i32, f64)> =
+        format!("{} LIMIT 4", query("likes / views", "DESC")).fetch(&mut conn);
+    assert_eq!(rows, vec![(2, 5.0), (3, 0.04), (1, 0.001), (4, 0.0)]);
+    let rows: Vec<(i32, f64)> =
+        format!("{} LIMIT 4", query("1 + likes / (views - views)", "DESC")).fetch(&mut conn);
+    assert!(rows.iter().all(|(_, sort_key)| *sort_key == 1.0));
+
+    // the expression can use the score
+    let rows: Vec<(f32, f64)> = r#"
+        SELECT paradedb.score(id), paradedb.sort_expression(id, 'score * 2') FROM articles
+        WHERE body @@@ 'rust'"#
+        .fetch(&mut conn);
+    assert_eq!(rows.len(), 4);
+    for (score, sort_key) in rows {
+        assert!(
+            (score as f64 * 2.0 - sort_key).abs() < 1e-5,
+            "{score} {sort_key}"
+        );
+    }
+
+    // only numeric fast fields have values for an expression to read
+    let result = format!("{} LIMIT 3", query("body * 2", "DESC")).execute_result(&mut conn);
+    assert!(result.is_err());
+    let result = format!("{} LIMIT 3", query("views +", "DESC")).execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn field_scores_add_up_to_score(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE posts (id SERIAL PRIMARY KEY, title TEXT, body TEXT);
+    INSERT INTO posts (title, body) VALUES
+        ('rust', 'learning rust the hard way'),
+        ('rust', 'nothing to see here'),
+        ('python', 'rust is a language too');
+
+    CALL paradedb.create_bm25(
+        index_name => 'posts',
+        table_name => 'posts',
+        key_field => 'id',
+        text_fields => paradedb.field('title') || paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32, f32, Value)> = r#"
+        SELECT id, paradedb.score(id), paradedb.field_scores(id) FROM posts
+        WHERE id @@@ paradedb.boolean(should => ARRAY[
+            paradedb.term(field => 'title', value => 'rust'),
+            paradedb.boost(2.0, paradedb.term(field => 'body', value => 'rust'))
+        ])
+        ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows.len(), 3);
+
+    for (id, score, field_scores) in rows {
+        let field_scores = field_scores.as_object().unwrap();
+        let expected_fields: &[&str] = match id {
+            1 => &["body", "title"],
+            2 => &["title"],
+            _ => &["body"],
+        };
+        assert_eq!(
+            field_scores.keys().map(String::as_str).collect::<Vec<_>>(),
+            expected_fields
+        );
+
+        let total = field_scores
+            .values()
+            .map(|v| v.as_f64().unwrap())
+            .sum::<f64>();
+        assert!((total - score as f64).abs() < 1e-5, "{id}: {score} {total}");
+    }
+}
+
+#[rstest]
+fn features_are_the_fast_field_values_of_the_top_rows(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE articles (id SERIAL PRIMARY KEY, body TEXT, views INT, ctr FLOAT8);
+    INSERT INTO articles (body, views, ctr)
+    SELECT CASE WHEN n % 3 = 0 THEN 'rust rust' ELSE 'rust' END, n * 10, n / 100.0
+    FROM generate_series(1, 50) n;
+
+    CALL paradedb.create_bm25(
+        index_name => 'articles',
+        table_name => 'articles',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        numeric_fields => paradedb.field('views') || paradedb.field('ctr')
+    );
+    "#
+    .execute(&mut conn);
+
+    let rows: Vec<(i32, i32, f64, f32, Value)> = r#"
+        SELECT id, views, ctr, paradedb.score(id), paradedb.features(id, ARRAY['views', 'ctr'])
+        FROM articles
+        WHERE body @@@ 'rust'
+        ORDER BY paradedb.score(id) DESC
+        LIMIT 10"#
+        .fetch(&mut conn);
+    assert_eq!(rows.len(), 10);
+
+    for (id, views, ctr, score, features) in rows {
+        let feature_score = features.get("score").and_then(Value::as_f64).unwrap();
+        assert!(
+            (feature_score - score as f64).abs() < 1e-5,
+            "{id}: {features}"
+        );
+        assert_eq!(
+            features.pointer("/features/views").and_then(Value::as_i64),
+            Some(views as i64),
+            "{id}: {features}"
+        );
+        let feature_ctr = features.pointer("/features/ctr").and_then(Value::as_f64);
+        assert!(
+            (feature_ctr.unwrap() - ctr).abs() < 1e-9,
+            "{id}: {features}"
+        );
+    }
+
+    // only fast fields have values to read
+    let result = r#"
+        SELECT paradedb.features(id, ARRAY['body']) FROM articles
+        WHERE body @@@ 'rust'"#
+        .execute_result(&mut conn);
+    assert!(result.is_err());
+}
+
+#[rstest]
+fn sort_by_min_or_max_of_multivalued_field(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE products (id SERIAL PRIMARY KEY, name TEXT, prices INT[]);
+    INSERT INTO products (name, prices) VALUES
+        ('socks', ARRAY[5, 40]),
+        ('socks', ARRAY[12]),
+        ('socks', ARRAY[30, 3, 8]),
+        ('socks', ARRAY[]::INT[]),
+        ('socks', ARRAY[20, 25]),
+        ('shoes', ARRAY[1, 100]);
+
+    CALL paradedb.create_bm25(
+        index_name => 'products',
+        table_name => 'products',
+        key_field => 'id',
+        text_fields => paradedb.field('name'),
+        numeric_fields => paradedb.field('prices')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = |order_by: &str| {
+        format!("SELECT id FROM products WHERE name @@@ 'socks' ORDER BY {order_by} LIMIT 3")
+    };
+
+    // a product without prices sorts like a NULL, first in descending order and last in ascending
+    let rows: Vec<(i32,)> = query("paradedb.array_max(prices) DESC").fetch(&mut conn);
+    assert_eq!(rows, vec![(4,), (1,), (3,)]);
+    let rows: Vec<(i32,)> = query("paradedb.array_min(prices)").fetch(&mut conn);
+    assert_eq!(rows, vec![(3,), (1,), (2,)]);
+    let rows: Vec<(i32,)> = query("paradedb.array_min(prices) DESC").fetch(&mut conn);
+    assert_eq!(rows, vec![(4,), (5,), (2,)]);
+    let rows: Vec<(i32,)> = query("paradedb.array_max(prices) ASC").fetch(&mut conn);
+    assert_eq!(rows, vec![(2,), (5,), (3,)]);
+
+    for (order_by, reduction) in [
+        ("paradedb.array_max(prices) DESC", "max"),
+        ("paradedb.array_min(prices)", "min"),
+    ] {
+        let (plan,) =
+            format!("EXPLAIN (FORMAT JSON) {}", query(order_by)).fetch_one::<(Value,)>(&mut conn);
+        eprintln!("{plan:#?}");
+        let plan = plan.pointer("/0/Plan/Plans/0").unwrap();
+        assert_eq!(
+            plan.get("   Sort Field"),
+            Some(&Value::String("prices".into()))
+        );
+        assert_eq!(
+            plan.get("   Sort Reduction"),
+            Some(&Value::String(reduction.into()))
+        );
+    }
+
+    // an order the index can't produce is sorted by Postgres
+    let order_by = "paradedb.array_max(prices) DESC NULLS LAST";
+    let (plan,) =
+        format!("EXPLAIN (FORMAT JSON) {}", query(order_by)).fetch_one::<(Value,)>(&mut conn);
+    assert!(!plan.to_string().contains("Sort Reduction"), "{plan:#?}");
+    let rows: Vec<(i32,)> = query(order_by).fetch(&mut conn);
+    assert_eq!(rows, vec![(1,), (3,), (5,)]);
+
+    let rows: Vec<(Option<i32>, Option<i32>)> =
+        "SELECT paradedb.array_min(ARRAY[3, NULL, 1]), paradedb.array_max(ARRAY[]::INT[])"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(Some(1), None)]);
+}
+
+#[rstest]
+fn sort_by_timestamp_across_epoch(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE posts (id SERIAL PRIMARY KEY, body TEXT, updated_at TIMESTAMP);
+    INSERT INTO posts (body, updated_at) VALUES
+        ('post one', '1969-12-31 23:59:59'),
+        ('post two', '1970-01-01 00:00:00'),
+        ('post three', '1970-01-01 00:00:01'),
+        ('post four', '1901-12-13 20:45:52'),
+        ('post five', '2038-01-19 03:14:08'),
+        ('post six', '1969-07-20 20:17:40'),
+        ('post seven', '2024-02-29 12:00:00'),
+        ('post eight', '1900-01-01 00:00:00');
+
+    CALL paradedb.create_bm25(
+        index_name => 'posts_idx',
+        table_name => 'posts',
+        key_field => 'id',
+        text_fields => paradedb.field('body'),
+        datetime_fields => paradedb.field('updated_at', fast => true)
+    );
+    "#
+    .execute(&mut conn);
+
+    // the top N is found in the index, by the fast field, rather than sorted by Postgres
+    let query = "SELECT id FROM posts WHERE body @@@ 'post' ORDER BY updated_at DESC LIMIT 5";
+    let (plan,) = format!("EXPLAIN (FORMAT JSON) {query}").fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.pointer("/0/Plan/Plans/0").unwrap();
+    eprintln!("{plan:#?}");
+    assert_eq!(
+        plan.get("   Sort Field"),
+        Some(&Value::String(String::from("updated_at")))
+    );
+    assert_eq!(plan.get("   Top N Limit"), Some(&Value::from(5)));
+
+    // timestamps before the epoch are negative, and must still sort below those after it
+    let rows: Vec<(i32,)> = query.fetch(&mut conn);
+    assert_eq!(rows, vec![(5,), (7,), (3,), (2,), (1,)]);
+
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM posts WHERE body @@@ 'post' ORDER BY updated_at DESC LIMIT 8"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(5,), (7,), (3,), (2,), (1,), (6,), (4,), (8,)]);
+
+    let rows: Vec<(i32,)> =
+        "SELECT id FROM posts WHERE body @@@ 'post' ORDER BY updated_at ASC LIMIT 4"
+            .fetch(&mut conn);
+    assert_eq!(rows, vec![(8,), (4,), (6,), (1,)]);
+}
+
+#[rstest]
+fn sort_by_field_with_nulls(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE items (id SERIAL PRIMARY KEY, name TEXT, rating INT);
+    INSERT INTO items (name, rating) VALUES
+        ('widget', 3),
+        ('widget', NULL),
+        ('widget', 5),
+        ('widget', NULL),
+        ('widget', 1),
+        ('widget', 4),
+        ('widget', NULL);
+
+    CALL paradedb.create_bm25(
+        index_name => 'items',
+        table_name => 'items',
+        key_field => 'id',
+        text_fields => paradedb.field('name'),
+        numeric_fields => paradedb.field('rating')
+    );
+    "#
+    .execute(&mut conn);
+
+    let query = |order_by: &str, limit: usize| {
+        format!("SELECT id FROM items WHERE name @@@ 'widget' ORDER BY {order_by} LIMIT {limit}")
+    };
+
+    // NULLs are last in ascending order and first in descending order, unless the query says
+    // otherwise, and they aren't dropped from the top-N, or sorted as if they were zero
+    for (order_by, limit, nulls_first, expected) in [
+        ("rating", 5, false, vec![(5,), (1,), (6,), (3,), (2,)]),
+        ("rating", 2, false, vec![(5,), (1,)]),
+        ("rating DESC", 5, true, vec![(2,), (4,), (7,), (3,), (6,)]),
+        ("rating NULLS FIRST", 4, true, vec![(2,), (4,), (7,), (5,)]),
+        (
+            "rating DESC NULLS LAST",
+            6,
+            false,
+            vec![(3,), (6,), (1,), (5,), (2,), (4,)],
+        ),
+    ] {
+        let (plan,) = format!("EXPLAIN (FORMAT JSON) {}", query(order_by, limit))
+            .fetch_one::<(Value,)>(&mut conn);
+        eprintln!("{plan:#?}");
+        let plan = plan.pointer("/0/Plan/Plans/0").unwrap();
+        assert_eq!(
+            plan.get("   Sort Field"),
+            Some(&Value::String(String::from("rating")))
+        );
+        assert_eq!(plan.get("   Nulls First"), Some(&Value::Bool(nulls_first)));
+
+        let rows: Vec<(i32,)> = query(order_by, limit).fetch(&mut conn);
+        assert_eq!(rows.len(), limit, "{order_by}");
+        assert_eq!(rows, expected, "{order_by}");
+    }
+}
+
+#[rstest]
+fn cursor_fetches_matches_in_score_order_lazily(mut conn: PgConnection) {
+    SimpleProductsTable::setup().execute(&mut conn);
+    "SET enable_indexscan TO off".execute(&mut conn);
+
+    let expected: Vec<(i32, f32)> = r#"
+    SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'
+    ORDER BY paradedb.score(id) DESC, ctid"#
+        .fetch(&mut conn);
+    assert!(expected.len() > 5);
+
+    let cursor = r#"
+    CURSOR FOR SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'
+    ORDER BY paradedb.score(id) DESC"#;
+
+    "BEGIN".execute(&mut conn);
+
+    // the scan returns the matches in order itself, so Postgres doesn't sort them first
+    let (plan,) =
+        format!("EXPLAIN (FORMAT JSON) DECLARE matches {cursor}").fetch_one::<(Value,)>(&mut conn);
+    let plan = plan.pointer("/0/Plan").unwrap();
+    eprintln!("{plan:#?}");
+    assert_eq!(
+        plan.get("Custom Plan Provider"),
+        Some(&Value::String(String::from("ParadeDB Scan")))
+    );
+    assert_eq!(plan.get("   Lazy Sort"), Some(&Value::Bool(true)));
+
+    // and every fetch continues from the next best match across all of them
+    format!("DECLARE matches {cursor}").execute(&mut conn);
+    let mut fetched = Vec::new();
+    loop {
+        let rows: Vec<(i32, f32)> = "FETCH 2 FROM matches".fetch(&mut conn);
+        if rows.is_empty() {
+            break;
+        }
+        fetched.extend(rows);
+    }
+    "COMMIT".execute(&mut conn);
+
+    assert_eq!(fetched.len(), expected.len());
+    for ((id, score), (expected_id, expected_score)) in fetched.iter().zip(&expected) {
+        assert_eq!(id, expected_id);
+        assert!((score - expected_score).abs() < 1e-5);
+    }
+
+    // a query that isn't for a cursor is still sorted by Postgres
+    let (plan,) = r#"
+    EXPLAIN (FORMAT JSON) SELECT id, paradedb.score(id) FROM paradedb.bm25_search
+    WHERE description @@@ 'shoes OR keyboard OR plastic'
+    ORDER BY paradedb.score(id) DESC"#
+        .fetch_one::<(Value,)>(&mut conn);
+    assert_eq!(
+        plan.pointer("/0/Plan/Node Type"),
+        Some(&Value::String(String::from("Sort")))
+    );
+}
+
+#[rstest]
+fn matched_fields_name_the_fields_that_matched(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE posts (id SERIAL PRIMARY KEY, title TEXT, body TEXT);
+    INSERT INTO posts (title, body) VALUES
+        ('rust', 'learning rust the hard way'),
+        ('rust', 'nothing to see here'),
+        ('python', 'rust is a language too'),
+        ('python', 'nothing about it');
+
+    CALL paradedb.create_bm25(
+        index_name => 'posts',
+        table_name => 'posts',
+        key_field => 'id',
+        text_fields => paradedb.field('title') || paradedb.field('body')
+    );
+    "#
+    .execute(&mut conn);
+
+    let expected = vec![
+        (1, vec!["body".to_string(), "title".to_string()]),
+        (2, vec!["title".to_string()]),
+        (3, vec!["body".to_string()]),
+    ];
+
+    let rows: Vec<(i32, Vec<String>)> = r#"
+        SELECT id, paradedb.matched_fields(id) FROM posts
+        WHERE id @@@ paradedb.boolean(should => ARRAY[
+            paradedb.term(field => 'title', value => 'rust'),
+            paradedb.term(field => 'body', value => 'rust')
+        ])
+        ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, expected);
+
+    // the same, for a single query that searches both fields
+    let rows: Vec<(i32, Vec<String>)> = r#"
+        SELECT id, paradedb.matched_fields(id) FROM posts
+        WHERE id @@@ paradedb.disjunction_max(ARRAY[
+            paradedb.term(field => 'title', value => 'rust'),
+            paradedb.term(field => 'body', value => 'rust')
+        ])
+        ORDER BY id"#
+        .fetch(&mut conn);
+    assert_eq!(rows, expected);
+}
@@ -336,3 +336,66 @@ fn datetime_term(mut conn: PgConnection) {
     .fetch_collect(&mut conn);
     assert_eq!(rows, vec![(2,)]);
 }
+
+#[rstest]
+fn terms_query_skips_tokenizer(mut conn: PgConnection) {
+    r#"
+    CREATE TABLE test_table (
+        id SERIAL PRIMARY KEY,
+        value TEXT
+    );
+
+    INSERT INTO test_table (value) VALUES ('Running Shoes'), ('running'), ('Shoe rack');
+
+    CALL paradedb.create_bm25(
+        table_name => 'test_table',
+        index_name => 'test_index',
+        key_field => 'id',
+        text_fields => paradedb.field('value')
+    );
+    "#
+    .execute(&mut conn);
+
+    let ids = |query: &str, conn: &mut PgConnection| -> Vec<(i32,)> {
+        format!("SELECT id FROM test_table WHERE test_table @@@ {query} ORDER BY id")
+            .fetch_collect(conn)
+    };
+
+    // the terms are matched as they're stored, which the tokenizer lowercased
+    assert_eq!(
+        ids("paradedb.terms_query('value', ARRAY['Running'])", &mut conn),
+        vec![]
+    );
+    assert_eq!(
+        ids("paradedb.parse('value:Running')", &mut conn),
+        vec![(1,), (2,)]
+    );
+
+    assert_eq!(
+        ids(
+            "paradedb.terms_query('value', ARRAY['running', 'rack'])",
+            &mut conn
+        ),
+        vec![(1,), (2,), (3,)]
+    );
+    assert_eq!(
+        ids(
+            "paradedb.terms_query('value', ARRAY['running', 'shoes'], conjunction_mode => true)",
+            &mut conn
+        ),
+        vec![(1,)]
+    );
+
+    // a term containing a space is never split
+    assert_eq!(
+        ids(
+            "paradedb.terms_query('value', ARRAY['running shoes'])",
+            &mut conn
+        ),
+        vec![]
+    );
+    assert_eq!(
+        ids("paradedb.terms_query('value', ARRAY[]::text[])", &mut conn),
+        vec![]
+    );
+}
@@ -0,0 +1,200 @@
+// Copyright (c) 2023-2024 Retake, Inc.
+//
+// This file is part of ParadeDB - Postgres for Search and Analytics
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::manager::SearchTokenizer;
+use serde_json::Value;
+use tantivy::tokenizer::{
+    AsciiFoldingFilter, Language, LowerCaser, NgramTokenizer, RawTokenizer, RemoveLongFilter,
+    Stemmer, TextAnalyzer,
+};
+
+/// An analyzer put together at query time, rather than configured for a field when it's indexed:
+/// a tokenizer, and then filters that each replace every token with whatever it turns into, in
+/// order.  It's described by a JSON object such as
+///
+/// ```json
+/// {
+///     "tokenizer": {"type": "whitespace"},
+///     "filters": ["lowercase", "ascii_folding", {"edge_ngram": {"min_gram": 2, "max_gram": 5}}]
+/// }
+/// ```
+///
+/// where the tokenizer is configured like a field's, and is the `default` tokenizer if it's left
+/// out.  The filters are `lowercase`, `ascii_folding`, `{"stemmer": <language>}`,
+/// `{"remove_long": <length>}`, and `{"ngram": ...}` or `{"edge_ngram": ...}` with a `min_gram`
+/// and a `max_gram`, which split each token into its n-grams, or only those it starts with.
+pub struct AnalyzerChain {
+    tokenizer: TextAnalyzer,
+    filters: Vec<TextAnalyzer>,
+}
+
+impl AnalyzerChain {
+    pub fn from_json_value(value: &Value) -> Result<Self, anyhow::Error> {
+        let Value::Object(spec) = value else {
+            anyhow::bail!("an analyzer chain must be a JSON object, found: {value}");
+        };
+        if let Some(key) = spec
+            .keys()
+            .find(|key| !["tokenizer", "filters"].contains(&key.as_str()))
+        {
+            anyhow::bail!("unknown analyzer chain option '{key}'");
+        }
+
+        let tokenizer = match spec.get("tokenizer") {
+            Some(tokenizer) => SearchTokenizer::from_json_value(tokenizer)?,
+            None => SearchTokenizer::default(),
+        };
+        let tokenizer = tokenizer
+            .to_tantivy_tokenizer()
+            .ok_or_else(|| anyhow::anyhow!("tokenizer '{}' isn't available", tokenizer.name()))?;
+
+        let filters = match spec.get("filters") {
+            None => vec![],
+            Some(Value::Array(filters)) => filters
+                .iter()
+                .map(filter_from_json_value)
+                .collect::<Result<_, _>>()?,
+            Some(other) => {
+                anyhow::bail!("analyzer chain 'filters' must be an array, found: {other}")
+            }
+        };
+
+        Ok(AnalyzerChain { tokenizer, filters })
+    }
+
+    /// The tokens `text` analyzes into, in order
+    pub fn analyze(&mut self, text: &str) -> Vec<String> {
+        let mut tokens = token_texts(&mut self.tokenizer, text);
+        for filter in &mut self.filters {
+            tokens = tokens
+                .iter()
+                .flat_map(|token| token_texts(filter, token))
+                .collect();
+        }
+        tokens
+    }
+}
+
+/// The text of each of the tokens `analyzer` turns `text` into
+fn token_texts(analyzer: &mut TextAnalyzer, text: &str) -> Vec<String> {
+    let mut stream = analyzer.token_stream(text);
+    let mut tokens = vec![];
+    while stream.advance() {
+        tokens.push(stream.token().text.clone());
+    }
+    tokens
+}
+
+/// A filter of an [`AnalyzerChain`], which is an analyzer of a single token
+fn filter_from_json_value(value: &Value) -> Result<TextAnalyzer, anyhow::Error> {
+    let token = || TextAnalyzer::builder(RawTokenizer::default());
+    match value {
+        Value::String(name) => match name.as_str() {
+            "lowercase" => Ok(token().filter(LowerCaser).build()),
+            "ascii_folding" => Ok(token().filter(AsciiFoldingFilter).build()),
+            _ => anyhow::bail!("unknown analyzer chain filter '{name}'"),
+        },
+        Value::Object(filter) if filter.len() == 1 => {
+            let (name, options) = filter.iter().next().unwrap();
+            match name.as_str() {
+                "stemmer" => {
+                    let language: Language =
+                        serde_json::from_value(options.clone()).map_err(|_| {
+                            anyhow::anyhow!("the 'stemmer' filter requires a valid language")
+                        })?;
+                    Ok(token().filter(Stemmer::new(language)).build())
+                }
+                "remove_long" => {
+                    let length = options.as_u64().ok_or_else(|| {
+                        anyhow::anyhow!("the 'remove_long' filter requires a length")
+                    })?;
+                    Ok(token()
+                        .filter(RemoveLongFilter::limit(length as usize))
+                        .build())
+                }
+                "ngram" | "edge_ngram" => {
+                    let gram = |key: &str| {
+                        options.get(key).and_then(Value::as_u64).ok_or_else(|| {
+                            anyhow::anyhow!("the '{name}' filter requires an integer '{key}'")
+                        })
+                    };
+                    let (min_gram, max_gram) = (gram("min_gram")?, gram("max_gram")?);
+                    let ngrams = NgramTokenizer::new(
+                        min_gram as usize,
+                        max_gram as usize,
+                        name == "edge_ngram",
+                    )?;
+                    Ok(TextAnalyzer::builder(ngrams).build())
+                }
+                _ => anyhow::bail!("unknown analyzer chain filter '{name}'"),
+            }
+        }
+        _ => anyhow::bail!("an analyzer chain filter must be a name or an object, found: {value}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn analyze(spec: &str, text: &str) -> Vec<String> {
+        AnalyzerChain::from_json_value(&serde_json::from_str(spec).unwrap())
+            .unwrap()
+            .analyze(text)
+    }
+
+    #[rstest]
+    fn test_analyzer_chain() {
+        assert_eq!(analyze("{}", "Crème Brûlée"), vec!["crème", "brûlée"]);
+        assert_eq!(
+            analyze(r#"{"filters": ["ascii_folding"]}"#, "Crème Brûlée"),
+            vec!["creme", "brulee"]
+        );
+        assert_eq!(
+            analyze(
+                r#"{
+                    "tokenizer": {"type": "whitespace", "lowercase": false},
+                    "filters": ["lowercase", "ascii_folding", {"edge_ngram": {"min_gram": 2, "max_gram": 3}}]
+                }"#,
+                "Café Noir"
+            ),
+            vec!["ca", "caf", "no", "noi"]
+        );
+        assert_eq!(
+            analyze(r#"{"filters": [{"stemmer": "English"}]}"#, "running shoes"),
+            vec!["run", "shoe"]
+        );
+    }
+
+    #[rstest]
+    fn test_invalid_analyzer_chain() {
+        for spec in [
+            r#"[]"#,
+            r#"{"filter": []}"#,
+            r#"{"filters": "lowercase"}"#,
+            r#"{"filters": ["uppercase"]}"#,
+            r#"{"filters": [{"edge_ngram": {"min_gram": 2}}]}"#,
+            r#"{"tokenizer": {"type": "unknown"}}"#,
+        ] {
+            assert!(
+                AnalyzerChain::from_json_value(&serde_json::from_str(spec).unwrap()).is_err(),
+                "{spec}"
+            );
+        }
+    }
+}
@@ -15,6 +15,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+pub mod chain;
 pub mod cjk;
 pub mod code;
 #[cfg(feature = "icu")]
@@ -31,6 +32,10 @@ pub use manager::{SearchNormalizer, SearchTokenizer};
 
 pub const DEFAULT_REMOVE_TOKEN_LENGTH: usize = 255;
 
+/// The length of the longest value Postgres can store, `MaxAllocSize`.  A `remove_long` limit at
+/// least this long never drops a value.
+pub const MAX_VALUE_LENGTH: usize = 0x3fff_ffff;
+
 pub fn create_tokenizer_manager(search_tokenizers: Vec<&SearchTokenizer>) -> TokenizerManager {
     let tokenizer_manager = TokenizerManager::default();
 
@@ -23,7 +23,7 @@ use crate::{
     cjk::ChineseTokenizer,
     code::CodeTokenizer,
     lindera::{LinderaChineseTokenizer, LinderaJapaneseTokenizer, LinderaKoreanTokenizer},
-    DEFAULT_REMOVE_TOKEN_LENGTH,
+    DEFAULT_REMOVE_TOKEN_LENGTH, MAX_VALUE_LENGTH,
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -121,6 +121,10 @@ impl SearchTokenizerFilters {
         Some(RemoveLongFilter::limit(limit))
     }
 
+    fn keeps_long_values(&self) -> bool {
+        self.remove_long.unwrap_or(DEFAULT_REMOVE_TOKEN_LENGTH) >= MAX_VALUE_LENGTH
+    }
+
     fn lower_caser(&self) -> Option<LowerCaser> {
         match self.lowercase {
             Some(false) => None, // Only disable if explicitly requested.
@@ -427,6 +431,45 @@ impl SearchTokenizer {
     }
 }
 
+/// The language of an ISO 639-1 code, like `fr`, or of a name, like `French`, in any case
+pub fn language_from_str(value: &str) -> Option<Language> {
+    let language = match value.trim().to_lowercase().as_str() {
+        "ar" | "arabic" => Language::Arabic,
+        "da" | "danish" => Language::Danish,
+        "nl" | "dutch" => Language::Dutch,
+        "en" | "english" => Language::English,
+        "fi" | "finnish" => Language::Finnish,
+        "fr" | "french" => Language::French,
+        "de" | "german" => Language::German,
+        "el" | "greek" => Language::Greek,
+        "hu" | "hungarian" => Language::Hungarian,
+        "it" | "italian" => Language::Italian,
+        "no" | "norwegian" => Language::Norwegian,
+        "pt" | "portuguese" => Language::Portuguese,
+        "ro" | "romanian" => Language::Romanian,
+        "ru" | "russian" => Language::Russian,
+        "es" | "spanish" => Language::Spanish,
+        "sv" | "swedish" => Language::Swedish,
+        "ta" | "tamil" => Language::Tamil,
+        "tr" | "turkish" => Language::Turkish,
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// The language the tokenizer named `name`, as [`SearchTokenizer::name()`] names them, stems its
+/// tokens in, if it stems them at all
+pub fn stemmer_language(name: &str) -> Option<Language> {
+    if name.starts_with("en_stem") {
+        return Some(Language::English);
+    }
+    let language = match name.strip_prefix("stem_") {
+        Some(rest) => rest.split('[').next()?,
+        None => name.split("stemmer=").nth(1)?.split([',', ']']).next()?,
+    };
+    language_from_str(language)
+}
+
 pub fn language_to_str(lang: &Language) -> &str {
     match lang {
         Language::Arabic => "Arabic",
@@ -486,6 +529,32 @@ impl SearchTokenizer {
             SearchTokenizer::ICUTokenizer(_filters) => format!("icu{filters_suffix}"),
         }
     }
+
+    /// If this tokenizer indexes a whole value as a single term, changed at most by lowercasing,
+    /// returns the [`SearchNormalizer`] that describes that change.
+    ///
+    /// Note that values longer than the `remove_long` limit are still dropped, unless
+    /// [`SearchTokenizer::keeps_long_values`] says there's no such value.
+    pub fn keyword_normalizer(&self) -> Option<SearchNormalizer> {
+        match self {
+            SearchTokenizer::Raw(filters) | SearchTokenizer::Lowercase(filters)
+                if filters.stemmer.is_none() =>
+            {
+                if filters.lower_caser().is_some() {
+                    Some(SearchNormalizer::Lowercase)
+                } else {
+                    Some(SearchNormalizer::Raw)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this tokenizer's `remove_long` limit is at least [`MAX_VALUE_LENGTH`], so that no
+    /// value Postgres can store is too long to be indexed.
+    pub fn keeps_long_values(&self) -> bool {
+        self.filters().keeps_long_values()
+    }
 }
 
 // Normalizers for fast fields
@@ -554,6 +623,32 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn test_stemmer_language() {
+        assert_eq!(language_from_str("fr"), Some(Language::French));
+        assert_eq!(language_from_str(" French "), Some(Language::French));
+        assert_eq!(language_from_str("klingon"), None);
+
+        let stem = |value: &str| {
+            SearchTokenizer::from_json_value(&serde_json::from_str(value).unwrap())
+                .unwrap()
+                .name()
+        };
+        assert_eq!(
+            stemmer_language(&stem(r#"{"type": "stem", "language": "French"}"#)),
+            Some(Language::French)
+        );
+        assert_eq!(
+            stemmer_language(&stem(r#"{"type": "default", "stemmer": "German"}"#)),
+            Some(Language::German)
+        );
+        assert_eq!(
+            stemmer_language(&stem(r#"{"type": "en_stem"}"#)),
+            Some(Language::English)
+        );
+        assert_eq!(stemmer_language(&stem(r#"{"type": "default"}"#)), None);
+    }
+
     #[rstest]
     fn test_regexizer() {
         let json = r#"{
@@ -576,6 +671,46 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn test_keyword_normalizer() {
+        let raw = |lowercase, stemmer| {
+            SearchTokenizer::Raw(SearchTokenizerFilters {
+                remove_long: None,
+                lowercase,
+                stemmer,
+            })
+        };
+
+        assert_eq!(
+            raw(None, None).keyword_normalizer(),
+            Some(SearchNormalizer::Lowercase)
+        );
+        assert_eq!(
+            raw(Some(false), None).keyword_normalizer(),
+            Some(SearchNormalizer::Raw)
+        );
+        assert_eq!(
+            raw(Some(false), Some(Language::English)).keyword_normalizer(),
+            None
+        );
+        assert_eq!(SearchTokenizer::default().keyword_normalizer(), None);
+    }
+
+    #[rstest]
+    fn test_keeps_long_values() {
+        let raw = |remove_long| {
+            SearchTokenizer::Raw(SearchTokenizerFilters {
+                remove_long,
+                lowercase: None,
+                stemmer: None,
+            })
+        };
+
+        assert!(!raw(None).keeps_long_values());
+        assert!(!raw(Some(1000)).keeps_long_values());
+        assert!(raw(Some(MAX_VALUE_LENGTH)).keeps_long_values());
+    }
+
     #[rstest]
     fn test_search_normalizer() {
         assert_eq!(SearchNormalizer::Lowercase.name(), "lowercase");